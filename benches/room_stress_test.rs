@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
+#[allow(unused_imports)]
 use tokio::net::TcpStream;
 use tokio::runtime::Runtime;
 use tokio::sync::{Barrier, Mutex};
@@ -40,6 +41,7 @@ struct BenchReducer {
 impl Dispatchable for BenchReducer {
     type Action = BenchAction;
     type State = BenchState;
+    type Error = String;
 
     async fn dispatch(
         &mut self,
@@ -77,6 +79,7 @@ impl Dispatchable for BenchReducer {
 fn create_message(method: JointMessageMethod) -> JointMessage {
     JointMessage {
         client_token: String::new(),
+        v: None,
         message: method,
     }
 }
@@ -86,7 +89,13 @@ fn create_action_message(action: BenchAction) -> JointMessage {
     create_message(JointMessageMethod::Action(action_json))
 }
 
-async fn run_websocket_benchmark(num_clients: usize, actions_per_client: usize) -> f64 {
+#[allow(clippy::clone_on_copy, clippy::single_match)]
+async fn run_websocket_benchmark(
+    num_rooms: usize,
+    num_clients: usize,
+    actions_per_client: usize,
+    room_actors: bool,
+) -> f64 {
     let server_ready = Arc::new(Barrier::new(2));
     let server_ready_clone = server_ready.clone();
     let local_addr_arc = Arc::new(Mutex::new(None::<SocketAddr>));
@@ -94,6 +103,9 @@ async fn run_websocket_benchmark(num_clients: usize, actions_per_client: usize)
 
     let server_handle = tokio::spawn(async move {
         let mut joint = WebsocketJoint::<BenchReducer>::new(BenchReducer::default());
+        // fixed client IDs keep runs comparable
+        joint.set_id_seed(42);
+        joint.set_room_actors(room_actors);
         joint
             .bind_addr("127.0.0.1:0")
             .await
@@ -122,115 +134,39 @@ async fn run_websocket_benchmark(num_clients: usize, actions_per_client: usize)
         }
     };
 
-    let room_id = Arc::new(Mutex::new(None::<u64>));
-
     let url = format!("ws://{}", actual_addr);
     let url = Url::parse(&url).unwrap();
 
     let start_time = Instant::now();
 
-    let room_id_clone = room_id.clone();
-    let url_str = url.to_string();
-
-    let first_client = tokio::spawn(async move {
-        let (ws_stream, _) = connect_async(url_str).await.expect("Failed to connect");
-        let (mut write, mut read) = ws_stream.split();
-
-        let create_msg = create_message(JointMessageMethod::Create);
-        let json = serde_json::to_string(&create_msg).unwrap();
-        write
-            .send(Message::Text(Utf8Bytes::from(&json)))
-            .await
-            .unwrap();
-
-        let mut room_created = false;
-        while !room_created {
-            if let Some(msg) = read.next().await {
-                let msg = msg.unwrap();
-                if let Message::Text(text) = msg {
-                    let response: Response = serde_json::from_str(&text).unwrap();
-                    if let Response::RoomCreated(id) = response {
-                        let mut room_id_guard = room_id_clone.lock().await;
-                        *room_id_guard = Some(id);
-                        room_created = true;
-                    }
-                }
-            }
-        }
-
-        let mut actions_completed = 0;
-        while actions_completed < actions_per_client {
-            let action_msg = create_action_message(BenchAction::Add(1));
-            let json = serde_json::to_string(&action_msg).unwrap();
-            write
-                .send(Message::Text(Utf8Bytes::from(&json)))
-                .await
-                .unwrap();
-
-            while let Ok(Some(msg)) =
-                tokio::time::timeout(Duration::from_millis(100), read.next()).await
-            {
-                let msg = msg.unwrap();
-                if let Message::Text(text) = msg {
-                    let response: Response = serde_json::from_str(&text).unwrap();
-                    match response {
-                        Response::Action(_) => {
-                            actions_completed += 1;
-                            break; 
-                        }
-                        Response::StateSent(_) => {
-                            continue;
-                        }
-                        _ => {
-                            continue;
-                        }
-                    }
-                }
-            }
-        }
-
-        let leave_msg = create_message(JointMessageMethod::Leave);
-        let json = serde_json::to_string(&leave_msg).unwrap();
-        write
-            .send(Message::Text(Utf8Bytes::from(&json)))
-            .await
-            .unwrap();
-    });
-
-    let mut room = None;
-    while room.is_none() {
-        let id = room_id.lock().await.clone();
-        if id.is_some() {
-            room = id;
-        }
-    }
-
-    let room_id = room.unwrap();
-
-    let mut client_handles = vec![first_client];
+    let mut client_handles = Vec::new();
+    // the first client of each room creates it, the others join it
+    for _ in 0..num_rooms {
+        let room_id = Arc::new(Mutex::new(None::<u64>));
+        let room_id_clone = room_id.clone();
+        let url_str = url.to_string();
 
-    for _ in 1..num_clients {
-        let url_clone = url.to_string();
-        let handle = tokio::spawn(async move {
-            let (ws_stream, _) = connect_async(&url_clone).await.expect("Failed to connect");
+        let first_client = tokio::spawn(async move {
+            let (ws_stream, _) = connect_async(url_str).await.expect("Failed to connect");
             let (mut write, mut read) = ws_stream.split();
 
-            let join_msg = create_message(JointMessageMethod::Join(room_id));
-            let json = serde_json::to_string(&join_msg).unwrap();
+            let create_msg = create_message(JointMessageMethod::Create(None));
+            let json = serde_json::to_string(&create_msg).unwrap();
             write
                 .send(Message::Text(Utf8Bytes::from(&json)))
                 .await
                 .unwrap();
 
-            let mut joined = false;
-            while !joined {
+            let mut room_created = false;
+            while !room_created {
                 if let Some(msg) = read.next().await {
                     let msg = msg.unwrap();
                     if let Message::Text(text) = msg {
                         let response: Response = serde_json::from_str(&text).unwrap();
-                        match response {
-                            Response::StateSent(_) => joined = true,
-                            _ => {}
+                        if let Response::RoomCreated(id) = response {
+                            let mut room_id_guard = room_id_clone.lock().await;
+                            *room_id_guard = Some(id);
+                            room_created = true;
                         }
                     }
                 }
@@ -252,11 +188,11 @@ async fn run_websocket_benchmark(num_clients: usize, actions_per_client: usize)
                     if let Message::Text(text) = msg {
                         let response: Response = serde_json::from_str(&text).unwrap();
                         match response {
-                            Response::Action(_) => {
+                            Response::Action(..) => {
                                 actions_completed += 1;
-                                break;
+                                break; 
                             }
-                            Response::StateSent(_) => {
+                            Response::StateSent(..) => {
                                 continue;
                             }
                             _ => {
@@ -275,7 +211,86 @@ async fn run_websocket_benchmark(num_clients: usize, actions_per_client: usize)
                 .unwrap();
         });
 
-        client_handles.push(handle);
+        let mut room = None;
+        while room.is_none() {
+            let id = room_id.lock().await.clone();
+            if id.is_some() {
+                room = id;
+            }
+        }
+
+        let room_id = room.unwrap();
+
+        client_handles.push(first_client);
+
+        for _ in 1..num_clients {
+            let url_clone = url.to_string();
+            let handle = tokio::spawn(async move {
+                let (ws_stream, _) = connect_async(&url_clone).await.expect("Failed to connect");
+                let (mut write, mut read) = ws_stream.split();
+
+                let join_msg = create_message(JointMessageMethod::Join(room_id));
+                let json = serde_json::to_string(&join_msg).unwrap();
+                write
+                    .send(Message::Text(Utf8Bytes::from(&json)))
+                    .await
+                    .unwrap();
+
+                let mut joined = false;
+                while !joined {
+                    if let Some(msg) = read.next().await {
+                        let msg = msg.unwrap();
+                        if let Message::Text(text) = msg {
+                            let response: Response = serde_json::from_str(&text).unwrap();
+                            match response {
+                                Response::StateSent(..) => joined = true,
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+
+                let mut actions_completed = 0;
+                while actions_completed < actions_per_client {
+                    let action_msg = create_action_message(BenchAction::Add(1));
+                    let json = serde_json::to_string(&action_msg).unwrap();
+                    write
+                        .send(Message::Text(Utf8Bytes::from(&json)))
+                        .await
+                        .unwrap();
+
+                    while let Ok(Some(msg)) =
+                        tokio::time::timeout(Duration::from_millis(100), read.next()).await
+                    {
+                        let msg = msg.unwrap();
+                        if let Message::Text(text) = msg {
+                            let response: Response = serde_json::from_str(&text).unwrap();
+                            match response {
+                                Response::Action(..) => {
+                                    actions_completed += 1;
+                                    break;
+                                }
+                                Response::StateSent(..) => {
+                                    continue;
+                                }
+                                _ => {
+                                    continue;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let leave_msg = create_message(JointMessageMethod::Leave);
+                let json = serde_json::to_string(&leave_msg).unwrap();
+                write
+                    .send(Message::Text(Utf8Bytes::from(&json)))
+                    .await
+                    .unwrap();
+            });
+
+            client_handles.push(handle);
+        }
     }
 
     for handle in client_handles {
@@ -311,12 +326,34 @@ fn websocket_joint_benchmark(c: &mut Criterion) {
         let id = format!("clients={}_actions={}", clients, actions);
 
         group.bench_function(id, |b| {
-            b.iter(|| rt.block_on(run_websocket_benchmark(clients, actions)));
+            b.iter(|| rt.block_on(run_websocket_benchmark(1, clients, actions, false)));
+        });
+    }
+
+    group.finish();
+}
+
+fn multi_room_benchmark(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    // n rooms, m clients per room, k actions each
+    let configs = vec![(10, 1, 1000), (10, 10, 1000), (50, 2, 1000)];
+
+    let mut group = c.benchmark_group("WebSocket Joint Multi-Room Performance");
+    group.measurement_time(Duration::from_secs(50));
+    group.sample_size(50);
+
+    for (rooms, clients, actions) in configs {
+        let id = format!("rooms={}_clients={}_actions={}", rooms, clients, actions);
+
+        // room actors apply the actions of separate rooms concurrently
+        group.bench_function(id, |b| {
+            b.iter(|| rt.block_on(run_websocket_benchmark(rooms, clients, actions, true)));
         });
     }
 
     group.finish();
 }
 
-criterion_group!(benches, websocket_joint_benchmark);
+criterion_group!(benches, websocket_joint_benchmark, multi_room_benchmark);
 criterion_main!(benches);
@@ -0,0 +1,108 @@
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use injoint::codec::WireFormat;
+use injoint::response::Response;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct Entity {
+    id: u64,
+    name: String,
+    position: (f64, f64, f64),
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct LargeState {
+    tick: u64,
+    entities: Vec<Entity>,
+    scores: HashMap<u64, i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StateEnvelope {
+    #[allow(dead_code)]
+    status: String,
+    message: LargeState,
+}
+
+fn create_state(entities: u64) -> LargeState {
+    LargeState {
+        tick: 1,
+        entities: (0..entities)
+            .map(|id| Entity {
+                id,
+                name: format!("entity-{}", id),
+                position: (id as f64 * 0.5, id as f64 * 1.5, id as f64 * 2.5),
+                tags: vec!["visible".to_string(), "movable".to_string()],
+            })
+            .collect(),
+        scores: (0..entities).map(|id| (id, id as i64 * 10)).collect(),
+    }
+}
+
+// server encodes `StateSent` and client decodes it into its typed state
+fn deliver_state(format: WireFormat, response: &Response) -> LargeState {
+    let bytes = format.encode(response).unwrap();
+    let envelope: StateEnvelope = format.decode(&bytes).unwrap();
+    envelope.message
+}
+
+fn state_codec_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("StateSent delivery");
+
+    for entities in [100, 1000, 10000] {
+        let state = serde_json::to_string(&create_state(entities)).unwrap();
+        let response = Response::StateSent(state, None);
+
+        for format in [WireFormat::Json, WireFormat::MessagePack] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", format), entities),
+                &response,
+                |b, response| b.iter(|| black_box(deliver_state(format, response))),
+            );
+        }
+    }
+
+    group.finish();
+}
+
+// broadcast of `response` to `recipients` clients, encoding it for every recipient
+fn encode_per_recipient(format: WireFormat, response: &Response, recipients: usize) -> usize {
+    (0..recipients)
+        .map(|_| format.encode(response).unwrap().len())
+        .sum()
+}
+
+// broadcast of `response` to `recipients` clients, sharing a single encoding
+fn encode_once(format: WireFormat, response: &Response, recipients: usize) -> usize {
+    let encoded = Bytes::from(format.encode(response).unwrap());
+    (0..recipients).map(|_| encoded.clone().len()).sum()
+}
+
+fn broadcast_encoding_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Broadcast to 100 recipients");
+    let state = serde_json::to_string(&create_state(1000)).unwrap();
+    let response = Response::StateSent(state, None);
+
+    for format in [WireFormat::Json, WireFormat::MessagePack] {
+        group.bench_with_input(
+            BenchmarkId::new("per recipient", format!("{:?}", format)),
+            &response,
+            |b, response| b.iter(|| black_box(encode_per_recipient(format, response, 100))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("single encoding", format!("{:?}", format)),
+            &response,
+            |b, response| b.iter(|| black_box(encode_once(format, response, 100))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, state_codec_benchmark, broadcast_encoding_benchmark);
+criterion_main!(benches);
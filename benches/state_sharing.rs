@@ -0,0 +1,120 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+use injoint::dispatcher::{ActionResponse, Dispatchable};
+use injoint::joint::mpsc::MPSCJoint;
+use injoint::utils::types::{Broadcastable, Receivable};
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct LargeState {
+    entities: Vec<(u64, String)>,
+}
+
+impl Broadcastable for LargeState {}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+enum BenchAction {
+    Spawn(String),
+}
+
+impl Receivable for BenchAction {}
+
+fn create_state(entities: u64) -> LargeState {
+    LargeState {
+        entities: (0..entities)
+            .map(|id| (id, format!("entity-{}", id)))
+            .collect(),
+    }
+}
+
+// reducer keeping its state in an `Arc`, handing it out through `state_arc` if `shared` is set
+#[derive(Clone, Default)]
+struct BenchReducer {
+    state: Arc<LargeState>,
+    shared: bool,
+}
+
+impl Dispatchable for BenchReducer {
+    type Action = BenchAction;
+    type State = LargeState;
+    type Error = String;
+
+    async fn dispatch(
+        &mut self,
+        client_id: u64,
+        action: BenchAction,
+    ) -> Result<ActionResponse<LargeState>, String> {
+        let BenchAction::Spawn(name) = action;
+        let state = Arc::make_mut(&mut self.state);
+        state.entities.push((state.entities.len() as u64, name));
+        Ok(ActionResponse {
+            status: "success".into(),
+            state: self.get_state(),
+            author: client_id,
+            data: String::new(),
+        })
+    }
+
+    async fn extern_dispatch(
+        &mut self,
+        client_id: u64,
+        action_str: &str,
+    ) -> Result<ActionResponse<LargeState>, String> {
+        let action: BenchAction = serde_json::from_str(action_str)
+            .map_err(|e| format!("Failed to parse action: {}", e))?;
+        self.dispatch(client_id, action).await
+    }
+
+    fn get_state(&self) -> LargeState {
+        (*self.state).clone()
+    }
+
+    fn state_arc(&self) -> Arc<LargeState> {
+        if self.shared {
+            self.state.clone()
+        } else {
+            Arc::new(self.get_state())
+        }
+    }
+}
+
+// connects `clients` clients to a single room, each receiving the initial state
+async fn join_clients(reducer: BenchReducer, clients: usize) {
+    let mut joint = MPSCJoint::new(reducer);
+    joint.single_room_mode();
+    let mut connections = Vec::with_capacity(clients);
+    for _ in 0..clients {
+        let (_tx, mut rx, driver) = joint.connect_inline(4).await;
+        rx.recv().await.unwrap();
+        connections.push(driver);
+    }
+}
+
+fn state_sharing_benchmark(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("StateSent to 100 clients");
+    group.sample_size(20);
+
+    for entities in [1000, 10000] {
+        let state = Arc::new(create_state(entities));
+        for shared in [false, true] {
+            let name = if shared { "state_arc" } else { "get_state" };
+            group.bench_with_input(BenchmarkId::new(name, entities), &state, |b, state| {
+                b.iter(|| {
+                    let reducer = BenchReducer {
+                        state: state.clone(),
+                        shared,
+                    };
+                    runtime.block_on(join_clients(reducer, 100))
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, state_sharing_benchmark);
+criterion_main!(benches);
@@ -1,25 +1,89 @@
 /// This file contains the implementation of the injoint codegen.
 extern crate proc_macro;
-use crate::utils::snake_to_camel;
+use crate::utils::{object_schema, snake_to_camel, type_schema};
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::{
-    parse_macro_input, DeriveInput, FnArg, Ident, ImplItem, ImplItemFn, ItemImpl, ItemStruct,
+    parse_macro_input, Data, DeriveInput, Fields, FnArg, Ident, ImplItem, ImplItemFn, ItemImpl, ItemStruct,
     PatType, Signature, Token, Type,
 };
 
 mod utils;
 
+/// An argument of `#[reducer_actions]`: the state struct, a flag, or a typed option.
+enum ReducerArg {
+    /// A bare identifier, the state struct or a flag such as `strict_actions`.
+    Flag(Ident),
+    /// `error = Type`, the `Dispatchable::Error` of the reducer.
+    Error(Type),
+}
+
+impl Parse for ReducerArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        if !input.peek(Token![=]) {
+            return Ok(ReducerArg::Flag(name));
+        }
+        input.parse::<Token![=]>()?;
+        if name != "error" {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("Unknown reducer_actions option: {}", name),
+            ));
+        }
+        Ok(ReducerArg::Error(input.parse()?))
+    }
+}
+
 /// This macro derives the `Broadcastable` trait for a struct.
+///
+/// For structs with named fields, `Broadcastable::schema` returns the JSON Schema of the
+/// struct, built from the field types. Serde attributes such as renames are not taken into account.
 #[proc_macro_derive(Broadcastable)]
 pub fn derive_broadcastable(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let struct_name = input.ident;
+    let struct_name_str = struct_name.to_string();
+
+    let schema = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => {
+                let fields = fields
+                    .named
+                    .iter()
+                    .map(|field| (field.ident.as_ref().unwrap().to_string(), &field.ty))
+                    .collect::<Vec<_>>();
+                let object = object_schema(&fields, false);
+                quote! {
+                    fn schema() -> serde_json::Value {
+                        let mut schema = #object;
+                        schema["title"] = serde_json::json!(#struct_name_str);
+                        schema
+                    }
+                }
+            }
+            _ => quote! {},
+        },
+        _ => quote! {},
+    };
+
+    // checked ahead of the impl so a missing `Serialize` is reported at the derive site
+    let assert_serialize = quote_spanned! {struct_name.span()=>
+        const _: fn() = || {
+            fn assert_serialize<T: serde::Serialize>() {}
+            assert_serialize::<#struct_name>();
+        };
+    };
 
     let expanded = quote! {
-        impl injoint::utils::types::Broadcastable for #struct_name {}
+        #assert_serialize
+
+        impl injoint::utils::types::Broadcastable for #struct_name {
+            #schema
+        }
     };
 
     TokenStream::from(expanded)
@@ -57,18 +121,49 @@ pub fn reducer_struct(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// The macro takes the state struct as an argument and generates the
 /// necessary code for the reducer actions.
+///
+/// Passing `strict_actions` after the state struct, as in `#[reducer_actions(State, strict_actions)]`,
+/// makes action deserialization reject unknown fields instead of silently ignoring them.
+///
+/// The error type of the reducer defaults to `String`. Passing `error = MyError`, as in
+/// `#[reducer_actions(State, error = MyError)]`, makes it `MyError` instead, which must implement
+/// `Serialize` and `From<String>`; actions then return `Result<String, E>` with `MyError: From<E>`.
+///
+/// The generated enum implements `Receivable::schema`, describing every action and its data
+/// as JSON Schema.
+///
+/// Actions take the acting client's ID as `client_id: u64`, or its whole context as
+/// `ctx: &DispatchContext`, as their first argument after `&mut self`.
+///
+/// The `data` of an action message depends on the number of its remaining arguments:
+/// it is omitted for actions without arguments, holds the single argument as is,
+/// and is an object keyed by argument name for actions with several arguments, e.g.
+/// `{"type":"ActionMovePiece","data":{"from":1,"to":2}}` for `move_piece(&mut self, client_id: u64, from: u8, to: u8)`.
 #[proc_macro_attribute]
 pub fn reducer_actions(attr: TokenStream, item: TokenStream) -> TokenStream {
     let input: ItemImpl = parse_macro_input!(item);
 
     let implementation = input.clone();
 
-    let args: Vec<Ident> =
-        parse_macro_input!(attr with Punctuated::<Ident, Token![,]>::parse_terminated)
+    let args: Vec<ReducerArg> =
+        parse_macro_input!(attr with Punctuated::<ReducerArg, Token![,]>::parse_terminated)
             .into_iter()
             .collect();
 
-    let state_struct = args[0].clone();
+    let state_struct = match args.first() {
+        Some(ReducerArg::Flag(state_struct)) => state_struct.clone(),
+        _ => panic!("reducer_actions expects the state struct as its first argument"),
+    };
+
+    let mut strict_actions = false;
+    let mut error_type = quote! { String };
+    for arg in args.iter().skip(1) {
+        match arg {
+            ReducerArg::Flag(flag) if flag == "strict_actions" => strict_actions = true,
+            ReducerArg::Flag(flag) => panic!("Unknown reducer_actions option: {}", flag),
+            ReducerArg::Error(ty) => error_type = ty.to_token_stream(),
+        }
+    }
 
     let reducer_name = match *input.self_ty {
         Type::Path(ref type_path) => &type_path.path.segments.last().unwrap().ident,
@@ -111,13 +206,35 @@ pub fn reducer_actions(attr: TokenStream, item: TokenStream) -> TokenStream {
         args
     }
 
+    fn parse_client_arg(sig: &Signature) -> proc_macro2::TokenStream {
+        let takes_context = sig.inputs.iter().find_map(|arg| match arg {
+            FnArg::Typed(item) => Some(item),
+            _ => None,
+        });
+        let takes_context = match takes_context.map(|item| &*item.ty) {
+            Some(Type::Reference(reference)) => match &*reference.elem {
+                Type::Path(type_path) => type_path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "DispatchContext"),
+                _ => false,
+            },
+            _ => false,
+        };
+
+        if takes_context {
+            quote! { ctx }
+        } else {
+            quote! { ctx.client_id }
+        }
+    }
+
     fn parse_action_arg_types(sig: &Signature) -> Vec<proc_macro2::TokenStream> {
-        let span = sig.ident.clone().span();
+        // keep the whole type, generic arguments and paths included
         parse_action_args(sig)
             .iter()
-            .map(|item| {
-                Ident::new(&item.ty.clone().to_token_stream().to_string(), span).to_token_stream()
-            })
+            .map(|item| item.ty.to_token_stream())
             .collect::<Vec<_>>()
     }
 
@@ -137,15 +254,39 @@ pub fn reducer_actions(attr: TokenStream, item: TokenStream) -> TokenStream {
         .map(|&ref method| {
             let name = parse_action_name(&method.sig);
             let args = parse_action_arg_types(&method.sig);
-
-            quote! {
-                #name(#(#args),*)
+            let arg_names = parse_action_arg_names(&method.sig);
+
+            // actions without arguments besides the client become unit variants,
+            // actions with several arguments become struct variants keyed by argument name
+            match args.len() {
+                0 => quote! { #name },
+                1 => quote! { #name(#(#args),*) },
+                _ => quote! { #name { #(#arg_names: #args),* } },
             }
         })
         .collect::<Vec<_>>();
 
     let action_enum_name = Ident::new(&format!("Action{}", reducer_name), reducer_span);
 
+    let action_shapes = methods
+        .clone()
+        .iter()
+        .map(|method| {
+            let name = parse_action_name(&method.sig);
+            let arg_names = parse_action_arg_names(&method.sig);
+            let arg_types = parse_action_args(&method.sig)
+                .iter()
+                .map(|item| item.ty.clone())
+                .collect::<Vec<_>>();
+
+            match arg_names.len() {
+                0 => quote! { #name },
+                1 => quote! { #name(#(#arg_names: #arg_types),*) },
+                _ => quote! { #name { #(#arg_names: #arg_types),* } },
+            }
+        })
+        .collect::<Vec<_>>();
+
     let action_names = methods
         .clone()
         .iter()
@@ -155,10 +296,9 @@ pub fn reducer_actions(attr: TokenStream, item: TokenStream) -> TokenStream {
             // let action_name_str =
             //     Ident::new(&format!("{}", action_name), action_name.span()).to_token_stream();
             let action_name_str = &format!("{}", action_name);
-            let args = parse_action_arg_names(&method.sig);
 
             let result = quote! {
-                #enum_name::#action_name(#(#args),*) => String::from(#action_name_str)
+                #enum_name::#action_name { .. } => String::from(#action_name_str)
             };
 
             result
@@ -173,41 +313,144 @@ pub fn reducer_actions(attr: TokenStream, item: TokenStream) -> TokenStream {
             let action_name = parse_action_name(&method.sig);
             let method_name = method.sig.ident.clone();
             let args = parse_action_arg_names(&method.sig);
-
-            let result = quote! {
-                #enum_name::#action_name(#(#args),*) => self.#method_name(client_id, #(#args),*).await?
+            let client_arg = parse_client_arg(&method.sig);
+
+            let result = match args.len() {
+                0 => quote! {
+                    #enum_name::#action_name => self.#method_name(#client_arg).await?
+                },
+                1 => quote! {
+                    #enum_name::#action_name(#(#args),*) => self.#method_name(#client_arg, #(#args),*).await?
+                },
+                _ => quote! {
+                    #enum_name::#action_name { #(#args),* } => self.#method_name(#client_arg, #(#args),*).await?
+                },
             };
 
             result
         })
         .collect::<Vec<_>>();
 
+    let action_schemas = methods
+        .clone()
+        .iter()
+        .map(|method| {
+            let action_name = parse_action_name(&method.sig).to_string();
+            let args = parse_action_args(&method.sig)
+                .iter()
+                .map(|item| {
+                    let arg_name = item.pat.to_token_stream().to_string();
+                    let arg_type = item.ty.to_token_stream().to_string().replace(' ', "");
+                    quote! { serde_json::json!({ "name": #arg_name, "type": #arg_type }) }
+                })
+                .collect::<Vec<_>>();
+
+            quote! {
+                serde_json::json!({ "type": #action_name, "args": [#(#args),*] })
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let action_variant_schemas = methods
+        .clone()
+        .iter()
+        .map(|method| {
+            let action_name = parse_action_name(&method.sig).to_string();
+            let args = parse_action_args(&method.sig);
+
+            let data = match args.as_slice() {
+                [] => None,
+                [arg] => Some(type_schema(&arg.ty)),
+                _ => {
+                    let fields = args
+                        .iter()
+                        .map(|arg| (arg.pat.to_token_stream().to_string(), &*arg.ty))
+                        .collect::<Vec<_>>();
+                    Some(object_schema(&fields, strict_actions))
+                }
+            };
+
+            match data {
+                None => quote! {
+                    serde_json::json!({
+                        "type": "object",
+                        "properties": { "type": { "const": #action_name } },
+                        "required": ["type"],
+                    })
+                },
+                Some(data) => quote! {
+                    serde_json::json!({
+                        "type": "object",
+                        "properties": { "type": { "const": #action_name }, "data": #data },
+                        "required": ["type", "data"],
+                    })
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+
     let enum_name = &action_enum_name.clone();
+    let enum_name_str = enum_name.to_string();
+
+    let deny_unknown_fields = if strict_actions {
+        quote! { #[serde(deny_unknown_fields)] }
+    } else {
+        quote! {}
+    };
 
     let expanded = quote! {
         #implementation
 
         #[derive(serde::Deserialize, Debug)]
         #[serde(tag = "type", content = "data")]
+        #[allow(clippy::enum_variant_names)]
+        #deny_unknown_fields
         enum #enum_name {
             #(#actions),*
         }
 
-        impl injoint::utils::types::Receivable for #enum_name {}
+        impl injoint::utils::types::Receivable for #enum_name {
+            fn schema() -> serde_json::Value {
+                serde_json::json!({
+                    "title": #enum_name_str,
+                    "oneOf": [#(#action_variant_schemas),*],
+                })
+            }
+        }
+
+        injoint::__reducer_action_strategy!(#enum_name { #(#action_shapes),* });
 
         impl injoint::dispatcher::Dispatchable for #reducer_name {
             type Action = #enum_name;
             type State = #state_struct;
+            type Error = #error_type;
 
             fn get_state(&self) -> #state_struct {
                 self.state.clone()
             }
 
+            fn action_schema() -> serde_json::Value {
+                serde_json::json!({
+                    "tag": "type",
+                    "content": "data",
+                    "actions": [#(#action_schemas),*],
+                })
+            }
+
             async fn dispatch(
                 &mut self,
                 client_id: u64,
                 action:  #enum_name,
-            ) -> Result<injoint::dispatcher::ActionResponse<#state_struct>, String> {
+            ) -> Result<injoint::dispatcher::ActionResponse<#state_struct>, #error_type> {
+                let ctx = injoint::dispatcher::DispatchContext::new(client_id);
+                self.dispatch_with_context(&ctx, action).await
+            }
+
+            async fn dispatch_with_context(
+                &mut self,
+                ctx: &injoint::dispatcher::DispatchContext,
+                action:  #enum_name,
+            ) -> Result<injoint::dispatcher::ActionResponse<#state_struct>, #error_type> {
                 let name = match &action {
                     #(#action_names),*
                 };
@@ -216,20 +459,16 @@ pub fn reducer_actions(attr: TokenStream, item: TokenStream) -> TokenStream {
                     #(#action_handlers),*
                 };
 
-                Ok(injoint::dispatcher::ActionResponse {
-                    status: name,
-                    state: self.state.clone(),
-                    author: client_id,
-                    data: msg,
-                })
+                Ok(ctx.response(self, name, msg))
             }
 
             async fn extern_dispatch(
                 &mut self,
                 client_id: u64,
                 action: &str,
-            ) -> Result<injoint::dispatcher::ActionResponse<#state_struct>, String> {
-                let action: #enum_name = serde_json::from_str(action).unwrap();
+            ) -> Result<injoint::dispatcher::ActionResponse<#state_struct>, #error_type> {
+                let action: #enum_name = serde_json::from_str(action)
+                    .map_err(|e| format!("Failed to parse action: {}", e))?;
                 self.dispatch(client_id, action).await
             }
         }
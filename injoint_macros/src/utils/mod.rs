@@ -1,3 +1,7 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{GenericArgument, PathArguments, Type};
+
 pub fn snake_to_camel(snake: &str) -> String {
     let mut camel_case = String::new();
     let mut capitalize_next = true;
@@ -15,3 +19,129 @@ pub fn snake_to_camel(snake: &str) -> String {
 
     camel_case
 }
+
+/// Builds a `serde_json::json!` expression holding the JSON Schema of a Rust type.
+///
+/// Primitives, strings, sequences, maps, options and smart pointers are mapped to their
+/// JSON counterparts; other types are only described by their name.
+pub fn type_schema(ty: &Type) -> TokenStream {
+    match ty {
+        Type::Reference(reference) => type_schema(&reference.elem),
+        Type::Paren(paren) => type_schema(&paren.elem),
+        Type::Group(group) => type_schema(&group.elem),
+        Type::Array(array) => {
+            let items = type_schema(&array.elem);
+            quote! { serde_json::json!({ "type": "array", "items": #items }) }
+        }
+        Type::Slice(slice) => {
+            let items = type_schema(&slice.elem);
+            quote! { serde_json::json!({ "type": "array", "items": #items }) }
+        }
+        Type::Tuple(tuple) if tuple.elems.is_empty() => {
+            quote! { serde_json::json!({ "type": "null" }) }
+        }
+        Type::Tuple(tuple) => {
+            let items = tuple.elems.iter().map(type_schema).collect::<Vec<_>>();
+            let len = items.len();
+            quote! {
+                serde_json::json!({
+                    "type": "array",
+                    "prefixItems": [#(#items),*],
+                    "minItems": #len,
+                    "maxItems": #len,
+                })
+            }
+        }
+        Type::Path(type_path) => {
+            let Some(segment) = type_path.path.segments.last() else {
+                return quote! { serde_json::json!({}) };
+            };
+            let args = generic_type_args(&segment.arguments);
+            match (segment.ident.to_string().as_str(), args.as_slice()) {
+                ("u8" | "u16" | "u32" | "u64" | "u128" | "usize", _) => {
+                    quote! { serde_json::json!({ "type": "integer", "minimum": 0 }) }
+                }
+                ("i8" | "i16" | "i32" | "i64" | "i128" | "isize", _) => {
+                    quote! { serde_json::json!({ "type": "integer" }) }
+                }
+                ("f32" | "f64", _) => quote! { serde_json::json!({ "type": "number" }) },
+                ("bool", _) => quote! { serde_json::json!({ "type": "boolean" }) },
+                ("String" | "str" | "char", _) => quote! { serde_json::json!({ "type": "string" }) },
+                ("Value", _) => quote! { serde_json::json!({}) },
+                ("Box" | "Arc" | "Rc" | "Cow", [.., inner]) => type_schema(inner),
+                ("Option", [inner]) => {
+                    let inner = type_schema(inner);
+                    quote! { serde_json::json!({ "anyOf": [#inner, { "type": "null" }] }) }
+                }
+                (
+                    "Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet" | "IndexSet",
+                    [inner],
+                ) => {
+                    let items = type_schema(inner);
+                    quote! { serde_json::json!({ "type": "array", "items": #items }) }
+                }
+                ("HashMap" | "BTreeMap" | "IndexMap", [_, value]) => {
+                    let values = type_schema(value);
+                    quote! { serde_json::json!({ "type": "object", "additionalProperties": #values }) }
+                }
+                (name, _) => quote! { serde_json::json!({ "title": #name }) },
+            }
+        }
+        _ => quote! { serde_json::json!({}) },
+    }
+}
+
+/// Returns whether a type is an `Option`, which serde lets be omitted from objects.
+pub fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Builds a `serde_json::json!` expression holding the JSON Schema of an object
+/// with the given named fields.
+pub fn object_schema(fields: &[(String, &Type)], deny_unknown_fields: bool) -> TokenStream {
+    let names = fields.iter().map(|(name, _)| name).collect::<Vec<_>>();
+    let schemas = fields
+        .iter()
+        .map(|(_, ty)| type_schema(ty))
+        .collect::<Vec<_>>();
+    let required = fields
+        .iter()
+        .filter(|(_, ty)| !is_option(ty))
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    let additional = if deny_unknown_fields {
+        quote! { "additionalProperties": false, }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        serde_json::json!({
+            "type": "object",
+            "properties": { #(#names: #schemas),* },
+            "required": [#(#required),*],
+            #additional
+        })
+    }
+}
+
+fn generic_type_args(arguments: &PathArguments) -> Vec<&Type> {
+    match arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -1,22 +1,495 @@
 /// Broadcaster module for implementing `Broadcaster` struct that handles clients, connections, and rooms
 mod test;
 
-use crate::client::Client;
-use crate::connection::{SinkAdapter, StreamAdapter};
-use crate::dispatcher::{ActionResponse, Dispatchable};
-use crate::message::{JointMessage, JointMessageMethod};
-use crate::response::{ClientResponse, Response, RoomResponse};
-use crate::room::{Room, RoomStatus};
+use crate::client::{Client, ClientStatus};
+use crate::codec::{Compression, WireFormat};
+use crate::connection::{SinkAdapter, SinkClosed, StreamAdapter};
+use crate::dispatcher::{
+    ActionResponse, DispatchContext, Dispatchable, PendingWork, Phase, PostDispatchHook,
+    PreDispatchHook, Projection, RecordedAction, ReducerFactory,
+};
+use crate::load_shedding::{LoadShedding, PendingActions};
+use crate::message::{
+    CreateOptions, JointMessage, JointMessageMethod, RoomConfig, DEFAULT_PROTOCOL_VERSION,
+    SCHEMA_VERSION,
+};
+use crate::metrics::{Counters, JointStats};
+use crate::protocol::{describe_protocol, ProtocolExtensions};
+use crate::rate_limit::RateLimit;
+use crate::response::{
+    ClientInfo, ClientResponse, ClientsResponse, ErrorCode, EventResponse, Response,
+    RoomConfigInfo, RoomInfo, RoomResponse, Stamp,
+};
+use crate::room::{HistoryConfig, Room, RoomShards, RoomStatus, ROOM_SHARDS};
+use bytes::Bytes;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+#[allow(clippy::single_component_path_imports)]
 use serde_json;
-use std::collections::{HashMap, HashSet};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
+use tokio::time::Instant;
+
+/// Error reported when a response exceeds the configured outgoing size limit.
+const OUTGOING_LIMIT_EXCEEDED: &str = "Outgoing message size limit exceeded";
+/// Error reported when an action response carries data exceeding the configured size limit.
+const DATA_LIMIT_EXCEEDED: &str = "Action data size limit exceeded";
+/// Error reported when the actor of a room stopped before applying an action.
+const ROOM_ACTOR_STOPPED: &str = "Room actor stopped";
+
+/// A client disconnected while in a room, kept for its session to be resumed.
+struct Session {
+    /// The disconnected client, its room still set.
+    client: Client,
+    /// When the client got disconnected.
+    disconnected_at: Instant,
+    /// Room broadcasts missed while disconnected, oldest first.
+    queued: VecDeque<Response>,
+}
+
+/// Configuration of the sessions kept for disconnected clients.
+#[derive(Debug, Clone, Copy, Default)]
+struct SessionConfig {
+    /// The number of room broadcasts queued per disconnected client, disabled with `0`.
+    buffer_size: usize,
+    /// How long a session can be resumed after its client disconnected, forever if `None`.
+    grace: Option<Duration>,
+}
+
+impl SessionConfig {
+    fn is_expired(&self, session: &Session) -> bool {
+        self.grace
+            .is_some_and(|grace| session.disconnected_at.elapsed() > grace)
+    }
+}
+
+/// Reason a message could not be delivered to a client connection.
+enum SendError {
+    /// The sink returned an error.
+    Failed(Box<dyn std::error::Error + Send + Sync>),
+    /// The sink did not accept the message within the configured send timeout.
+    TimedOut,
+    /// The sink reported its connection is gone for good.
+    Closed,
+}
+
+impl SendError {
+    fn from_sink(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        if error.is::<SinkClosed>() {
+            SendError::Closed
+        } else {
+            SendError::Failed(error)
+        }
+    }
+}
+
+/// Returns the response encoded in the given wire format, encoding it on first use.
+///
+/// Lets a broadcast encode its response once per wire format instead of once per recipient.
+/// Returns `None` if the response can't be encoded in the format.
+fn encoded_response(
+    encoded: &mut Vec<(WireFormat, Option<Bytes>)>,
+    format: WireFormat,
+    response: &Response,
+) -> Option<Bytes> {
+    let index = match encoded.iter().position(|(f, _)| *f == format) {
+        Some(index) => index,
+        None => {
+            encoded.push((format, format.encode(response).ok().map(Bytes::from)));
+            encoded.len() - 1
+        }
+    };
+    encoded[index].1.clone()
+}
+
+/// Renders an error returned by a reducer as the message of a `ClientError` response.
+///
+/// String errors are sent as is, other errors as their JSON serialization.
+fn reducer_error_message<E: Serialize>(error: &E) -> String {
+    match serde_json::to_value(error) {
+        Ok(Value::String(message)) => message,
+        Ok(value) => value.to_string(),
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Returns the room members the reducer hides an applied action from.
+fn hidden_recipients<R: Dispatchable>(
+    reducer: &R,
+    members: &HashSet<u64>,
+    result: &ActionResponse<R::State>,
+) -> HashSet<u64> {
+    members
+        .iter()
+        .copied()
+        .filter(|&member| !reducer.should_broadcast_to(member, result))
+        .collect()
+}
+
+/// Phase of the two-phase dispatch a client action is applied in.
+enum ActionStep<A> {
+    /// The action was received from the client, beginning its dispatch.
+    Begin(A),
+    /// The pending work begun by the action resolved to this completing action.
+    Complete(A),
+}
+
+/// Outcome of applying a client action to a room reducer.
+enum Applied<R: Dispatchable> {
+    /// The action was applied, yielding the broadcast to the room members.
+    Done(RoomResponse),
+    /// The action awaits work to run without holding the reducer before it completes.
+    Pending(PendingWork<R>),
+}
+
+/// Settings of every action applied to a room reducer.
+#[derive(Clone, Copy, Default)]
+struct ActionSettings {
+    /// Maximum size of a room state in bytes, actions exceeding it are reverted.
+    max_state_bytes: Option<usize>,
+    /// Maximum size of a single outgoing JSON-serialized response in bytes.
+    max_outgoing_bytes: Option<usize>,
+    /// Maximum size of the `data` of an action response in bytes, actions exceeding it are reverted.
+    max_data_bytes: Option<usize>,
+    /// Whether action broadcasts carry a JSON Patch from the previous state instead of the state.
+    state_diffs: bool,
+    /// Whether state and action broadcasts carry the room's sequence number and a timestamp.
+    stamps: bool,
+}
+
+impl ActionSettings {
+    /// checks whether serialized response exceeds the outgoing size limit
+    fn exceeds_outgoing_limit(&self, response: &Response) -> bool {
+        self.max_outgoing_bytes.is_some_and(|max_outgoing_bytes| {
+            serde_json::to_vec(response).map_or(0, |bytes| bytes.len()) > max_outgoing_bytes
+        })
+    }
+
+    /// checks whether reducer state exceeds the state size limit
+    fn exceeds_state_budget<R: Dispatchable>(&self, reducer: &R) -> bool {
+        self.max_state_bytes
+            .is_some_and(|max_state_bytes| reducer.state_size_bytes() > max_state_bytes)
+    }
+
+    /// checks whether action response data exceeds the data size limit, logging the offending action
+    fn exceeds_data_limit<S: Serialize>(&self, room_id: u64, result: &ActionResponse<S>) -> bool {
+        let Some(max_data_bytes) = self.max_data_bytes else {
+            return false;
+        };
+        if result.data.len() <= max_data_bytes {
+            return false;
+        }
+        eprintln!(
+            "Action {} of client {} in room {} returned {} bytes of data, over the {} bytes limit",
+            result.status,
+            result.author,
+            room_id,
+            result.data.len(),
+            max_data_bytes
+        );
+        true
+    }
+
+    /// applies a client action to the reducer, returning the broadcast to the room members,
+    /// or the work to await without holding the reducer before applying the completing action
+    ///
+    /// Actions making the state, the response data or the broadcast exceed the limits, or whose
+    /// broadcast fails to serialize, are reverted.
+    async fn apply<R: Dispatchable>(
+        self,
+        reducer: &mut R,
+        room_id: u64,
+        members: &HashSet<u64>,
+        ctx: &DispatchContext,
+        step: ActionStep<R::Action>,
+        callbacks: &ActionCallbacks<R::State>,
+    ) -> Result<Applied<R>, ClientResponse> {
+        let client_id = ctx.client_id;
+        let snapshot = reducer.clone();
+        let previous_states = self
+            .state_diffs
+            .then(|| callbacks.previous_states(&*reducer.state_arc()))
+            .flatten();
+        let outcome = match step {
+            ActionStep::Begin(action) => match reducer.begin(ctx, action).await {
+                Phase::Applied(outcome) => outcome,
+                Phase::Pending(work) => return Ok(Applied::Pending(work)),
+            },
+            ActionStep::Complete(action) => reducer.complete(ctx, action).await,
+        };
+        let result = match outcome {
+            Ok(_) if self.exceeds_state_budget(reducer) => {
+                *reducer = snapshot;
+                return Err(ClientResponse::client_error(
+                    client_id,
+                    ErrorCode::LimitExceeded,
+                    "State size limit exceeded".to_string(),
+                ));
+            }
+            Ok(result) if self.exceeds_data_limit(room_id, &result) => {
+                *reducer = snapshot;
+                return Err(ClientResponse::server_error(
+                    client_id,
+                    ErrorCode::LimitExceeded,
+                    DATA_LIMIT_EXCEEDED.to_string(),
+                ));
+            }
+            Ok(result) => result,
+            Err(e) => {
+                return Err(ClientResponse::client_error(
+                    client_id,
+                    ErrorCode::InvalidAction,
+                    reducer_error_message(&e),
+                ))
+            }
+        };
+        let mut response = match callbacks.action_response(room_id, &result, previous_states) {
+            Ok(response) => response,
+            Err(e) => {
+                *reducer = snapshot;
+                return Err(ClientResponse::server_error(
+                    client_id,
+                    ErrorCode::Internal,
+                    e.to_string(),
+                ));
+            }
+        };
+        response.skip_clients = hidden_recipients(reducer, members, &result);
+        if self.exceeds_outgoing_limit(&response.response) {
+            *reducer = snapshot;
+            return Err(ClientResponse::server_error(
+                client_id,
+                ErrorCode::LimitExceeded,
+                OUTGOING_LIMIT_EXCEEDED.to_string(),
+            ));
+        }
+
+        if let Some(hook) = &callbacks.post_dispatch_hook {
+            hook(ctx, &result);
+        }
+        Ok(Applied::Done(response))
+    }
+}
+
+/// States of a room before an action, for broadcasts carrying a patch from them.
+struct PreviousStates {
+    /// The full state.
+    state: Value,
+    /// The state rendered through each projection.
+    projected: HashMap<String, Value>,
+}
+
+/// Callbacks run on the result of every action applied to a room reducer, shared with room actors.
+struct ActionCallbacks<S: Serialize> {
+    /// Hook run after each client action is applied.
+    post_dispatch_hook: Option<Arc<PostDispatchHook<S>>>,
+    /// Projections of the room states, by name.
+    projections: HashMap<String, Arc<Projection<S>>>,
+}
+
+impl<S: Serialize> Default for ActionCallbacks<S> {
+    fn default() -> Self {
+        ActionCallbacks {
+            post_dispatch_hook: None,
+            projections: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Serialize> Clone for ActionCallbacks<S> {
+    fn clone(&self) -> Self {
+        ActionCallbacks {
+            post_dispatch_hook: self.post_dispatch_hook.clone(),
+            projections: self.projections.clone(),
+        }
+    }
+}
+
+impl<S: Serialize> ActionCallbacks<S> {
+    /// captures the state of a room before an action, in full and through each projection
+    fn previous_states(&self, state: &S) -> Option<PreviousStates> {
+        Some(PreviousStates {
+            state: serde_json::to_value(state).ok()?,
+            projected: self
+                .projections
+                .iter()
+                .map(|(name, projection)| (name.clone(), projection(state)))
+                .collect(),
+        })
+    }
+
+    /// builds the broadcast of an action response to a room, also rendered through each projection
+    fn action_response(
+        &self,
+        room_id: u64,
+        result: &ActionResponse<S>,
+        previous_states: Option<PreviousStates>,
+    ) -> Result<RoomResponse, serde_json::Error> {
+        let (previous_state, mut previous_projected) = match previous_states {
+            Some(previous) => (Some(previous.state), previous.projected),
+            None => (None, HashMap::new()),
+        };
+        let mut response = RoomResponse::action(room_id, action_payload(result, previous_state)?);
+        for (name, projection) in &self.projections {
+            let projected = ActionResponse {
+                status: result.status.clone(),
+                state: projection(&result.state),
+                author: result.author,
+                data: result.data.clone(),
+            };
+            let payload = action_payload(&projected, previous_projected.remove(name))?;
+            response
+                .projected
+                .insert(name.clone(), Response::Action(payload, None));
+        }
+        Ok(response)
+    }
+}
+
+/// Serializes an action response for broadcast.
+///
+/// Given the previous state, the `state` field is replaced with a `patch` field holding
+/// the JSON Patch (RFC 6902) turning the previous state into the new one.
+fn action_payload<S: Serialize>(
+    result: &ActionResponse<S>,
+    previous_state: Option<Value>,
+) -> Result<String, serde_json::Error> {
+    let Some(previous_state) = previous_state else {
+        return serde_json::to_string(result);
+    };
+    let mut payload = serde_json::to_value(result)?;
+    if let Some(fields) = payload.as_object_mut() {
+        let state = fields.remove("state").unwrap_or(Value::Null);
+        let patch = json_patch::diff(&previous_state, &state);
+        fields.insert("patch".to_string(), serde_json::to_value(patch)?);
+    }
+    serde_json::to_string(&payload)
+}
+
+/// Client action sent to a room actor, answered over `reply` once applied.
+pub(crate) struct ActionCommand<R: Dispatchable> {
+    ctx: DispatchContext,
+    action: ActionStep<R::Action>,
+    raw_action: String,
+    protocol_version: u32,
+    base_version: Option<u64>,
+    reply: oneshot::Sender<Result<RoomResponse, ClientResponse>>,
+}
+
+/// Sender of client actions to the task applying them to a room reducer one at a time.
+pub(crate) type RoomActor<R> = mpsc::UnboundedSender<ActionCommand<R>>;
+
+/// Backlog of a room actor, queued per client and served round-robin.
+///
+/// A client flooding the room only delays its own actions: every other client with a
+/// pending action gets a turn before its next one is applied.
+struct FairQueue<R: Dispatchable> {
+    /// Pending actions of every client with at least one, in order of arrival.
+    queues: HashMap<u64, VecDeque<ActionCommand<R>>>,
+    /// Clients with pending actions, in order of their next turn.
+    turns: VecDeque<u64>,
+}
+
+impl<R: Dispatchable> FairQueue<R> {
+    fn new() -> Self {
+        FairQueue {
+            queues: HashMap::new(),
+            turns: VecDeque::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    /// queues an action behind the pending actions of the same client
+    fn push(&mut self, command: ActionCommand<R>) {
+        let client_id = command.ctx.client_id;
+        let queue = self.queues.entry(client_id).or_default();
+        if queue.is_empty() {
+            self.turns.push_back(client_id);
+        }
+        queue.push_back(command);
+    }
+
+    /// takes the next action of the client whose turn it is
+    fn pop(&mut self) -> Option<ActionCommand<R>> {
+        let client_id = self.turns.pop_front()?;
+        let queue = self.queues.get_mut(&client_id)?;
+        let command = queue.pop_front();
+        if queue.is_empty() {
+            self.queues.remove(&client_id);
+        } else {
+            self.turns.push_back(client_id);
+        }
+        command
+    }
+}
+
+/// rejects an action based on another state version than the current one of its room
+fn check_base_version(
+    client_id: u64,
+    base_version: Option<u64>,
+    current_version: u64,
+) -> Result<(), ClientResponse> {
+    match base_version {
+        Some(base_version) if base_version != current_version => Err(ClientResponse::new(
+            client_id,
+            Response::Conflict { current_version },
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// bumps the state version of a room after an action was applied, recording the action if enabled,
+/// stamping its broadcast with the new version if enabled, keeping it in the room history
+/// and publishing the new state to its subscribers
+async fn record_applied_action<R: Dispatchable>(
+    room: Option<&mut Room<R>>,
+    recorded: impl FnOnce() -> RecordedAction,
+    response: &mut RoomResponse,
+    record_actions: bool,
+    history: HistoryConfig,
+    stamps: bool,
+) {
+    if let Some(room) = room {
+        room.state_version += 1;
+        if record_actions {
+            room.action_log.push(recorded());
+        }
+        let stamp = stamps.then(|| Stamp::now(room.state_version));
+        if stamp.is_some() {
+            for response in
+                std::iter::once(&mut response.response).chain(response.projected.values_mut())
+            {
+                if let Response::Action(_, action_stamp) = response {
+                    *action_stamp = stamp;
+                }
+            }
+        }
+        if let Response::Action(payload, _) = &response.response {
+            room.push_history(payload, stamp, history);
+        }
+        room.publish_state().await;
+    }
+}
 
 /// Broadcaster struct that manages clients, connections, and rooms
 ///
 /// This struct is responsible for handling the main publish-subscribe logic,
 /// including creating and joining rooms, dispatching actions, and broadcasting messages.
 ///
+/// Clients, connections and rooms are behind read-write locks: broadcasts and lookups share
+/// read access, so only mutations serialize with each other. Rooms are further sharded over
+/// buckets locked independently, so rooms of different buckets don't contend at all.
+///
 /// - S is the type of the sink adapter used for sending messages to clients.
 /// - R is the type of the reducer used for managing the state of the rooms.
 pub struct Broadcaster<S, R>
@@ -25,130 +498,1539 @@ where
     R: Dispatchable + Send,
 {
     /// A map of client IDs to their corresponding Client objects.
-    clients: Arc<Mutex<HashMap<u64, Client>>>,
+    clients: Arc<RwLock<HashMap<u64, Client>>>,
     /// A map of client IDs to their corresponding connection objects.
-    connections: Arc<Mutex<HashMap<u64, S>>>,
-    /// A map of room IDs to their corresponding Room objects.
-    rooms: Arc<Mutex<HashMap<u64, Room<R>>>>,
-    /// The default reducer used for managing the state of the rooms.
-    default_reducer: R,
+    connections: Arc<RwLock<HashMap<u64, S>>>,
+    /// A map of room IDs to their corresponding Room objects, sharded by room ID.
+    rooms: Arc<RoomShards<R>>,
+    /// The ID given to the next created room. Never decreases, so IDs of deleted rooms are not reused.
+    next_room_id: AtomicU64,
+    /// The correlation ID given to the next dispatch made with a callback.
+    next_correlation_id: AtomicU64,
+    /// Generator of the IDs given to connecting clients.
+    id_rng: std::sync::Mutex<StdRng>,
+    /// Constructor of the reducer used for managing the state of each new room.
+    reducer_factory: ReducerFactory<R>,
+    /// Whether joining clients must acknowledge the initial state before receiving broadcasts.
+    join_requires_ack: bool,
+    /// Settings of the actions applied to room reducers.
+    settings: ActionSettings,
+    /// Whether applied actions are recorded in the per-room action log.
+    record_actions: bool,
+    /// Configuration of the action broadcasts kept per room for resuming clients.
+    history: HistoryConfig,
+    /// Clients disconnected while in a room by their token, for sessions to be resumed.
+    sessions: Mutex<HashMap<String, Session>>,
+    /// Configuration of the sessions kept for disconnected clients.
+    session_config: SessionConfig,
+    /// Rooms created with `CreateOrJoin` by their creation key.
+    key_index: Mutex<HashMap<String, u64>>,
+    /// Subscribers to the summaries of all rooms, sent as rooms are created or change members.
+    overview_subscribers: std::sync::Mutex<Vec<mpsc::UnboundedSender<Response>>>,
+    /// Maximum time a single send may take before the client is considered stuck and removed.
+    send_timeout: Option<Duration>,
+    /// Per-client action rate limit applied in rooms that don't override it.
+    rate_limit: Option<RateLimit>,
+    /// Most permissive rate limit a room creator may request.
+    max_rate_limit: Option<RateLimit>,
+    /// Policy rejecting low-priority actions while too many actions are pending.
+    load_shedding: Option<LoadShedding>,
+    /// Number of client actions being processed or waiting to be.
+    pending_actions: PendingActions,
+    /// Number of room members above which action broadcasts are skipped, members pulling with `Sync`.
+    max_fanout: Option<usize>,
+    /// Whether rooms are deleted as soon as their last client leaves.
+    delete_empty_rooms: bool,
+    /// The room every connecting client joins automatically in single-room mode.
+    single_room: Option<u64>,
+    /// Whether clients may be members of several rooms at once.
+    multi_room: bool,
+    /// Interval after which idle clients are sent a `Keepalive`.
+    keepalive_interval: Option<Duration>,
+    /// Hook run before each client action is dispatched, able to veto it.
+    pre_dispatch_hook: Option<PreDispatchHook>,
+    /// Hook run after each client action is applied and projections of the room states.
+    callbacks: ActionCallbacks<R::State>,
+    /// Whether client actions are applied by a dedicated actor task per room.
+    room_actors: bool,
+    /// Runtime counters exposed through `stats`.
+    counters: Counters,
 }
 
-// Class that implements main publish-subscribe logic, by handling clients and rooms
-impl<S, R> Broadcaster<S, R>
-where
-    S: SinkAdapter + Unpin + Clone,
-    R: Dispatchable + Send,
-{
-    /// Creates a new Broadcaster instance with the given default reducer.
-    pub fn new(default_reducer: R) -> Self {
-        Broadcaster {
-            clients: Arc::new(Mutex::new(HashMap::<u64, Client>::new())),
-            connections: Arc::new(Mutex::new(HashMap::<u64, S>::new())),
-            rooms: Arc::new(Mutex::new(HashMap::<u64, Room<R>>::new())),
-            default_reducer,
+// Class that implements main publish-subscribe logic, by handling clients and rooms
+impl<S, R> Broadcaster<S, R>
+where
+    S: SinkAdapter + Unpin + Clone,
+    R: Dispatchable + Send + 'static,
+{
+    /// Creates a new Broadcaster instance with the given default reducer, cloned for each new room.
+    pub fn new(default_reducer: R) -> Self {
+        Self::new_with_factory(Box::new(move || default_reducer.clone()))
+    }
+
+    /// Creates a new Broadcaster instance constructing the reducer of each new room with `reducer_factory`.
+    pub fn new_with_factory(reducer_factory: ReducerFactory<R>) -> Self {
+        Broadcaster {
+            clients: Arc::new(RwLock::new(HashMap::<u64, Client>::new())),
+            connections: Arc::new(RwLock::new(HashMap::<u64, S>::new())),
+            rooms: Arc::new(RoomShards::new(ROOM_SHARDS)),
+            next_room_id: AtomicU64::new(0),
+            next_correlation_id: AtomicU64::new(0),
+            id_rng: std::sync::Mutex::new(StdRng::from_os_rng()),
+            reducer_factory,
+            join_requires_ack: false,
+            settings: ActionSettings::default(),
+            record_actions: false,
+            history: HistoryConfig::default(),
+            sessions: Mutex::new(HashMap::new()),
+            session_config: SessionConfig::default(),
+            key_index: Mutex::new(HashMap::new()),
+            overview_subscribers: std::sync::Mutex::new(Vec::new()),
+            send_timeout: None,
+            rate_limit: None,
+            max_rate_limit: None,
+            load_shedding: None,
+            pending_actions: PendingActions::default(),
+            max_fanout: None,
+            delete_empty_rooms: false,
+            single_room: None,
+            multi_room: false,
+            keepalive_interval: None,
+            pre_dispatch_hook: None,
+            callbacks: ActionCallbacks::default(),
+            room_actors: false,
+            counters: Counters::default(),
+        }
+    }
+
+    /// Seeds the generator of client IDs, so the same sequence of connections is given
+    /// the same IDs across runs.
+    ///
+    /// Meant for reproducible benchmarks and fuzzing: seeded IDs are predictable, so they
+    /// must not be relied on as secrets. IDs are drawn from an OS-seeded generator by default.
+    pub fn set_id_seed(&mut self, seed: u64) {
+        self.id_rng = std::sync::Mutex::new(StdRng::seed_from_u64(seed));
+    }
+
+    /// returns the ID given to the next connecting client
+    pub(crate) fn next_client_id(&self) -> u64 {
+        self.id_rng.lock().unwrap().random::<u64>()
+    }
+
+    /// Sets whether joining clients must send `Ack` after receiving the initial state
+    /// before they are considered joined and included in room broadcasts.
+    pub fn set_join_requires_ack(&mut self, join_requires_ack: bool) {
+        self.join_requires_ack = join_requires_ack;
+    }
+
+    /// Sets the maximum size of a room state in bytes.
+    ///
+    /// Actions that grow the state beyond this limit are reverted and rejected.
+    pub fn set_max_state_bytes(&mut self, max_state_bytes: Option<usize>) {
+        self.settings.max_state_bytes = max_state_bytes;
+    }
+
+    /// Sets the maximum size in bytes of the `data` returned by the reducer for an action.
+    ///
+    /// Actions returning more data are reverted and not broadcast; their author is sent
+    /// a `ServerError` and the offending action is logged.
+    pub fn set_max_data_bytes(&mut self, max_data_bytes: Option<usize>) {
+        self.settings.max_data_bytes = max_data_bytes;
+    }
+
+    /// Sets whether every applied action is recorded with its author in the per-room action log.
+    pub fn set_record_actions(&mut self, record_actions: bool) {
+        self.record_actions = record_actions;
+    }
+
+    /// Sets the number of action broadcasts kept per room for clients resuming their session.
+    ///
+    /// Resuming clients missing fewer actions are replayed these broadcasts instead of
+    /// being sent the full state. Disabled with `0`, the default.
+    pub fn set_history_size(&mut self, history_size: usize) {
+        self.history.size = history_size;
+    }
+
+    /// Sets the compression applied to the action broadcasts kept per room.
+    ///
+    /// Trades CPU on every applied action and replay for memory held by long histories.
+    /// Defaults to `Compression::None`.
+    pub fn set_history_compression(&mut self, compression: Compression) {
+        self.history.compression = compression;
+    }
+
+    /// Sets the number of room broadcasts queued for each client disconnected from a room
+    /// with a session token, sent to it once it resumes its session with `resume_client`.
+    ///
+    /// Only the latest broadcasts are kept. Disabled with `0`, the default.
+    pub fn set_offline_buffer_size(&mut self, buffer_size: usize) {
+        self.session_config.buffer_size = buffer_size;
+    }
+
+    /// Sets how long after disconnecting a client can resume its session.
+    ///
+    /// Clients are issued a session token on connect only when set, and expired sessions can't
+    /// be resumed and stop queueing broadcasts. Sessions are disabled with `None`, the default.
+    pub fn set_session_grace(&mut self, grace: Option<Duration>) {
+        self.session_config.grace = grace;
+    }
+
+    /// Sets the maximum time a single send may take.
+    ///
+    /// Clients whose sink does not accept a message within this time are removed,
+    /// so one stuck client can't stall broadcasts to the rest of the room.
+    pub fn set_send_timeout(&mut self, send_timeout: Option<Duration>) {
+        self.send_timeout = send_timeout;
+    }
+
+    /// Sets the interval after which idle clients are sent an application-level `Keepalive`.
+    ///
+    /// A client is idle when nothing was broadcast to its room during the last interval,
+    /// or when it is not in a room.
+    pub fn set_keepalive_interval(&mut self, keepalive_interval: Option<Duration>) {
+        self.keepalive_interval = keepalive_interval;
+    }
+
+    /// Sets the per-client action rate limit used in rooms that don't override it.
+    pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.rate_limit = rate_limit;
+    }
+
+    /// Sets the most permissive rate limit room creators may request in `CreateOptions`.
+    ///
+    /// Without a maximum, rooms may not override the default rate limit at all.
+    pub fn set_max_rate_limit(&mut self, max_rate_limit: Option<RateLimit>) {
+        self.max_rate_limit = max_rate_limit;
+    }
+
+    /// Sets the policy shedding low-priority actions while too many actions are pending.
+    pub fn set_load_shedding(&mut self, load_shedding: Option<LoadShedding>) {
+        self.load_shedding = load_shedding;
+    }
+
+    /// Sets the number of room members above which action broadcasts are skipped.
+    ///
+    /// Members of rooms over the cap pull the room state with `Sync` instead.
+    pub fn set_max_fanout(&mut self, max_fanout: Option<usize>) {
+        self.max_fanout = max_fanout;
+    }
+
+    /// Sets the maximum size of a single outgoing JSON-serialized response in bytes.
+    ///
+    /// Actions producing a larger broadcast are reverted and rejected with a `ServerError`,
+    /// and clients can't create or join rooms whose initial state is larger.
+    pub fn set_max_outgoing_bytes(&mut self, max_outgoing_bytes: Option<usize>) {
+        self.settings.max_outgoing_bytes = max_outgoing_bytes;
+    }
+
+    /// Sets whether rooms are deleted as soon as their last client leaves or disconnects.
+    pub fn set_delete_empty_rooms(&mut self, delete_empty_rooms: bool) {
+        self.delete_empty_rooms = delete_empty_rooms;
+    }
+
+    /// Sets the hook run before each action received from a client is dispatched.
+    pub fn with_pre_hook(&mut self, hook: PreDispatchHook) {
+        self.pre_dispatch_hook = Some(hook);
+    }
+
+    /// Sets the hook run after each action received from a client is applied.
+    pub fn with_post_hook(&mut self, hook: PostDispatchHook<R::State>) {
+        self.callbacks.post_dispatch_hook = Some(Arc::new(hook));
+    }
+
+    /// Registers a projection of the room states, selected by name by clients joining with `JoinProjected`.
+    ///
+    /// Rooms created before the projection was registered don't render their actions through it.
+    pub fn add_projection(&mut self, name: String, projection: Projection<R::State>) {
+        self.callbacks
+            .projections
+            .insert(name, Arc::new(projection));
+    }
+
+    /// Sets whether action broadcasts carry a JSON Patch from the previous state instead of the state.
+    pub fn set_state_diffs(&mut self, state_diffs: bool) {
+        self.settings.state_diffs = state_diffs;
+    }
+
+    /// Sets whether `StateSent` and `Action` broadcasts carry the room's sequence number and a timestamp.
+    ///
+    /// The sequence number is the state version of the room, increasing by one with each
+    /// applied action, so clients can detect missed broadcasts and measure their latency.
+    pub fn set_broadcast_stamps(&mut self, stamps: bool) {
+        self.settings.stamps = stamps;
+    }
+
+    /// Sets whether client actions are applied by a dedicated actor task per room.
+    ///
+    /// Each room's actor applies its actions one at a time, without holding the clients lock,
+    /// so actions to different rooms run concurrently. When actions back up, the actor
+    /// serves clients round-robin, so a client flooding the room can't starve the others.
+    pub fn set_room_actors(&mut self, room_actors: bool) {
+        self.room_actors = room_actors;
+    }
+
+    /// Enables single-room mode, creating the shared room every connecting client joins automatically.
+    ///
+    /// The shared room is owned by no client and is never deleted for being empty.
+    /// Returns the ID of the shared room.
+    ///
+    /// # Panics
+    /// * This method panics if the rooms are locked elsewhere.
+    pub fn single_room_mode(&mut self) -> u64 {
+        if let Some(room_id) = self.single_room {
+            return room_id;
+        }
+        let room_id = self.next_room_id.fetch_add(1, Ordering::Relaxed);
+        let room = Room::new(
+            room_id,
+            0,
+            HashSet::new(),
+            RoomStatus::Public,
+            Arc::new(Mutex::new((self.reducer_factory)())),
+        );
+        self.rooms
+            .try_write(room_id)
+            .expect("rooms must not be locked while configuring the broadcaster")
+            .insert(room_id, room);
+        self.single_room = Some(room_id);
+        room_id
+    }
+
+    /// Sets whether clients may be members of several rooms at once.
+    ///
+    /// Clients already in a room then create and join further rooms instead of being rejected,
+    /// and target them with `RoomAction`. The first room joined stays the room of the messages
+    /// not naming one until left; `LeaveRoom` leaves any of the rooms.
+    pub fn set_multi_room(&mut self, multi_room: bool) {
+        self.multi_room = multi_room;
+    }
+
+    /// creates an empty public room owned by no client, managed by the given reducer instance
+    /// instead of a clone of the default reducer, returning the ID of the room
+    pub async fn preload_room(&self, reducer: R) -> u64 {
+        let room_id = self.next_room_id.fetch_add(1, Ordering::Relaxed);
+        let origin = self.record_actions.then(|| reducer.clone());
+        let mut room = Room::new(
+            room_id,
+            0,
+            HashSet::new(),
+            RoomStatus::Public,
+            Arc::new(Mutex::new(reducer)),
+        );
+        room.origin = origin;
+        let mut rooms = self.rooms.write(room_id).await;
+        rooms.insert(room_id, room);
+        self.publish_summary(&rooms, room_id);
+        room_id
+    }
+
+    /// Subscribes to the state of a room, sent right away and then every time it changes.
+    ///
+    /// The subscription ends when the room is deleted.
+    pub(crate) async fn subscribe(
+        &self,
+        room_id: u64,
+    ) -> Result<mpsc::UnboundedReceiver<R::State>, String> {
+        let mut rooms = self.rooms.write(room_id).await;
+        let room = rooms
+            .get_mut(&room_id)
+            .ok_or_else(|| format!("Room {} not found", room_id))?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        // the room lock keeps actions from being recorded until the subscriber is registered
+        let _ = sender.send(room.reducer.lock().await.get_state());
+        room.state_subscribers.push(sender);
+        Ok(receiver)
+    }
+
+    /// Subscribes to the summaries of all rooms, without joining any of them.
+    ///
+    /// The summary of every existing room is sent right away. Then a `RoomSummary` is sent
+    /// whenever a room is created or its members change, and `RoomDeleted` when it is deleted.
+    pub(crate) async fn subscribe_overview(&self) -> mpsc::UnboundedReceiver<Response> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        // the room locks keep summaries from being published until the subscriber is registered
+        let rooms = self.rooms.read_all().await;
+        for room in rooms.iter().flat_map(|shard| shard.values()) {
+            let _ = sender.send(Response::RoomSummary(room.summary()));
+        }
+        self.overview_subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// sends the summary of a room to overview subscribers, or its deletion if it is gone,
+    /// forgetting the subscribers that hung up
+    fn publish_summary(&self, rooms: &HashMap<u64, Room<R>>, room_id: u64) {
+        let mut subscribers = self.overview_subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+        let response = match rooms.get(&room_id) {
+            Some(room) => Response::RoomSummary(room.summary()),
+            None => Response::RoomDeleted(room_id),
+        };
+        subscribers.retain(|subscriber| subscriber.send(response.clone()).is_ok());
+    }
+
+    /// joins a newly connected client to the shared room in single-room mode
+    pub(crate) async fn join_single_room(&self, client_id: u64) -> Result<(), String> {
+        match self.single_room {
+            Some(room_id) => self.insert_client_to_room(client_id, room_id).await,
+            None => Ok(()),
+        }
+    }
+
+    /// removes client from room, deleting the room if it became empty and empty rooms are not kept
+    fn remove_room_member(&self, rooms: &mut HashMap<u64, Room<R>>, room_id: u64, client_id: u64) {
+        if let Some(room) = rooms.get_mut(&room_id) {
+            room.client_ids.remove(&client_id);
+            if self.delete_empty_rooms
+                && room.client_ids.is_empty()
+                && self.single_room != Some(room_id)
+            {
+                rooms.remove(&room_id);
+            }
+            self.publish_summary(rooms, room_id);
+        }
+    }
+
+    /// checks whether serialized response exceeds configured outgoing size limit
+    fn exceeds_outgoing_limit(&self, response: &Response) -> bool {
+        self.settings.exceeds_outgoing_limit(response)
+    }
+
+    /// checks whether reducer state exceeds configured size limit
+    fn exceeds_state_budget(&self, reducer: &R) -> bool {
+        self.settings.exceeds_state_budget(reducer)
+    }
+
+    /// returns the lock applying and recording the actions of a room in order, if the room exists
+    async fn room_sequencer(&self, room_id: u64) -> Option<Arc<Mutex<()>>> {
+        let rooms = self.rooms.read(room_id).await;
+        rooms.get(&room_id).map(|room| room.sequencer.clone())
+    }
+
+    /// returns the actor applying the actions of the room, spawning it on first use
+    fn room_actor(&self, room: &mut Room<R>) -> RoomActor<R> {
+        let room_id = room.id;
+        let reducer = room.reducer.clone();
+        room.actor
+            .get_or_insert_with(|| {
+                let (sender, mut receiver) = mpsc::unbounded_channel::<ActionCommand<R>>();
+                // completing actions are sent back without keeping the actor alive
+                let actor = sender.downgrade();
+                let rooms = Arc::downgrade(&self.rooms);
+                let settings = self.settings;
+                let record_actions = self.record_actions;
+                let history = self.history;
+                let callbacks = self.callbacks.clone();
+
+                tokio::spawn(async move {
+                    let mut backlog = FairQueue::new();
+                    loop {
+                        if backlog.is_empty() {
+                            match receiver.recv().await {
+                                Some(command) => backlog.push(command),
+                                None => break,
+                            }
+                        }
+                        while let Ok(command) = receiver.try_recv() {
+                            backlog.push(command);
+                        }
+                        let Some(command) = backlog.pop() else {
+                            continue;
+                        };
+
+                        let (members, state_version) = match rooms.upgrade() {
+                            Some(rooms) => rooms
+                                .read(room_id)
+                                .await
+                                .get(&room_id)
+                                .map(|room| (room.client_ids.clone(), room.state_version))
+                                .unwrap_or_default(),
+                            None => (HashSet::new(), 0),
+                        };
+                        let ActionCommand {
+                            ctx,
+                            action,
+                            raw_action,
+                            protocol_version,
+                            base_version,
+                            reply,
+                        } = command;
+                        let checked_version = match action {
+                            ActionStep::Begin(_) => base_version,
+                            // the version was checked when the action began
+                            ActionStep::Complete(_) => None,
+                        };
+                        let result =
+                            match check_base_version(ctx.client_id, checked_version, state_version)
+                            {
+                                Ok(()) => {
+                                    settings
+                                        .apply(
+                                            &mut *reducer.lock().await,
+                                            room_id,
+                                            &members,
+                                            &ctx,
+                                            action,
+                                            &callbacks,
+                                        )
+                                        .await
+                                }
+                                Err(conflict) => Err(conflict),
+                            };
+                        let mut result = match result {
+                            Ok(Applied::Done(response)) => Ok(response),
+                            Ok(Applied::Pending(work)) => {
+                                // the actor applies other actions while the work runs
+                                let actor = actor.clone();
+                                tokio::spawn(async move {
+                                    let action = match work.await {
+                                        Ok(action) => action,
+                                        Err(e) => {
+                                            let _ = reply.send(Err(ClientResponse::client_error(
+                                                ctx.client_id,
+                                                ErrorCode::InvalidAction,
+                                                reducer_error_message(&e),
+                                            )));
+                                            return;
+                                        }
+                                    };
+                                    if let Some(actor) = actor.upgrade() {
+                                        let _ = actor.send(ActionCommand {
+                                            ctx,
+                                            action: ActionStep::Complete(action),
+                                            raw_action,
+                                            protocol_version,
+                                            base_version,
+                                            reply,
+                                        });
+                                    }
+                                });
+                                continue;
+                            }
+                            Err(e) => Err(e),
+                        };
+                        if let (Ok(response), Some(rooms)) = (&mut result, rooms.upgrade()) {
+                            record_applied_action(
+                                rooms.write(room_id).await.get_mut(&room_id),
+                                || RecordedAction {
+                                    author: ctx.client_id,
+                                    action: raw_action.clone(),
+                                    protocol_version,
+                                    context: Some(ctx.clone()),
+                                },
+                                response,
+                                record_actions,
+                                history,
+                                settings.stamps,
+                            )
+                            .await;
+                        }
+                        let _ = reply.send(result);
+                    }
+                });
+                sender
+            })
+            .clone()
+    }
+
+    /// Handles the creation of a new room.
+    pub(crate) async fn handle_create(
+        &self,
+        client_id: u64,
+        options: CreateOptions,
+    ) -> Result<RoomResponse, ClientResponse> {
+        let clients = self.clients.read().await;
+        let client = clients.get(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+        self.check_can_create(client)?;
+
+        if let Some(rate_limit) = &options.rate_limit {
+            if !self
+                .max_rate_limit
+                .is_some_and(|max_rate_limit| rate_limit.within(&max_rate_limit))
+            {
+                return Err(ClientResponse::client_error(
+                    client_id,
+                    ErrorCode::InvalidConfig,
+                    "Requested rate limit exceeds server maximum".to_string(),
+                ));
+            }
+        }
+
+        if let Some(room_group) = &options.room_group {
+            self.check_room_group(client, room_group).await?;
+        }
+
+        if options.capacity == Some(0) {
+            return Err(ClientResponse::client_error(
+                client_id,
+                ErrorCode::InvalidConfig,
+                "Room capacity must be at least 1".to_string(),
+            ));
+        }
+        drop(clients);
+
+        // the client entry is only locked for writing to enter the new room
+        let mut clients = self.clients.write().await;
+        let client = clients.get_mut(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+        self.check_can_create(client)?;
+
+        let room_id = self.next_room_id.fetch_add(1, Ordering::Relaxed);
+        let mut rooms = self.rooms.write(room_id).await;
+
+        let mut room_clients = HashSet::<u64>::new();
+        room_clients.insert(client_id);
+        client.enter_room(room_id);
+
+        let status = match options.password {
+            Some(password) => RoomStatus::Private(password),
+            None => RoomStatus::Public,
+        };
+        let mut room = Room::new(
+            room_id,
+            client.id,
+            room_clients,
+            status,
+            Arc::new(Mutex::new((self.reducer_factory)())),
+        );
+        room.rate_limit = options.rate_limit;
+        room.capacity = options.capacity;
+        room.waitlist_enabled = options.waitlist;
+        room.room_group = options.room_group;
+
+        rooms.insert(room_id, room);
+        self.publish_summary(&rooms, room_id);
+
+        Ok(RoomResponse::create_room(room_id))
+    }
+
+    /// rejects a client already in a room, unless in multi-room mode
+    fn check_can_create(&self, client: &Client) -> Result<(), ClientResponse> {
+        if client.room_id.is_some() && !self.multi_room {
+            return Err(ClientResponse::client_error(
+                client.id,
+                ErrorCode::AlreadyInRoom,
+                "Leave current room before creating new".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// rejects a client already in the room, or in any room unless in multi-room mode
+    fn check_can_join(&self, client: &Client, room_id: u64) -> Result<(), ClientResponse> {
+        if client.in_room(room_id) || (client.room_id.is_some() && !self.multi_room) {
+            return Err(ClientResponse::client_error(
+                client.id,
+                ErrorCode::AlreadyInRoom,
+                "Leave current room before joining new".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// rejects a client already in a room of the group, rooms of a group being mutually exclusive
+    async fn check_room_group(
+        &self,
+        client: &Client,
+        room_group: &str,
+    ) -> Result<(), ClientResponse> {
+        for room_id in client.rooms() {
+            let in_group = self
+                .rooms
+                .read(room_id)
+                .await
+                .get(&room_id)
+                .is_some_and(|room| room.room_group.as_deref() == Some(room_group));
+            if in_group {
+                return Err(ClientResponse::client_error(
+                    client.id,
+                    ErrorCode::AlreadyInRoom,
+                    format!("Already in a room of group {}", room_group),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the joining of the room created for `key`, creating it first if there is none.
+    ///
+    /// The key index stays locked until the room is created, so clients racing for the same
+    /// key all end up in a single room.
+    pub(crate) async fn handle_create_or_join(
+        &self,
+        client_id: u64,
+        key: String,
+    ) -> Result<RoomResponse, ClientResponse> {
+        let mut key_index = self.key_index.lock().await;
+        let existing = match key_index.get(&key) {
+            // rooms of deleted keys are replaced, room IDs are never reused
+            Some(room_id) if self.rooms.read(*room_id).await.contains_key(room_id) => {
+                Some(*room_id)
+            }
+            _ => None,
+        };
+
+        match existing {
+            Some(room_id) => self.handle_join(client_id, room_id, None).await,
+            None => {
+                let response = self
+                    .handle_create(client_id, CreateOptions::default())
+                    .await?;
+                key_index.insert(key, response.room);
+                Ok(response)
+            }
+        }
+    }
+
+    /// Handles the joining of an existing room.
+    ///
+    /// Rooms that exist but refuse the client respond with the specific reason
+    /// (`RoomFull`, `WrongPassword`, `Banned` or `InviteRequired`) instead of `NotFound`.
+    pub(crate) async fn handle_join(
+        &self,
+        client_id: u64,
+        room_id: u64,
+        password: Option<&str>,
+    ) -> Result<RoomResponse, ClientResponse> {
+        let clients = self.clients.read().await;
+        let client = clients.get(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+        self.check_can_join(client, room_id)?;
+
+        let room_group = self
+            .rooms
+            .read(room_id)
+            .await
+            .get(&room_id)
+            .and_then(|room| room.room_group.clone());
+        if let Some(room_group) = room_group {
+            self.check_room_group(client, &room_group).await?;
+        }
+        drop(clients);
+
+        // the client entry is only locked for writing to enter the room
+        let mut clients = self.clients.write().await;
+        let client = clients.get_mut(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+        self.check_can_join(client, room_id)?;
+        let mut rooms = self.rooms.write(room_id).await;
+        match rooms.get_mut(&room_id) {
+            None => Err(ClientResponse::not_found(
+                client.id,
+                ErrorCode::RoomNotFound,
+                "Room not found".to_string(),
+            )),
+            Some(room) => {
+                let client_id = client.id;
+                room.check_joinable(client_id, password)
+                    .map_err(|response| match response {
+                        Response::RoomFull(_) if room.waitlist_enabled => {
+                            let position = room.enqueue_waitlist(client_id);
+                            ClientResponse::new(client_id, Response::Waitlisted { position })
+                        }
+                        response => ClientResponse::new(client_id, response),
+                    })?;
+                room.client_ids.insert(client_id);
+                // further rooms of multi-room clients take no acknowledgement
+                if client.enter_room(room.id) && self.join_requires_ack {
+                    client.status = ClientStatus::Joining;
+                }
+                self.publish_summary(&rooms, room_id);
+                Ok(RoomResponse::join_room(room_id, client_id))
+            }
+        }
+    }
+
+    /// handles resume event, rejoining the room the session of `token` was in on disconnect
+    ///
+    /// `token` is the session token issued to the disconnected client, and only resumes the
+    /// session from a connection with the same client token, checked by the authenticator on
+    /// connect. The client is sent what it missed like on `resume_client`, starting from the
+    /// action broadcasts applied after `last_seq` if the room history still keeps them.
+    pub(crate) async fn handle_resume(
+        &self,
+        client_id: u64,
+        token: String,
+        last_seq: u64,
+    ) -> Result<RoomResponse, ClientResponse> {
+        let own_token = self
+            .clients
+            .read()
+            .await
+            .get(&client_id)
+            .map(|client| client.token.clone())
+            .unwrap_or_default();
+        let (room_id, mut session) = self
+            .take_session(&token, |session| {
+                !own_token.is_empty() && session.client.token == own_token
+            })
+            .await
+            .and_then(|session| Some((session.client.room_id?, session)))
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::SessionNotFound,
+                    "Session not found".to_string(),
+                )
+            })?;
+
+        match self
+            .rejoin_session(client_id, room_id, &mut session.queued, Some(last_seq))
+            .await
+        {
+            Ok(()) => Ok(RoomResponse::join_room(room_id, client_id)),
+            Err(response) => {
+                self.sessions.lock().await.insert(token, session);
+                Err(response)
+            }
+        }
+    }
+
+    /// issues a session token to the client, sent to it to resume its session with
+    ///
+    /// Does nothing unless sessions are enabled with a grace period. Session tokens are random
+    /// rather than derived from the client ID seed, so they can't be guessed.
+    pub(crate) async fn open_session(&self, client_id: u64) {
+        if self.session_config.grace.is_none() {
+            return;
+        }
+        let session_token = format!("{:032x}", rand::rng().random::<u128>());
+        match self.clients.write().await.get_mut(&client_id) {
+            Some(client) => client.session_token = session_token.clone(),
+            None => return,
+        }
+        self.react_to_client(client_id, Response::SessionToken(session_token))
+            .await;
+    }
+
+    /// returns the `client_token` the client of the unexpired session of `session_token`
+    /// connected with
+    pub(crate) async fn session_client_token(&self, session_token: &str) -> Option<String> {
+        let sessions = self.sessions.lock().await;
+        let session = sessions.get(session_token)?;
+        if self.session_config.is_expired(session) {
+            return None;
+        }
+        Some(session.client.token.clone())
+    }
+
+    /// takes the unexpired session of `session_token` out of the kept sessions if it matches
+    async fn take_session<F>(&self, session_token: &str, predicate: F) -> Option<Session>
+    where
+        F: FnOnce(&Session) -> bool,
+    {
+        let mut sessions = self.sessions.lock().await;
+        let session = sessions.get(session_token)?;
+        if self.session_config.is_expired(session) || !predicate(session) {
+            return None;
+        }
+        sessions.remove(session_token)
+    }
+
+    /// rejoins a client to the room of its resumed session, sending it what it missed
+    ///
+    /// The client is replayed the action broadcasts applied after `last_seq` if the room history
+    /// still keeps them. Otherwise it is sent the broadcasts queued for the session while it was
+    /// disconnected, then the current state, which already reflects them.
+    async fn rejoin_session(
+        &self,
+        client_id: u64,
+        room_id: u64,
+        queued: &mut VecDeque<Response>,
+        last_seq: Option<u64>,
+    ) -> Result<(), ClientResponse> {
+        let replay = {
+            let mut clients = self.clients.write().await;
+            let client = clients.get_mut(&client_id).ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::ClientNotFound,
+                    "Client not found".to_string(),
+                )
+            })?;
+            if client.room_id.is_some() {
+                return Err(ClientResponse::client_error(
+                    client_id,
+                    ErrorCode::AlreadyInRoom,
+                    "Leave current room before resuming".to_string(),
+                ));
+            }
+
+            let mut rooms = self.rooms.write(room_id).await;
+            let room = rooms.get_mut(&room_id).ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::RoomNotFound,
+                    "Room not found".to_string(),
+                )
+            })?;
+            if let Some(reason) = &room.draining {
+                return Err(ClientResponse::new(
+                    client_id,
+                    Response::RoomDraining(reason.clone()),
+                ));
+            }
+            if room
+                .capacity
+                .is_some_and(|capacity| room.client_ids.len() >= capacity)
+            {
+                return Err(ClientResponse::new(client_id, Response::RoomFull(room_id)));
+            }
+
+            // replays of the history join under the room's bucket, so no action is missed
+            let replay = last_seq
+                .and_then(|last_seq| room.actions_since(last_seq, self.history.compression));
+            if replay.is_some() {
+                room.client_ids.insert(client_id);
+                client.enter_room(room_id);
+                self.publish_summary(&rooms, room_id);
+            }
+            replay
+        };
+
+        match replay {
+            Some(responses) => {
+                for response in responses {
+                    self.react_to_client(client_id, response).await;
+                }
+            }
+            None => {
+                for response in queued.drain(..) {
+                    self.react_to_client(client_id, response).await;
+                }
+                self.insert_client_to_room(client_id, room_id)
+                    .await
+                    .map_err(|e| ClientResponse::server_error(client_id, ErrorCode::Internal, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// resolves the room an action applies to and its reducer
+    ///
+    /// `None` targets the room the client joined, an explicit room must list the client as a member.
+    async fn resolve_action_room(
+        &self,
+        client_id: u64,
+        room_id: Option<u64>,
+    ) -> Result<(u64, Arc<Mutex<R>>), ClientResponse> {
+        let clients = self.clients.read().await;
+        let client = clients.get(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+        let room_id = room_id.or(client.room_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::NotInRoom,
+                "Client not in room".to_string(),
+            )
+        })?;
+
+        let rooms = self.rooms.read(room_id).await;
+        let room = rooms.get(&room_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::RoomNotFound,
+                "Room not found".to_string(),
+            )
+        })?;
+        if !room.client_ids.contains(&client_id) {
+            return Err(ClientResponse::client_error(
+                client_id,
+                ErrorCode::NotInRoom,
+                "Client is not a member of the target room".to_string(),
+            ));
+        }
+        Ok((room_id, room.reducer.clone()))
+    }
+
+    /// handles dispatchable action event
+    ///
+    /// The action applies to `room_id`, or to the room the client joined if `None`.
+    /// `client_token` is the token sent along with the action, passed to the reducer.
+    /// If `base_version` is set, the action is rejected with a `Conflict` response unless
+    /// it matches the state version of the room when the action is applied.
+    pub(crate) async fn handle_action(
+        &self,
+        client_id: u64,
+        room_id: Option<u64>,
+        client_token: &str,
+        raw_action: &str,
+        base_version: Option<u64>,
+        reducer: Arc<Mutex<R>>,
+    ) -> Result<RoomResponse, ClientResponse> {
+        let clients = self.clients.read().await;
+        let client = clients.get(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+
+        let room_id = room_id.or(client.room_id);
+        if room_id.is_none() {
+            return Err(ClientResponse::not_found(
+                client.id,
+                ErrorCode::NotInRoom,
+                "Client not in room".to_string(),
+            ));
+        }
+        let room_id = room_id.unwrap();
+
+        if client.status == ClientStatus::Joining {
+            return Err(ClientResponse::client_error(
+                client.id,
+                ErrorCode::StateNotAcknowledged,
+                "Acknowledge initial state before sending actions".to_string(),
+            ));
+        }
+
+        let rate_limit = {
+            let rooms = self.rooms.read(room_id).await;
+            let room = rooms.get(&room_id);
+            if let Some(reason) = room.and_then(|room| room.draining.clone()) {
+                return Err(ClientResponse::new(
+                    client_id,
+                    Response::RoomDraining(reason),
+                ));
+            }
+            if room.is_some_and(|room| room.muted.contains(&client_id)) {
+                return Err(ClientResponse::new(client_id, Response::Muted(client_id)));
+            }
+            room.and_then(|room| room.rate_limit)
+        }
+        .or(self.rate_limit);
+        if let Some(rate_limit) = rate_limit {
+            if !client.rate_bucket.lock().unwrap().try_acquire(&rate_limit) {
+                return Err(ClientResponse::client_error(
+                    client_id,
+                    ErrorCode::RateLimited,
+                    "rate limited".to_string(),
+                ));
+            }
+        }
+
+        let protocol_version = client.protocol_version;
+        let action = R::deserialize_action(raw_action, protocol_version).map_err(|_| {
+            ClientResponse::server_error(
+                client_id,
+                ErrorCode::InvalidAction,
+                "Invalid action".to_string(),
+            )
+        })?;
+        if self.load_shedding.is_some_and(|load_shedding| {
+            load_shedding.sheds(self.pending_actions.count(), R::priority(&action))
+        }) {
+            return Err(ClientResponse::new(client_id, Response::Overloaded));
+        }
+        let ctx = DispatchContext {
+            client_id,
+            label: client.label.clone(),
+            token: client.token.clone(),
+            client_token: client_token.to_string(),
+            room_id: Some(room_id),
+        };
+        // the client is resolved, actions of different rooms are applied concurrently
+        drop(clients);
+        if let Some(hook) = &self.pre_dispatch_hook {
+            hook(&ctx, raw_action).map_err(|e| {
+                ClientResponse::client_error(client_id, ErrorCode::InvalidAction, e)
+            })?;
+        }
+
+        let response = if self.room_actors {
+            let actor = match self.rooms.write(room_id).await.get_mut(&room_id) {
+                Some(room) => self.room_actor(room),
+                None => {
+                    return Err(ClientResponse::not_found(
+                        client_id,
+                        ErrorCode::RoomNotFound,
+                        "Room not found".to_string(),
+                    ))
+                }
+            };
+
+            let (reply, response) = oneshot::channel();
+            let command = ActionCommand {
+                ctx,
+                action: ActionStep::Begin(action),
+                raw_action: raw_action.to_string(),
+                protocol_version,
+                base_version,
+                reply,
+            };
+            if actor.send(command).is_err() {
+                return Err(ClientResponse::server_error(
+                    client_id,
+                    ErrorCode::Internal,
+                    ROOM_ACTOR_STOPPED.to_string(),
+                ));
+            }
+            response.await.map_err(|_| {
+                ClientResponse::server_error(
+                    client_id,
+                    ErrorCode::Internal,
+                    ROOM_ACTOR_STOPPED.to_string(),
+                )
+            })??
+        } else {
+            // the room's sequencer is held from the version check until the action is recorded,
+            // so actions of the room are versioned in the order they are applied, while its
+            // bucket is only locked to check the version and to record the action
+            let sequencer = self.room_sequencer(room_id).await.ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::RoomNotFound,
+                    "Room not found".to_string(),
+                )
+            })?;
+            let mut sequenced = sequencer.lock().await;
+            let (mut members, state_version) = self
+                .rooms
+                .read(room_id)
+                .await
+                .get(&room_id)
+                .map(|room| (room.client_ids.clone(), room.state_version))
+                .unwrap_or_default();
+            check_base_version(client_id, base_version, state_version)?;
+            let mut applied = self
+                .settings
+                .apply(
+                    &mut *reducer.lock().await,
+                    room_id,
+                    &members,
+                    &ctx,
+                    ActionStep::Begin(action),
+                    &self.callbacks,
+                )
+                .await?;
+            let mut response = loop {
+                match applied {
+                    Applied::Done(response) => break response,
+                    Applied::Pending(work) => {
+                        // actions of other clients are applied while the work runs
+                        drop(sequenced);
+                        let action = work.await.map_err(|e| {
+                            ClientResponse::client_error(
+                                client_id,
+                                ErrorCode::InvalidAction,
+                                reducer_error_message(&e),
+                            )
+                        })?;
+                        sequenced = sequencer.lock().await;
+                        // members may have joined or left while the work ran
+                        members = self
+                            .rooms
+                            .read(room_id)
+                            .await
+                            .get(&room_id)
+                            .map(|room| room.client_ids.clone())
+                            .unwrap_or_default();
+                        applied = self
+                            .settings
+                            .apply(
+                                &mut *reducer.lock().await,
+                                room_id,
+                                &members,
+                                &ctx,
+                                ActionStep::Complete(action),
+                                &self.callbacks,
+                            )
+                            .await?;
+                    }
+                }
+            };
+            record_applied_action(
+                self.rooms.write(room_id).await.get_mut(&room_id),
+                || RecordedAction {
+                    author: client_id,
+                    action: raw_action.to_string(),
+                    protocol_version,
+                    context: Some(ctx),
+                },
+                &mut response,
+                self.record_actions,
+                self.history,
+                self.settings.stamps,
+            )
+            .await;
+            drop(sequenced);
+            response
+        };
+        self.counters.record_action(raw_action.len());
+
+        Ok(response)
+    }
+
+    /// handles initial state acknowledgement event
+    pub(crate) async fn handle_ack(
+        &self,
+        client_id: u64,
+    ) -> Result<ClientResponse, ClientResponse> {
+        let mut clients = self.clients.write().await;
+        let client = clients.get_mut(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+
+        if client.room_id.is_none() || client.status != ClientStatus::Joining {
+            return Err(ClientResponse::client_error(
+                client.id,
+                ErrorCode::InvalidRequest,
+                "Nothing to acknowledge".to_string(),
+            ));
         }
+
+        client.status = ClientStatus::Joined;
+        Ok(ClientResponse::new(
+            client.id,
+            Response::RoomJoined(client.id),
+        ))
     }
 
-    /// Handles the creation of a new room.
-    pub(crate) async fn handle_create(
+    /// handles cursor update event, relayed to the other room members without touching the reducer
+    pub(crate) async fn handle_cursor(
         &self,
         client_id: u64,
+        data: serde_json::Value,
     ) -> Result<RoomResponse, ClientResponse> {
-        let mut clients = self.clients.lock().await;
-        let client = clients
-            .get_mut(&client_id)
-            .ok_or_else(|| ClientResponse::not_found(client_id, "Client not found".to_string()))?;
-
-        if client.room_id.is_some() {
+        let clients = self.clients.read().await;
+        let client = clients.get(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+        let room_id = client.room_id.ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::NotInRoom,
+                "Client not in room".to_string(),
+            )
+        })?;
+        if client.status == ClientStatus::Joining {
             return Err(ClientResponse::client_error(
                 client_id,
-                "Leave current room before creating new".to_string(),
+                ErrorCode::StateNotAcknowledged,
+                "Acknowledge initial state before sending cursor updates".to_string(),
             ));
         }
 
-        let mut rooms = self.rooms.lock().await;
-        let room_id = rooms.len() as u64;
+        Ok(RoomResponse::cursor(room_id, client_id, data))
+    }
 
-        let mut room_clients = HashSet::<u64>::new();
-        room_clients.insert(client_id);
-        client.room_id = Some(room_id);
-
-        let room = Room {
-            id: room_id,
-            owner_id: client.id,
-            client_ids: room_clients,
-            status: RoomStatus::Public,
-            reducer: Arc::new(Mutex::new(self.default_reducer.clone())),
-        };
+    /// handles protocol version, compression and wire format negotiation event
+    ///
+    /// Responses to the client, starting with the `Hello` confirmation, use the negotiated
+    /// compression and wire format. Broadcasts are encoded once per format in use in the room.
+    /// A wire format the client's connection can't switch to is rejected.
+    pub(crate) async fn handle_hello(
+        &self,
+        client_id: u64,
+        version: u32,
+        compression: Compression,
+        format: Option<WireFormat>,
+    ) -> Result<ClientResponse, ClientResponse> {
+        let mut clients = self.clients.write().await;
+        let client = clients.get_mut(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
 
-        rooms.insert(room_id, room);
+        if let Some(connection) = self.connections.write().await.get_mut(&client_id) {
+            if let Some(format) = format {
+                connection.set_wire_format(format);
+                if connection
+                    .wire_format()
+                    .is_some_and(|current| current != format)
+                {
+                    return Err(ClientResponse::client_error(
+                        client_id,
+                        ErrorCode::InvalidRequest,
+                        format!(
+                            "Wire format {:?} is not supported by the connection",
+                            format
+                        ),
+                    ));
+                }
+            }
+            connection.set_compression(compression);
+        }
+        client.protocol_version = version;
+        client.compression = compression;
+        Ok(ClientResponse::new(client.id, Response::Hello(version)))
+    }
 
-        Ok(RoomResponse::create_room(room_id))
+    /// handles room listing event, describing every room without exposing reducers
+    pub(crate) async fn handle_list_rooms(
+        &self,
+        client_id: u64,
+    ) -> Result<ClientResponse, ClientResponse> {
+        let rooms = self.rooms.read_all().await;
+        let mut room_list: Vec<RoomInfo> = rooms
+            .iter()
+            .flat_map(|shard| shard.values())
+            .map(Room::info)
+            .collect();
+        room_list.sort_by_key(|room| room.id);
+        Ok(ClientResponse::new(
+            client_id,
+            Response::RoomList(room_list),
+        ))
     }
 
-    /// Handles the joining of an existing room.
-    pub(crate) async fn handle_join(
+    /// handles direct message event, forwarding the payload to a client of the sender's room
+    pub(crate) async fn handle_direct_message(
         &self,
         client_id: u64,
-        room_id: u64,
-    ) -> Result<RoomResponse, ClientResponse> {
-        let mut clients = self.clients.lock().await;
-        let client = clients
-            .get_mut(&client_id)
-            .ok_or_else(|| ClientResponse::not_found(client_id, "Client not found".to_string()))?;
+        target: u64,
+        payload: String,
+    ) -> Result<ClientResponse, ClientResponse> {
+        let clients = self.clients.read().await;
+        let room_id = clients
+            .get(&client_id)
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::ClientNotFound,
+                    "Client not found".to_string(),
+                )
+            })?
+            .room_id
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::NotInRoom,
+                    "Client not in room".to_string(),
+                )
+            })?;
 
-        if client.room_id.is_some() {
-            return Err(ClientResponse::client_error(
+        if clients.get(&target).and_then(|target| target.room_id) != Some(room_id) {
+            return Err(ClientResponse::not_found(
                 client_id,
-                "Leave current room before joining new".to_string(),
+                ErrorCode::NotInRoom,
+                "Target client not in room".to_string(),
             ));
         }
+        Ok(ClientResponse::new(
+            target,
+            Response::DirectMessage {
+                from: client_id,
+                payload,
+            },
+        ))
+    }
 
-        let mut rooms = self.rooms.lock().await;
-        match rooms.get_mut(&room_id) {
-            None => Err(ClientResponse::not_found(
-                client.id,
+    /// handles presence event, listing the clients in the sender's room
+    pub(crate) async fn handle_presence(
+        &self,
+        client_id: u64,
+    ) -> Result<ClientResponse, ClientResponse> {
+        let clients = self.clients.read().await;
+        let room_id = clients
+            .get(&client_id)
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::ClientNotFound,
+                    "Client not found".to_string(),
+                )
+            })?
+            .room_id
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::NotInRoom,
+                    "Client not in room".to_string(),
+                )
+            })?;
+
+        let rooms = self.rooms.read(room_id).await;
+        let room = rooms.get(&room_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::RoomNotFound,
                 "Room not found".to_string(),
-            )),
-            Some(room) => {
-                let client_id = client.id;
-                room.client_ids.insert(client_id);
-                client.room_id = Some(room.id);
-                Ok(RoomResponse::join_room(room_id, client_id))
+            )
+        })?;
+        let mut presence: Vec<ClientInfo> = room
+            .client_ids
+            .iter()
+            .filter_map(|member_id| clients.get(member_id))
+            .map(|member| ClientInfo {
+                id: member.id,
+                label: member.label.clone(),
+            })
+            .collect();
+        presence.sort_by_key(|member| member.id);
+        Ok(ClientResponse::new(client_id, Response::Presence(presence)))
+    }
+
+    /// handles session info event, describing the client's session to the client
+    ///
+    /// Roles are derived from the client's current room: every member has the `member` role,
+    /// its owner the `owner` role, and muted members the `muted` role.
+    pub(crate) async fn handle_who_am_i(
+        &self,
+        client_id: u64,
+    ) -> Result<ClientResponse, ClientResponse> {
+        let clients = self.clients.read().await;
+        let client = clients.get(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+
+        let mut roles = Vec::new();
+        if let Some(room_id) = client.room_id {
+            if let Some(room) = self.rooms.read(room_id).await.get(&room_id) {
+                roles.push("member".to_string());
+                if room.owner_id == client_id {
+                    roles.push("owner".to_string());
+                }
+                if room.muted.contains(&client_id) {
+                    roles.push("muted".to_string());
+                }
             }
         }
+
+        Ok(ClientResponse::new(
+            client_id,
+            Response::SessionInfo {
+                client_id,
+                room_id: client.room_id,
+                label: client.label.clone(),
+                roles,
+            },
+        ))
     }
 
-    /// handles dispatchable action event
-    pub(crate) async fn handle_action(
+    /// handles state sync event, sending the current state of the client's room to the client
+    pub(crate) async fn handle_sync(
         &self,
         client_id: u64,
-        action: R::Action,
-        reducer: Arc<Mutex<R>>,
-    ) -> Result<RoomResponse, ClientResponse> {
-        let mut clients = self.clients.lock().await;
-        let client = clients
-            .get_mut(&client_id)
-            .ok_or_else(|| ClientResponse::not_found(client_id, "Client not found".to_string()))?;
+    ) -> Result<ClientResponse, ClientResponse> {
+        let clients = self.clients.read().await;
+        let client = clients.get(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+        let room_id = client.room_id.ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::NotInRoom,
+                "Client not in room".to_string(),
+            )
+        })?;
 
-        let room_id = client.room_id;
-        if room_id.is_none() {
+        let mut rooms = self.rooms.write(room_id).await;
+        let room = rooms.get_mut(&room_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::RoomNotFound,
+                "Room not found".to_string(),
+            )
+        })?;
+        let state = self
+            .member_state(room, client.projection.as_deref())
+            .await
+            .map_err(|e| {
+                ClientResponse::server_error(client_id, ErrorCode::Internal, e.to_string())
+            })?;
+        Ok(ClientResponse::new(
+            client_id,
+            Response::StateSent(state, self.state_stamp(room)),
+        ))
+    }
+
+    /// handles join event receiving the room state through a projection
+    ///
+    /// The projection is selected before joining, so clients put on the waitlist are sent
+    /// the projected state once admitted.
+    pub(crate) async fn handle_join_projected(
+        &self,
+        client_id: u64,
+        room_id: u64,
+        projection: String,
+    ) -> Result<RoomResponse, ClientResponse> {
+        if !self.callbacks.projections.contains_key(&projection) {
             return Err(ClientResponse::not_found(
-                client.id,
-                "Client not in room".to_string(),
+                client_id,
+                ErrorCode::ProjectionNotFound,
+                format!("Projection {} not found", projection),
             ));
         }
-        let room_id = room_id.unwrap();
+        {
+            let mut clients = self.clients.write().await;
+            let client = clients.get_mut(&client_id).ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::ClientNotFound,
+                    "Client not found".to_string(),
+                )
+            })?;
+            if client.room_id.is_some() {
+                return Err(ClientResponse::client_error(
+                    client_id,
+                    ErrorCode::AlreadyInRoom,
+                    "Leave current room before joining new".to_string(),
+                ));
+            }
+            client.projection = Some(projection);
+        }
 
-        let mut reducer_guard = reducer.lock().await;
-        match reducer_guard.dispatch(client_id, action).await {
-            Ok(state) => Ok(RoomResponse::action(
-                room_id,
-                serde_json::to_string(&state).unwrap(),
-            )),
-            Err(_) => Err(ClientResponse::not_found(0, "Client not found".to_string())),
+        let result = self.handle_join(client_id, room_id, None).await;
+        if result
+            .as_ref()
+            .is_err_and(|error| !matches!(error.response, Response::Waitlisted { .. }))
+        {
+            if let Some(client) = self.clients.write().await.get_mut(&client_id) {
+                if client.room_id.is_none() {
+                    client.projection = None;
+                }
+            }
         }
+        result
     }
 
     /// handles user leave event
@@ -156,33 +2038,401 @@ where
         &self,
         client_id: u64,
     ) -> Result<RoomResponse, ClientResponse> {
-        let mut clients = self.clients.lock().await;
-        let client = clients
-            .get_mut(&client_id)
-            .ok_or_else(|| ClientResponse::not_found(client_id, "Client not found".to_string()))?;
+        self.leave_room(client_id, None).await
+    }
 
-        let room_id = client.room_id;
-        if room_id.is_none() {
+    /// handles leave event of a given room the client is a member of, in multi-room mode
+    pub(crate) async fn handle_leave_room(
+        &self,
+        client_id: u64,
+        room_id: u64,
+    ) -> Result<RoomResponse, ClientResponse> {
+        self.leave_room(client_id, Some(room_id)).await
+    }
+
+    /// removes a client from a room it is a member of, or from its current room if `None`
+    async fn leave_room(
+        &self,
+        client_id: u64,
+        room_id: Option<u64>,
+    ) -> Result<RoomResponse, ClientResponse> {
+        let mut clients = self.clients.write().await;
+        let client = clients.get_mut(&client_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::ClientNotFound,
+                "Client not found".to_string(),
+            )
+        })?;
+
+        let room_id = room_id
+            .or(client.room_id)
+            .filter(|room_id| client.in_room(*room_id))
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::NotInRoom,
+                    "Client not in room".to_string(),
+                )
+            })?;
+
+        let mut rooms = self.rooms.write(room_id).await;
+        if !rooms.contains_key(&room_id) {
             return Err(ClientResponse::not_found(
                 client.id,
-                "Client not in room".to_string(),
+                ErrorCode::RoomNotFound,
+                "Room not found".to_string(),
             ));
         }
-        let room_id = room_id.unwrap();
 
-        let mut rooms = self.rooms.lock().await;
-        let room = rooms.get_mut(&room_id);
-        if room.is_none() {
-            return Err(ClientResponse::not_found(
-                client.id,
+        self.remove_room_member(&mut rooms, room_id, client.id);
+        client.exit_room(room_id);
+        Ok(RoomResponse::leave_room(room_id, client.id))
+    }
+
+    /// handles room deletion event, only allowed to the room owner
+    pub(crate) async fn handle_delete(
+        &self,
+        client_id: u64,
+    ) -> Result<ClientsResponse, ClientResponse> {
+        let mut clients = self.clients.write().await;
+        let room_id = clients
+            .get(&client_id)
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::ClientNotFound,
+                    "Client not found".to_string(),
+                )
+            })?
+            .room_id
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    client_id,
+                    ErrorCode::NotInRoom,
+                    "Client not in room".to_string(),
+                )
+            })?;
+
+        let mut rooms = self.rooms.write(room_id).await;
+        let room = rooms.get(&room_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                client_id,
+                ErrorCode::RoomNotFound,
                 "Room not found".to_string(),
+            )
+        })?;
+        if room.owner_id != client_id {
+            return Err(ClientResponse::client_error(
+                client_id,
+                ErrorCode::Unauthorized,
+                "Only the room owner can delete the room".to_string(),
             ));
         }
-        let room = room.unwrap();
 
-        room.client_ids.remove(&client.id);
-        client.room_id = None;
-        Ok(RoomResponse::leave_room(room_id, client.id))
+        let members = Self::remove_room(&mut clients, &mut rooms, room_id);
+        self.publish_summary(&rooms, room_id);
+        Ok(ClientsResponse::delete_room(room_id, members))
+    }
+
+    /// removes room and clears room of its members, returning the former members
+    fn remove_room(
+        clients: &mut HashMap<u64, Client>,
+        rooms: &mut HashMap<u64, Room<R>>,
+        room_id: u64,
+    ) -> Vec<u64> {
+        let Some(room) = rooms.remove(&room_id) else {
+            return Vec::new();
+        };
+        let members: Vec<u64> = room.client_ids.into_iter().collect();
+        for member_id in &members {
+            if let Some(member) = clients.get_mut(member_id) {
+                member.exit_room(room_id);
+            }
+        }
+        members
+    }
+
+    /// drains room: rejects actions and joins, notifies members, then waits for grace period
+    ///
+    /// After the grace period the room is deleted if `delete` is set, otherwise reopened.
+    pub(crate) async fn drain_room(
+        &self,
+        room_id: u64,
+        grace: Duration,
+        reason: &str,
+        delete: bool,
+    ) -> Result<(), String> {
+        {
+            let mut rooms = self.rooms.write(room_id).await;
+            let room = rooms
+                .get_mut(&room_id)
+                .ok_or_else(|| format!("Room {} not found", room_id))?;
+            if room.draining.is_some() {
+                return Err(format!("Room {} is already draining", room_id));
+            }
+            room.draining = Some(reason.to_string());
+        }
+        self.react_on_message(room_id, Response::RoomDraining(reason.to_string()))
+            .await;
+
+        tokio::time::sleep(grace).await;
+
+        if delete {
+            let members = {
+                let mut clients = self.clients.write().await;
+                let mut rooms = self.rooms.write(room_id).await;
+                let members = Self::remove_room(&mut clients, &mut rooms, room_id);
+                self.publish_summary(&rooms, room_id);
+                members
+            };
+            self.react_to_clients(&members, Response::RoomDeleted(room_id))
+                .await;
+        } else if let Some(room) = self.rooms.write(room_id).await.get_mut(&room_id) {
+            room.draining = None;
+        }
+        Ok(())
+    }
+
+    /// kicks target client out of the room owned by `owner_id`
+    ///
+    /// The kicked client and the remaining members receive `RoomLeft` with the target ID.
+    pub(crate) async fn kick_client(
+        &self,
+        owner_id: u64,
+        target_id: u64,
+    ) -> Result<(), ClientResponse> {
+        let room_id = {
+            let mut clients = self.clients.write().await;
+            let room_id = clients
+                .get(&owner_id)
+                .ok_or_else(|| {
+                    ClientResponse::not_found(
+                        owner_id,
+                        ErrorCode::ClientNotFound,
+                        "Client not found".to_string(),
+                    )
+                })?
+                .room_id
+                .ok_or_else(|| {
+                    ClientResponse::not_found(
+                        owner_id,
+                        ErrorCode::NotInRoom,
+                        "Client not in room".to_string(),
+                    )
+                })?;
+
+            let mut rooms = self.rooms.write(room_id).await;
+            let room = rooms.get_mut(&room_id).ok_or_else(|| {
+                ClientResponse::not_found(
+                    owner_id,
+                    ErrorCode::RoomNotFound,
+                    "Room not found".to_string(),
+                )
+            })?;
+            if room.owner_id != owner_id {
+                return Err(ClientResponse::client_error(
+                    owner_id,
+                    ErrorCode::Unauthorized,
+                    "Only the room owner can kick clients".to_string(),
+                ));
+            }
+            if target_id == owner_id {
+                return Err(ClientResponse::client_error(
+                    owner_id,
+                    ErrorCode::InvalidRequest,
+                    "Room owner cannot kick themselves".to_string(),
+                ));
+            }
+
+            let target = clients
+                .get_mut(&target_id)
+                .filter(|target| target.in_room(room_id))
+                .ok_or_else(|| {
+                    ClientResponse::client_error(
+                        owner_id,
+                        ErrorCode::NotInRoom,
+                        "Target client is not in your room".to_string(),
+                    )
+                })?;
+            room.client_ids.remove(&target_id);
+            target.exit_room(room_id);
+            self.publish_summary(&rooms, room_id);
+            room_id
+        };
+
+        self.react_to_client(target_id, Response::RoomLeft(target_id))
+            .await;
+        self.react_on_message(room_id, Response::RoomLeft(target_id))
+            .await;
+        self.admit_waitlisted(room_id).await;
+        Ok(())
+    }
+
+    /// mutes or unmutes target client in the room owned by `owner_id`
+    ///
+    /// Muted clients keep receiving broadcasts, but their actions are rejected.
+    pub(crate) async fn handle_mute(
+        &self,
+        owner_id: u64,
+        target_id: u64,
+        muted: bool,
+    ) -> Result<RoomResponse, ClientResponse> {
+        let clients = self.clients.read().await;
+        let room_id = clients
+            .get(&owner_id)
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    owner_id,
+                    ErrorCode::ClientNotFound,
+                    "Client not found".to_string(),
+                )
+            })?
+            .room_id
+            .ok_or_else(|| {
+                ClientResponse::not_found(
+                    owner_id,
+                    ErrorCode::NotInRoom,
+                    "Client not in room".to_string(),
+                )
+            })?;
+
+        let mut rooms = self.rooms.write(room_id).await;
+        let room = rooms.get_mut(&room_id).ok_or_else(|| {
+            ClientResponse::not_found(
+                owner_id,
+                ErrorCode::RoomNotFound,
+                "Room not found".to_string(),
+            )
+        })?;
+        if room.owner_id != owner_id {
+            return Err(ClientResponse::client_error(
+                owner_id,
+                ErrorCode::Unauthorized,
+                "Only the room owner can mute clients".to_string(),
+            ));
+        }
+        if target_id == owner_id {
+            return Err(ClientResponse::client_error(
+                owner_id,
+                ErrorCode::InvalidRequest,
+                "Room owner cannot mute themselves".to_string(),
+            ));
+        }
+        if !room.client_ids.contains(&target_id) {
+            return Err(ClientResponse::client_error(
+                owner_id,
+                ErrorCode::NotInRoom,
+                "Target client is not in your room".to_string(),
+            ));
+        }
+
+        if muted {
+            room.muted.insert(target_id);
+        } else {
+            room.muted.remove(&target_id);
+        }
+        Ok(RoomResponse::mute(room_id, target_id, muted))
+    }
+
+    /// replaces the configuration of the room owned by `owner_id`
+    ///
+    /// The config is validated like `CreateOptions`. Shrinking the capacity below the number
+    /// of clients currently in the room is rejected, nobody is evicted; growing it admits
+    /// waitlisted clients into the new slots.
+    pub(crate) async fn handle_update_room_config(
+        &self,
+        owner_id: u64,
+        config: RoomConfig,
+    ) -> Result<RoomResponse, ClientResponse> {
+        if let Some(rate_limit) = &config.rate_limit {
+            if !self
+                .max_rate_limit
+                .is_some_and(|max_rate_limit| rate_limit.within(&max_rate_limit))
+            {
+                return Err(ClientResponse::client_error(
+                    owner_id,
+                    ErrorCode::InvalidConfig,
+                    "Requested rate limit exceeds server maximum".to_string(),
+                ));
+            }
+        }
+
+        if config.capacity == Some(0) {
+            return Err(ClientResponse::client_error(
+                owner_id,
+                ErrorCode::InvalidConfig,
+                "Room capacity must be at least 1".to_string(),
+            ));
+        }
+
+        let (room_id, info) = {
+            let clients = self.clients.read().await;
+            let room_id = clients
+                .get(&owner_id)
+                .ok_or_else(|| {
+                    ClientResponse::not_found(
+                        owner_id,
+                        ErrorCode::ClientNotFound,
+                        "Client not found".to_string(),
+                    )
+                })?
+                .room_id
+                .ok_or_else(|| {
+                    ClientResponse::not_found(
+                        owner_id,
+                        ErrorCode::NotInRoom,
+                        "Client not in room".to_string(),
+                    )
+                })?;
+
+            let mut rooms = self.rooms.write(room_id).await;
+            let room = rooms.get_mut(&room_id).ok_or_else(|| {
+                ClientResponse::not_found(
+                    owner_id,
+                    ErrorCode::RoomNotFound,
+                    "Room not found".to_string(),
+                )
+            })?;
+            if room.owner_id != owner_id {
+                return Err(ClientResponse::client_error(
+                    owner_id,
+                    ErrorCode::Unauthorized,
+                    "Only the room owner can update the room config".to_string(),
+                ));
+            }
+            if let Some(capacity) = config.capacity {
+                if capacity < room.client_ids.len() {
+                    return Err(ClientResponse::client_error(
+                        owner_id,
+                        ErrorCode::InvalidConfig,
+                        format!(
+                            "Room capacity cannot be below its {} current clients",
+                            room.client_ids.len()
+                        ),
+                    ));
+                }
+            }
+
+            let info = RoomConfigInfo {
+                name: config.name.clone(),
+                capacity: config.capacity,
+                rate_limit: config.rate_limit,
+                private: config.password.is_some(),
+            };
+            room.name = config.name;
+            room.capacity = config.capacity;
+            room.rate_limit = config.rate_limit;
+            room.status = match config.password {
+                Some(password) => RoomStatus::Private(password),
+                None => RoomStatus::Public,
+            };
+            self.publish_summary(&rooms, room_id);
+            (room_id, info)
+        };
+
+        self.admit_waitlisted(room_id).await;
+        Ok(RoomResponse::config_changed(room_id, info))
     }
 
     /// processes abstract event
@@ -195,89 +2445,286 @@ where
         &self,
         client_id: u64,
         event: JointMessage,
-    ) -> Result<RoomResponse, ClientResponse> {
-        {
-            let clients = self.clients.lock().await;
-            let client_exists = clients.contains_key(&client_id);
-            if !client_exists {
+    ) -> Result<EventResponse, ClientResponse> {
+        if let Some(version) = event.v.filter(|version| *version != SCHEMA_VERSION) {
+            return Err(ClientResponse::client_error(
+                client_id,
+                ErrorCode::UnsupportedVersion,
+                format!(
+                    "Unsupported schema version {}, expected {}",
+                    version, SCHEMA_VERSION
+                ),
+            ));
+        }
+
+        // actions count as pending from the moment they are received, queue included
+        let _pending = matches!(
+            event.message,
+            JointMessageMethod::Action(_) | JointMessageMethod::RoomAction { .. }
+        )
+        .then(|| self.pending_actions.enter());
+
+        // the first token a client sends identifies it for the rest of the connection,
+        // the registry is only locked for writing until it is stored
+        let has_token = match self.clients.read().await.get(&client_id) {
+            Some(client) => !client.token.is_empty(),
+            None => {
                 return Err(ClientResponse::not_found(
                     client_id,
+                    ErrorCode::ClientNotFound,
                     "Client not found".to_string(),
-                ));
+                ))
+            }
+        };
+        if !has_token {
+            if let Some(client) = self.clients.write().await.get_mut(&client_id) {
+                if client.token.is_empty() {
+                    client.token = event.client_token.clone();
+                }
             }
         }
 
         match event.message {
-            JointMessageMethod::Create => {
-                let result = self.handle_create(client_id).await;
-                if let Ok(room_response) = &result {
-                    let _ = self
-                        .insert_client_to_room(client_id, room_response.room)
-                        .await;
-                }
-                result
+            JointMessageMethod::Create(options) => {
+                let result = self
+                    .handle_create(client_id, options.unwrap_or_default())
+                    .await;
+                self.send_initial_state(client_id, result).await
+            }
+            JointMessageMethod::Join(room_id) => {
+                let result = self.handle_join(client_id, room_id, None).await;
+                self.send_initial_state(client_id, result).await
+            }
+            JointMessageMethod::CreateOrJoin(key) => {
+                let result = self.handle_create_or_join(client_id, key).await;
+                self.send_initial_state(client_id, result).await
+            }
+            JointMessageMethod::JoinWithPassword { room_id, password } => {
+                let result = self.handle_join(client_id, room_id, Some(&password)).await;
+                self.send_initial_state(client_id, result).await
+            }
+            JointMessageMethod::JoinProjected {
+                room_id,
+                projection,
+            } => {
+                let result = self
+                    .handle_join_projected(client_id, room_id, projection)
+                    .await;
+                self.send_initial_state(client_id, result).await
+            }
+            JointMessageMethod::Action(raw_action) => {
+                let (room_id, reducer_arc) = self.resolve_action_room(client_id, None).await?;
+
+                self.handle_action(
+                    client_id,
+                    Some(room_id),
+                    &event.client_token,
+                    &raw_action,
+                    None,
+                    reducer_arc,
+                )
+                .await
+                .map(EventResponse::from)
+            }
+            JointMessageMethod::RoomAction {
+                room_id,
+                payload,
+                base_version,
+            } => {
+                let (room_id, reducer_arc) = self.resolve_action_room(client_id, room_id).await?;
+
+                self.handle_action(
+                    client_id,
+                    Some(room_id),
+                    &event.client_token,
+                    &payload,
+                    base_version,
+                    reducer_arc,
+                )
+                .await
+                .map(EventResponse::from)
             }
-            JointMessageMethod::Join(room_id) => {
-                let result = self.handle_join(client_id, room_id).await;
-                if let Ok(room_response) = &result {
-                    let _ = self
-                        .insert_client_to_room(client_id, room_response.room)
-                        .await;
-                }
-                result
+            JointMessageMethod::Ack => self.handle_ack(client_id).await.map(EventResponse::from),
+            JointMessageMethod::Cursor(data) => self
+                .handle_cursor(client_id, data)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::Hello {
+                version,
+                compression,
+                format,
+            } => self
+                .handle_hello(client_id, version, compression, format)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::DirectMessage { target, payload } => self
+                .handle_direct_message(client_id, target, payload)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::Mute(target_id) => self
+                .handle_mute(client_id, target_id, true)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::Unmute(target_id) => self
+                .handle_mute(client_id, target_id, false)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::UpdateRoomConfig(config) => self
+                .handle_update_room_config(client_id, config)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::Resume { token, last_seq } => self
+                .handle_resume(client_id, token, last_seq)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::Presence => self
+                .handle_presence(client_id)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::WhoAmI => self
+                .handle_who_am_i(client_id)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::Sync => self.handle_sync(client_id).await.map(EventResponse::from),
+            JointMessageMethod::ListRooms => self
+                .handle_list_rooms(client_id)
+                .await
+                .map(EventResponse::from),
+            JointMessageMethod::Leave => self.leave_event(client_id, None).await,
+            JointMessageMethod::LeaveRoom(room_id) => {
+                self.leave_event(client_id, Some(room_id)).await
             }
-            JointMessageMethod::Action(raw_action) => {
-                let action: R::Action = serde_json::from_str(&raw_action).map_err(|_| {
-                    ClientResponse::server_error(client_id, "Invalid action".to_string())
-                })?;
+            JointMessageMethod::Delete => {
+                self.handle_delete(client_id).await.map(EventResponse::from)
+            }
+        }
+    }
 
-                let reducer_arc = {
-                    let clients = self.clients.lock().await;
-                    let client = clients.get(&client_id).ok_or_else(|| {
-                        ClientResponse::not_found(client_id, "Client not found".to_string())
-                    })?;
-                    let room_id = client.room_id.ok_or_else(|| {
-                        ClientResponse::not_found(client_id, "Client not in room".to_string())
-                    })?;
+    /// handles leave events, notifying the room left
+    async fn leave_event(
+        &self,
+        client_id: u64,
+        room_id: Option<u64>,
+    ) -> Result<EventResponse, ClientResponse> {
+        let room_response = match room_id {
+            Some(room_id) => self.handle_leave_room(client_id, room_id).await?,
+            None => self.handle_leave(client_id).await?,
+        };
+        let room_id = room_response.room;
+        if self.rooms.read(room_id).await.contains_key(&room_id) {
+            Ok(EventResponse::from(room_response))
+        } else {
+            // nobody is left to notify in a deleted room, confirm to the leaving client
+            Ok(EventResponse::from(ClientResponse::new(
+                client_id,
+                room_response.response,
+            )))
+        }
+    }
 
-                    let rooms = self.rooms.lock().await;
-                    let room = rooms.get(&room_id).ok_or_else(|| {
-                        ClientResponse::not_found(client_id, "Room not found".to_string())
-                    })?;
-                    room.reducer.clone()
-                };
+    /// broadcasts response state to all clients in room, returning the number of recipients,
+    /// or `None` if the broadcast was skipped because of the fanout cap
+    ///
+    /// # Arguments
+    /// * `room_id` - The ID of the room to which the response should be sent.
+    /// * `response` - The response to be sent to the clients.
+    ///
+    pub(crate) async fn react_on_message(&self, room_id: u64, response: Response) -> Option<usize> {
+        self.react_on_room_response(RoomResponse::new(room_id, response))
+            .await
+    }
 
-                self.handle_action(client_id, action, reducer_arc).await
-            }
-            JointMessageMethod::Leave => self.handle_leave(client_id).await,
-        }
+    /// broadcasts a room response to all clients in its room except the skipped ones,
+    /// returning the number of recipients, or `None` if the broadcast was capped
+    ///
+    /// Members that joined with a projection are sent the response rendered through it, if any.
+    pub(crate) async fn react_on_room_response(
+        &self,
+        room_response: RoomResponse,
+    ) -> Option<usize> {
+        let RoomResponse {
+            room,
+            response,
+            skip_clients,
+            projected,
+        } = room_response;
+        self.react_on_message_filtered(room, response, &projected, |client| {
+            !skip_clients.contains(&client.id)
+        })
+        .await
     }
 
-    /// broadcasts response state to all clients in room
+    /// broadcasts response state to the clients in room matching the predicate,
+    /// returning the number of recipients
     ///
+    /// Recipients whose send failed, or whose connection turned out to be dead, are not counted.
+    /// Actions are not broadcast at all in rooms over the fanout cap, which returns `None`
+    /// rather than zero recipients.
     /// # Arguments
     /// * `room_id` - The ID of the room to which the response should be sent.
     /// * `response` - The response to be sent to the clients.
+    /// * `projected` - The response rendered through each projection, sent instead to the
+    ///   members that joined with it.
+    /// * `predicate` - Selects the room members receiving the response.
     ///
-    pub(crate) async fn react_on_message(&self, room_id: u64, response: Response) {
-        let client_connections_to_send: Vec<(u64, S)> = {
-            let clients = self.clients.lock().await;
-            let rooms = self.rooms.lock().await;
-            let connections = self.connections.lock().await;
+    pub(crate) async fn react_on_message_filtered<F>(
+        &self,
+        room_id: u64,
+        response: Response,
+        projected: &HashMap<String, Response>,
+        predicate: F,
+    ) -> Option<usize>
+    where
+        F: Fn(&Client) -> bool,
+    {
+        let is_action = matches!(response, Response::Action(..));
+        let client_connections_to_send: Vec<(u64, S, Option<String>)> = {
+            let clients = self.clients.read().await;
+            let rooms = self.rooms.read(room_id).await;
+            let connections = self.connections.read().await;
 
             let room = match rooms.get(&room_id) {
                 Some(r) => r,
                 None => {
                     eprintln!("Warning: Trying to react in non-existent room {}", room_id);
-                    return;
+                    return Some(0);
                 }
             };
 
+            if self.exceeds_outgoing_limit(&response) {
+                eprintln!(
+                    "Warning: Dropping broadcast exceeding outgoing size limit in room {}",
+                    room_id
+                );
+                return Some(0);
+            }
+            if is_action
+                && self
+                    .max_fanout
+                    .is_some_and(|max_fanout| room.client_ids.len() > max_fanout)
+            {
+                // the room is too large to push actions to, members pull the state with Sync
+                self.counters.record_capped_broadcast();
+                return None;
+            }
+            *room.last_broadcast.lock().unwrap() = Instant::now();
+
             let mut connections_to_send = Vec::new();
             for client_id in room.client_ids.iter() {
-                if clients.contains_key(client_id) {
+                if let Some(client) = clients.get(client_id) {
+                    // the acknowledgement and projection of a client concern its first room
+                    let in_first_room = client.room_id == Some(room_id);
+                    if (in_first_room && client.status == ClientStatus::Joining)
+                        || !predicate(client)
+                    {
+                        continue;
+                    }
                     if let Some(connection) = connections.get(client_id) {
-                        connections_to_send.push((*client_id, connection.clone()));
+                        let projection = client
+                            .projection
+                            .clone()
+                            .filter(|name| in_first_room && projected.contains_key(name));
+                        connections_to_send.push((*client_id, connection.clone(), projection));
                     } else {
                         eprintln!(
                             "Warning: Connection not found for client {} in room {}",
@@ -291,27 +2738,127 @@ where
                     );
                 }
             }
+            if self.session_config.buffer_size > 0 {
+                self.queue_for_sessions(room_id, &response, &predicate)
+                    .await;
+            }
             connections_to_send
         };
 
-        for (_, mut connection) in client_connections_to_send {
-            if let Err(_) = connection.send(response.clone()).await {}
+        let mut recipients = 0;
+        let mut dead_clients = Vec::new();
+        // encodings of the response and of each projected rendering
+        let mut encoded: HashMap<Option<String>, Vec<_>> = HashMap::new();
+        for (client_id, mut connection, projection) in client_connections_to_send {
+            let response = match &projection {
+                Some(name) => &projected[name],
+                None => &response,
+            };
+            let encoded = encoded.entry(projection).or_default();
+            let payload = connection
+                .wire_format()
+                .and_then(|format| encoded_response(encoded, format, response));
+            let result = match payload {
+                Some(payload) => self.send_with_timeout(connection.send_raw(payload)).await,
+                None => {
+                    self.send_with_timeout(connection.send(response.clone()))
+                        .await
+                }
+            };
+            match result {
+                Err(SendError::TimedOut) => {
+                    eprintln!(
+                        "Warning: Sending to client {} in room {} timed out, removing client",
+                        client_id, room_id
+                    );
+                    dead_clients.push(client_id);
+                }
+                Err(SendError::Closed) => {
+                    eprintln!(
+                        "Warning: Connection of client {} in room {} is closed, removing client",
+                        client_id, room_id
+                    );
+                    dead_clients.push(client_id);
+                }
+                Err(SendError::Failed(e)) => {
+                    eprintln!(
+                        "Warning: Sending to client {} in room {} failed: {}",
+                        client_id, room_id, e
+                    );
+                }
+                Ok(()) => recipients += 1,
+            }
+        }
+
+        if is_action {
+            self.counters.record_fanout(recipients);
+        }
+        for client_id in dead_clients {
+            self.remove_client_connection(client_id).await;
+        }
+        Some(recipients)
+    }
+
+    /// awaits a send to a connection, respecting configured send timeout
+    async fn send_with_timeout<F>(&self, send: F) -> Result<(), SendError>
+    where
+        F: Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>>,
+    {
+        let result = match self.send_timeout {
+            Some(send_timeout) => match tokio::time::timeout(send_timeout, send).await {
+                Ok(result) => result.map_err(SendError::from_sink),
+                Err(_) => Err(SendError::TimedOut),
+            },
+            None => send.await.map_err(SendError::from_sink),
+        };
+        if result.is_err() {
+            self.counters.record_failed_send();
+        }
+        result
+    }
+
+    /// sends response to each of the given clients
+    pub(crate) async fn react_to_clients(&self, client_ids: &[u64], response: Response) {
+        for client_id in client_ids {
+            self.react_to_client(*client_id, response.clone()).await;
         }
     }
 
     /// sends error message to client
     pub(crate) async fn react_with_error(&self, client_id: u64, error: Response) {
+        self.react_to_client(client_id, error).await
+    }
+
+    /// sends response to a single client
+    pub(crate) async fn react_to_client(&self, client_id: u64, response: Response) {
         let connection_to_send: Option<S> = {
-            let connections = self.connections.lock().await;
+            let connections = self.connections.read().await;
             connections.get(&client_id).cloned()
         };
 
         if let Some(mut sender) = connection_to_send {
-            if let Err(e) = sender.send(error).await {
-                eprintln!(
-                    "Error sending error message to client {}: {}. Consider removing client.",
-                    client_id, e
-                );
+            match self.send_with_timeout(sender.send(response)).await {
+                Ok(()) => {}
+                Err(SendError::Failed(e)) => {
+                    eprintln!(
+                        "Error sending message to client {}: {}. Consider removing client.",
+                        client_id, e
+                    );
+                }
+                Err(SendError::TimedOut) => {
+                    eprintln!(
+                        "Warning: Sending to client {} timed out, removing client",
+                        client_id
+                    );
+                    self.remove_client_connection(client_id).await;
+                }
+                Err(SendError::Closed) => {
+                    eprintln!(
+                        "Warning: Connection of client {} is closed, removing client",
+                        client_id
+                    );
+                    self.remove_client_connection(client_id).await;
+                }
             }
         }
     }
@@ -326,93 +2873,624 @@ where
     where
         C: StreamAdapter + Unpin,
     {
-        while let Ok(event) = rx.next().await {
-            let response = self.process_event(client_id, event).await;
+        let Some(keepalive_interval) = self.keepalive_interval else {
+            while let Ok(event) = rx.next().await {
+                self.handle_event(client_id, event).await;
+            }
+            return;
+        };
 
-            match response {
-                Ok(room_response) => {
-                    self.react_on_message(room_response.room, room_response.response)
-                        .await
-                }
-                Err(error_response) => {
-                    self.react_with_error(error_response.client, error_response.response)
-                        .await
+        let mut keepalive = tokio::time::interval_at(
+            tokio::time::Instant::now() + keepalive_interval,
+            keepalive_interval,
+        );
+        loop {
+            tokio::select! {
+                event = rx.next() => match event {
+                    Ok(event) => self.handle_event(client_id, event).await,
+                    Err(_) => break,
+                },
+                _ = keepalive.tick() => self.send_keepalive(client_id, keepalive_interval).await,
+            }
+        }
+    }
+
+    /// sends a keepalive to the client if nothing was broadcast to its room for `idle_for`
+    async fn send_keepalive(&self, client_id: u64, idle_for: Duration) {
+        let idle = {
+            let clients = self.clients.read().await;
+            match clients.get(&client_id).and_then(|client| client.room_id) {
+                Some(room_id) => self
+                    .rooms
+                    .read(room_id)
+                    .await
+                    .get(&room_id)
+                    .is_none_or(|room| room.last_broadcast.lock().unwrap().elapsed() >= idle_for),
+                None => true,
+            }
+        };
+        if idle {
+            self.react_to_client(client_id, Response::Keepalive).await;
+        }
+    }
+
+    /// processes a single event and delivers the response to its recipients
+    pub(crate) async fn handle_event(&self, client_id: u64, event: JointMessage) {
+        let result = self.process_event(client_id, event).await;
+        let left_room = match &result {
+            Ok(EventResponse::Room(room_response))
+                if matches!(room_response.response, Response::RoomLeft(_)) =>
+            {
+                Some(room_response.room)
+            }
+            _ => None,
+        };
+        self.deliver(client_id, result).await;
+        if let Some(room_id) = left_room {
+            self.admit_waitlisted(room_id).await;
+        }
+    }
+
+    /// delivers the response to an event to its recipients
+    async fn deliver(&self, client_id: u64, result: Result<EventResponse, ClientResponse>) {
+        match result {
+            Ok(EventResponse::Room(room_response)) => {
+                let is_action = matches!(room_response.response, Response::Action(..));
+                let room_id = room_response.room;
+                let recipients = self.react_on_room_response(room_response).await;
+                // capped broadcasts are skipped on purpose, members pull the state instead
+                if is_action && recipients == Some(0) {
+                    eprintln!(
+                        "Debug: Action of client {} in room {} was broadcast to no clients",
+                        client_id, room_id
+                    );
                 }
             }
+            Ok(EventResponse::Client(client_response)) => {
+                self.react_to_client(client_response.client, client_response.response)
+                    .await
+            }
+            Ok(EventResponse::Clients(clients_response)) => {
+                self.react_to_clients(&clients_response.clients, clients_response.response)
+                    .await
+            }
+            Err(error_response) => {
+                self.react_with_error(error_response.client, error_response.response)
+                    .await
+            }
         }
     }
 
     /// adds a new client connection
     pub async fn add_client_connection(&self, client: Client, sender: S) {
         let id = client.id;
-        let mut clients = self.clients.lock().await;
+        let mut clients = self.clients.write().await;
         clients.insert(id, client);
-        let mut connections = self.connections.lock().await;
+        let mut connections = self.connections.write().await;
         connections.insert(id, sender);
     }
 
     /// removes a client connection
     pub async fn remove_client_connection(&self, client_id: u64) {
-        let mut clients = self.clients.lock().await;
-        if let Some(client) = clients.get(&client_id) {
-            if let Some(room_id) = client.room_id {
-                let mut rooms = self.rooms.lock().await;
-                if let Some(room) = rooms.get_mut(&room_id) {
-                    room.client_ids.remove(&client_id);
+        let left_rooms = {
+            let mut clients = self.clients.write().await;
+            let client = clients.remove(&client_id);
+            let left_rooms: Vec<u64> = client.iter().flat_map(Client::rooms).collect();
+            for room_id in &left_rooms {
+                let mut rooms = self.rooms.write(*room_id).await;
+                self.remove_room_member(&mut rooms, *room_id, client_id);
+            }
+            // sessions are only kept for a grace period, under the token issued to the client
+            if let Some(client) = client.filter(|client| client.room_id.is_some()) {
+                if self.session_config.grace.is_some() && !client.session_token.is_empty() {
+                    let mut sessions = self.sessions.lock().await;
+                    sessions.retain(|_, session| !self.session_config.is_expired(session));
+                    sessions.insert(
+                        client.session_token.clone(),
+                        Session {
+                            client,
+                            disconnected_at: Instant::now(),
+                            queued: VecDeque::new(),
+                        },
+                    );
                 }
             }
+
+            let mut connections = self.connections.write().await;
+            connections.remove(&client_id);
+            left_rooms
+        };
+
+        for room_id in left_rooms {
+            self.admit_waitlisted(room_id).await;
+        }
+    }
+
+    /// queues a room broadcast for the resumable sessions of clients disconnected from the room
+    async fn queue_for_sessions<F>(&self, room_id: u64, response: &Response, predicate: &F)
+    where
+        F: Fn(&Client) -> bool,
+    {
+        let mut sessions = self.sessions.lock().await;
+        for session in sessions.values_mut() {
+            if session.client.room_id != Some(room_id)
+                || self.session_config.is_expired(session)
+                || !predicate(&session.client)
+            {
+                continue;
+            }
+            if session.queued.len() >= self.session_config.buffer_size {
+                session.queued.pop_front();
+            }
+            session.queued.push_back(response.clone());
+        }
+    }
+
+    /// rebinds a new connection to the client of a disconnected session, keeping its ID and room
+    ///
+    /// The session of `session_token` is kept for the client it was issued to from its
+    /// disconnection until its grace window ends. The resumed client is sent the broadcasts queued
+    /// while it was disconnected, then the current state of its room, and the room is notified
+    /// of the join like on `Join`. The client is issued a new session token, and only rejoins
+    /// its first room in multi-room mode. Returns the ID of the resumed client.
+    pub async fn resume_client(&self, session_token: &str, sender: S) -> Result<u64, String> {
+        let Session {
+            mut client,
+            mut queued,
+            ..
+        } = self
+            .take_session(session_token, |_| true)
+            .await
+            .ok_or_else(|| "Session not found".to_string())?;
+        let client_id = client.id;
+        let room_id = client
+            .room_id
+            .take()
+            .ok_or_else(|| "Session not in room".to_string())?;
+
+        {
+            let mut clients = self.clients.write().await;
+            if clients.contains_key(&client_id) {
+                return Err(format!("Client {} is already connected", client_id));
+            }
+            // the new connection negotiates its own encoding
+            client.extra_rooms.clear();
+            client.status = ClientStatus::Joined;
+            client.compression = Compression::None;
+            clients.insert(client_id, client);
+            self.connections.write().await.insert(client_id, sender);
+        }
+
+        if let Err(response) = self
+            .rejoin_session(client_id, room_id, &mut queued, None)
+            .await
+        {
+            self.remove_client_connection(client_id).await;
+            return Err(format!(
+                "Failed to rejoin room {}: {:?}",
+                room_id, response.response
+            ));
         }
+        self.react_on_message(room_id, Response::RoomJoined(client_id))
+            .await;
+        self.open_session(client_id).await;
+        Ok(client_id)
+    }
+
+    /// moves clients from the waitlist of a room into its free slots, in order of arrival
+    ///
+    /// Admitted clients are sent the initial state and announced to the room like on `Join`.
+    /// Clients that disconnected, joined another room or got banned meanwhile are skipped.
+    /// Boxed, as admitting clients may remove dead connections, admitting clients in turn.
+    fn admit_waitlisted(&self, room_id: u64) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(async move {
+            let admitted = {
+                let mut clients = self.clients.write().await;
+                let mut rooms = self.rooms.write(room_id).await;
+                let Some(room) = rooms.get_mut(&room_id) else {
+                    return;
+                };
+                let mut admitted = Vec::new();
+                while room.draining.is_none()
+                    && room
+                        .capacity
+                        .is_none_or(|capacity| room.client_ids.len() < capacity)
+                {
+                    let Some(client_id) = room.waitlist.pop_front() else {
+                        break;
+                    };
+                    let Some(client) = clients.get_mut(&client_id).filter(|client| {
+                        !client.in_room(room_id) && (client.room_id.is_none() || self.multi_room)
+                    }) else {
+                        continue;
+                    };
+                    if room.banned.contains(&client_id) {
+                        continue;
+                    }
+                    room.client_ids.insert(client_id);
+                    if client.enter_room(room_id) && self.join_requires_ack {
+                        client.status = ClientStatus::Joining;
+                    }
+                    admitted.push(client_id);
+                }
+                if !admitted.is_empty() {
+                    self.publish_summary(&rooms, room_id);
+                }
+                admitted
+            };
 
-        clients.remove(&client_id);
-        let mut connections = self.connections.lock().await;
-        connections.remove(&client_id);
+            for client_id in admitted {
+                let result = self
+                    .send_initial_state(client_id, Ok(RoomResponse::join_room(room_id, client_id)))
+                    .await;
+                self.deliver(client_id, result).await;
+            }
+        })
     }
 
-    /// dispatches an action to the reducer
+    /// dispatches an action to the reducer and broadcasts it to the client's room
+    ///
+    /// The action is broadcast like one received in an `Action` message, to every room
+    /// member the reducer doesn't hide it from.
     pub async fn extern_dispatch(
         &self,
         client_id: u64,
         action: &str,
     ) -> Result<ActionResponse<R::State>, String> {
-        let mut clients = self.clients.lock().await;
-        let client = clients
-            .get_mut(&client_id)
-            .ok_or_else(|| format!("Client not found: {}", client_id))?;
+        let (room_response, result) = self.dispatch_in_room(client_id, action).await?;
+        self.react_on_room_response(room_response).await;
+        Ok(result)
+    }
 
-        let room_id = client.room_id;
-        if room_id.is_none() {
-            return Err("Client not in room".to_string());
+    /// dispatches an action to the reducer and broadcasts it to the client's room
+    ///
+    /// Once the broadcast completes, `callback` is called with the correlation ID of the
+    /// dispatch, also returned by this method, and the action response.
+    pub async fn dispatch_with_callback<F>(
+        &self,
+        client_id: u64,
+        action: &str,
+        callback: F,
+    ) -> Result<u64, String>
+    where
+        F: FnOnce(u64, ActionResponse<R::State>) + Send,
+    {
+        let (room_response, result) = self.dispatch_in_room(client_id, action).await?;
+        let correlation_id = self.next_correlation_id.fetch_add(1, Ordering::Relaxed);
+
+        self.react_on_room_response(room_response).await;
+
+        callback(correlation_id, result);
+        Ok(correlation_id)
+    }
+
+    /// dispatches an action on behalf of `author` to the reducers of several rooms as one transaction
+    ///
+    /// Reducers are locked in order of room ID, so concurrent multi-room dispatches can't deadlock.
+    /// If the action fails in any room, every room is rolled back to its state before the dispatch;
+    /// otherwise the action is broadcast to each room. Responses are returned in order of room ID.
+    pub(crate) async fn dispatch_multi_room(
+        &self,
+        room_ids: &[u64],
+        author: u64,
+        action: &str,
+    ) -> Result<Vec<ActionResponse<R::State>>, String> {
+        let mut room_ids = room_ids.to_vec();
+        room_ids.sort_unstable();
+        room_ids.dedup();
+
+        // sequencers are locked in order of room ID too, before the rooms' buckets
+        let mut sequencers = Vec::with_capacity(room_ids.len());
+        for room_id in &room_ids {
+            let sequencer = self
+                .room_sequencer(*room_id)
+                .await
+                .ok_or_else(|| format!("Room {} not found", room_id))?;
+            sequencers.push(sequencer);
         }
-        let room_id = room_id.unwrap();
+        let mut sequenced = Vec::with_capacity(sequencers.len());
+        for sequencer in &sequencers {
+            sequenced.push(sequencer.lock().await);
+        }
+
+        let mut rooms = self.rooms.write_many(&room_ids).await;
+        let mut reducers = Vec::with_capacity(room_ids.len());
+        for room_id in &room_ids {
+            let room = rooms
+                .get(*room_id)
+                .ok_or_else(|| format!("Room {} not found", room_id))?;
+            if let Some(reason) = &room.draining {
+                return Err(format!("Room {} is draining: {}", room_id, reason));
+            }
+            reducers.push(room.reducer.clone());
+        }
+
+        let mut guards = Vec::with_capacity(reducers.len());
+        for reducer in &reducers {
+            guards.push(reducer.lock().await);
+        }
+        let snapshots = guards
+            .iter()
+            .map(|guard| (**guard).clone())
+            .collect::<Vec<_>>();
+
+        let mut applied = Vec::with_capacity(guards.len());
+        for (guard, room_id) in guards.iter_mut().zip(&room_ids) {
+            let previous_states = self
+                .settings
+                .state_diffs
+                .then(|| self.callbacks.previous_states(&*guard.state_arc()))
+                .flatten();
+            let outcome = async {
+                let parsed_action = R::deserialize_action(action, DEFAULT_PROTOCOL_VERSION)?;
+                let result = guard
+                    .dispatch(author, parsed_action)
+                    .await
+                    .map_err(|e| reducer_error_message(&e))?;
+                if self.exceeds_state_budget(guard) {
+                    return Err("State size limit exceeded".to_string());
+                }
+                if self.settings.exceeds_data_limit(*room_id, &result) {
+                    return Err(DATA_LIMIT_EXCEEDED.to_string());
+                }
+                let members = &rooms.get(*room_id).expect("room was checked").client_ids;
+                let mut response = self
+                    .callbacks
+                    .action_response(*room_id, &result, previous_states)
+                    .map_err(|e| e.to_string())?;
+                response.skip_clients = hidden_recipients(&**guard, members, &result);
+                Ok((response, result))
+            }
+            .await;
+            match outcome {
+                Ok(outcome) => applied.push(outcome),
+                Err(e) => {
+                    for (guard, snapshot) in guards.iter_mut().zip(snapshots) {
+                        **guard = snapshot;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        drop(guards);
+
+        for (room_id, (response, _)) in room_ids.iter().zip(&mut applied) {
+            self.counters.record_action(action.len());
+            record_applied_action(
+                rooms.shard_mut(*room_id).get_mut(room_id),
+                || RecordedAction {
+                    author,
+                    action: action.to_string(),
+                    protocol_version: DEFAULT_PROTOCOL_VERSION,
+                    context: None,
+                },
+                response,
+                self.record_actions,
+                self.history,
+                self.settings.stamps,
+            )
+            .await;
+        }
+        drop(rooms);
+        drop(sequenced);
+
+        let mut results = Vec::with_capacity(applied.len());
+        for (response, result) in applied {
+            self.react_on_room_response(response).await;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// dispatches an action to the reducer of the client's room
+    ///
+    /// Returns the action broadcast to the room along with the action response.
+    async fn dispatch_in_room(
+        &self,
+        client_id: u64,
+        action: &str,
+    ) -> Result<(RoomResponse, ActionResponse<R::State>), String> {
+        let (room_id, protocol_version) = {
+            let clients = self.clients.read().await;
+            let client = clients
+                .get(&client_id)
+                .ok_or_else(|| format!("Client not found: {}", client_id))?;
+            let room_id = client
+                .room_id
+                .ok_or_else(|| "Client not in room".to_string())?;
+            (room_id, client.protocol_version)
+        };
+
+        let sequencer = self
+            .room_sequencer(room_id)
+            .await
+            .ok_or_else(|| "Room not found".to_string())?;
+        let _sequenced = sequencer.lock().await;
+        let mut rooms = self.rooms.write(room_id).await;
+        self.apply_in_room(&mut rooms, room_id, client_id, protocol_version, action)
+            .await
+    }
+
+    /// dispatches an action on behalf of `author` to the reducer of a room and broadcasts it
+    ///
+    /// The author doesn't need to be a member of the room, or even a connected client,
+    /// so server-side actions can target a room directly. The action is broadcast to
+    /// every room member the reducer doesn't hide it from.
+    pub(crate) async fn dispatch_to_room(
+        &self,
+        room_id: u64,
+        author: u64,
+        action: &str,
+    ) -> Result<ActionResponse<R::State>, String> {
+        let sequencer = self
+            .room_sequencer(room_id)
+            .await
+            .ok_or_else(|| "Room not found".to_string())?;
+        let sequenced = sequencer.lock().await;
+        let mut rooms = self.rooms.write(room_id).await;
+        let (response, result) = self
+            .apply_in_room(
+                &mut rooms,
+                room_id,
+                author,
+                DEFAULT_PROTOCOL_VERSION,
+                action,
+            )
+            .await?;
+        drop(rooms);
+        drop(sequenced);
+
+        self.react_on_room_response(response).await;
+        Ok(result)
+    }
 
-        let mut rooms = self.rooms.lock().await;
-        let room = rooms.get_mut(&room_id);
-        if room.is_none() {
-            return Err("Room not found".to_string());
+    /// applies an action on behalf of `author` to the reducer of a room and records it
+    ///
+    /// Returns the action broadcast to the room along with the action response.
+    async fn apply_in_room(
+        &self,
+        rooms: &mut HashMap<u64, Room<R>>,
+        room_id: u64,
+        author: u64,
+        protocol_version: u32,
+        action: &str,
+    ) -> Result<(RoomResponse, ActionResponse<R::State>), String> {
+        let room = rooms
+            .get_mut(&room_id)
+            .ok_or_else(|| "Room not found".to_string())?;
+        if let Some(reason) = &room.draining {
+            return Err(format!("Room is draining: {}", reason));
         }
-        let room = room.unwrap();
 
         let mut reducer_guard = room.reducer.lock().await;
 
-        let parsed_action = serde_json::from_str(action).map_err(|e| e.to_string())?;
+        let parsed_action = R::deserialize_action(action, protocol_version)?;
+
+        let snapshot = reducer_guard.clone();
+        let previous_states = self
+            .settings
+            .state_diffs
+            .then(|| self.callbacks.previous_states(&*reducer_guard.state_arc()))
+            .flatten();
+        let result = reducer_guard
+            .dispatch(author, parsed_action)
+            .await
+            .map_err(|e| reducer_error_message(&e))?;
+        if self.exceeds_state_budget(&reducer_guard) {
+            *reducer_guard = snapshot;
+            return Err("State size limit exceeded".to_string());
+        }
+        if self.settings.exceeds_data_limit(room_id, &result) {
+            *reducer_guard = snapshot;
+            return Err(DATA_LIMIT_EXCEEDED.to_string());
+        }
+        let mut response = match self
+            .callbacks
+            .action_response(room_id, &result, previous_states)
+        {
+            Ok(response) => response,
+            Err(e) => {
+                *reducer_guard = snapshot;
+                return Err(e.to_string());
+            }
+        };
+        response.skip_clients = hidden_recipients(&*reducer_guard, &room.client_ids, &result);
+        drop(reducer_guard);
+        self.counters.record_action(action.len());
+        record_applied_action(
+            rooms.get_mut(&room_id),
+            || RecordedAction {
+                author,
+                action: action.to_string(),
+                protocol_version,
+                context: None,
+            },
+            &mut response,
+            self.record_actions,
+            self.history,
+            self.settings.stamps,
+        )
+        .await;
+        Ok((response, result))
+    }
+
+    /// serializes the state of a room as sent to a member, through the projection it joined with
+    async fn member_state(
+        &self,
+        room: &mut Room<R>,
+        projection: Option<&str>,
+    ) -> serde_json::Result<String> {
+        match projection.and_then(|name| self.callbacks.projections.get(name)) {
+            Some(projection) => {
+                serde_json::to_string(&projection(&*room.reducer.lock().await.state_arc()))
+            }
+            None => room.serialized_state().await,
+        }
+    }
+
+    /// stamps the state of a room sent to a member with its version, if enabled
+    fn state_stamp(&self, room: &Room<R>) -> Option<Stamp> {
+        self.settings.stamps.then(|| Stamp::now(room.state_version))
+    }
+
+    /// returns the state of a room along with its version
+    pub(crate) async fn room_state(&self, room_id: u64) -> Option<(u64, R::State)> {
+        let rooms = self.rooms.read(room_id).await;
+        let room = rooms.get(&room_id)?;
+        let state = room.reducer.lock().await.get_state();
+        Some((room.state_version, state))
+    }
 
-        reducer_guard.dispatch(client_id, parsed_action).await
+    /// sends the initial state to a client that created or joined a room
+    ///
+    /// States too large to be sent are reported to the client instead of the room response.
+    async fn send_initial_state(
+        &self,
+        client_id: u64,
+        result: Result<RoomResponse, ClientResponse>,
+    ) -> Result<EventResponse, ClientResponse> {
+        let room_response = result?;
+        if let Err(e) = self
+            .insert_client_to_room(client_id, room_response.room)
+            .await
+        {
+            if e == OUTGOING_LIMIT_EXCEEDED {
+                return Err(ClientResponse::server_error(
+                    client_id,
+                    ErrorCode::LimitExceeded,
+                    e,
+                ));
+            }
+        }
+        Ok(EventResponse::from(room_response))
     }
 
     /// inserts a client into a room and sends the initial state to the client
+    ///
+    /// fails if the client is not yet a member and the room is at capacity,
+    /// or removes the client from the room if the state exceeds the outgoing size limit
     pub(crate) async fn insert_client_to_room(
         &self,
         client_id: u64,
         room_id: u64,
     ) -> Result<(), String> {
-        let (state_str, connection_to_send) = {
-            let mut clients = self.clients.lock().await;
-            let mut rooms = self.rooms.lock().await;
-            let connections = self.connections.lock().await;
-
+        // projections apply to the first room joined only
+        let projection = {
+            let clients = self.clients.read().await;
             let client = clients
-                .get_mut(&client_id)
+                .get(&client_id)
                 .ok_or_else(|| format!("Client {} not found", client_id))?;
+            client
+                .projection
+                .clone()
+                .filter(|_| client.room_id.is_none_or(|current| current == room_id))
+        };
+
+        // the state is serialized under the room's bucket only, membership of the client
+        // entry is updated afterwards under a short write lock
+        let sent = {
+            let mut rooms = self.rooms.write(room_id).await;
+            let connections = self.connections.read().await;
 
             let room = rooms
                 .get_mut(&room_id)
@@ -422,18 +3500,45 @@ where
                 .get(&client_id)
                 .ok_or_else(|| format!("Connection not found for client {}", client_id))?;
 
-            room.client_ids.insert(client_id);
-            client.room_id = Some(room_id);
+            if !room.client_ids.contains(&client_id)
+                && room
+                    .capacity
+                    .is_some_and(|capacity| room.client_ids.len() >= capacity)
+            {
+                return Err(format!("Room {} is full", room_id));
+            }
 
-            let state = room.reducer.lock().await.get_state();
-            let state_str = serde_json::to_string(&state)
+            let state_str = self
+                .member_state(room, projection.as_deref())
+                .await
                 .map_err(|e| format!("Failed to serialize state: {}", e))?;
+            let response = Response::StateSent(state_str, self.state_stamp(room));
 
-            (state_str, connection.clone())
+            if self.exceeds_outgoing_limit(&response) {
+                room.client_ids.remove(&client_id);
+                None
+            } else {
+                room.client_ids.insert(client_id);
+                self.publish_summary(&rooms, room_id);
+                Some((response, connection.clone()))
+            }
         };
 
+        {
+            let mut clients = self.clients.write().await;
+            if let Some(client) = clients.get_mut(&client_id) {
+                if sent.is_none() {
+                    client.exit_room(room_id);
+                } else if !client.in_room(room_id) {
+                    client.enter_room(room_id);
+                }
+            }
+        }
+        let (response_to_send, connection_to_send) =
+            sent.ok_or_else(|| OUTGOING_LIMIT_EXCEEDED.to_string())?;
+
         let mut connection = connection_to_send;
-        if let Err(e) = connection.send(Response::StateSent(state_str)).await {
+        if let Err(e) = connection.send(response_to_send).await {
             eprintln!(
                 "Error sending initial state to client {}: {}. Client may not be fully joined.",
                 client_id, e
@@ -443,21 +3548,81 @@ where
         Ok(())
     }
 
+    /// returns a snapshot of runtime statistics
+    pub(crate) async fn stats(&self) -> JointStats {
+        let clients = self.clients.read().await.len();
+        let rooms = self.rooms.room_count().await;
+        self.counters.snapshot(clients, rooms)
+    }
+
+    /// returns the description of the control protocol spoken by the joint
+    pub(crate) fn protocol_description(&self) -> Value {
+        let extensions = ProtocolExtensions {
+            join_requires_ack: self.join_requires_ack,
+            state_diffs: self.settings.state_diffs,
+            stamps: self.settings.stamps,
+            single_room: self.single_room.is_some(),
+            multi_room: self.multi_room,
+        };
+        describe_protocol(extensions, R::action_schema())
+    }
+
+    /// returns the recorded action log of a room
+    pub(crate) async fn action_log(&self, room_id: u64) -> Option<Vec<RecordedAction>> {
+        let rooms = self.rooms.read(room_id).await;
+        rooms.get(&room_id).map(|room| room.action_log.clone())
+    }
+
+    /// applies an action log to the reducer a room started from and returns the resulting state
+    ///
+    /// Actions dispatched with a context go through the two-phase dispatch like live ones,
+    /// running their pending work, the others are dispatched straight to the reducer.
+    pub(crate) async fn replay(
+        &self,
+        room_id: u64,
+        actions: &[RecordedAction],
+    ) -> Result<R::State, String> {
+        let origin = self
+            .rooms
+            .read(room_id)
+            .await
+            .get(&room_id)
+            .ok_or_else(|| format!("Room {} not found", room_id))?
+            .origin
+            .clone();
+        let mut reducer = origin.unwrap_or_else(|| (self.reducer_factory)());
+        for recorded in actions {
+            let action = R::deserialize_action(&recorded.action, recorded.protocol_version)?;
+            let outcome = match &recorded.context {
+                Some(ctx) => match reducer.begin(ctx, action).await {
+                    Phase::Applied(outcome) => outcome,
+                    Phase::Pending(work) => {
+                        let action = work.await.map_err(|e| reducer_error_message(&e))?;
+                        reducer.complete(ctx, action).await
+                    }
+                },
+                None => reducer.dispatch(recorded.author, action).await,
+            };
+            outcome.map_err(|e| reducer_error_message(&e))?;
+        }
+        Ok(reducer.get_state())
+    }
+
     /// returns broadcaster clients
     #[allow(dead_code)] // getter is used in tests
-    pub(crate) fn get_clients(&self) -> Arc<Mutex<HashMap<u64, Client>>> {
+    pub(crate) fn get_clients(&self) -> Arc<RwLock<HashMap<u64, Client>>> {
         self.clients.clone()
     }
 
     /// returns broadcaster rooms
     #[allow(dead_code)] // getter is used in tests
-    pub(crate) fn get_rooms(&self) -> Arc<Mutex<HashMap<u64, Room<R>>>> {
+    pub(crate) fn get_rooms(&self) -> Arc<RoomShards<R>> {
         self.rooms.clone()
     }
 
     /// returns broadcaster connections
     #[allow(dead_code)] // getter is used in tests
-    pub(crate) fn get_connections(&self) -> Arc<Mutex<HashMap<u64, S>>> {
+    pub(crate) fn get_connections(&self) -> Arc<RwLock<HashMap<u64, S>>> {
         self.connections.clone()
     }
 }
@@ -1,19 +1,36 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
     use crate::broadcaster::Broadcaster;
     use crate::client::Client;
+    use crate::codec::{Compression, WireFormat};
     use crate::connection::{SinkAdapter, StreamAdapter};
-    use crate::dispatcher::{ActionResponse, Dispatchable};
-    use crate::message::{JointMessage, JointMessageMethod};
-    use crate::response::{ClientResponse, Response, RoomResponse};
-    use crate::room::{Room, RoomStatus};
+    use crate::dispatcher::{ActionResponse, DispatchContext, Dispatchable, Phase};
+    use crate::load_shedding::LoadShedding;
+    use crate::message::{
+        CreateOptions, JointMessage, JointMessageMethod, RoomConfig, DEFAULT_PROTOCOL_VERSION,
+        SCHEMA_VERSION,
+    };
+    use crate::rate_limit::RateLimit;
+    #[allow(unused_imports)]
+    use crate::response::{
+        ClientInfo, ClientResponse, ErrorCode, EventResponse, Response, ResponseStatus,
+        RoomConfigInfo, RoomInfo, RoomResponse, Stamp,
+    };
+    #[allow(unused_imports)]
+    use crate::room::{Room, RoomStatus, ROOM_SHARDS};
+    use crate::testing::RecordingSink;
     use crate::utils::types::{Broadcastable, Receivable};
     use async_trait::async_trait;
     use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
     use std::collections::{HashMap, HashSet};
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex as StdMutex};
-    use tokio::sync::Mutex;
+    use std::time::Duration;
+    #[allow(unused_imports)]
+    use tokio::sync::{Mutex, Notify, Semaphore};
 
     #[derive(Clone)]
     struct MockSink {
@@ -78,6 +95,7 @@ mod tests {
     impl Dispatchable for TestReducer {
         type Action = TestAction;
         type State = TestState;
+        type Error = String;
 
         async fn dispatch(
             &mut self,
@@ -137,6 +155,7 @@ mod tests {
     fn create_message(client_id: u64, method: JointMessageMethod) -> JointMessage {
         JointMessage {
             client_token: client_id.to_string(),
+            v: None,
             message: method,
         }
     }
@@ -161,9 +180,9 @@ mod tests {
         let reducer = TestReducer::default();
         let broadcaster = Broadcaster::<MockSink, TestReducer>::new(reducer);
 
-        assert_eq!(broadcaster.get_clients().clone().lock().await.len(), 0);
-        assert_eq!(broadcaster.get_rooms().clone().lock().await.len(), 0);
-        assert_eq!(broadcaster.get_connections().clone().lock().await.len(), 0);
+        assert_eq!(broadcaster.get_clients().clone().read().await.len(), 0);
+        assert_eq!(broadcaster.get_rooms().room_count().await, 0);
+        assert_eq!(broadcaster.get_connections().clone().read().await.len(), 0);
     }
 
     #[tokio::test]
@@ -180,12 +199,12 @@ mod tests {
 
         {
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.len(), 1);
             assert!(clients.contains_key(&1));
 
             let connections = broadcaster.get_connections();
-            let connections = connections.lock().await;
+            let connections = connections.read().await;
             assert_eq!(connections.len(), 1);
             assert!(connections.contains_key(&1));
         }
@@ -194,11 +213,11 @@ mod tests {
 
         {
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.len(), 0);
 
             let connections = broadcaster.get_connections();
-            let connections = connections.lock().await;
+            let connections = connections.read().await;
             assert_eq!(connections.len(), 0);
         }
     }
@@ -215,7 +234,7 @@ mod tests {
 
         broadcaster.add_client_connection(client, sink).await;
 
-        let result = broadcaster.handle_create(1).await;
+        let result = broadcaster.handle_create(1, CreateOptions::default()).await;
 
         assert!(result.is_ok());
         let room_response = result.unwrap();
@@ -223,15 +242,15 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
-            assert_eq!(rooms.len(), 1);
+            assert_eq!(rooms.room_count().await, 1);
+            let rooms = rooms.read(0).await;
             let room = rooms.get(&0).unwrap();
             assert_eq!(room.id, 0);
             assert_eq!(room.owner_id, 1);
             assert!(room.client_ids.contains(&1));
 
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             let client = clients.get(&1).unwrap();
             assert_eq!(client.room_id, Some(0));
         }
@@ -257,7 +276,7 @@ mod tests {
         broadcaster.add_client_connection(client1, sink1).await;
         broadcaster.add_client_connection(client2, sink2).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
         let room_id = match create_result.unwrap().response {
             Response::RoomCreated(id) => id,
@@ -266,7 +285,7 @@ mod tests {
 
         responses1.lock().unwrap().clear();
 
-        let join_result = broadcaster.handle_join(2, room_id).await;
+        let join_result = broadcaster.handle_join(2, room_id, None).await;
 
         assert!(join_result.is_ok());
         let room_response = join_result.unwrap();
@@ -274,14 +293,14 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             assert_eq!(room.client_ids.len(), 2);
             assert!(room.client_ids.contains(&1));
             assert!(room.client_ids.contains(&2));
 
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.get(&1).unwrap().room_id, Some(room_id));
             assert_eq!(clients.get(&2).unwrap().room_id, Some(room_id));
         }
@@ -300,7 +319,7 @@ mod tests {
 
         broadcaster.add_client_connection(client, sink).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
         let room_id = match create_result.unwrap().response {
             Response::RoomCreated(id) => id,
@@ -311,18 +330,25 @@ mod tests {
 
         let room_reducer = {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             rooms.get(&room_id).unwrap().reducer.clone()
         };
 
         let action = TestAction::Add(5);
         let action_result = broadcaster
-            .handle_action(1, action, room_reducer.clone())
+            .handle_action(
+                1,
+                None,
+                "",
+                &serde_json::to_string(&action).unwrap(),
+                None,
+                room_reducer.clone(),
+            )
             .await;
 
         assert!(action_result.is_ok());
         let room_response = action_result.unwrap();
-        assert!(matches!(room_response.response, Response::Action(_)));
+        assert!(matches!(room_response.response, Response::Action(..)));
 
         {
             let reducer = room_reducer.lock().await;
@@ -331,7 +357,14 @@ mod tests {
 
         let action = TestAction::Message("Hello".to_string());
         let action_result = broadcaster
-            .handle_action(1, action, room_reducer.clone())
+            .handle_action(
+                1,
+                None,
+                "",
+                &serde_json::to_string(&action).unwrap(),
+                None,
+                room_reducer.clone(),
+            )
             .await;
         assert!(action_result.is_ok());
 
@@ -363,14 +396,14 @@ mod tests {
         broadcaster.add_client_connection(client1, sink1).await;
         broadcaster.add_client_connection(client2, sink2).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
         let room_id = match create_result.unwrap().response {
             Response::RoomCreated(id) => id,
             _ => panic!("Expected RoomCreated response"),
         };
 
-        let join_result = broadcaster.handle_join(2, room_id).await;
+        let join_result = broadcaster.handle_join(2, room_id, None).await;
         assert!(join_result.is_ok());
 
         responses1.lock().unwrap().clear();
@@ -384,14 +417,14 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             assert_eq!(room.client_ids.len(), 1);
             assert!(!room.client_ids.contains(&1));
             assert!(room.client_ids.contains(&2));
 
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.get(&1).unwrap().room_id, None);
             assert_eq!(clients.get(&2).unwrap().room_id, Some(room_id));
         }
@@ -410,12 +443,15 @@ mod tests {
 
         broadcaster.add_client_connection(client, sink).await;
 
-        let create_event = create_message(1, JointMessageMethod::Create);
+        let create_event = create_message(1, JointMessageMethod::Create(None));
         let result = broadcaster.process_event(1, create_event).await;
 
         assert!(result.is_ok());
-        let room_id = match result.unwrap().response {
-            Response::RoomCreated(id) => id,
+        let room_id = match result.unwrap() {
+            EventResponse::Room(RoomResponse {
+                response: Response::RoomCreated(id),
+                ..
+            }) => id,
             _ => panic!("Expected RoomCreated response"),
         };
 
@@ -428,7 +464,7 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             let state = room.reducer.lock().await.get_state();
             assert_eq!(state.counter, 10);
@@ -441,7 +477,7 @@ mod tests {
 
         {
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.get(&1).unwrap().room_id, None);
         }
     }
@@ -459,7 +495,7 @@ mod tests {
 
         broadcaster.add_client_connection(client, sink).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
 
         let action_json = r#"{"Increment":null}"#;
@@ -477,6 +513,33 @@ mod tests {
         assert_eq!(response.state.counter, 6);
     }
 
+    #[tokio::test]
+    async fn test_extern_dispatch_broadcasts_to_room() {
+        let (broadcaster, _, sinks) = room_with_clients(CreateOptions::default(), 2).await;
+        for sink in &sinks {
+            sink.clear();
+        }
+
+        let result = broadcaster
+            .extern_dispatch(2, r#"{"Add":3}"#)
+            .await
+            .unwrap();
+        assert_eq!(result.state.counter, 3);
+
+        for sink in &sinks {
+            let actions = sink.received_of_kind(ResponseStatus::Action);
+            assert_eq!(actions.len(), 1);
+            match &actions[0] {
+                Response::Action(payload, _) => {
+                    let action: ActionResponse<TestState> = serde_json::from_str(payload).unwrap();
+                    assert_eq!(action.author, 2);
+                    assert_eq!(action.state.counter, 3);
+                }
+                other => panic!("Expected Action response, got {:?}", other),
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_insert_client_to_room() {
         let reducer = TestReducer::default();
@@ -497,7 +560,7 @@ mod tests {
         broadcaster.add_client_connection(client1, sink1).await;
         broadcaster.add_client_connection(client2, sink2).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
         let room_id = match create_result.unwrap().response {
             Response::RoomCreated(id) => id,
@@ -506,7 +569,7 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             let mut reducer = room.reducer.lock().await;
             reducer.state.counter = 42;
@@ -521,17 +584,17 @@ mod tests {
 
         {
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.get(&2).unwrap().room_id, Some(room_id));
 
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             assert!(room.client_ids.contains(&2));
         }
 
         assert!(get_response_count(&responses2) > 0);
-        if let Some(Response::StateSent(state_json)) = get_last_response(&responses2) {
+        if let Some(Response::StateSent(state_json, _)) = get_last_response(&responses2) {
             let state: TestState = serde_json::from_str(&state_json).unwrap();
             assert_eq!(state.counter, 42);
             assert_eq!(state.messages, vec!["Initial"]);
@@ -554,7 +617,7 @@ mod tests {
         broadcaster.add_client_connection(client, sink).await;
 
         let messages = vec![
-            create_message(1, JointMessageMethod::Create),
+            create_message(1, JointMessageMethod::Create(None)),
             create_action_message(1, TestAction::Add(7)),
             create_action_message(1, TestAction::Message("Test".to_string())),
             create_message(1, JointMessageMethod::Leave),
@@ -566,7 +629,7 @@ mod tests {
             broadcaster.handle_rx(1, &mut stream).await;
         });
 
-        let _ = tokio::time::timeout(std::time::Duration::from_millis(500), handle)
+        let _ = tokio::time::timeout(Duration::from_millis(500), handle)
             .await
             .expect("handle_rx did not complete");
 
@@ -591,7 +654,7 @@ mod tests {
 
         assert!(result.is_err());
         match result.err().unwrap().response {
-            Response::NotFound(_) => {}
+            Response::NotFound { .. } => {}
             _ => panic!("Expected NotFound response"),
         }
 
@@ -606,8 +669,4695 @@ mod tests {
 
         assert!(result.is_err());
         match result.err().unwrap().response {
-            Response::NotFound(_) => {}
+            Response::NotFound { .. } => {}
             _ => panic!("Expected NotFound response"),
         }
     }
+
+    #[tokio::test]
+    async fn test_join_requires_ack() {
+        let reducer = TestReducer::default();
+        let mut broadcaster = Broadcaster::<RecordingSink, TestReducer>::new(reducer);
+        broadcaster.set_join_requires_ack(true);
+
+        let sink1 = RecordingSink::new();
+        let sink2 = RecordingSink::new();
+        broadcaster
+            .add_client_connection(create_client(1), sink1.clone())
+            .await;
+        broadcaster
+            .add_client_connection(create_client(2), sink2.clone())
+            .await;
+
+        let create_event = create_message(1, JointMessageMethod::Create(None));
+        let room_id = match broadcaster.process_event(1, create_event).await.unwrap() {
+            EventResponse::Room(RoomResponse {
+                response: Response::RoomCreated(id),
+                ..
+            }) => id,
+            _ => panic!("Expected RoomCreated response"),
+        };
+
+        let join_event = create_message(2, JointMessageMethod::Join(room_id));
+        assert!(broadcaster.process_event(2, join_event).await.is_ok());
+        assert_eq!(sink2.received_of_kind(ResponseStatus::StateSent).len(), 1);
+
+        let action_event = create_action_message(2, TestAction::Increment);
+        let result = broadcaster.process_event(2, action_event).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+
+        let action_event = create_action_message(1, TestAction::Increment);
+        match broadcaster.process_event(1, action_event).await.unwrap() {
+            EventResponse::Room(room_response) => {
+                broadcaster
+                    .react_on_message(room_response.room, room_response.response)
+                    .await;
+            }
+            _ => panic!("Expected per-room response"),
+        }
+
+        assert_eq!(sink1.received_of_kind(ResponseStatus::Action).len(), 1);
+        assert_eq!(sink2.len(), 1);
+
+        let ack_event = create_message(2, JointMessageMethod::Ack);
+        match broadcaster.process_event(2, ack_event).await.unwrap() {
+            EventResponse::Client(client_response) => {
+                assert_eq!(client_response.client, 2);
+                assert!(matches!(client_response.response, Response::RoomJoined(2)));
+            }
+            _ => panic!("Expected per-client response"),
+        }
+
+        broadcaster
+            .react_on_message(room_id, Response::Action("{}".to_string(), None))
+            .await;
+        sink2.assert_received(Response::Action("{}".to_string(), None));
+        assert_eq!(sink2.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_max_state_bytes_reverts_action() {
+        let reducer = TestReducer::default();
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(reducer);
+        broadcaster.set_max_state_bytes(Some(64));
+
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let small = create_action_message(1, TestAction::Message("hi".to_string()));
+        assert!(broadcaster.process_event(1, small).await.is_ok());
+
+        let huge = create_action_message(1, TestAction::Message("x".repeat(128)));
+        let result = broadcaster.process_event(1, huge).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+
+        let rooms = broadcaster.get_rooms();
+        let rooms = rooms.read(room_id).await;
+        let state = rooms
+            .get(&room_id)
+            .unwrap()
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(state.messages, vec!["hi".to_string()]);
+    }
+
+    /// State failing to serialize once its counter goes negative.
+    #[derive(Debug, Clone, Default)]
+    struct FragileState(TestState);
+
+    impl Serialize for FragileState {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0.counter < 0 {
+                return Err(serde::ser::Error::custom("negative counter"));
+            }
+            self.0.serialize(serializer)
+        }
+    }
+
+    impl Broadcastable for FragileState {}
+
+    #[derive(Clone, Default)]
+    struct FragileReducer {
+        inner: TestReducer,
+    }
+
+    impl Dispatchable for FragileReducer {
+        type Action = TestAction;
+        type State = FragileState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<FragileState>, String> {
+            let response = self.inner.dispatch(client_id, action).await?;
+            Ok(ActionResponse {
+                status: response.status,
+                state: FragileState(response.state),
+                author: response.author,
+                data: response.data,
+            })
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<FragileState>, String> {
+            let action: TestAction = serde_json::from_str(action_str)
+                .map_err(|e| format!("Failed to parse action: {}", e))?;
+            self.dispatch(client_id, action).await
+        }
+
+        fn get_state(&self) -> FragileState {
+            FragileState(self.inner.get_state())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unserializable_state_reverts_action() {
+        for (room_actors, max_state_bytes) in [
+            (false, None),
+            (true, None),
+            (false, Some(1024)),
+            (true, Some(1024)),
+        ] {
+            let mut broadcaster =
+                Broadcaster::<RecordingSink, FragileReducer>::new(FragileReducer::default());
+            broadcaster.set_room_actors(room_actors);
+            broadcaster.set_max_state_bytes(max_state_bytes);
+            let sink = RecordingSink::new();
+            broadcaster
+                .add_client_connection(create_client(1), sink.clone())
+                .await;
+            let room_id = broadcaster
+                .handle_create(1, CreateOptions::default())
+                .await
+                .unwrap()
+                .room;
+
+            let negative = create_action_message(1, TestAction::Add(-1));
+            let error = broadcaster.process_event(1, negative).await.err().unwrap();
+            assert!(matches!(
+                error.response,
+                Response::ServerError {
+                    code: ErrorCode::Internal,
+                    ..
+                }
+            ));
+
+            // the room keeps serving actions from the reverted state
+            let increment = create_action_message(1, TestAction::Increment);
+            assert!(broadcaster.process_event(1, increment).await.is_ok());
+            let rooms = broadcaster.get_rooms();
+            let rooms = rooms.read(room_id).await;
+            let reducer = rooms[&room_id].reducer.lock().await;
+            assert_eq!(
+                reducer.get_state().0.counter,
+                1,
+                "room actors: {}, max state bytes: {:?}",
+                room_actors,
+                max_state_bytes
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unserializable_state_reverts_room_dispatch() {
+        let mut broadcaster =
+            Broadcaster::<RecordingSink, FragileReducer>::new(FragileReducer::default());
+        broadcaster.set_record_actions(true);
+        let sink = RecordingSink::new();
+        broadcaster
+            .add_client_connection(create_client(1), sink.clone())
+            .await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        sink.clear();
+
+        let negative = serde_json::to_string(&TestAction::Add(-1)).unwrap();
+        assert!(broadcaster
+            .dispatch_to_room(room_id, 9, &negative)
+            .await
+            .is_err());
+        assert_eq!(sink.received_of_kind(ResponseStatus::Action).len(), 0);
+
+        let rooms = broadcaster.get_rooms();
+        let rooms = rooms.read(room_id).await;
+        assert!(rooms[&room_id].action_log.is_empty());
+        assert_eq!(
+            rooms[&room_id].reducer.lock().await.get_state().0.counter,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_data_bytes_rejects_oversized_result() {
+        let (mut broadcaster, room_id, sinks) =
+            room_with_clients(CreateOptions::default(), 2).await;
+        broadcaster.set_max_data_bytes(Some(16));
+        for sink in &sinks {
+            sink.clear();
+        }
+
+        broadcaster
+            .handle_event(
+                1,
+                create_action_message(1, TestAction::Message("hi".to_string())),
+            )
+            .await;
+        for sink in &sinks {
+            assert_eq!(sink.received_of_kind(ResponseStatus::Action).len(), 1);
+        }
+
+        broadcaster
+            .handle_event(
+                1,
+                create_action_message(1, TestAction::Message("x".repeat(64))),
+            )
+            .await;
+        assert_eq!(
+            sinks[0].last(),
+            Some(Response::ServerError {
+                code: ErrorCode::LimitExceeded,
+                message: "Action data size limit exceeded".to_string(),
+            })
+        );
+        assert_eq!(sinks[1].len(), 1);
+
+        let huge = serde_json::to_string(&TestAction::Message("x".repeat(64))).unwrap();
+        assert!(broadcaster.extern_dispatch(2, &huge).await.is_err());
+        assert_eq!(sinks[1].len(), 1);
+
+        let rooms = broadcaster.rooms.read(room_id).await;
+        let state = rooms[&room_id].reducer.lock().await.get_state();
+        assert_eq!(state.messages, vec!["hi".to_string()]);
+    }
+
+    #[derive(Clone)]
+    struct StallingSink {
+        responses: Arc<StdMutex<Vec<Response>>>,
+        stalled: bool,
+    }
+
+    #[async_trait]
+    impl SinkAdapter for StallingSink {
+        async fn send(
+            &mut self,
+            response: Response,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            if self.stalled {
+                std::future::pending::<()>().await;
+            }
+            self.responses.lock().unwrap().push(response);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_timeout_removes_stuck_client() {
+        let reducer = TestReducer::default();
+        let mut broadcaster = Broadcaster::<StallingSink, TestReducer>::new(reducer);
+        broadcaster.set_send_timeout(Some(Duration::from_millis(50)));
+
+        let responses1 = Arc::new(StdMutex::new(Vec::new()));
+        let sink1 = StallingSink {
+            responses: responses1.clone(),
+            stalled: false,
+        };
+        let responses2 = Arc::new(StdMutex::new(Vec::new()));
+        let sink2 = StallingSink {
+            responses: responses2.clone(),
+            stalled: true,
+        };
+        let responses3 = Arc::new(StdMutex::new(Vec::new()));
+        let sink3 = StallingSink {
+            responses: responses3.clone(),
+            stalled: false,
+        };
+
+        broadcaster
+            .add_client_connection(create_client(1), sink1)
+            .await;
+        broadcaster
+            .add_client_connection(create_client(2), sink2)
+            .await;
+        broadcaster
+            .add_client_connection(create_client(3), sink3)
+            .await;
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster.handle_join(3, room_id, None).await.unwrap();
+
+        let broadcast =
+            broadcaster.react_on_message(room_id, Response::Action("{}".to_string(), None));
+        tokio::time::timeout(Duration::from_secs(1), broadcast)
+            .await
+            .expect("Broadcast should not stall on a stuck client");
+
+        assert!(matches!(
+            get_last_response(&responses1),
+            Some(Response::Action(..))
+        ));
+        assert!(matches!(
+            get_last_response(&responses3),
+            Some(Response::Action(..))
+        ));
+        assert_eq!(get_response_count(&responses2), 0);
+
+        let clients = broadcaster.get_clients();
+        assert!(!clients.read().await.contains_key(&2));
+        let rooms = broadcaster.get_rooms();
+        assert!(!rooms
+            .read(room_id)
+            .await
+            .get(&room_id)
+            .unwrap()
+            .client_ids
+            .contains(&2));
+    }
+
+    #[derive(Clone, Default)]
+    struct VersionedReducer {
+        inner: TestReducer,
+    }
+
+    #[derive(Deserialize)]
+    struct LegacyAddAction {
+        amount: i32,
+    }
+
+    impl Dispatchable for VersionedReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.dispatch(client_id, action).await
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.extern_dispatch(client_id, action_str).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+
+        fn deserialize_action(raw: &str, version: u32) -> Result<TestAction, String> {
+            if version == 1 {
+                let legacy: LegacyAddAction =
+                    serde_json::from_str(raw).map_err(|e| e.to_string())?;
+                return Ok(TestAction::Add(legacy.amount));
+            }
+            serde_json::from_str(raw).map_err(|e| e.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_deserializes_with_recorded_protocol_version() {
+        let mut broadcaster =
+            Broadcaster::<MockSink, VersionedReducer>::new(VersionedReducer::default());
+        broadcaster.set_record_actions(true);
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let legacy = create_message(1, JointMessageMethod::Action(r#"{"amount":5}"#.to_string()));
+        broadcaster.process_event(1, legacy).await.unwrap();
+        let hello_event = create_message(
+            1,
+            JointMessageMethod::Hello {
+                version: 2,
+                compression: Compression::None,
+                format: None,
+            },
+        );
+        broadcaster.process_event(1, hello_event).await.unwrap();
+        let current = create_action_message(1, TestAction::Add(2));
+        broadcaster.process_event(1, current).await.unwrap();
+
+        let log = broadcaster.action_log(room_id).await.unwrap();
+        let versions: Vec<u32> = log.iter().map(|action| action.protocol_version).collect();
+        assert_eq!(versions, vec![1, 2]);
+        assert_eq!(broadcaster.replay(room_id, &log).await.unwrap().counter, 7);
+    }
+
+    #[tokio::test]
+    async fn test_versioned_action_deserializer() {
+        let broadcaster =
+            Broadcaster::<MockSink, VersionedReducer>::new(VersionedReducer::default());
+
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let hello_event = create_message(
+            1,
+            JointMessageMethod::Hello {
+                version: 2,
+                compression: Compression::None,
+                format: None,
+            },
+        );
+        match broadcaster.process_event(1, hello_event).await.unwrap() {
+            EventResponse::Client(client_response) => {
+                assert!(matches!(client_response.response, Response::Hello(2)))
+            }
+            _ => panic!("Expected per-client response"),
+        }
+        let current = create_action_message(1, TestAction::Add(2));
+        assert!(broadcaster.process_event(1, current).await.is_ok());
+
+        let hello_event = create_message(
+            1,
+            JointMessageMethod::Hello {
+                version: 1,
+                compression: Compression::None,
+                format: None,
+            },
+        );
+        assert!(broadcaster.process_event(1, hello_event).await.is_ok());
+        let legacy = create_message(1, JointMessageMethod::Action(r#"{"amount":5}"#.to_string()));
+        assert!(broadcaster.process_event(1, legacy).await.is_ok());
+
+        let rooms = broadcaster.get_rooms();
+        let rooms = rooms.read(room_id).await;
+        let state = rooms
+            .get(&room_id)
+            .unwrap()
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(state.counter, 7);
+    }
+
+    #[tokio::test]
+    async fn test_join_refusal_reasons() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        for client_id in 1..=6 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let result = broadcaster.handle_join(2, room_id + 1, None).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::NotFound { .. }
+        ));
+
+        {
+            let rooms = broadcaster.get_rooms();
+            let mut rooms = rooms.write(room_id).await;
+            let room = rooms.get_mut(&room_id).unwrap();
+            room.banned.insert(2);
+        }
+        let result = broadcaster.handle_join(2, room_id, None).await;
+        assert!(matches!(result.err().unwrap().response, Response::Banned(id) if id == room_id));
+
+        {
+            let rooms = broadcaster.get_rooms();
+            let mut rooms = rooms.write(room_id).await;
+            let room = rooms.get_mut(&room_id).unwrap();
+            room.invite_only = true;
+            room.invited.extend([4, 5, 6]);
+        }
+        let result = broadcaster.handle_join(3, room_id, None).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::InviteRequired(id) if id == room_id
+        ));
+
+        {
+            let rooms = broadcaster.get_rooms();
+            let mut rooms = rooms.write(room_id).await;
+            let room = rooms.get_mut(&room_id).unwrap();
+            room.status = RoomStatus::Private("secret".to_string());
+            room.capacity = Some(2);
+        }
+        let result = broadcaster.handle_join(4, room_id, None).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::WrongPassword(id) if id == room_id
+        ));
+        let result = broadcaster.handle_join(4, room_id, Some("guess")).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::WrongPassword(id) if id == room_id
+        ));
+        assert!(broadcaster
+            .handle_join(4, room_id, Some("secret"))
+            .await
+            .is_ok());
+
+        let result = broadcaster.handle_join(5, room_id, Some("secret")).await;
+        assert!(matches!(result.err().unwrap().response, Response::RoomFull(id) if id == room_id));
+
+        let clients = broadcaster.get_clients();
+        let clients = clients.read().await;
+        assert_eq!(clients.get(&5).unwrap().room_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_room_specific_rate_limits() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_rate_limit(Some(RateLimit::new(1, 2)));
+        broadcaster.set_max_rate_limit(Some(RateLimit::new(10, 5)));
+        for client_id in 1..=3 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let default_room = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        let fast_room = broadcaster
+            .handle_create(
+                2,
+                CreateOptions {
+                    rate_limit: Some(RateLimit::new(10, 5)),
+                    ..CreateOptions::default()
+                },
+            )
+            .await
+            .unwrap()
+            .room;
+
+        let too_fast = CreateOptions {
+            rate_limit: Some(RateLimit::new(100, 5)),
+            ..CreateOptions::default()
+        };
+        let result = broadcaster.handle_create(3, too_fast).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+
+        for (client_id, room_id, allowed) in [(1, default_room, 2), (2, fast_room, 5)] {
+            let mut accepted = 0;
+            for _ in 0..8 {
+                let action = create_action_message(client_id, TestAction::Increment);
+                match broadcaster.process_event(client_id, action).await {
+                    Ok(_) => accepted += 1,
+                    Err(error) => {
+                        assert!(
+                            matches!(error.response, Response::ClientError { code: ErrorCode::RateLimited, message: ref m } if m == "rate limited")
+                        )
+                    }
+                }
+            }
+            assert_eq!(accepted, allowed);
+
+            let rooms = broadcaster.get_rooms();
+            let rooms = rooms.read(room_id).await;
+            let state = rooms
+                .get(&room_id)
+                .unwrap()
+                .reducer
+                .lock()
+                .await
+                .get_state();
+            assert_eq!(state.counter, allowed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_delete() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let mut responses = Vec::new();
+        for client_id in 1..=3 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        let other_room_id = broadcaster
+            .handle_create(3, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        let delete_event = create_message(2, JointMessageMethod::Delete);
+        let result = broadcaster.process_event(2, delete_event).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+        assert!(broadcaster
+            .get_rooms()
+            .read(room_id)
+            .await
+            .contains_key(&room_id));
+
+        let delete_event = create_message(1, JointMessageMethod::Delete);
+        match broadcaster.process_event(1, delete_event).await.unwrap() {
+            EventResponse::Clients(clients_response) => {
+                let mut recipients = clients_response.clients.clone();
+                recipients.sort();
+                assert_eq!(recipients, vec![1, 2]);
+                broadcaster
+                    .react_to_clients(&clients_response.clients, clients_response.response)
+                    .await;
+            }
+            _ => panic!("Expected response to explicit clients"),
+        }
+
+        for client_responses in &responses[..2] {
+            assert!(matches!(
+                get_last_response(client_responses),
+                Some(Response::RoomDeleted(id)) if id == room_id
+            ));
+        }
+        assert_eq!(get_response_count(&responses[2]), 0);
+
+        assert!(!broadcaster
+            .get_rooms()
+            .read(room_id)
+            .await
+            .contains_key(&room_id));
+        let clients = broadcaster.get_clients();
+        let clients = clients.read().await;
+        assert_eq!(clients.get(&1).unwrap().room_id, None);
+        assert_eq!(clients.get(&2).unwrap().room_id, None);
+        assert_eq!(clients.get(&3).unwrap().room_id, Some(other_room_id));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain_room() {
+        let broadcaster = Arc::new(Broadcaster::<MockSink, TestReducer>::new(
+            TestReducer::default(),
+        ));
+        let mut responses = Vec::new();
+        for client_id in 1..=3 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        let drain = tokio::spawn({
+            let broadcaster = broadcaster.clone();
+            async move {
+                broadcaster
+                    .drain_room(room_id, Duration::from_secs(10), "maintenance", true)
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        for client_responses in &responses[..2] {
+            assert!(matches!(
+                get_last_response(client_responses),
+                Some(Response::RoomDraining(reason)) if reason == "maintenance"
+            ));
+        }
+
+        let action = create_action_message(2, TestAction::Increment);
+        let result = broadcaster.process_event(2, action).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::RoomDraining(_)
+        ));
+        let result = broadcaster.handle_join(3, room_id, None).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::RoomDraining(_)
+        ));
+        assert!(broadcaster
+            .get_rooms()
+            .read(room_id)
+            .await
+            .contains_key(&room_id));
+
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        drain.await.unwrap().unwrap();
+
+        assert!(!broadcaster
+            .get_rooms()
+            .read(room_id)
+            .await
+            .contains_key(&room_id));
+        for client_responses in &responses[..2] {
+            assert!(matches!(
+                get_last_response(client_responses),
+                Some(Response::RoomDeleted(id)) if id == room_id
+            ));
+        }
+        let clients = broadcaster.get_clients();
+        assert_eq!(clients.read().await.get(&2).unwrap().room_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_private_room_password() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        for client_id in 1..=5 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let private_options = CreateOptions {
+            password: Some("secret".to_string()),
+            ..CreateOptions::default()
+        };
+        let create_event = create_message(1, JointMessageMethod::Create(Some(private_options)));
+        let private_room = match broadcaster.process_event(1, create_event).await.unwrap() {
+            EventResponse::Room(room_response) => room_response.room,
+            _ => panic!("Expected per-room response"),
+        };
+        let public_room = broadcaster
+            .handle_create(5, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let missing = create_message(2, JointMessageMethod::Join(private_room));
+        let result = broadcaster.process_event(2, missing).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::WrongPassword(_)
+        ));
+
+        let wrong = create_message(
+            3,
+            JointMessageMethod::JoinWithPassword {
+                room_id: private_room,
+                password: "guess".to_string(),
+            },
+        );
+        let result = broadcaster.process_event(3, wrong).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::WrongPassword(_)
+        ));
+
+        let correct = create_message(
+            4,
+            JointMessageMethod::JoinWithPassword {
+                room_id: private_room,
+                password: "secret".to_string(),
+            },
+        );
+        assert!(broadcaster.process_event(4, correct).await.is_ok());
+
+        let public = create_message(2, JointMessageMethod::Join(public_room));
+        assert!(broadcaster.process_event(2, public).await.is_ok());
+
+        let clients = broadcaster.get_clients();
+        let clients = clients.read().await;
+        assert_eq!(clients.get(&2).unwrap().room_id, Some(public_room));
+        assert_eq!(clients.get(&3).unwrap().room_id, None);
+        assert_eq!(clients.get(&4).unwrap().room_id, Some(private_room));
+    }
+
+    #[tokio::test]
+    async fn test_cursor_relayed_to_other_members() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_record_actions(true);
+        let mut responses = Vec::new();
+        for client_id in 1..=3 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        let cursor = serde_json::json!({ "line": 4, "column": 2 });
+        let mut stream = MockStream {
+            messages: vec![create_message(
+                1,
+                JointMessageMethod::Cursor(cursor.clone()),
+            )],
+            index: 0,
+        };
+        broadcaster.handle_rx(1, &mut stream).await;
+
+        assert_eq!(get_response_count(&responses[0]), 0);
+        assert!(matches!(
+            get_last_response(&responses[1]),
+            Some(Response::Cursor { client_id: 1, data }) if data == cursor
+        ));
+        assert_eq!(get_response_count(&responses[2]), 0);
+
+        let log = broadcaster.action_log(room_id).await.unwrap();
+        assert!(log.is_empty());
+        assert_eq!(
+            broadcaster.replay(room_id, &log).await.unwrap(),
+            TestState::default()
+        );
+
+        let rooms = broadcaster.get_rooms();
+        let rooms = rooms.read(room_id).await;
+        let state = rooms
+            .get(&room_id)
+            .unwrap()
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(state, TestState::default());
+    }
+
+    #[tokio::test]
+    async fn test_room_capacity() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        for client_id in 1..=4 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let result = broadcaster
+            .handle_create(
+                1,
+                CreateOptions {
+                    capacity: Some(0),
+                    ..CreateOptions::default()
+                },
+            )
+            .await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+
+        let room_id = broadcaster
+            .handle_create(
+                1,
+                CreateOptions {
+                    capacity: Some(2),
+                    ..CreateOptions::default()
+                },
+            )
+            .await
+            .unwrap()
+            .room;
+        assert!(broadcaster.handle_join(2, room_id, None).await.is_ok());
+
+        let result = broadcaster.handle_join(3, room_id, None).await;
+        assert!(matches!(result.err().unwrap().response, Response::RoomFull(id) if id == room_id));
+
+        assert!(broadcaster.insert_client_to_room(4, room_id).await.is_err());
+        assert!(broadcaster.insert_client_to_room(2, room_id).await.is_ok());
+
+        let rooms = broadcaster.get_rooms();
+        let rooms = rooms.read(room_id).await;
+        assert_eq!(rooms.get(&room_id).unwrap().client_ids.len(), 2);
+        drop(rooms);
+
+        let clients = broadcaster.get_clients();
+        let clients = clients.read().await;
+        assert_eq!(clients.get(&3).unwrap().room_id, None);
+        assert_eq!(clients.get(&4).unwrap().room_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_room_ids_not_reused() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        for client_id in 1..=2 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let first = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        let second = broadcaster
+            .handle_create(2, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_delete(1).await.unwrap();
+        let third = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        assert_ne!(first, second);
+        assert_ne!(first, third);
+        assert_ne!(second, third);
+
+        broadcaster.handle_delete(1).await.unwrap();
+        let fourth = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        assert!(![first, second, third].contains(&fourth));
+    }
+
+    #[tokio::test]
+    async fn test_kick_client() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let mut responses = Vec::new();
+        for client_id in 1..=4 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster.handle_join(3, room_id, None).await.unwrap();
+
+        let result = broadcaster.kick_client(2, 3).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+
+        let result = broadcaster.kick_client(1, 4).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+
+        broadcaster.kick_client(1, 3).await.unwrap();
+
+        for client_responses in [&responses[0], &responses[1], &responses[2]] {
+            assert!(matches!(
+                get_last_response(client_responses),
+                Some(Response::RoomLeft(3))
+            ));
+        }
+        assert_eq!(get_response_count(&responses[3]), 0);
+
+        let clients = broadcaster.get_clients();
+        let clients = clients.read().await;
+        assert_eq!(clients.get(&3).unwrap().room_id, None);
+        assert_eq!(clients.get(&2).unwrap().room_id, Some(room_id));
+        drop(clients);
+
+        let rooms = broadcaster.get_rooms();
+        let rooms = rooms.read(room_id).await;
+        let room = rooms.get(&room_id).unwrap();
+        assert!(!room.client_ids.contains(&3));
+        assert_eq!(room.client_ids.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_room_action_targets_rooms() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_multi_room(true);
+        for client_id in 1..=3 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let first_room = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        let second_room = broadcaster
+            .handle_create(2, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        let foreign_room = broadcaster
+            .handle_create(3, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        let join = create_message(1, JointMessageMethod::Join(second_room));
+        broadcaster.process_event(1, join).await.unwrap();
+        assert_eq!(
+            broadcaster.clients.read().await[&1].room_id,
+            Some(first_room)
+        );
+
+        let room_action = |room_id: Option<u64>, action: TestAction| {
+            create_message(
+                1,
+                JointMessageMethod::RoomAction {
+                    room_id,
+                    payload: serde_json::to_string(&action).unwrap(),
+                    base_version: None,
+                },
+            )
+        };
+
+        let targets = [
+            (Some(first_room), TestAction::Add(1)),
+            (Some(second_room), TestAction::Add(10)),
+            (None, TestAction::Add(100)),
+        ];
+        for (room_id, action) in targets {
+            match broadcaster
+                .process_event(1, room_action(room_id, action))
+                .await
+                .unwrap()
+            {
+                EventResponse::Room(room_response) => {
+                    assert_eq!(room_response.room, room_id.unwrap_or(first_room))
+                }
+                _ => panic!("Expected room response"),
+            }
+        }
+
+        let result = broadcaster
+            .process_event(1, room_action(Some(foreign_room), TestAction::Add(1000)))
+            .await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+
+        let rooms = broadcaster.get_rooms();
+        for (room_id, expected) in [(first_room, 101), (second_room, 10), (foreign_room, 0)] {
+            let state = rooms.read(room_id).await[&room_id]
+                .reducer
+                .lock()
+                .await
+                .get_state();
+            assert_eq!(state.counter, expected);
+        }
+
+        // leaving the first room makes the other one the target of actions naming no room
+        let leave = create_message(1, JointMessageMethod::LeaveRoom(first_room));
+        broadcaster.process_event(1, leave).await.unwrap();
+        assert!(!rooms.read(first_room).await[&first_room]
+            .client_ids
+            .contains(&1));
+        let result = broadcaster
+            .process_event(1, room_action(Some(first_room), TestAction::Add(1)))
+            .await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError {
+                code: ErrorCode::NotInRoom,
+                ..
+            }
+        ));
+        match broadcaster
+            .process_event(1, room_action(None, TestAction::Add(1)))
+            .await
+            .unwrap()
+        {
+            EventResponse::Room(room_response) => assert_eq!(room_response.room, second_room),
+            _ => panic!("Expected room response"),
+        }
+
+        // disconnecting leaves every room joined
+        broadcaster.remove_client_connection(1).await;
+        assert!(!rooms.read(second_room).await[&second_room]
+            .client_ids
+            .contains(&1));
+    }
+
+    #[tokio::test]
+    async fn test_joining_another_room_requires_multi_room() {
+        let (broadcaster, _, _) = room_with_clients(CreateOptions::default(), 2).await;
+        let other_room = broadcaster
+            .handle_create(2, CreateOptions::default())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            other_room.response,
+            Response::ClientError {
+                code: ErrorCode::AlreadyInRoom,
+                ..
+            }
+        ));
+
+        let preloaded_room = broadcaster.preload_room(TestReducer::default()).await;
+        let join = create_message(1, JointMessageMethod::Join(preloaded_room));
+        let error = broadcaster.process_event(1, join).await.err().unwrap();
+        assert!(matches!(
+            error.response,
+            Response::ClientError {
+                code: ErrorCode::AlreadyInRoom,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_room_action_rejects_stale_base_version() {
+        for room_actors in [false, true] {
+            let mut broadcaster =
+                Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+            broadcaster.set_room_actors(room_actors);
+            let sink = RecordingSink::new();
+            broadcaster
+                .add_client_connection(create_client(1), sink.clone())
+                .await;
+            broadcaster
+                .handle_event(1, create_message(1, JointMessageMethod::Create(None)))
+                .await;
+
+            let room_action = |base_version: Option<u64>| {
+                create_message(
+                    1,
+                    JointMessageMethod::RoomAction {
+                        room_id: None,
+                        payload: serde_json::to_string(&TestAction::Add(1)).unwrap(),
+                        base_version,
+                    },
+                )
+            };
+            broadcaster
+                .process_event(1, room_action(None))
+                .await
+                .unwrap();
+
+            // the action above bumped the state version to 1, so version 0 is stale
+            let conflict = broadcaster
+                .process_event(1, room_action(Some(0)))
+                .await
+                .err()
+                .unwrap();
+            assert_eq!(conflict.client, 1);
+            assert_eq!(conflict.response, Response::Conflict { current_version: 1 });
+
+            broadcaster
+                .process_event(1, room_action(Some(1)))
+                .await
+                .unwrap();
+            let room_id = broadcaster.clients.read().await[&1].room_id.unwrap();
+            let (version, state) = broadcaster.room_state(room_id).await.unwrap();
+            assert_eq!(version, 2);
+            assert_eq!(state.counter, 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_projections_shape_states_per_client() {
+        for (room_actors, state_diffs) in [(false, false), (true, false), (false, true)] {
+            let mut broadcaster =
+                Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+            broadcaster.set_room_actors(room_actors);
+            broadcaster.set_state_diffs(state_diffs);
+            broadcaster.add_projection(
+                "summary".to_string(),
+                Box::new(|state: &TestState| json!({ "counter": state.counter })),
+            );
+            broadcaster.add_projection(
+                "messages".to_string(),
+                Box::new(|state: &TestState| json!(state.messages)),
+            );
+            let sinks = [
+                RecordingSink::new(),
+                RecordingSink::new(),
+                RecordingSink::new(),
+            ];
+            for (client_id, sink) in (1..).zip(&sinks) {
+                broadcaster
+                    .add_client_connection(create_client(client_id), sink.clone())
+                    .await;
+            }
+            let room_id = broadcaster
+                .handle_create(1, CreateOptions::default())
+                .await
+                .unwrap()
+                .room;
+
+            let unknown = broadcaster
+                .process_event(
+                    2,
+                    create_message(
+                        2,
+                        JointMessageMethod::JoinProjected {
+                            room_id,
+                            projection: "unknown".to_string(),
+                        },
+                    ),
+                )
+                .await
+                .err()
+                .unwrap();
+            assert!(matches!(
+                unknown.response,
+                Response::NotFound {
+                    code: ErrorCode::ProjectionNotFound,
+                    ..
+                }
+            ));
+
+            for (client_id, projection) in [(2, "summary"), (3, "messages")] {
+                let join = JointMessageMethod::JoinProjected {
+                    room_id,
+                    projection: projection.to_string(),
+                };
+                broadcaster
+                    .handle_event(client_id, create_message(client_id, join))
+                    .await;
+            }
+            assert_eq!(
+                sinks[1].received_of_kind(ResponseStatus::StateSent),
+                vec![Response::StateSent(r#"{"counter":0}"#.to_string(), None)]
+            );
+            assert_eq!(
+                sinks[2].received_of_kind(ResponseStatus::StateSent),
+                vec![Response::StateSent("[]".to_string(), None)]
+            );
+
+            let action = create_action_message(1, TestAction::Message("hi".to_string()));
+            broadcaster.handle_event(1, action).await;
+
+            let broadcast = |sink: &RecordingSink| {
+                let actions = sink.received_of_kind(ResponseStatus::Action);
+                assert_eq!(actions.len(), 1, "room actors: {}", room_actors);
+                let Response::Action(payload, _) = &actions[0] else {
+                    unreachable!()
+                };
+                let payload: Value = serde_json::from_str(payload).unwrap();
+                assert_eq!(payload["author"], 1);
+                if state_diffs {
+                    payload["patch"].clone()
+                } else {
+                    payload["state"].clone()
+                }
+            };
+            if state_diffs {
+                assert_eq!(
+                    broadcast(&sinks[0]),
+                    json!([{ "op": "add", "path": "/messages/0", "value": "hi" }])
+                );
+                assert_eq!(broadcast(&sinks[1]), json!([]));
+                assert_eq!(
+                    broadcast(&sinks[2]),
+                    json!([{ "op": "add", "path": "/0", "value": "hi" }])
+                );
+            } else {
+                assert_eq!(
+                    broadcast(&sinks[0]),
+                    json!({ "counter": 0, "messages": ["hi"] })
+                );
+                assert_eq!(broadcast(&sinks[1]), json!({ "counter": 0 }));
+                assert_eq!(broadcast(&sinks[2]), json!(["hi"]));
+            }
+
+            // the projection is kept for pulled states, and dropped on leave
+            let synced = broadcaster.handle_sync(3).await.unwrap();
+            assert_eq!(
+                synced.response,
+                Response::StateSent(r#"["hi"]"#.to_string(), None)
+            );
+            broadcaster.handle_leave(3).await.unwrap();
+            assert_eq!(broadcaster.clients.read().await[&3].projection, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_stamps_number_room_sequence() {
+        for room_actors in [false, true] {
+            let mut broadcaster =
+                Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+            broadcaster.set_room_actors(room_actors);
+            broadcaster.set_broadcast_stamps(true);
+            let sinks = [RecordingSink::new(), RecordingSink::new()];
+            for (client_id, sink) in (1..).zip(&sinks) {
+                broadcaster
+                    .add_client_connection(create_client(client_id), sink.clone())
+                    .await;
+            }
+            let room_id = broadcaster
+                .handle_create(1, CreateOptions::default())
+                .await
+                .unwrap()
+                .room;
+            for _ in 0..3 {
+                let action = create_action_message(1, TestAction::Increment);
+                broadcaster.handle_event(1, action).await;
+            }
+            let join = create_message(2, JointMessageMethod::Join(room_id));
+            broadcaster.handle_event(2, join).await;
+            let action = create_action_message(2, TestAction::Increment);
+            broadcaster.handle_event(2, action).await;
+
+            let stamps = sinks[0]
+                .received_of_kind(ResponseStatus::Action)
+                .into_iter()
+                .map(|response| match response {
+                    Response::Action(_, Some(stamp)) => stamp,
+                    response => panic!("unstamped broadcast {:?}", response),
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                stamps.iter().map(|stamp| stamp.seq).collect::<Vec<_>>(),
+                vec![1, 2, 3, 4],
+                "room actors: {}",
+                room_actors
+            );
+            assert!(stamps
+                .windows(2)
+                .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+            assert!(stamps[0].timestamp > 0);
+
+            // the joining client is sent the state stamped with the version it reflects
+            let received = sinks[1]
+                .received()
+                .into_iter()
+                .filter(|response| {
+                    matches!(response, Response::StateSent(..) | Response::Action(..))
+                })
+                .collect::<Vec<_>>();
+            assert!(matches!(
+                &received[..],
+                [
+                    Response::StateSent(_, Some(Stamp { seq: 3, .. })),
+                    Response::Action(_, Some(Stamp { seq: 4, .. })),
+                ]
+            ));
+        }
+
+        let broadcaster = Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+        let sink = RecordingSink::new();
+        broadcaster
+            .add_client_connection(create_client(1), sink.clone())
+            .await;
+        broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap();
+        let action = create_action_message(1, TestAction::Increment);
+        broadcaster.handle_event(1, action).await;
+        assert!(matches!(sink.last(), Some(Response::Action(_, None))));
+    }
+
+    #[tokio::test]
+    async fn test_max_outgoing_bytes() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_max_outgoing_bytes(Some(256));
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+
+        let create = create_message(1, JointMessageMethod::Create(None));
+        let room_id = match broadcaster.process_event(1, create).await.unwrap() {
+            EventResponse::Room(room_response) => room_response.room,
+            _ => panic!("Expected room response"),
+        };
+
+        let action = TestAction::Message("x".repeat(512));
+        let event = create_message(
+            1,
+            JointMessageMethod::Action(serde_json::to_string(&action).unwrap()),
+        );
+        let result = broadcaster.process_event(1, event).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ServerError { .. }
+        ));
+
+        let action = TestAction::Add(1);
+        let event = create_message(
+            1,
+            JointMessageMethod::Action(serde_json::to_string(&action).unwrap()),
+        );
+        assert!(broadcaster.process_event(1, event).await.is_ok());
+
+        let state = broadcaster.get_rooms().read(room_id).await[&room_id]
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert!(state.messages.is_empty());
+        assert_eq!(state.counter, 1);
+
+        let mut reducer = TestReducer::default();
+        reducer.state.messages.push("x".repeat(512));
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(reducer);
+        broadcaster.set_max_outgoing_bytes(Some(256));
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+
+        let create = create_message(1, JointMessageMethod::Create(None));
+        let result = broadcaster.process_event(1, create).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ServerError { .. }
+        ));
+        assert_eq!(get_response_count(&responses), 0);
+        let clients = broadcaster.get_clients();
+        assert_eq!(clients.read().await.get(&1).unwrap().room_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_delete_empty_rooms() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_delete_empty_rooms(true);
+        let mut responses = Vec::new();
+        for client_id in 1..=2 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        broadcaster.handle_leave(1).await.unwrap();
+        assert!(broadcaster
+            .get_rooms()
+            .read(room_id)
+            .await
+            .contains_key(&room_id));
+
+        let leave = create_message(2, JointMessageMethod::Leave);
+        match broadcaster.process_event(2, leave).await.unwrap() {
+            EventResponse::Client(client_response) => {
+                assert_eq!(client_response.client, 2);
+                assert!(matches!(client_response.response, Response::RoomLeft(2)));
+            }
+            _ => panic!("Expected response to the leaving client"),
+        }
+        assert!(broadcaster.get_rooms().room_count().await == 0);
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.remove_client_connection(1).await;
+        assert!(!broadcaster
+            .get_rooms()
+            .read(room_id)
+            .await
+            .contains_key(&room_id));
+    }
+
+    #[tokio::test]
+    async fn test_list_rooms() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        for client_id in 1..=3 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let public_room = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, public_room, None).await.unwrap();
+        let private_room = broadcaster
+            .handle_create(
+                3,
+                CreateOptions {
+                    password: Some("secret".to_string()),
+                    ..CreateOptions::default()
+                },
+            )
+            .await
+            .unwrap()
+            .room;
+
+        let event = create_message(1, JointMessageMethod::ListRooms);
+        let rooms = match broadcaster.process_event(1, event).await.unwrap() {
+            EventResponse::Client(client_response) => match client_response.response {
+                Response::RoomList(rooms) => rooms,
+                _ => panic!("Expected RoomList response"),
+            },
+            _ => panic!("Expected response to the sender"),
+        };
+
+        assert_eq!(
+            rooms,
+            vec![
+                RoomInfo {
+                    id: public_room,
+                    owner_id: 1,
+                    client_count: 2,
+                    private: false,
+                },
+                RoomInfo {
+                    id: private_room,
+                    owner_id: 3,
+                    client_count: 1,
+                    private: true,
+                },
+            ]
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct TokenReducer {
+        inner: TestReducer,
+    }
+
+    impl Dispatchable for TokenReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.dispatch(client_id, action).await
+        }
+
+        async fn dispatch_with_token(
+            &mut self,
+            client_id: u64,
+            client_token: &str,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.state.messages.push(client_token.to_string());
+            self.dispatch(client_id, action).await
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.extern_dispatch(client_id, action_str).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_with_token() {
+        let broadcaster = Broadcaster::<MockSink, TokenReducer>::new(TokenReducer::default());
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        for device in ["phone", "laptop"] {
+            let event = JointMessage {
+                client_token: device.to_string(),
+                v: None,
+                message: JointMessageMethod::Action(
+                    serde_json::to_string(&TestAction::Increment).unwrap(),
+                ),
+            };
+            broadcaster.process_event(1, event).await.unwrap();
+        }
+
+        let state = broadcaster.get_rooms().read(room_id).await[&room_id]
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(state.messages, vec!["phone", "laptop"]);
+        assert_eq!(state.counter, 2);
+    }
+
+    #[tokio::test]
+    async fn test_presence() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        for client_id in 1..=3 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        let event = create_message(2, JointMessageMethod::Presence);
+        match broadcaster.process_event(2, event).await.unwrap() {
+            EventResponse::Client(client_response) => match client_response.response {
+                Response::Presence(clients) => assert_eq!(
+                    clients,
+                    vec![
+                        ClientInfo {
+                            id: 1,
+                            label: "User1".to_string(),
+                        },
+                        ClientInfo {
+                            id: 2,
+                            label: "User2".to_string(),
+                        },
+                    ]
+                ),
+                _ => panic!("Expected Presence response"),
+            },
+            _ => panic!("Expected response to the sender"),
+        }
+
+        let event = create_message(3, JointMessageMethod::Presence);
+        let result = broadcaster.process_event(3, event).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::NotFound { .. }
+        ));
+    }
+
+    struct ChannelStream {
+        receiver: tokio::sync::mpsc::Receiver<JointMessage>,
+    }
+
+    #[async_trait]
+    impl StreamAdapter for ChannelStream {
+        async fn next(&mut self) -> Result<JointMessage, Box<dyn std::error::Error + Send + Sync>> {
+            self.receiver
+                .recv()
+                .await
+                .ok_or_else(|| "Channel closed".into())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_keepalive_interval() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_keepalive_interval(Some(Duration::from_secs(10)));
+        let broadcaster = Arc::new(broadcaster);
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+
+        let (tx, receiver) = tokio::sync::mpsc::channel(4);
+        let worker = tokio::spawn({
+            let broadcaster = broadcaster.clone();
+            async move {
+                broadcaster
+                    .handle_rx(1, &mut ChannelStream { receiver })
+                    .await
+            }
+        });
+        let keepalives = || {
+            responses
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|response| matches!(response, Response::Keepalive))
+                .count()
+        };
+
+        tokio::time::sleep(Duration::from_secs(9)).await;
+        assert_eq!(keepalives(), 0);
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(keepalives(), 1);
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(keepalives(), 2);
+
+        // the broadcast of RoomCreated makes the room active until the next tick
+        tx.send(create_message(1, JointMessageMethod::Create(None)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(keepalives(), 2);
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(keepalives(), 3);
+
+        drop(tx);
+        worker.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_react_on_message_filtered() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let mut responses = Vec::new();
+        for client_id in 1..=3 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster.handle_join(3, room_id, None).await.unwrap();
+
+        broadcaster
+            .react_on_message_filtered(
+                room_id,
+                Response::ClientError {
+                    code: ErrorCode::InvalidRequest,
+                    message: "whisper".to_string(),
+                },
+                &HashMap::new(),
+                |client| client.label == "User2",
+            )
+            .await;
+
+        assert_eq!(get_response_count(&responses[0]), 0);
+        assert!(matches!(
+            get_last_response(&responses[1]),
+            Some(Response::ClientError { message, .. }) if message == "whisper"
+        ));
+        assert_eq!(get_response_count(&responses[2]), 0);
+    }
+
+    #[tokio::test]
+    async fn test_direct_message() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let mut responses = Vec::new();
+        for client_id in 1..=4 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster.handle_join(3, room_id, None).await.unwrap();
+        broadcaster
+            .handle_create(4, CreateOptions::default())
+            .await
+            .unwrap();
+
+        let direct_message = |target: u64| JointMessageMethod::DirectMessage {
+            target,
+            payload: "psst".to_string(),
+        };
+        let mut stream = MockStream {
+            messages: vec![create_message(1, direct_message(2))],
+            index: 0,
+        };
+        broadcaster.handle_rx(1, &mut stream).await;
+
+        assert!(matches!(
+            get_last_response(&responses[1]),
+            Some(Response::DirectMessage { from: 1, payload }) if payload == "psst"
+        ));
+        assert_eq!(get_response_count(&responses[0]), 0);
+        assert_eq!(get_response_count(&responses[2]), 0);
+
+        for target in [4, 5] {
+            let result = broadcaster
+                .process_event(1, create_message(1, direct_message(target)))
+                .await;
+            assert!(matches!(
+                result.err().unwrap().response,
+                Response::NotFound { .. }
+            ));
+        }
+        assert_eq!(get_response_count(&responses[3]), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_with_callback() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap();
+        let broadcasts_before = get_response_count(&responses);
+
+        let fired = Arc::new(StdMutex::new(Vec::new()));
+        let correlation_id = {
+            let fired = fired.clone();
+            let responses = responses.clone();
+            broadcaster
+                .dispatch_with_callback(1, r#"{"Add":7}"#, move |correlation_id, result| {
+                    assert_eq!(get_response_count(&responses), broadcasts_before + 1);
+                    fired.lock().unwrap().push((
+                        correlation_id,
+                        result.state.counter,
+                        result.author,
+                    ));
+                })
+                .await
+                .unwrap()
+        };
+        assert_eq!(*fired.lock().unwrap(), vec![(correlation_id, 7, 1)]);
+        assert!(matches!(
+            get_last_response(&responses),
+            Some(Response::Action(..))
+        ));
+
+        let next_id = broadcaster
+            .dispatch_with_callback(1, r#"{"Add":1}"#, |_, _| {})
+            .await
+            .unwrap();
+        assert_ne!(next_id, correlation_id);
+
+        let result = broadcaster
+            .dispatch_with_callback(2, r#"{"Add":1}"#, |_, _| panic!("unexpected callback"))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[derive(Clone, Default)]
+    struct ContextReducer {
+        inner: TestReducer,
+    }
+
+    impl Dispatchable for ContextReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.dispatch(client_id, action).await
+        }
+
+        async fn dispatch_with_context(
+            &mut self,
+            ctx: &DispatchContext,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.state.messages.push(format!(
+                "{}:{}:{}:{:?}",
+                ctx.label, ctx.token, ctx.client_token, ctx.room_id
+            ));
+            self.dispatch(ctx.client_id, action).await
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.extern_dispatch(client_id, action_str).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_with_context() {
+        let broadcaster = Broadcaster::<MockSink, ContextReducer>::new(ContextReducer::default());
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        let client = Client::new(1, None, "alice".to_string(), "secret".to_string());
+        broadcaster.add_client_connection(client, sink).await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let event = JointMessage {
+            client_token: "phone".to_string(),
+            v: None,
+            message: JointMessageMethod::Action(
+                serde_json::to_string(&TestAction::Increment).unwrap(),
+            ),
+        };
+        broadcaster.process_event(1, event).await.unwrap();
+
+        let state = broadcaster.get_rooms().read(room_id).await[&room_id]
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(
+            state.messages,
+            vec![format!("alice:secret:phone:Some({})", room_id)]
+        );
+        assert_eq!(state.counter, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_dispatches_with_recorded_context() {
+        for room_actors in [false, true] {
+            let mut broadcaster =
+                Broadcaster::<MockSink, ContextReducer>::new(ContextReducer::default());
+            broadcaster.set_record_actions(true);
+            broadcaster.set_room_actors(room_actors);
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            let client = Client::new(1, None, "alice".to_string(), "secret".to_string());
+            broadcaster.add_client_connection(client, sink).await;
+            let mut origin = ContextReducer::default();
+            origin.inner.state.counter = 10;
+            let room_id = broadcaster.preload_room(origin).await;
+            broadcaster.handle_join(1, room_id, None).await.unwrap();
+
+            let event = JointMessage {
+                client_token: "phone".to_string(),
+                v: None,
+                message: JointMessageMethod::Action(
+                    serde_json::to_string(&TestAction::Increment).unwrap(),
+                ),
+            };
+            broadcaster.process_event(1, event).await.unwrap();
+            let add = serde_json::to_string(&TestAction::Add(2)).unwrap();
+            broadcaster
+                .dispatch_to_room(room_id, 9, &add)
+                .await
+                .unwrap();
+
+            let log = broadcaster.action_log(room_id).await.unwrap();
+            assert_eq!(log.len(), 2);
+            assert_eq!(
+                log[0].context.as_ref().map(|ctx| ctx.token.as_str()),
+                Some("secret")
+            );
+            assert!(log[1].context.is_none());
+
+            // the replay starts from the preloaded reducer and sees the context of the client
+            let replayed = broadcaster.replay(room_id, &log).await.unwrap();
+            let live = broadcaster.get_rooms().read(room_id).await[&room_id]
+                .reducer
+                .lock()
+                .await
+                .get_state();
+            assert_eq!(replayed, live);
+            assert_eq!(replayed.counter, 13);
+            assert_eq!(
+                replayed.messages,
+                vec![format!("alice:secret:phone:Some({})", room_id)]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_client_token_is_preserved() {
+        let broadcaster = Broadcaster::<MockSink, ContextReducer>::new(ContextReducer::default());
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+
+        let mut stream = MockStream {
+            messages: vec![
+                JointMessage {
+                    client_token: "user-42".to_string(),
+                    v: None,
+                    message: JointMessageMethod::Create(None),
+                },
+                JointMessage {
+                    client_token: "other".to_string(),
+                    v: None,
+                    message: JointMessageMethod::Action(
+                        serde_json::to_string(&TestAction::Increment).unwrap(),
+                    ),
+                },
+            ],
+            index: 0,
+        };
+        broadcaster.handle_rx(1, &mut stream).await;
+
+        let clients = broadcaster.clients.read().await;
+        assert_eq!(clients[&1].token, "user-42");
+        let room_id = clients[&1].room_id.unwrap();
+        drop(clients);
+        let state = broadcaster.get_rooms().read(room_id).await[&room_id]
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(
+            state.messages,
+            vec![format!("User1:user-42:other:Some({})", room_id)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_react_on_message_recipient_count() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let action = create_action_message(1, TestAction::Increment);
+        let response = match broadcaster.process_event(1, action).await.unwrap() {
+            EventResponse::Room(room_response) => room_response.response,
+            _ => panic!("Expected per-room response"),
+        };
+        assert_eq!(
+            broadcaster
+                .react_on_message(room_id, response.clone())
+                .await,
+            Some(1)
+        );
+
+        broadcaster.remove_client_connection(1).await;
+        assert!(broadcaster
+            .get_rooms()
+            .read(room_id)
+            .await
+            .contains_key(&room_id));
+        assert_eq!(
+            broadcaster.react_on_message(room_id, response).await,
+            Some(0)
+        );
+        assert_eq!(
+            broadcaster
+                .react_on_message(room_id + 1, Response::Action("{}".to_string(), None))
+                .await,
+            Some(0)
+        );
+    }
+
+    #[derive(Clone)]
+    struct FailingSink;
+
+    #[async_trait]
+    impl SinkAdapter for FailingSink {
+        async fn send(
+            &mut self,
+            _response: Response,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Err("send failed".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_sends_are_not_counted_as_recipients() {
+        let broadcaster = Broadcaster::<FailingSink, TestReducer>::new(TestReducer::default());
+        broadcaster
+            .add_client_connection(create_client(1), FailingSink)
+            .await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let action = create_action_message(1, TestAction::Increment);
+        let response = match broadcaster.process_event(1, action).await.unwrap() {
+            EventResponse::Room(room_response) => room_response.response,
+            _ => panic!("Expected per-room response"),
+        };
+        assert_eq!(
+            broadcaster.react_on_message(room_id, response).await,
+            Some(0)
+        );
+
+        let stats = broadcaster.stats().await;
+        assert_eq!((stats.broadcasts_total, stats.fanout_total), (1, 0));
+    }
+
+    /// A sink that only sends JSON and ignores the wire format negotiation.
+    #[derive(Clone)]
+    struct JsonOnlySink;
+
+    #[async_trait]
+    impl SinkAdapter for JsonOnlySink {
+        async fn send(
+            &mut self,
+            _response: Response,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+
+        fn wire_format(&self) -> Option<WireFormat> {
+            Some(WireFormat::Json)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hello_rejects_unsupported_wire_format() {
+        let broadcaster = Broadcaster::<JsonOnlySink, TestReducer>::new(TestReducer::default());
+        broadcaster
+            .add_client_connection(create_client(1), JsonOnlySink)
+            .await;
+
+        let error = broadcaster
+            .handle_hello(1, 2, Compression::None, Some(WireFormat::MessagePack))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.response,
+            Response::ClientError {
+                code: ErrorCode::InvalidRequest,
+                ..
+            }
+        ));
+        // the rejected negotiation leaves the client's protocol version unchanged
+        assert_eq!(
+            broadcaster.clients.read().await[&1].protocol_version,
+            DEFAULT_PROTOCOL_VERSION
+        );
+
+        let hello = broadcaster
+            .handle_hello(1, 2, Compression::None, Some(WireFormat::Json))
+            .await
+            .unwrap();
+        assert!(matches!(hello.response, Response::Hello(2)));
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum CounterError {
+        WouldGoNegative { counter: i32, delta: i32 },
+        Other(String),
+    }
+
+    impl From<String> for CounterError {
+        fn from(message: String) -> Self {
+            CounterError::Other(message)
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct TypedErrorReducer {
+        inner: TestReducer,
+    }
+
+    impl Dispatchable for TypedErrorReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = CounterError;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, CounterError> {
+            if let TestAction::Add(delta) = action {
+                let counter = self.inner.state.counter;
+                if counter + delta < 0 {
+                    return Err(CounterError::WouldGoNegative { counter, delta });
+                }
+            }
+            Ok(self.inner.dispatch(client_id, action).await?)
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, CounterError> {
+            let action: TestAction = serde_json::from_str(action_str)
+                .map_err(|e| format!("Failed to parse action: {}", e))?;
+            self.dispatch(client_id, action).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_reducer_errors() {
+        let broadcaster =
+            Broadcaster::<MockSink, TypedErrorReducer>::new(TypedErrorReducer::default());
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap();
+
+        let action = create_action_message(1, TestAction::Add(-3));
+        let error = broadcaster.process_event(1, action).await.err().unwrap();
+        assert_eq!(error.client, 1);
+
+        let serialized = serde_json::to_string(&error.response).unwrap();
+        let message = match serde_json::from_str::<Response>(&serialized).unwrap() {
+            Response::ClientError { message, .. } => message,
+            response => panic!("Expected client error, got {:?}", response),
+        };
+        assert_eq!(
+            serde_json::from_str::<CounterError>(&message).unwrap(),
+            CounterError::WouldGoNegative {
+                counter: 0,
+                delta: -3
+            }
+        );
+
+        let action = create_action_message(1, TestAction::Add(3));
+        assert!(broadcaster.process_event(1, action).await.is_ok());
+    }
+
+    #[derive(Clone, Default)]
+    struct RejectingReducer {
+        inner: TestReducer,
+    }
+
+    impl Dispatchable for RejectingReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            match action {
+                TestAction::Message(_) => Err("nope".to_string()),
+                action => self.inner.dispatch(client_id, action).await,
+            }
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            let action: TestAction = serde_json::from_str(action_str)
+                .map_err(|e| format!("Failed to parse action: {}", e))?;
+            self.dispatch(client_id, action).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reducer_error_reaches_the_author() {
+        for room_actors in [false, true] {
+            let mut broadcaster =
+                Broadcaster::<RecordingSink, RejectingReducer>::new(RejectingReducer::default());
+            broadcaster.set_room_actors(room_actors);
+            let sinks = [RecordingSink::new(), RecordingSink::new()];
+            for (client_id, sink) in (1..).zip(&sinks) {
+                broadcaster
+                    .add_client_connection(create_client(client_id), sink.clone())
+                    .await;
+            }
+            let room_id = broadcaster
+                .handle_create(1, CreateOptions::default())
+                .await
+                .unwrap()
+                .room;
+            broadcaster.handle_join(2, room_id, None).await.unwrap();
+            for sink in &sinks {
+                sink.clear();
+            }
+
+            let action = create_action_message(2, TestAction::Message("hi".to_string()));
+            broadcaster.handle_event(2, action).await;
+
+            assert_eq!(
+                sinks[1].received(),
+                vec![Response::ClientError {
+                    code: ErrorCode::InvalidAction,
+                    message: "nope".to_string(),
+                }],
+                "room actors: {}",
+                room_actors
+            );
+            assert!(sinks[0].is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pre_dispatch_hook_vetoes_actions() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.with_pre_hook(Box::new(|ctx, raw_action| {
+            if raw_action.contains("Message") && ctx.label != "User1" {
+                return Err(format!("{} may not send messages", ctx.label));
+            }
+            Ok(())
+        }));
+        for client_id in 1..=2 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        let message = TestAction::Message("hello".to_string());
+        let error = broadcaster
+            .process_event(2, create_action_message(2, message.clone()))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error.response,
+            Response::ClientError { code: ErrorCode::InvalidAction, message: reason } if reason == "User2 may not send messages"
+        ));
+
+        broadcaster
+            .process_event(1, create_action_message(1, message))
+            .await
+            .unwrap();
+        broadcaster
+            .process_event(2, create_action_message(2, TestAction::Increment))
+            .await
+            .unwrap();
+
+        let state = broadcaster.get_rooms().read(room_id).await[&room_id]
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(state.messages, vec!["hello"]);
+        assert_eq!(state.counter, 1);
+    }
+
+    #[tokio::test]
+    async fn test_post_dispatch_hook_observes_state() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let observed = Arc::new(StdMutex::new(Vec::new()));
+        {
+            let observed = observed.clone();
+            broadcaster.with_post_hook(Box::new(move |ctx, result| {
+                observed
+                    .lock()
+                    .unwrap()
+                    .push((ctx.client_id, result.state.counter));
+            }));
+        }
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap();
+
+        for action in [TestAction::Increment, TestAction::Add(4)] {
+            broadcaster
+                .process_event(1, create_action_message(1, action))
+                .await
+                .unwrap();
+        }
+        let invalid = create_message(1, JointMessageMethod::Action("{".to_string()));
+        assert!(broadcaster.process_event(1, invalid).await.is_err());
+
+        assert_eq!(*observed.lock().unwrap(), vec![(1, 1), (1, 5)]);
+    }
+
+    #[derive(Clone, Default)]
+    struct SlowReducer {
+        inner: TestReducer,
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl Dispatchable for SlowReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            self.inner.dispatch(client_id, action).await
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.extern_dispatch(client_id, action_str).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_room_actors() {
+        let reducer = SlowReducer::default();
+        let max_in_flight = reducer.max_in_flight.clone();
+        let mut broadcaster = Broadcaster::<MockSink, SlowReducer>::new(reducer);
+        broadcaster.set_room_actors(true);
+        for client_id in 1..=3 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+        let first_room = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, first_room, None).await.unwrap();
+        let second_room = broadcaster
+            .handle_create(3, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let (first, second) = tokio::join!(
+            broadcaster.process_event(1, create_action_message(1, TestAction::Increment)),
+            broadcaster.process_event(2, create_action_message(2, TestAction::Add(2))),
+        );
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+        let (version, state) = broadcaster.room_state(first_room).await.unwrap();
+        assert_eq!((version, state.counter), (2, 3));
+
+        let (first, second) = tokio::join!(
+            broadcaster.process_event(1, create_action_message(1, TestAction::Increment)),
+            broadcaster.process_event(3, create_action_message(3, TestAction::Increment)),
+        );
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+        let (version, state) = broadcaster.room_state(second_room).await.unwrap();
+        assert_eq!((version, state.counter), (1, 1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_actions_of_different_rooms_run_concurrently() {
+        let reducer = SlowReducer::default();
+        let max_in_flight = reducer.max_in_flight.clone();
+        let broadcaster = Broadcaster::<MockSink, SlowReducer>::new(reducer);
+        for client_id in 1..=3 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+        let first_room = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, first_room, None).await.unwrap();
+        broadcaster
+            .handle_create(3, CreateOptions::default())
+            .await
+            .unwrap();
+
+        // actions of one room are applied one after the other
+        let (first, second) = tokio::join!(
+            broadcaster.process_event(1, create_action_message(1, TestAction::Increment)),
+            broadcaster.process_event(2, create_action_message(2, TestAction::Add(2))),
+        );
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+        let (version, state) = broadcaster.room_state(first_room).await.unwrap();
+        assert_eq!((version, state.counter), (2, 3));
+
+        // the client registry is not locked during the dispatch
+        let (first, second) = tokio::join!(
+            broadcaster.process_event(1, create_action_message(1, TestAction::Increment)),
+            broadcaster.process_event(3, create_action_message(3, TestAction::Increment)),
+        );
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_does_not_lock_rooms_of_the_same_bucket() {
+        let reducer = SlowReducer::default();
+        let in_flight = reducer.in_flight.clone();
+        let broadcaster = Broadcaster::<MockSink, SlowReducer>::new(reducer);
+        for client_id in 1..=2 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+        let first_room = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        let mut second_room = first_room;
+        while second_room % ROOM_SHARDS as u64 != first_room % ROOM_SHARDS as u64
+            || second_room == first_room
+        {
+            second_room = broadcaster.preload_room(SlowReducer::default()).await;
+        }
+
+        // the second client joins a room of the same bucket while the action is dispatched
+        let (action, in_flight_after_join) = tokio::join!(
+            broadcaster.process_event(1, create_action_message(1, TestAction::Increment)),
+            async {
+                while in_flight.load(Ordering::SeqCst) == 0 {
+                    tokio::task::yield_now().await;
+                }
+                broadcaster.handle_join(2, second_room, None).await.unwrap();
+                in_flight.load(Ordering::SeqCst)
+            },
+        );
+        assert!(action.is_ok());
+        assert_eq!(in_flight_after_join, 1);
+    }
+
+    #[tokio::test]
+    async fn test_state_diffs() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_state_diffs(true);
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let mut client_state = serde_json::to_value(TestState::default()).unwrap();
+        let actions = [
+            TestAction::Message("hello".to_string()),
+            TestAction::Add(5),
+            TestAction::Message("world".to_string()),
+        ];
+        for action in actions {
+            let mut stream = MockStream {
+                messages: vec![create_action_message(1, action)],
+                index: 0,
+            };
+            broadcaster.handle_rx(1, &mut stream).await;
+
+            let payload = match get_last_response(&responses) {
+                Some(Response::Action(payload, _)) => payload,
+                response => panic!("Expected Action response, got {:?}", response),
+            };
+            let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+            assert!(payload.get("state").is_none());
+            let patch: json_patch::Patch =
+                serde_json::from_value(payload["patch"].clone()).unwrap();
+            json_patch::patch(&mut client_state, &patch).unwrap();
+
+            let (_, state) = broadcaster.room_state(room_id).await.unwrap();
+            assert_eq!(client_state, serde_json::to_value(state).unwrap());
+        }
+
+        broadcaster.set_state_diffs(false);
+        let action = create_action_message(1, TestAction::Increment);
+        match broadcaster.process_event(1, action).await.unwrap() {
+            EventResponse::Room(room_response) => assert!(matches!(
+                room_response.response,
+                Response::Action(payload, _) if payload.contains("\"state\"")
+            )),
+            _ => panic!("Expected per-room response"),
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct SecretReducer {
+        inner: TestReducer,
+        secret_from: u64,
+    }
+
+    impl Dispatchable for SecretReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.dispatch(client_id, action).await
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.extern_dispatch(client_id, action_str).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+
+        fn should_broadcast_to(
+            &self,
+            recipient_id: u64,
+            response: &ActionResponse<TestState>,
+        ) -> bool {
+            recipient_id != self.secret_from || response.author == recipient_id
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_broadcast_to_hides_actions() {
+        for room_actors in [false, true] {
+            let mut broadcaster = Broadcaster::<MockSink, SecretReducer>::new(SecretReducer {
+                secret_from: 3,
+                ..SecretReducer::default()
+            });
+            broadcaster.set_room_actors(room_actors);
+            let mut responses = Vec::new();
+            for client_id in 1..=3 {
+                let client_responses = Arc::new(StdMutex::new(Vec::new()));
+                let sink = MockSink {
+                    responses: client_responses.clone(),
+                };
+                broadcaster
+                    .add_client_connection(create_client(client_id), sink)
+                    .await;
+                responses.push(client_responses);
+            }
+            let room_id = broadcaster
+                .handle_create(1, CreateOptions::default())
+                .await
+                .unwrap()
+                .room;
+            broadcaster.handle_join(2, room_id, None).await.unwrap();
+            broadcaster.handle_join(3, room_id, None).await.unwrap();
+
+            let mut stream = MockStream {
+                messages: vec![create_action_message(1, TestAction::Increment)],
+                index: 0,
+            };
+            broadcaster.handle_rx(1, &mut stream).await;
+
+            assert_eq!(get_response_count(&responses[0]), 1);
+            assert_eq!(get_response_count(&responses[1]), 1);
+            assert_eq!(get_response_count(&responses[2]), 0);
+
+            broadcaster
+                .dispatch_with_callback(2, r#""Increment""#, |_, _| {})
+                .await
+                .unwrap();
+            assert_eq!(get_response_count(&responses[0]), 2);
+            assert_eq!(get_response_count(&responses[1]), 2);
+            assert_eq!(get_response_count(&responses[2]), 0);
+
+            let mut stream = MockStream {
+                messages: vec![create_action_message(3, TestAction::Increment)],
+                index: 0,
+            };
+            broadcaster.handle_rx(3, &mut stream).await;
+            assert_eq!(get_response_count(&responses[0]), 3);
+            assert!(matches!(
+                get_last_response(&responses[2]),
+                Some(Response::Action(payload, _)) if payload.contains("\"counter\":3")
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_replays_missed_actions() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_history_size(2);
+        broadcaster.set_session_grace(Some(Duration::from_secs(60)));
+        let mut responses = Vec::new();
+        // clients 3 and 4 are later connections of the device of client 1
+        for (client_id, token) in [
+            (1, "device-1"),
+            (2, "device-2"),
+            (3, "device-1"),
+            (4, "device-1"),
+            (5, "device-5"),
+        ] {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            let client = Client::new(client_id, None, String::new(), token.to_string());
+            broadcaster.add_client_connection(client, sink).await;
+            responses.push(client_responses);
+        }
+        let mut session_tokens = Vec::new();
+        for client_id in [1, 3] {
+            broadcaster.open_session(client_id).await;
+            match responses[client_id as usize - 1].lock().unwrap().remove(0) {
+                Response::SessionToken(token) => session_tokens.push(token),
+                response => panic!("Expected SessionToken response, got {:?}", response),
+            }
+        }
+        assert_ne!(session_tokens[0], session_tokens[1]);
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        let increment = || create_action_message(2, TestAction::Increment);
+        broadcaster.handle_event(2, increment()).await;
+        assert_eq!(get_response_count(&responses[0]), 1);
+        broadcaster.remove_client_connection(1).await;
+        broadcaster.handle_event(2, increment()).await;
+        broadcaster.handle_event(2, increment()).await;
+
+        let resume = |token: &String, last_seq| {
+            create_message(
+                3,
+                JointMessageMethod::Resume {
+                    token: token.clone(),
+                    last_seq,
+                },
+            )
+        };
+        // the session of another client's token can't be taken over
+        let result = broadcaster
+            .process_event(5, resume(&session_tokens[0], 1))
+            .await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::NotFound {
+                code: ErrorCode::SessionNotFound,
+                ..
+            }
+        ));
+        assert_eq!(broadcaster.clients.read().await[&5].room_id, None);
+        assert_eq!(broadcaster.clients.read().await[&5].token, "device-5");
+
+        broadcaster
+            .handle_event(3, resume(&session_tokens[0], 1))
+            .await;
+        let replayed = responses[2].lock().unwrap().clone();
+        assert_eq!(replayed.len(), 3);
+        for (response, counter) in replayed.iter().zip([2, 3]) {
+            match response {
+                Response::Action(payload, _) => {
+                    let action: ActionResponse<TestState> = serde_json::from_str(payload).unwrap();
+                    assert_eq!(action.state.counter, counter);
+                }
+                response => panic!("Expected Action response, got {:?}", response),
+            }
+        }
+        assert!(matches!(replayed[2], Response::RoomJoined(3)));
+        assert!(matches!(
+            get_last_response(&responses[1]),
+            Some(Response::RoomJoined(3))
+        ));
+
+        broadcaster.remove_client_connection(3).await;
+        broadcaster.handle_event(2, increment()).await;
+        broadcaster.handle_event(2, increment()).await;
+        broadcaster
+            .handle_event(4, resume(&session_tokens[1], 2))
+            .await;
+        assert!(matches!(
+            responses[3].lock().unwrap().first(),
+            Some(Response::StateSent(state, _)) if state.contains("\"counter\":5")
+        ));
+
+        // sessions are resumed once
+        let result = broadcaster
+            .process_event(4, resume(&session_tokens[1], 5))
+            .await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::NotFound { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resume_replays_actions_with_their_stamps() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_history_size(4);
+        broadcaster.set_session_grace(Some(Duration::from_secs(60)));
+        broadcaster.set_broadcast_stamps(true);
+        let mut responses = Vec::new();
+        // client 3 is a later connection of the device of client 1
+        for (client_id, token) in [(1, "device-1"), (2, "device-2"), (3, "device-1")] {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            let client = Client::new(client_id, None, String::new(), token.to_string());
+            broadcaster.add_client_connection(client, sink).await;
+            responses.push(client_responses);
+        }
+        broadcaster.open_session(1).await;
+        let session_token = match responses[0].lock().unwrap().remove(0) {
+            Response::SessionToken(token) => token,
+            response => panic!("Expected SessionToken response, got {:?}", response),
+        };
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        let increment = || create_action_message(2, TestAction::Increment);
+        broadcaster.handle_event(2, increment()).await;
+        broadcaster.remove_client_connection(1).await;
+        broadcaster.handle_event(2, increment()).await;
+        broadcaster.handle_event(2, increment()).await;
+
+        let resume = create_message(
+            3,
+            JointMessageMethod::Resume {
+                token: session_token,
+                last_seq: 1,
+            },
+        );
+        broadcaster.handle_event(3, resume).await;
+
+        let stamps = |responses: &[Response]| -> Vec<Stamp> {
+            responses
+                .iter()
+                .filter_map(|response| match response {
+                    Response::Action(_, stamp) => Some(stamp.expect("Action should be stamped")),
+                    _ => None,
+                })
+                .collect()
+        };
+        let live = stamps(&responses[1].lock().unwrap());
+        let replayed = stamps(&responses[2].lock().unwrap());
+        assert_eq!(live.iter().map(|stamp| stamp.seq).collect::<Vec<_>>(), [1, 2, 3]);
+        // the missed actions are replayed with the stamps they were broadcast with
+        assert_eq!(replayed, live[1..]);
+    }
+
+    #[tokio::test]
+    async fn test_mute_rejects_actions() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let mut responses = Vec::new();
+        for client_id in 1..=3 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster.handle_join(3, room_id, None).await.unwrap();
+
+        let result = broadcaster
+            .process_event(3, create_message(3, JointMessageMethod::Mute(2)))
+            .await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { .. }
+        ));
+
+        broadcaster
+            .handle_event(1, create_message(1, JointMessageMethod::Mute(2)))
+            .await;
+        for client_responses in &responses {
+            assert!(matches!(
+                get_last_response(client_responses),
+                Some(Response::Muted(2))
+            ));
+        }
+
+        broadcaster
+            .handle_event(2, create_action_message(2, TestAction::Increment))
+            .await;
+        assert!(matches!(
+            get_last_response(&responses[1]),
+            Some(Response::Muted(2))
+        ));
+        assert!(matches!(
+            get_last_response(&responses[2]),
+            Some(Response::Muted(2))
+        ));
+
+        broadcaster
+            .handle_event(3, create_action_message(3, TestAction::Increment))
+            .await;
+        assert!(matches!(
+            get_last_response(&responses[1]),
+            Some(Response::Action(payload, _)) if payload.contains("\"counter\":1")
+        ));
+
+        broadcaster
+            .handle_event(1, create_message(1, JointMessageMethod::Unmute(2)))
+            .await;
+        assert!(matches!(
+            get_last_response(&responses[1]),
+            Some(Response::Unmuted(2))
+        ));
+        broadcaster
+            .handle_event(2, create_action_message(2, TestAction::Increment))
+            .await;
+        assert!(matches!(
+            get_last_response(&responses[2]),
+            Some(Response::Action(payload, _)) if payload.contains("\"counter\":2")
+        ));
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct CountedState {
+        counter: i32,
+        serializations: Arc<AtomicUsize>,
+    }
+
+    impl Serialize for CountedState {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.serializations.fetch_add(1, Ordering::SeqCst);
+            self.counter.serialize(serializer)
+        }
+    }
+
+    impl Broadcastable for CountedState {}
+
+    #[derive(Clone, Default)]
+    struct ArcReducer {
+        state: Arc<CountedState>,
+    }
+
+    impl Dispatchable for ArcReducer {
+        type Action = TestAction;
+        type State = CountedState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            _action: TestAction,
+        ) -> Result<ActionResponse<CountedState>, String> {
+            Arc::make_mut(&mut self.state).counter += 1;
+            Ok(ActionResponse {
+                status: "success".to_string(),
+                state: self.get_state(),
+                author: client_id,
+                data: String::new(),
+            })
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<CountedState>, String> {
+            let action: TestAction = serde_json::from_str(action_str).map_err(|e| e.to_string())?;
+            self.dispatch(client_id, action).await
+        }
+
+        fn get_state(&self) -> CountedState {
+            (*self.state).clone()
+        }
+
+        fn state_arc(&self) -> Arc<CountedState> {
+            self.state.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_is_serialized_once_for_all_recipients() {
+        let reducer = ArcReducer::default();
+        let serializations = reducer.state.serializations.clone();
+        let broadcaster = Broadcaster::<MockSink, ArcReducer>::new(reducer);
+        let mut responses = Vec::new();
+        for client_id in 1..=4 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+        let received_state = |client_responses: &Arc<StdMutex<Vec<Response>>>, expected: &str| {
+            client_responses.lock().unwrap().iter().any(
+                |response| matches!(response, Response::StateSent(state, _) if state == expected),
+            )
+        };
+
+        let mut stream = MockStream {
+            messages: vec![create_message(1, JointMessageMethod::Create(None))],
+            index: 0,
+        };
+        broadcaster.handle_rx(1, &mut stream).await;
+        let room_id = broadcaster.clients.read().await[&1].room_id.unwrap();
+        for client_id in 2..=3 {
+            let mut stream = MockStream {
+                messages: vec![create_message(client_id, JointMessageMethod::Join(room_id))],
+                index: 0,
+            };
+            broadcaster.handle_rx(client_id, &mut stream).await;
+        }
+        assert_eq!(serializations.load(Ordering::SeqCst), 1);
+        for client_responses in &responses[..3] {
+            assert!(received_state(client_responses, "0"));
+        }
+
+        let mut stream = MockStream {
+            messages: vec![create_action_message(1, TestAction::Increment)],
+            index: 0,
+        };
+        broadcaster.handle_rx(1, &mut stream).await;
+        assert_eq!(serializations.load(Ordering::SeqCst), 2);
+        for client_responses in &responses[..3] {
+            assert!(matches!(
+                get_last_response(client_responses),
+                Some(Response::Action(payload, _)) if payload.contains("\"state\":1")
+            ));
+        }
+
+        let mut stream = MockStream {
+            messages: vec![create_message(4, JointMessageMethod::Join(room_id))],
+            index: 0,
+        };
+        broadcaster.handle_rx(4, &mut stream).await;
+        assert_eq!(serializations.load(Ordering::SeqCst), 3);
+        assert!(received_state(&responses[3], "1"));
+    }
+
+    #[tokio::test]
+    async fn test_waitlisted_clients_join_in_order() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let mut responses = Vec::new();
+        for client_id in 1..=4 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+        let send = |client_id: u64, method: JointMessageMethod| {
+            let broadcaster = &broadcaster;
+            async move {
+                let mut stream = MockStream {
+                    messages: vec![create_message(client_id, method)],
+                    index: 0,
+                };
+                broadcaster.handle_rx(client_id, &mut stream).await;
+            }
+        };
+
+        let options = CreateOptions {
+            capacity: Some(2),
+            waitlist: true,
+            ..CreateOptions::default()
+        };
+        send(1, JointMessageMethod::Create(Some(options))).await;
+        let room_id = broadcaster.clients.read().await[&1].room_id.unwrap();
+        send(2, JointMessageMethod::Join(room_id)).await;
+        send(3, JointMessageMethod::Join(room_id)).await;
+        send(4, JointMessageMethod::Join(room_id)).await;
+        assert!(matches!(
+            get_last_response(&responses[2]),
+            Some(Response::Waitlisted { position: 1 })
+        ));
+        assert!(matches!(
+            get_last_response(&responses[3]),
+            Some(Response::Waitlisted { position: 2 })
+        ));
+        assert_eq!(
+            broadcaster.rooms.read(room_id).await[&room_id]
+                .client_ids
+                .len(),
+            2
+        );
+
+        send(2, JointMessageMethod::Leave).await;
+        assert_eq!(broadcaster.clients.read().await[&3].room_id, Some(room_id));
+        assert_eq!(broadcaster.clients.read().await[&4].room_id, None);
+        assert!(responses[2]
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|response| matches!(response, Response::StateSent(..))));
+        assert!(matches!(
+            get_last_response(&responses[0]),
+            Some(Response::RoomJoined(3))
+        ));
+
+        broadcaster.remove_client_connection(1).await;
+        assert_eq!(broadcaster.clients.read().await[&4].room_id, Some(room_id));
+        assert!(matches!(
+            get_last_response(&responses[2]),
+            Some(Response::RoomJoined(4))
+        ));
+        let room = &broadcaster.rooms.read(room_id).await[&room_id];
+        assert_eq!(room.client_ids, HashSet::from([3, 4]));
+        assert!(room.waitlist.is_empty());
+    }
+
+    #[derive(Clone, Default)]
+    struct PriorityReducer {
+        inner: SlowReducer,
+    }
+
+    impl Dispatchable for PriorityReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.dispatch(client_id, action).await
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.extern_dispatch(client_id, action_str).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+
+        fn priority(action: &TestAction) -> u8 {
+            match action {
+                TestAction::Increment => 0,
+                _ => 1,
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_load_shedding_rejects_low_priority_actions_under_pressure() {
+        let mut broadcaster =
+            Broadcaster::<MockSink, PriorityReducer>::new(PriorityReducer::default());
+        broadcaster.set_load_shedding(Some(LoadShedding::new(1, 1)));
+        // actions queue at the room actor, so each is checked before the previous ones complete
+        broadcaster.set_room_actors(true);
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let (first, second, low, high) = tokio::join!(
+            broadcaster.process_event(1, create_action_message(1, TestAction::Add(1))),
+            broadcaster.process_event(1, create_action_message(1, TestAction::Add(1))),
+            broadcaster.process_event(1, create_action_message(1, TestAction::Increment)),
+            broadcaster.process_event(1, create_action_message(1, TestAction::Add(5))),
+        );
+        assert!(first.is_ok() && second.is_ok() && high.is_ok());
+        assert!(matches!(low.err().unwrap().response, Response::Overloaded));
+        let (_, state) = broadcaster.room_state(room_id).await.unwrap();
+        assert_eq!(state.counter, 7);
+
+        let result = broadcaster
+            .process_event(1, create_action_message(1, TestAction::Increment))
+            .await;
+        assert!(result.is_ok());
+        let (_, state) = broadcaster.room_state(room_id).await.unwrap();
+        assert_eq!(state.counter, 8);
+    }
+
+    #[tokio::test]
+    async fn test_fanout_is_recorded_and_capped() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_max_fanout(Some(20));
+        let mut responses = Vec::new();
+        for client_id in 1..=50 {
+            let client_responses = Arc::new(StdMutex::new(Vec::new()));
+            let sink = MockSink {
+                responses: client_responses.clone(),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+            responses.push(client_responses);
+        }
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        for client_id in 2..=10 {
+            broadcaster
+                .handle_join(client_id, room_id, None)
+                .await
+                .unwrap();
+        }
+
+        broadcaster
+            .handle_event(1, create_action_message(1, TestAction::Add(2)))
+            .await;
+        let stats = broadcaster.stats().await;
+        assert_eq!(
+            (stats.broadcasts_total, stats.fanout_total, stats.max_fanout),
+            (1, 10, 10)
+        );
+        assert!(matches!(
+            get_last_response(&responses[9]),
+            Some(Response::Action(..))
+        ));
+
+        for client_id in 11..=50 {
+            broadcaster
+                .handle_join(client_id, room_id, None)
+                .await
+                .unwrap();
+        }
+        let received_before = get_response_count(&responses[49]);
+        broadcaster
+            .handle_event(1, create_action_message(1, TestAction::Add(3)))
+            .await;
+        let stats = broadcaster.stats().await;
+        assert_eq!(stats.capped_broadcasts_total, 1);
+        assert_eq!(stats.broadcasts_total, 1);
+        assert_eq!(get_response_count(&responses[49]), received_before);
+        assert_eq!(
+            broadcaster
+                .react_on_message(room_id, Response::Action("{}".to_string(), None))
+                .await,
+            None
+        );
+        assert_eq!(broadcaster.stats().await.capped_broadcasts_total, 2);
+
+        broadcaster
+            .handle_event(50, create_message(50, JointMessageMethod::Sync))
+            .await;
+        match get_last_response(&responses[49]) {
+            Some(Response::StateSent(state, _)) => {
+                let state: TestState = serde_json::from_str(&state).unwrap();
+                assert_eq!(state.counter, 5);
+            }
+            other => panic!("Expected StateSent response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_or_join_races_into_a_single_room() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        for client_id in 1..=3 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+
+        let create_or_join = |client_id| {
+            create_message(
+                client_id,
+                JointMessageMethod::CreateOrJoin("document-42".to_string()),
+            )
+        };
+        let (first, second) = tokio::join!(
+            broadcaster.process_event(1, create_or_join(1)),
+            broadcaster.process_event(2, create_or_join(2)),
+        );
+        assert!(first.is_ok() && second.is_ok());
+
+        let rooms = broadcaster.get_rooms();
+        assert_eq!(rooms.room_count().await, 1);
+        let room_id = broadcaster.clients.read().await[&1].room_id.unwrap();
+        let members = rooms.read(room_id).await[&room_id].client_ids.clone();
+        assert_eq!(members, HashSet::from([1, 2]));
+
+        let other_key = create_message(3, JointMessageMethod::CreateOrJoin("other".to_string()));
+        broadcaster.process_event(3, other_key).await.unwrap();
+        assert_eq!(rooms.room_count().await, 2);
+    }
+
+    #[derive(Clone)]
+    struct ConfiguredReducer {
+        state: TestState,
+        step: i32,
+    }
+
+    impl ConfiguredReducer {
+        fn new(initial: i32, step: i32) -> Self {
+            ConfiguredReducer {
+                state: TestState {
+                    counter: initial,
+                    messages: Vec::new(),
+                },
+                step,
+            }
+        }
+    }
+
+    impl Dispatchable for ConfiguredReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            _action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.state.counter += self.step;
+            DispatchContext::new(client_id).ok(self, "")
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            let action = serde_json::from_str(action_str).map_err(|e| e.to_string())?;
+            self.dispatch(client_id, action).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.state.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preload_room_with_constructed_reducer() {
+        let broadcaster =
+            Broadcaster::<MockSink, ConfiguredReducer>::new(ConfiguredReducer::new(0, 1));
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+
+        let room_id = broadcaster
+            .preload_room(ConfiguredReducer::new(100, 10))
+            .await;
+        broadcaster
+            .handle_event(1, create_message(1, JointMessageMethod::Join(room_id)))
+            .await;
+        let initial_state = responses
+            .lock()
+            .unwrap()
+            .iter()
+            .find_map(|response| match response {
+                Response::StateSent(state, _) => Some(state.clone()),
+                _ => None,
+            })
+            .expect("Expected StateSent response");
+        let initial_state: TestState = serde_json::from_str(&initial_state).unwrap();
+        assert_eq!(initial_state.counter, 100);
+
+        broadcaster
+            .handle_event(1, create_action_message(1, TestAction::Increment))
+            .await;
+        let (_, state) = broadcaster.room_state(room_id).await.unwrap();
+        assert_eq!(state.counter, 110);
+
+        broadcaster
+            .handle_event(1, create_message(1, JointMessageMethod::Leave))
+            .await;
+        broadcaster
+            .handle_event(1, create_message(1, JointMessageMethod::Create(None)))
+            .await;
+        let created = broadcaster.clients.read().await[&1].room_id.unwrap();
+        assert_ne!(created, room_id);
+        let (_, state) = broadcaster.room_state(created).await.unwrap();
+        assert_eq!(state.counter, 0);
+    }
+
+    #[tokio::test]
+    async fn test_room_group_membership_is_exclusive() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_multi_room(true);
+        for client_id in 1..=4 {
+            let sink = MockSink {
+                responses: Arc::new(StdMutex::new(Vec::new())),
+            };
+            broadcaster
+                .add_client_connection(create_client(client_id), sink)
+                .await;
+        }
+        let group_options = |room_group: &str| CreateOptions {
+            room_group: Some(room_group.to_string()),
+            ..CreateOptions::default()
+        };
+        let team_a = broadcaster
+            .handle_create(1, group_options("teams"))
+            .await
+            .unwrap()
+            .room;
+        let team_b = broadcaster
+            .handle_create(2, group_options("teams"))
+            .await
+            .unwrap()
+            .room;
+        let squad = broadcaster
+            .handle_create(3, group_options("squads"))
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(4, team_a, None).await.unwrap();
+        let result = broadcaster.handle_create(4, group_options("teams")).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError {
+                code: ErrorCode::AlreadyInRoom,
+                ..
+            }
+        ));
+
+        let result = broadcaster.handle_join(4, team_b, None).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { code: ErrorCode::AlreadyInRoom, message: reason } if reason == "Already in a room of group teams"
+        ));
+        assert!(!broadcaster.get_rooms().read(team_b).await[&team_b]
+            .client_ids
+            .contains(&4));
+
+        broadcaster.handle_join(4, squad, None).await.unwrap();
+        assert!(broadcaster.get_rooms().read(squad).await[&squad]
+            .client_ids
+            .contains(&4));
+
+        // leaving team A frees the client to join another team
+        broadcaster.handle_leave_room(4, team_a).await.unwrap();
+        broadcaster.handle_join(4, team_b, None).await.unwrap();
+        assert!(broadcaster.clients.read().await[&4].in_room(team_b));
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_schema_version_is_rejected() {
+        let broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+
+        let mut event = create_message(1, JointMessageMethod::Create(None));
+        event.v = Some(99);
+        let result = broadcaster.process_event(1, event).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { code: ErrorCode::UnsupportedVersion, message: reason } if reason == "Unsupported schema version 99, expected 1"
+        ));
+        assert_eq!(broadcaster.stats().await.rooms, 0);
+
+        let mut event = create_message(1, JointMessageMethod::Create(None));
+        event.v = Some(SCHEMA_VERSION);
+        assert!(broadcaster.process_event(1, event).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_compressed_history_replays_identically() {
+        let mut replays = Vec::new();
+        let mut history_bytes = Vec::new();
+        for compression in [Compression::None, Compression::Deflate] {
+            let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+            broadcaster.set_history_size(8);
+            broadcaster.set_history_compression(compression);
+            broadcaster.set_session_grace(Some(Duration::from_secs(60)));
+            let mut responses = Vec::new();
+            for (client_id, token) in [(1, "device-1"), (2, "device-2"), (3, "device-1")] {
+                let client_responses = Arc::new(StdMutex::new(Vec::new()));
+                let sink = MockSink {
+                    responses: client_responses.clone(),
+                };
+                let client = Client::new(client_id, None, String::new(), token.to_string());
+                broadcaster.add_client_connection(client, sink).await;
+                responses.push(client_responses);
+            }
+            broadcaster.open_session(1).await;
+            let session_token = broadcaster.clients.read().await[&1].session_token.clone();
+            let room_id = broadcaster
+                .handle_create(1, CreateOptions::default())
+                .await
+                .unwrap()
+                .room;
+            broadcaster.handle_join(2, room_id, None).await.unwrap();
+            broadcaster.remove_client_connection(1).await;
+
+            for _ in 0..4 {
+                let message = TestAction::Message("the same chat line ".repeat(16));
+                broadcaster
+                    .handle_event(2, create_action_message(2, message))
+                    .await;
+            }
+            history_bytes
+                .push(broadcaster.get_rooms().read(room_id).await[&room_id].history_bytes());
+
+            let resume = JointMessageMethod::Resume {
+                token: session_token,
+                last_seq: 0,
+            };
+            broadcaster.handle_event(3, create_message(3, resume)).await;
+            let replayed = responses[2]
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(|response| match response {
+                    Response::Action(payload, _) => Some(payload.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(replayed.len(), 4);
+            replays.push(replayed);
+        }
+
+        assert_eq!(replays[0], replays[1]);
+        assert!(history_bytes[1] < history_bytes[0] / 2);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_multi_room_applies_all_or_nothing() {
+        let mut broadcaster = Broadcaster::<MockSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_max_state_bytes(Some(200));
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        broadcaster
+            .add_client_connection(create_client(1), sink)
+            .await;
+        let room_a = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        let room_b = broadcaster
+            .preload_room(TestReducer {
+                state: TestState {
+                    counter: 0,
+                    messages: vec!["x".repeat(150)],
+                },
+            })
+            .await;
+
+        let add = serde_json::to_string(&TestAction::Add(5)).unwrap();
+        let results = broadcaster
+            .dispatch_multi_room(&[room_b, room_a], 9, &add)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.author == 9));
+        for room_id in [room_a, room_b] {
+            let (version, state) = broadcaster.room_state(room_id).await.unwrap();
+            assert_eq!((version, state.counter), (1, 5));
+        }
+        assert!(matches!(
+            get_last_response(&responses),
+            Some(Response::Action(payload, _)) if payload.contains("\"counter\":5")
+        ));
+
+        // fits in room A, but pushes room B over the state size limit
+        let message = serde_json::to_string(&TestAction::Message("y".repeat(40))).unwrap();
+        let count = get_response_count(&responses);
+        let result = broadcaster
+            .dispatch_multi_room(&[room_a, room_b], 9, &message)
+            .await;
+        assert_eq!(result.unwrap_err(), "State size limit exceeded");
+        let (version, state) = broadcaster.room_state(room_a).await.unwrap();
+        assert_eq!((version, state.messages.len()), (1, 0));
+        let (version, state) = broadcaster.room_state(room_b).await.unwrap();
+        assert_eq!((version, state.messages.len()), (1, 1));
+        assert_eq!(get_response_count(&responses), count);
+
+        let result = broadcaster
+            .dispatch_multi_room(&[room_a, room_b + 1], 9, &add)
+            .await;
+        assert_eq!(
+            result.unwrap_err(),
+            format!("Room {} not found", room_b + 1)
+        );
+        assert_eq!(broadcaster.room_state(room_a).await.unwrap().1.counter, 5);
+    }
+
+    async fn room_with_clients(
+        options: CreateOptions,
+        client_count: u64,
+    ) -> (
+        Broadcaster<RecordingSink, TestReducer>,
+        u64,
+        Vec<RecordingSink>,
+    ) {
+        let broadcaster = Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+        let mut sinks = Vec::new();
+        for client_id in 1..=client_count {
+            let sink = RecordingSink::new();
+            broadcaster
+                .add_client_connection(create_client(client_id), sink.clone())
+                .await;
+            sinks.push(sink);
+        }
+        broadcaster
+            .handle_event(
+                1,
+                create_message(1, JointMessageMethod::Create(Some(options))),
+            )
+            .await;
+        let room_id = broadcaster.clients.read().await[&1].room_id.unwrap();
+        for client_id in 2..=client_count {
+            broadcaster
+                .handle_event(
+                    client_id,
+                    create_message(client_id, JointMessageMethod::Join(room_id)),
+                )
+                .await;
+        }
+        (broadcaster, room_id, sinks)
+    }
+
+    #[tokio::test]
+    async fn test_update_room_config() {
+        let (broadcaster, room_id, sinks) = room_with_clients(CreateOptions::default(), 2).await;
+
+        let config = RoomConfig {
+            name: Some("Lobby".to_string()),
+            capacity: Some(3),
+            password: Some("secret".to_string()),
+            ..RoomConfig::default()
+        };
+        broadcaster
+            .handle_event(
+                1,
+                create_message(1, JointMessageMethod::UpdateRoomConfig(config)),
+            )
+            .await;
+
+        let expected = Response::RoomConfigChanged(RoomConfigInfo {
+            name: Some("Lobby".to_string()),
+            capacity: Some(3),
+            rate_limit: None,
+            private: true,
+        });
+        for sink in &sinks {
+            assert_eq!(sink.last(), Some(expected.clone()));
+        }
+        {
+            let rooms = broadcaster.rooms.read(room_id).await;
+            let room = &rooms[&room_id];
+            assert_eq!(room.name.as_deref(), Some("Lobby"));
+            assert_eq!(room.capacity, Some(3));
+            assert!(matches!(&room.status, RoomStatus::Private(password) if password == "secret"));
+        }
+
+        broadcaster
+            .add_client_connection(create_client(3), RecordingSink::new())
+            .await;
+        let result = broadcaster.handle_join(3, room_id, None).await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::WrongPassword(_)
+        ));
+
+        // omitted fields reset to their defaults
+        broadcaster
+            .handle_event(
+                1,
+                create_message(
+                    1,
+                    JointMessageMethod::UpdateRoomConfig(RoomConfig::default()),
+                ),
+            )
+            .await;
+        assert!(broadcaster.handle_join(3, room_id, None).await.is_ok());
+        let rooms = broadcaster.rooms.read(room_id).await;
+        assert_eq!(rooms[&room_id].name, None);
+        assert_eq!(rooms[&room_id].capacity, None);
+    }
+
+    #[tokio::test]
+    async fn test_update_room_config_is_owner_only() {
+        let (broadcaster, room_id, sinks) = room_with_clients(CreateOptions::default(), 2).await;
+
+        let config = RoomConfig {
+            capacity: Some(5),
+            ..RoomConfig::default()
+        };
+        let result = broadcaster
+            .process_event(
+                2,
+                create_message(2, JointMessageMethod::UpdateRoomConfig(config)),
+            )
+            .await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { code: ErrorCode::Unauthorized, message: reason } if reason.contains("owner")
+        ));
+        assert_eq!(
+            broadcaster.rooms.read(room_id).await[&room_id].capacity,
+            None
+        );
+        for sink in &sinks {
+            assert!(sink
+                .received_of_kind(ResponseStatus::RoomConfigChanged)
+                .is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_room_config_capacity_below_occupancy() {
+        let options = CreateOptions {
+            capacity: Some(3),
+            waitlist: true,
+            ..CreateOptions::default()
+        };
+        let (broadcaster, room_id, sinks) = room_with_clients(options, 4).await;
+        assert_eq!(sinks[3].last(), Some(Response::Waitlisted { position: 1 }));
+
+        // shrinking below the current occupancy is rejected, nobody is evicted
+        let config = RoomConfig {
+            capacity: Some(2),
+            ..RoomConfig::default()
+        };
+        let result = broadcaster
+            .process_event(
+                1,
+                create_message(1, JointMessageMethod::UpdateRoomConfig(config)),
+            )
+            .await;
+        assert!(matches!(
+            result.err().unwrap().response,
+            Response::ClientError { code: ErrorCode::InvalidConfig, message: reason } if reason.contains("below its 3 current clients")
+        ));
+        {
+            let rooms = broadcaster.rooms.read(room_id).await;
+            assert_eq!(rooms[&room_id].capacity, Some(3));
+            assert_eq!(rooms[&room_id].client_ids.len(), 3);
+        }
+
+        // growing the capacity admits the waitlisted client
+        let config = RoomConfig {
+            capacity: Some(4),
+            ..RoomConfig::default()
+        };
+        broadcaster
+            .handle_event(
+                1,
+                create_message(1, JointMessageMethod::UpdateRoomConfig(config)),
+            )
+            .await;
+        assert_eq!(broadcaster.clients.read().await[&4].room_id, Some(room_id));
+        assert_eq!(
+            sinks[3].received_of_kind(ResponseStatus::StateSent).len(),
+            1
+        );
+        for sink in &sinks {
+            assert_eq!(
+                sink.received_of_kind(ResponseStatus::RoomConfigChanged)
+                    .len(),
+                1
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_who_am_i_describes_the_session() {
+        let (broadcaster, room_id, sinks) = room_with_clients(CreateOptions::default(), 2).await;
+        broadcaster
+            .handle_event(1, create_message(1, JointMessageMethod::Mute(2)))
+            .await;
+        for sink in &sinks {
+            sink.clear();
+        }
+
+        for client_id in 1..=2 {
+            broadcaster
+                .handle_event(
+                    client_id,
+                    create_message(client_id, JointMessageMethod::WhoAmI),
+                )
+                .await;
+        }
+        assert_eq!(
+            sinks[0].received(),
+            vec![Response::SessionInfo {
+                client_id: 1,
+                room_id: Some(room_id),
+                label: "User1".to_string(),
+                roles: vec!["member".to_string(), "owner".to_string()],
+            }]
+        );
+        assert_eq!(
+            sinks[1].received(),
+            vec![Response::SessionInfo {
+                client_id: 2,
+                room_id: Some(room_id),
+                label: "User2".to_string(),
+                roles: vec!["member".to_string(), "muted".to_string()],
+            }]
+        );
+
+        broadcaster
+            .handle_event(2, create_message(2, JointMessageMethod::Leave))
+            .await;
+        sinks[1].clear();
+        broadcaster
+            .handle_event(2, create_message(2, JointMessageMethod::WhoAmI))
+            .await;
+        assert_eq!(
+            sinks[1].last(),
+            Some(Response::SessionInfo {
+                client_id: 2,
+                room_id: None,
+                label: "User2".to_string(),
+                roles: Vec::new(),
+            })
+        );
+    }
+
+    /// Reducer whose actions wait for a permit of a shared gate before being applied.
+    #[derive(Clone)]
+    struct GatedReducer {
+        inner: TestReducer,
+        gate: Arc<tokio::sync::Semaphore>,
+    }
+
+    impl Dispatchable for GatedReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.gate.acquire().await.unwrap().forget();
+            self.inner.dispatch(client_id, action).await
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            let action: TestAction = serde_json::from_str(action_str)
+                .map_err(|e| format!("Failed to parse action: {}", e))?;
+            self.dispatch(client_id, action).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_room_actor_interleaves_flooding_client() {
+        let gate = Arc::new(tokio::sync::Semaphore::new(0));
+        let mut broadcaster = Broadcaster::<RecordingSink, GatedReducer>::new(GatedReducer {
+            inner: TestReducer::default(),
+            gate: gate.clone(),
+        });
+        broadcaster.set_room_actors(true);
+        broadcaster.set_record_actions(true);
+        let broadcaster = Arc::new(broadcaster);
+        for client_id in 1..=2 {
+            broadcaster
+                .add_client_connection(create_client(client_id), RecordingSink::new())
+                .await;
+        }
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+
+        // the first action holds the actor at the gate while the flood backs up behind it
+        let mut tasks = Vec::new();
+        for _ in 0..50 {
+            let broadcaster = broadcaster.clone();
+            tasks.push(tokio::spawn(async move {
+                broadcaster
+                    .handle_event(1, create_action_message(1, TestAction::Increment))
+                    .await;
+            }));
+            tokio::task::yield_now().await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        tasks.push(tokio::spawn({
+            let broadcaster = broadcaster.clone();
+            async move {
+                broadcaster
+                    .handle_event(2, create_action_message(2, TestAction::Add(100)))
+                    .await;
+            }
+        }));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        gate.add_permits(51);
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let rooms = broadcaster.get_rooms();
+        let rooms = rooms.read(room_id).await;
+        let log = &rooms[&room_id].action_log;
+        assert_eq!(log.len(), 51);
+        let position = log.iter().position(|action| action.author == 2).unwrap();
+        assert!(
+            position <= 2,
+            "second client applied at position {}",
+            position
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_client_keeps_id_and_room() {
+        let mut broadcaster =
+            Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_session_grace(Some(Duration::from_secs(60)));
+        let owner = RecordingSink::new();
+        broadcaster
+            .add_client_connection(create_client(1), owner.clone())
+            .await;
+        let client = Client::new(2, None, "User2".to_string(), "session-2".to_string());
+        let sink = RecordingSink::new();
+        broadcaster
+            .add_client_connection(client, sink.clone())
+            .await;
+        broadcaster.open_session(2).await;
+        let session_token = match sink.received_of_kind(ResponseStatus::SessionToken).first() {
+            Some(Response::SessionToken(token)) => token.clone(),
+            other => panic!("Expected SessionToken response, got {:?}", other),
+        };
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster
+            .handle_event(2, create_action_message(2, TestAction::Add(4)))
+            .await;
+
+        // the connection drops, then comes back with the session token
+        broadcaster.remove_client_connection(2).await;
+        assert!(!broadcaster.rooms.read(room_id).await[&room_id]
+            .client_ids
+            .contains(&2));
+        owner.clear();
+
+        let reconnected = RecordingSink::new();
+        let client_id = broadcaster
+            .resume_client(&session_token, reconnected.clone())
+            .await
+            .unwrap();
+        assert_eq!(client_id, 2);
+        assert!(broadcaster.rooms.read(room_id).await[&room_id]
+            .client_ids
+            .contains(&2));
+        {
+            let clients = broadcaster.clients.read().await;
+            assert_eq!(clients[&2].room_id, Some(room_id));
+            assert_eq!(clients[&2].label, "User2");
+        }
+        match reconnected
+            .received_of_kind(ResponseStatus::StateSent)
+            .first()
+        {
+            Some(Response::StateSent(state, _)) => assert!(state.contains("\"counter\":4")),
+            other => panic!("Expected StateSent response, got {:?}", other),
+        }
+        owner.assert_received(Response::RoomJoined(2));
+        // the resumed client is issued a new session token
+        match reconnected.received().last() {
+            Some(Response::SessionToken(token)) => assert_ne!(token, &session_token),
+            other => panic!("Expected SessionToken response, got {:?}", other),
+        }
+
+        // the resumed client keeps acting under its ID
+        broadcaster
+            .handle_event(2, create_action_message(2, TestAction::Increment))
+            .await;
+        assert_eq!(owner.received_of_kind(ResponseStatus::Action).len(), 1);
+
+        // sessions are resumed once
+        assert!(broadcaster
+            .resume_client(&session_token, RecordingSink::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_only_kept_with_a_grace_period() {
+        let broadcaster = Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+        broadcaster
+            .add_client_connection(create_client(1), RecordingSink::new())
+            .await;
+        let sink = RecordingSink::new();
+        let client = Client::new(2, None, "User2".to_string(), "device-2".to_string());
+        broadcaster
+            .add_client_connection(client, sink.clone())
+            .await;
+        broadcaster.open_session(2).await;
+        assert!(sink.is_empty());
+
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster.remove_client_connection(2).await;
+        assert!(broadcaster.sessions.lock().await.is_empty());
+
+        // the client token doesn't resume a session
+        assert!(broadcaster
+            .resume_client("device-2", RecordingSink::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_broadcasts_queued_while_offline_arrive_on_resume() {
+        let mut broadcaster =
+            Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_offline_buffer_size(2);
+        broadcaster.set_session_grace(Some(Duration::from_secs(60)));
+        broadcaster
+            .add_client_connection(create_client(1), RecordingSink::new())
+            .await;
+        let client = Client::new(2, None, "User2".to_string(), "session-2".to_string());
+        broadcaster
+            .add_client_connection(client, RecordingSink::new())
+            .await;
+        broadcaster.open_session(2).await;
+        let session_token = broadcaster.clients.read().await[&2].session_token.clone();
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster.remove_client_connection(2).await;
+
+        // only the latest broadcasts are kept
+        for amount in [1, 2, 3] {
+            broadcaster
+                .handle_event(1, create_action_message(1, TestAction::Add(amount)))
+                .await;
+        }
+
+        let reconnected = RecordingSink::new();
+        broadcaster
+            .resume_client(&session_token, reconnected.clone())
+            .await
+            .unwrap();
+        let received = reconnected.received();
+        assert!(received.len() >= 3);
+        assert!(
+            matches!(&received[0], Response::Action(payload, _) if payload.contains("\"counter\":3"))
+        );
+        assert!(
+            matches!(&received[1], Response::Action(payload, _) if payload.contains("\"counter\":6"))
+        );
+        // the queued broadcasts are followed by the current state
+        assert!(
+            matches!(&received[2], Response::StateSent(state, _) if state.contains("\"counter\":6"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_cannot_be_resumed() {
+        let mut broadcaster =
+            Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+        broadcaster.set_offline_buffer_size(8);
+        broadcaster.set_session_grace(Some(Duration::from_millis(10)));
+        broadcaster
+            .add_client_connection(create_client(1), RecordingSink::new())
+            .await;
+        let client = Client::new(2, None, "User2".to_string(), "session-2".to_string());
+        broadcaster
+            .add_client_connection(client, RecordingSink::new())
+            .await;
+        broadcaster.open_session(2).await;
+        let session_token = broadcaster.clients.read().await[&2].session_token.clone();
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster.handle_join(2, room_id, None).await.unwrap();
+        broadcaster.remove_client_connection(2).await;
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        broadcaster
+            .handle_event(1, create_action_message(1, TestAction::Increment))
+            .await;
+
+        let reconnected = RecordingSink::new();
+        assert!(broadcaster
+            .resume_client(&session_token, reconnected.clone())
+            .await
+            .is_err());
+        assert!(reconnected.is_empty());
+        assert!(!broadcaster.clients.read().await.contains_key(&2));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_simultaneous_broadcasts_share_room_access() {
+        let (broadcaster, room_id, sinks) = room_with_clients(CreateOptions::default(), 8).await;
+        let broadcaster = Arc::new(broadcaster);
+        for sink in &sinks {
+            sink.clear();
+        }
+
+        // a long-running reader of the rooms doesn't hold broadcasts back
+        let rooms = broadcaster.rooms.read(room_id).await;
+        let broadcasts = (0..32).map(|i| {
+            let broadcaster = broadcaster.clone();
+            tokio::spawn(async move {
+                broadcaster
+                    .react_on_message(room_id, Response::RoomJoined(i))
+                    .await
+            })
+        });
+        let recipients = tokio::time::timeout(
+            Duration::from_secs(5),
+            futures::future::join_all(broadcasts),
+        )
+        .await
+        .expect("broadcasts blocked by a reader of the rooms");
+        drop(rooms);
+
+        for count in recipients {
+            assert_eq!(count.unwrap(), Some(8));
+        }
+        for sink in &sinks {
+            let mut joined: Vec<_> = sink
+                .received_of_kind(ResponseStatus::RoomJoined)
+                .into_iter()
+                .map(|response| match response {
+                    Response::RoomJoined(i) => i,
+                    other => panic!("Expected RoomJoined response, got {:?}", other),
+                })
+                .collect();
+            joined.sort_unstable();
+            assert_eq!(joined, (0..32).collect::<Vec<_>>());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_simultaneous_actions_in_many_rooms() {
+        const ROOMS: u64 = 40;
+        const ACTIONS: u64 = 25;
+        for room_actors in [false, true] {
+            let mut broadcaster =
+                Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+            broadcaster.set_room_actors(room_actors);
+            let broadcaster = Arc::new(broadcaster);
+            // more rooms than buckets, so some rooms share a bucket
+            for client_id in 1..=ROOMS {
+                broadcaster
+                    .add_client_connection(create_client(client_id), RecordingSink::new())
+                    .await;
+                broadcaster
+                    .handle_event(
+                        client_id,
+                        create_message(client_id, JointMessageMethod::Create(None)),
+                    )
+                    .await;
+            }
+
+            let clients = (1..=ROOMS).map(|client_id| {
+                let broadcaster = broadcaster.clone();
+                tokio::spawn(async move {
+                    for _ in 0..ACTIONS {
+                        let action = create_action_message(client_id, TestAction::Increment);
+                        broadcaster.process_event(client_id, action).await.unwrap();
+                    }
+                })
+            });
+            tokio::time::timeout(Duration::from_secs(10), futures::future::join_all(clients))
+                .await
+                .expect("actions in separate rooms blocked each other");
+
+            assert_eq!(broadcaster.rooms.room_count().await, ROOMS as usize);
+            for client_id in 1..=ROOMS {
+                let room_id = broadcaster.clients.read().await[&client_id]
+                    .room_id
+                    .unwrap();
+                let (version, state) = broadcaster.room_state(room_id).await.unwrap();
+                assert_eq!(version, ACTIONS);
+                assert_eq!(state.counter, ACTIONS as i32);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overview_receives_room_summaries() {
+        let broadcaster = Broadcaster::<RecordingSink, TestReducer>::new(TestReducer::default());
+        for client_id in 1..=3 {
+            broadcaster
+                .add_client_connection(create_client(client_id), RecordingSink::new())
+                .await;
+        }
+        let lobby = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+
+        let mut overview = broadcaster.subscribe_overview().await;
+        let mut next_summary = || match overview.try_recv() {
+            Ok(Response::RoomSummary(summary)) => (summary.id, summary.client_count),
+            other => panic!("Expected RoomSummary response, got {:?}", other),
+        };
+        // existing rooms are summarized right away
+        assert_eq!(next_summary(), (lobby, 1));
+
+        let game = broadcaster
+            .handle_create(2, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        assert_eq!(next_summary(), (game, 1));
+        broadcaster.handle_join(3, game, None).await.unwrap();
+        assert_eq!(next_summary(), (game, 2));
+        broadcaster.handle_leave(3).await.unwrap();
+        assert_eq!(next_summary(), (game, 1));
+
+        broadcaster.handle_delete(2).await.unwrap();
+        assert!(matches!(overview.try_recv(), Ok(Response::RoomDeleted(id)) if id == game));
+        // deleted rooms are not summarized
+        assert!(overview.try_recv().is_err());
+    }
+
+    /// Sink with a JSON wire format, recording the raw payloads it is sent.
+    #[derive(Clone, Default)]
+    struct RawSink {
+        payloads: Arc<StdMutex<Vec<bytes::Bytes>>>,
+    }
+
+    #[async_trait]
+    impl SinkAdapter for RawSink {
+        async fn send(
+            &mut self,
+            response: Response,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.send_raw(serde_json::to_vec(&response)?.into()).await
+        }
+
+        fn wire_format(&self) -> Option<WireFormat> {
+            Some(WireFormat::Json)
+        }
+
+        async fn send_raw(
+            &mut self,
+            bytes: bytes::Bytes,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.payloads.lock().unwrap().push(bytes);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_is_serialized_once() {
+        let broadcaster = Broadcaster::<RawSink, TestReducer>::new(TestReducer::default());
+        let sinks: Vec<RawSink> = (0..5).map(|_| RawSink::default()).collect();
+        for (client_id, sink) in (1..).zip(&sinks) {
+            broadcaster
+                .add_client_connection(create_client(client_id), sink.clone())
+                .await;
+        }
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        for client_id in 2..=5 {
+            broadcaster
+                .handle_join(client_id, room_id, None)
+                .await
+                .unwrap();
+        }
+
+        let response = Response::Action(r#"{"counter":3}"#.to_string(), None);
+        assert_eq!(
+            broadcaster
+                .react_on_message(room_id, response.clone())
+                .await,
+            Some(5)
+        );
+
+        let expected = serde_json::to_vec(&response).unwrap();
+        let payloads: Vec<bytes::Bytes> = sinks
+            .iter()
+            .map(|sink| sink.payloads.lock().unwrap().last().cloned().unwrap())
+            .collect();
+        for payload in &payloads {
+            // the pre-serialized payload matches the serialization of the response
+            assert_eq!(payload[..], expected[..]);
+            // and every recipient shares the same buffer
+            assert_eq!(payload.as_ptr(), payloads[0].as_ptr());
+        }
+    }
+
+    /// Reducer looking messages up with async I/O, which waits for a permit of `io`.
+    #[derive(Clone)]
+    struct IoReducer {
+        state: TestState,
+        io: Arc<Semaphore>,
+        started: Arc<Notify>,
+        /// The member actions are hidden from, if any.
+        hidden_from: Option<u64>,
+    }
+
+    impl Dispatchable for IoReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            match action {
+                TestAction::Increment => self.state.counter += 1,
+                TestAction::Add(value) => self.state.counter += value,
+                TestAction::Message(message) => self.state.messages.push(message),
+            }
+            DispatchContext::new(client_id).ok(self, "")
+        }
+
+        async fn begin(&mut self, ctx: &DispatchContext, action: TestAction) -> Phase<Self> {
+            match action {
+                TestAction::Message(message) => {
+                    let io = self.io.clone();
+                    let started = self.started.clone();
+                    Phase::pending(async move {
+                        started.notify_one();
+                        io.acquire().await.map_err(|e| e.to_string())?.forget();
+                        Ok(TestAction::Message(format!("{} (looked up)", message)))
+                    })
+                }
+                action => Phase::Applied(self.dispatch_with_context(ctx, action).await),
+            }
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            let action = serde_json::from_str(action_str).map_err(|e| e.to_string())?;
+            self.dispatch(client_id, action).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.state.clone()
+        }
+
+        fn should_broadcast_to(
+            &self,
+            recipient_id: u64,
+            _response: &ActionResponse<TestState>,
+        ) -> bool {
+            Some(recipient_id) != self.hidden_from
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_pending_action_releases_the_room() {
+        for room_actors in [false, true] {
+            let reducer = IoReducer {
+                state: TestState::default(),
+                io: Arc::new(Semaphore::new(0)),
+                started: Arc::new(Notify::new()),
+                hidden_from: None,
+            };
+            let io = reducer.io.clone();
+            let started = reducer.started.clone();
+            let mut broadcaster = Broadcaster::<RecordingSink, IoReducer>::new(reducer);
+            broadcaster.set_room_actors(room_actors);
+            let broadcaster = Arc::new(broadcaster);
+            for client_id in 1..=2 {
+                broadcaster
+                    .add_client_connection(create_client(client_id), RecordingSink::new())
+                    .await;
+            }
+            broadcaster
+                .handle_event(1, create_message(1, JointMessageMethod::Create(None)))
+                .await;
+            let room_id = broadcaster.clients.read().await[&1].room_id.unwrap();
+            broadcaster
+                .handle_event(2, create_message(2, JointMessageMethod::Join(room_id)))
+                .await;
+
+            let lookup = tokio::spawn({
+                let broadcaster = broadcaster.clone();
+                async move {
+                    let action = create_action_message(1, TestAction::Message("hi".to_string()));
+                    broadcaster.process_event(1, action).await.is_ok()
+                }
+            });
+            started.notified().await;
+
+            // the action of the second client is applied during the lookup of the first one
+            tokio::time::timeout(
+                Duration::from_secs(5),
+                broadcaster.process_event(2, create_action_message(2, TestAction::Add(5))),
+            )
+            .await
+            .expect("action blocked by the pending lookup")
+            .unwrap();
+            let (version, state) = broadcaster.room_state(room_id).await.unwrap();
+            assert_eq!(version, 1);
+            assert_eq!(state.counter, 5);
+            assert!(state.messages.is_empty());
+
+            io.add_permits(1);
+            assert!(lookup.await.unwrap());
+            let (version, state) = broadcaster.room_state(room_id).await.unwrap();
+            assert_eq!(version, 2);
+            assert_eq!(state.counter, 5);
+            assert_eq!(state.messages, vec!["hi (looked up)".to_string()]);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_pending_action_is_filtered_against_current_members() {
+        for room_actors in [false, true] {
+            let reducer = IoReducer {
+                state: TestState::default(),
+                io: Arc::new(Semaphore::new(0)),
+                started: Arc::new(Notify::new()),
+                hidden_from: Some(2),
+            };
+            let io = reducer.io.clone();
+            let started = reducer.started.clone();
+            let mut broadcaster = Broadcaster::<RecordingSink, IoReducer>::new(reducer);
+            broadcaster.set_room_actors(room_actors);
+            let broadcaster = Arc::new(broadcaster);
+            for client_id in 1..=2 {
+                broadcaster
+                    .add_client_connection(create_client(client_id), RecordingSink::new())
+                    .await;
+            }
+            let room_id = broadcaster
+                .handle_create(1, CreateOptions::default())
+                .await
+                .unwrap()
+                .room;
+
+            let lookup = tokio::spawn({
+                let broadcaster = broadcaster.clone();
+                async move {
+                    let action = create_action_message(1, TestAction::Message("hi".to_string()));
+                    broadcaster.process_event(1, action).await
+                }
+            });
+            started.notified().await;
+
+            // the second client joins while the lookup runs
+            broadcaster.handle_join(2, room_id, None).await.unwrap();
+            io.add_permits(1);
+            match lookup.await.unwrap().unwrap() {
+                EventResponse::Room(room_response) => assert!(
+                    room_response.skip_clients.contains(&2),
+                    "room actors: {}",
+                    room_actors
+                ),
+                _ => panic!("Expected per-room response"),
+            }
+        }
+    }
 }
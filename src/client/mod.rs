@@ -1,14 +1,46 @@
 #![allow(unused)]
 /// This module defines the `Client` struct, which represents a participant in a room.
 
+#[allow(clippy::useless_attribute, clippy::empty_line_after_doc_comments)]
+use crate::codec::Compression;
+use crate::message::DEFAULT_PROTOCOL_VERSION;
+use crate::rate_limit::TokenBucket;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// Membership state of a client within its current room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientStatus {
+    /// Client has been sent the initial state but has not acknowledged it yet.
+    Joining,
+    /// Client is a full member of its room and receives broadcasts.
+    Joined,
+}
+
 /// A client is a participant in a room.
 ///
 /// Each client has a unique ID, an optional room ID, a label (username), and a token.
+/// In multi-room mode, the client may join further rooms besides its `room_id`, which stays
+/// the room targeted by messages not naming one.
+/// The token is the `client_token` of the first message sent by the client, for
+/// application-defined identity; it is exposed to reducers through `DispatchContext::token`.
 pub struct Client {
     pub id: u64,
     pub room_id: Option<u64>,
+    /// The rooms joined besides `room_id` in multi-room mode, in order of room ID.
+    pub extra_rooms: BTreeSet<u64>,
     pub label: String,
     pub token: String,
+    /// The token issued by the server to resume the client's session with after reconnecting,
+    /// empty unless sessions are enabled with a grace period.
+    pub session_token: String,
+    pub status: ClientStatus,
+    pub protocol_version: u32,
+    pub compression: Compression,
+    /// The projection of the room state the client joined its room with, if any.
+    pub projection: Option<String>,
+    /// Behind a lock of its own, so actions are rate limited under shared access to the client.
+    pub(crate) rate_bucket: Mutex<TokenBucket>,
 }
 
 impl Client {
@@ -17,8 +49,54 @@ impl Client {
         Client {
             id,
             room_id,
+            extra_rooms: BTreeSet::new(),
             label,
             token,
+            session_token: String::new(),
+            status: ClientStatus::Joined,
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            compression: Compression::None,
+            projection: None,
+            rate_bucket: Mutex::new(TokenBucket::default()),
+        }
+    }
+
+    /// Returns whether the client is a member of the room.
+    pub fn in_room(&self, room_id: u64) -> bool {
+        self.room_id == Some(room_id) || self.extra_rooms.contains(&room_id)
+    }
+
+    /// Returns every room the client is a member of, starting with its `room_id`.
+    pub fn rooms(&self) -> impl Iterator<Item = u64> + '_ {
+        self.room_id
+            .into_iter()
+            .chain(self.extra_rooms.iter().copied())
+    }
+
+    /// Records that the client joined the room, as its `room_id` unless it already has one.
+    ///
+    /// Returns whether the room became the client's `room_id`.
+    pub(crate) fn enter_room(&mut self, room_id: u64) -> bool {
+        if self.room_id.is_none() {
+            self.room_id = Some(room_id);
+            true
+        } else {
+            self.extra_rooms.insert(room_id);
+            false
+        }
+    }
+
+    /// Records that the client left the room.
+    ///
+    /// Leaving its `room_id` resets the membership state of the client, and makes the extra
+    /// room with the lowest ID its `room_id`.
+    pub(crate) fn exit_room(&mut self, room_id: u64) {
+        if self.room_id == Some(room_id) {
+            self.room_id = self.extra_rooms.pop_first();
+            self.status = ClientStatus::Joined;
+            self.projection = None;
+        } else {
+            self.extra_rooms.remove(&room_id);
         }
     }
 }
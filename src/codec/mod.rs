@@ -0,0 +1,92 @@
+/// This module defines the wire formats used to encode responses sent to clients.
+mod test;
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::{Read, Write};
+
+/// Wire format used by a joint to encode outgoing `Response`s.
+///
+/// `Json` is sent as text frames and is understood by every client, while `MessagePack`
+/// is sent as binary frames and spares native clients from parsing large JSON states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WireFormat {
+    /// JSON encoding, sent as text frames.
+    #[default]
+    Json,
+    /// MessagePack encoding, sent as binary frames.
+    MessagePack,
+}
+
+impl WireFormat {
+    /// Returns `true` if the format produces binary (non UTF-8) payloads.
+    pub fn is_binary(&self) -> bool {
+        matches!(self, WireFormat::MessagePack)
+    }
+
+    /// Encodes a value into bytes using this format.
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        match self {
+            WireFormat::Json => Ok(serde_json::to_vec(value)?),
+            WireFormat::MessagePack => Ok(rmp_serde::to_vec_named(value)?),
+        }
+    }
+
+    /// Decodes a value from bytes encoded with this format.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        match self {
+            WireFormat::Json => Ok(serde_json::from_slice(bytes)?),
+            WireFormat::MessagePack => Ok(rmp_serde::from_slice(bytes)?),
+        }
+    }
+}
+
+/// Compression applied to encoded responses, negotiated by each client with `Hello`.
+///
+/// Clients that can't inflate compressed frames, such as browsers, keep the default `None`
+/// and receive plain frames. Compressed responses are always sent as binary frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Compression {
+    /// Responses are sent uncompressed.
+    #[default]
+    None,
+    /// Responses are compressed with raw DEFLATE.
+    Deflate,
+}
+
+impl Compression {
+    /// Returns `true` if payloads are actually compressed.
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, Compression::None)
+    }
+
+    /// Compresses an encoded payload.
+    pub fn compress(&self, payload: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        match self {
+            Compression::None => Ok(payload),
+            Compression::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&payload)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    /// Decompresses a payload compressed with this compression.
+    pub fn decompress(&self, payload: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        match self {
+            Compression::None => Ok(payload.to_vec()),
+            Compression::Deflate => {
+                let mut decompressed = Vec::new();
+                DeflateDecoder::new(payload).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+        }
+    }
+}
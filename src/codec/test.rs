@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use crate::codec::{Compression, WireFormat};
+    use crate::response::{ErrorCode, Response, Stamp};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct TestState {
+        counter: i32,
+        messages: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct StateEnvelope {
+        status: String,
+        message: TestState,
+    }
+
+    #[test]
+    fn test_default_format() {
+        assert_eq!(WireFormat::default(), WireFormat::Json);
+        assert!(!WireFormat::Json.is_binary());
+        assert!(WireFormat::MessagePack.is_binary());
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let responses = vec![
+            Response::RoomCreated(1),
+            Response::RoomJoined(2),
+            Response::RoomLeft(3),
+            Response::ClientError {
+                code: ErrorCode::InvalidAction,
+                message: "oops".to_string(),
+            },
+            Response::Hello(2),
+            Response::Action(
+                r#"{"counter":4}"#.to_string(),
+                Some(Stamp {
+                    seq: 4,
+                    timestamp: 1_700_000_000_000,
+                }),
+            ),
+        ];
+
+        for format in [WireFormat::Json, WireFormat::MessagePack] {
+            for response in &responses {
+                let bytes = format.encode(response).unwrap();
+                let decoded: Response = format.decode(&bytes).unwrap();
+                assert_eq!(
+                    format.encode(&decoded).unwrap(),
+                    bytes,
+                    "{:?} round trip failed for {:?}",
+                    format,
+                    response
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_sent_is_encoded_natively() {
+        let response =
+            Response::StateSent(r#"{"counter":3,"messages":["a","b"]}"#.to_string(), None);
+        let bytes = WireFormat::MessagePack.encode(&response).unwrap();
+
+        assert!(serde_json::from_slice::<serde_json::Value>(&bytes).is_err());
+
+        let envelope: StateEnvelope = WireFormat::MessagePack.decode(&bytes).unwrap();
+        assert_eq!(envelope.status, "StateSent");
+        assert_eq!(
+            envelope.message,
+            TestState {
+                counter: 3,
+                messages: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_compression_round_trip() {
+        let payload = serde_json::to_vec(&Response::StateSent("x".repeat(1000), None)).unwrap();
+
+        assert!(!Compression::default().is_enabled());
+        assert_eq!(
+            Compression::None.compress(payload.clone()).unwrap(),
+            payload
+        );
+
+        let compressed = Compression::Deflate.compress(payload.clone()).unwrap();
+        assert!(compressed.len() < payload.len());
+        assert_eq!(
+            Compression::Deflate.decompress(&compressed).unwrap(),
+            payload
+        );
+    }
+}
@@ -1,9 +1,57 @@
 /// This module defines the `SinkAdapter` and `StreamAdapter` traits.
 mod test;
 
+use crate::codec::{Compression, WireFormat};
 use crate::message::JointMessage;
 use crate::response::Response;
 use async_trait::async_trait;
+use bytes::Bytes;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Error returned by a `SinkAdapter` when its underlying connection is gone for good.
+///
+/// Clients whose sink fails with this error are removed from the joint.
+#[derive(Debug)]
+pub struct SinkClosed;
+
+impl fmt::Display for SinkClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sink connection closed")
+    }
+}
+
+impl std::error::Error for SinkClosed {}
+
+/// Shared flag telling whether the task forwarding sink messages to a connection is still running.
+#[derive(Clone, Debug)]
+pub(crate) struct ForwarderLiveness(Arc<AtomicBool>);
+
+impl ForwarderLiveness {
+    pub fn new() -> Self {
+        ForwarderLiveness(Arc::new(AtomicBool::new(true)))
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Returns a guard to be held by the forwarder task, marking it dead once dropped (including on panic).
+    pub fn guard(&self) -> ForwarderGuard {
+        ForwarderGuard(self.0.clone())
+    }
+}
+
+/// Guard held by a forwarder task, see [`ForwarderLiveness::guard`].
+pub(crate) struct ForwarderGuard(Arc<AtomicBool>);
+
+impl Drop for ForwarderGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
 
 /// `SinkAdapter` is a trait that defines the interface for sending messages.
 ///
@@ -41,11 +89,50 @@ use async_trait::async_trait;
 /// ```
 ///
 #[async_trait]
-pub trait SinkAdapter {
+pub trait SinkAdapter: Send + Sync {
     async fn send(
         &mut self,
         response: Response,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Applies the compression negotiated by the client to the following responses.
+    ///
+    /// Defaults to ignoring the negotiation, for sinks that don't send encoded frames.
+    fn set_compression(&mut self, compression: Compression) {
+        let _ = compression;
+    }
+
+    /// Encodes the following responses in the wire format negotiated by the client.
+    ///
+    /// Defaults to ignoring the negotiation, for sinks that don't send encoded frames.
+    fn set_wire_format(&mut self, format: WireFormat) {
+        let _ = format;
+    }
+
+    /// Returns the wire format the sink encodes responses with, if it sends encoded frames.
+    ///
+    /// Broadcasts are encoded once per wire format and sent to such sinks with `send_raw`,
+    /// instead of being encoded again for every recipient. Defaults to `None`, so every
+    /// response is sent with `send`.
+    fn wire_format(&self) -> Option<WireFormat> {
+        None
+    }
+
+    /// Sends a response already encoded in the sink's `wire_format`.
+    ///
+    /// The encoded payload is shared by every recipient of a broadcast, so sinks can pass it
+    /// on without copying. Must write the same frame `send` would write for the decoded
+    /// response. Only called on sinks with a wire format; defaults to failing.
+    async fn send_raw(
+        &mut self,
+        bytes: Bytes,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = bytes;
+        Err(Box::new(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Sink does not support raw sends",
+        )))
+    }
 }
 
 /// `StreamAdapter` is a trait that defines the interface for receiving messages.
@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
     use crate::connection::{SinkAdapter, StreamAdapter};
     use crate::message::{JointMessage, JointMessageMethod};
@@ -65,14 +66,17 @@ mod tests {
         let messages = vec![
             JointMessage {
                 client_token: "1".to_string(),
-                message: JointMessageMethod::Create,
+                v: None,
+                message: JointMessageMethod::Create(None),
             },
             JointMessage {
                 client_token: "1".to_string(),
+                v: None,
                 message: JointMessageMethod::Join(42),
             },
             JointMessage {
                 client_token: "1".to_string(),
+                v: None,
                 message: JointMessageMethod::Leave,
             },
         ];
@@ -83,7 +87,7 @@ mod tests {
         };
 
         let message1 = stream.next().await.unwrap();
-        assert!(matches!(message1.message, JointMessageMethod::Create));
+        assert!(matches!(message1.message, JointMessageMethod::Create(None)));
 
         let message2 = stream.next().await.unwrap();
         if let JointMessageMethod::Join(room_id) = message2.message {
@@ -1,9 +1,12 @@
 /// This module contains the core functionality for dispatching actions to reducers.
 mod test;
 
+use crate::message::DEFAULT_PROTOCOL_VERSION;
 use crate::utils::types::{Broadcastable, Receivable};
 use serde::{Deserialize, Serialize};
 use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
 
 /// Response structure for actions dispatched to the reducer.
 ///
@@ -16,6 +19,139 @@ pub struct ActionResponse<S: Serialize> {
     pub data: String,
 }
 
+/// Information about the client an action is dispatched on behalf of.
+///
+/// Populated from the client's connection for actions received from clients.
+#[derive(Debug, Clone, Default)]
+pub struct DispatchContext {
+    /// The ID of the acting client.
+    pub client_id: u64,
+    /// The label of the acting client.
+    pub label: String,
+    /// The token identifying the acting client, taken from the first message it sent.
+    pub token: String,
+    /// The token sent along with the action message.
+    pub client_token: String,
+    /// The ID of the room the action is dispatched to, if known.
+    pub room_id: Option<u64>,
+}
+
+impl DispatchContext {
+    /// Creates a context knowing only the ID of the acting client.
+    pub fn new(client_id: u64) -> Self {
+        DispatchContext {
+            client_id,
+            ..DispatchContext::default()
+        }
+    }
+
+    /// Builds the response to an action of the acting client, carrying the current state of `reducer`.
+    pub fn response<R: Dispatchable>(
+        &self,
+        reducer: &R,
+        status: impl Into<String>,
+        data: impl Into<String>,
+    ) -> ActionResponse<R::State> {
+        ActionResponse {
+            status: status.into(),
+            state: reducer.get_state(),
+            author: self.client_id,
+            data: data.into(),
+        }
+    }
+
+    /// Accepts an action of the acting client with a `"success"` status and the given data.
+    ///
+    /// Shorthand for returning `Ok` with a [`response`](Self::response) from a reducer,
+    /// so the state and author don't have to be filled in by hand.
+    pub fn ok<R: Dispatchable>(
+        &self,
+        reducer: &R,
+        data: impl Into<String>,
+    ) -> Result<ActionResponse<R::State>, R::Error> {
+        Ok(self.response(reducer, "success", data))
+    }
+
+    /// Rejects an action of the acting client with the given message.
+    pub fn err<S: Serialize, E: From<String>>(
+        &self,
+        message: impl Into<String>,
+    ) -> Result<ActionResponse<S>, E> {
+        Err(E::from(message.into()))
+    }
+}
+
+/// An action applied to a room, recorded in its action log along with what it was dispatched with.
+///
+/// Replays deserialize the action for the same protocol version, and dispatch it with the same
+/// context, so the log reproduces the state reducers reading the context ended up with.
+#[derive(Debug, Clone)]
+pub struct RecordedAction {
+    /// The ID of the acting client.
+    pub author: u64,
+    /// The action as received, before deserialization.
+    pub action: String,
+    /// The protocol version the action was deserialized for.
+    pub protocol_version: u32,
+    /// The context the action was dispatched with through [`Dispatchable::begin`], `None` for
+    /// actions dispatched straight with [`Dispatchable::dispatch`].
+    pub context: Option<DispatchContext>,
+}
+
+impl From<(u64, String)> for RecordedAction {
+    /// Records an `(author, action)` pair as dispatched straight to the reducer,
+    /// in the default protocol version.
+    fn from((author, action): (u64, String)) -> Self {
+        RecordedAction {
+            author,
+            action,
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            context: None,
+        }
+    }
+}
+
+/// Async work started by [`Dispatchable::begin`], run without holding the reducer.
+///
+/// Resolves to the action completing the dispatch, passed to [`Dispatchable::complete`],
+/// or to an error reported to the acting client like a rejected action.
+pub type PendingWork<R> = Pin<
+    Box<
+        dyn Future<Output = Result<<R as Dispatchable>::Action, <R as Dispatchable>::Error>> + Send,
+    >,
+>;
+
+/// Outcome of the first phase of a two-phase dispatch, returned by [`Dispatchable::begin`].
+pub enum Phase<R: Dispatchable> {
+    /// The action was applied right away.
+    Applied(Result<ActionResponse<R::State>, R::Error>),
+    /// The action awaits async work, run once the reducer is released.
+    Pending(PendingWork<R>),
+}
+
+impl<R: Dispatchable> Phase<R> {
+    /// Defers an action until `work` resolves to the action completing it.
+    pub fn pending(
+        work: impl Future<Output = Result<R::Action, R::Error>> + Send + 'static,
+    ) -> Self {
+        Phase::Pending(Box::pin(work))
+    }
+}
+
+/// Projection of a room state, sent instead of the state to the clients that joined with it.
+pub type Projection<S> = Box<dyn Fn(&S) -> serde_json::Value + Send + Sync>;
+
+/// Constructor of the reducer managing the state of each new room.
+pub type ReducerFactory<R> = Box<dyn Fn() -> R + Send + Sync>;
+
+/// Hook run before an action received from a client is dispatched, with its raw payload.
+///
+/// Returning an error aborts the action, answering the client with a `ClientError`.
+pub type PreDispatchHook = Box<dyn Fn(&DispatchContext, &str) -> Result<(), String> + Send + Sync>;
+
+/// Hook run after an action received from a client is applied, with the resulting response.
+pub type PostDispatchHook<S> = Box<dyn Fn(&DispatchContext, &ActionResponse<S>) + Send + Sync>;
+
 /// Core trait for dispatching actions to a reducer.
 ///
 /// Required to be implemented by any reducer that will be used in `Joint`.
@@ -55,6 +191,7 @@ pub struct ActionResponse<S: Serialize> {
 /// impl Dispatchable for TestReducer {
 ///     type Action = TestAction;
 ///     type State = TestState;
+///     type Error = String;
 ///
 ///     async fn dispatch(
 ///         &mut self,
@@ -95,7 +232,14 @@ pub struct ActionResponse<S: Serialize> {
 ///
 pub trait Dispatchable: Send + Sync + Clone {
     type Action: Receivable + Send;
-    type State: Broadcastable;
+    /// State broadcast to room members, also sent across tasks to in-process state subscribers.
+    type State: Broadcastable + Send;
+    /// Error returned when the reducer rejects an action.
+    ///
+    /// Serialized into the `ClientError` response sent to the acting client, so reducers can
+    /// return rich error enums. The `From<String>` bound lets string errors, such as action
+    /// parsing failures, convert with `?`; reducers with plain string errors use `String`.
+    type Error: Serialize + Send + From<String>;
 
     /// Dispatches an action to the reducer.
     ///
@@ -106,7 +250,65 @@ pub trait Dispatchable: Send + Sync + Clone {
         &mut self,
         client_id: u64,
         action: Self::Action,
-    ) -> impl Future<Output = Result<ActionResponse<Self::State>, String>> + Send;
+    ) -> impl Future<Output = Result<ActionResponse<Self::State>, Self::Error>> + Send;
+
+    /// Dispatches an action sent along with the client's `client_token`.
+    ///
+    /// Called by the default `dispatch_with_context`, so reducers can read the raw token
+    /// (e.g. a device ID) supplied with the message. Defaults to forwarding to `dispatch`.
+    fn dispatch_with_token(
+        &mut self,
+        client_id: u64,
+        client_token: &str,
+        action: Self::Action,
+    ) -> impl Future<Output = Result<ActionResponse<Self::State>, Self::Error>> + Send {
+        let _ = client_token;
+        self.dispatch(client_id, action)
+    }
+
+    /// Dispatches an action along with the context of the acting client.
+    ///
+    /// Called for every action received from a client, so reducers can validate actions against
+    /// the client's label or token. Defaults to forwarding to `dispatch_with_token`.
+    fn dispatch_with_context(
+        &mut self,
+        ctx: &DispatchContext,
+        action: Self::Action,
+    ) -> impl Future<Output = Result<ActionResponse<Self::State>, Self::Error>> + Send {
+        self.dispatch_with_token(ctx.client_id, &ctx.client_token, action)
+    }
+
+    /// Begins a two-phase dispatch of an action received from a client.
+    ///
+    /// Reducers needing async I/O, e.g. a database call, return [`Phase::Pending`] with the
+    /// I/O to perform instead of awaiting it while holding the reducer, which would block the
+    /// whole room. The reducer is released while the work runs, then locked again to apply the
+    /// action the work resolved to with [`complete`](Self::complete) and broadcast the result.
+    ///
+    /// Other actions may be applied to the room meanwhile, so the state `complete` sees may
+    /// differ from the state seen here: the work must not rely on it, and `complete` should
+    /// validate its action against the current state. State changes made before returning
+    /// `Pending` are not broadcast until the action completes, and aren't reverted if it fails.
+    /// Defaults to applying the action right away with `dispatch_with_context`.
+    fn begin(
+        &mut self,
+        ctx: &DispatchContext,
+        action: Self::Action,
+    ) -> impl Future<Output = Phase<Self>> + Send {
+        async move { Phase::Applied(self.dispatch_with_context(ctx, action).await) }
+    }
+
+    /// Completes a two-phase dispatch with the action the pending work of `begin` resolved to.
+    ///
+    /// Completing actions never come from clients directly, so reducers can accept here actions
+    /// they reject in `begin`. Defaults to forwarding to `dispatch_with_context`.
+    fn complete(
+        &mut self,
+        ctx: &DispatchContext,
+        action: Self::Action,
+    ) -> impl Future<Output = Result<ActionResponse<Self::State>, Self::Error>> + Send {
+        self.dispatch_with_context(ctx, action)
+    }
 
     /// Handles external dispatches to the reducer.
     ///
@@ -117,8 +319,67 @@ pub trait Dispatchable: Send + Sync + Clone {
         &mut self,
         client_id: u64,
         action: &str,
-    ) -> impl Future<Output = Result<ActionResponse<Self::State>, String>> + Send;
+    ) -> impl Future<Output = Result<ActionResponse<Self::State>, Self::Error>> + Send;
 
     /// Retrieves the current state of the reducer.
     fn get_state(&self) -> Self::State;
+
+    /// Retrieves the current state of the reducer behind a shared pointer.
+    ///
+    /// Used wherever the state is only serialized, e.g. for `StateSent` responses and state
+    /// diffs. Reducers keeping their state in an `Arc` can override this to hand out the
+    /// pointer instead of deep-cloning the state. Defaults to wrapping `get_state`.
+    fn state_arc(&self) -> Arc<Self::State> {
+        Arc::new(self.get_state())
+    }
+
+    /// Returns whether an applied action is broadcast to the given room member.
+    ///
+    /// Consulted for every member of the room, so reducers can hide actions from some members,
+    /// e.g. a secret move from opponents. Defaults to broadcasting to every member.
+    fn should_broadcast_to(
+        &self,
+        recipient_id: u64,
+        response: &ActionResponse<Self::State>,
+    ) -> bool {
+        let _ = (recipient_id, response);
+        true
+    }
+
+    /// Deserializes a raw action payload sent by a client speaking given protocol version.
+    ///
+    /// Override this to translate payloads of older protocol versions into the current `Action` enum.
+    /// Defaults to plain JSON deserialization ignoring the version.
+    fn deserialize_action(raw: &str, version: u32) -> Result<Self::Action, String> {
+        let _ = version;
+        serde_json::from_str(raw).map_err(|e| e.to_string())
+    }
+
+    /// Returns the priority of an action, consulted when the joint sheds load.
+    ///
+    /// Under pressure, actions below the `min_priority` of the joint's `LoadShedding` policy are
+    /// rejected with `Overloaded`, so critical actions keep flowing during spikes.
+    /// Defaults to `0`, the lowest priority.
+    fn priority(action: &Self::Action) -> u8 {
+        let _ = action;
+        0
+    }
+
+    /// Returns a machine-readable description of the actions accepted by the reducer.
+    ///
+    /// Included in the joint's `protocol_description`, for client SDK generation and API docs.
+    /// Generated by `#[reducer_actions]` from the action methods; defaults to `null`.
+    fn action_schema() -> serde_json::Value {
+        serde_json::Value::Null
+    }
+
+    /// Returns the size of the current state in bytes.
+    ///
+    /// Used to enforce the per-room state budget configured with `max_state_bytes`.
+    /// Defaults to the length of the JSON-serialized state.
+    fn state_size_bytes(&self) -> usize {
+        serde_json::to_vec(&*self.state_arc())
+            .map(|bytes| bytes.len())
+            .unwrap_or(0)
+    }
 }
@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
-    use crate::dispatcher::{ActionResponse, Dispatchable};
+    use crate::dispatcher::{ActionResponse, DispatchContext, Dispatchable};
     use crate::utils::types::{Broadcastable, Receivable};
     use serde::{Deserialize, Serialize};
     use std::future::Future;
@@ -31,7 +32,9 @@ mod tests {
     impl Dispatchable for TestReducer {
         type Action = TestAction;
         type State = TestState;
+        type Error = String;
 
+        #[allow(clippy::manual_async_fn)]
         fn dispatch(
             &mut self,
             client_id: u64,
@@ -59,6 +62,7 @@ mod tests {
             }
         }
 
+        #[allow(clippy::manual_async_fn)]
         fn extern_dispatch(
             &mut self,
             client_id: u64,
@@ -155,4 +159,21 @@ mod tests {
         let result = reducer.extern_dispatch(client_id, invalid_json).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_context_helpers_match_manual_response() {
+        let mut reducer = TestReducer::default();
+        let manual = reducer.dispatch(7, TestAction::Add(3)).await.unwrap();
+
+        let ctx = DispatchContext::new(7);
+        let helper = ctx.ok(&reducer, "").unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&helper).unwrap(),
+            serde_json::to_value(&manual).unwrap()
+        );
+
+        let rejected: Result<ActionResponse<TestState>, String> = ctx.err("Not allowed");
+        assert_eq!(rejected.unwrap_err(), "Not allowed");
+    }
 }
@@ -1,27 +1,42 @@
 /// Provides joint implementations for Axum applications using WebSockets.
 mod test;
 
-use crate::connection::{SinkAdapter, StreamAdapter};
-use crate::dispatcher::{ActionResponse, Dispatchable};
-use crate::joint::AbstractJoint;
-use crate::message::JointMessage;
+use crate::codec::{Compression, WireFormat};
+use crate::connection::{ForwarderLiveness, SinkAdapter, SinkClosed, StreamAdapter};
+use crate::dispatcher::{
+    ActionResponse, Dispatchable, PostDispatchHook, PreDispatchHook, Projection, RecordedAction,
+};
+use crate::joint::{AbstractJoint, Authenticator, ConnectionHook};
+use crate::load_shedding::LoadShedding;
+use crate::message::{JointMessage, JointMessageMethod};
+use crate::metrics::JointStats;
+use crate::rate_limit::RateLimit;
 use crate::response::Response;
 use async_trait::async_trait;
-use axum::extract::ws::{Message, WebSocket};
-use axum::extract::WebSocketUpgrade;
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
+use axum::extract::{Path, WebSocketUpgrade};
+use axum::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use axum::routing::get;
-use axum::Router;
+use axum::{Json, Router};
+use bytes::Bytes;
 use futures_util::stream::SplitStream;
 use futures_util::{SinkExt, StreamExt};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{self};
 use tokio::net::TcpListener;
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
 
 #[derive(Clone)]
 pub struct AxumWSSink {
     tx: mpsc::Sender<Result<Message, axum::Error>>,
+    format: WireFormat,
+    compression: Compression,
+    forwarder: ForwarderLiveness,
 }
 
 /// An implementation of [`SinkAdapter`] for sending messages over an Axum WebSocket connection.
@@ -34,43 +49,136 @@ impl SinkAdapter for AxumWSSink {
         &mut self,
         response: Response,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let message_text = serde_json::to_string(&response)?;
+        let payload = self.format.encode(&response)?;
+        self.send_raw(payload.into()).await
+    }
+
+    fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    fn set_wire_format(&mut self, format: WireFormat) {
+        self.format = format;
+    }
+
+    fn wire_format(&self) -> Option<WireFormat> {
+        Some(self.format)
+    }
+
+    async fn send_raw(
+        &mut self,
+        bytes: Bytes,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !self.forwarder.is_alive() {
+            return Err(Box::new(SinkClosed));
+        }
+        let message = if self.compression.is_enabled() {
+            let payload = self.compression.compress(bytes.to_vec())?;
+            Message::Binary(payload.into())
+        } else if self.format.is_binary() {
+            Message::Binary(bytes)
+        } else {
+            Message::Text(Utf8Bytes::try_from(bytes)?)
+        };
         self.tx
-            .send(Ok(Message::Text(message_text.into())))
+            .send(Ok(message))
             .await
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+            .map_err(|_| Box::new(SinkClosed) as Box<dyn std::error::Error + Send + Sync>)?;
         Ok(())
     }
 }
 
 pub struct AxumWSStream {
     stream: SplitStream<WebSocket>,
+    format: WireFormat,
 }
 
 /// An implementation of [`StreamAdapter`] for receiving messages from an Axum WebSocket connection.
 ///
 /// This struct represents the stream of messages received from an Axum WebSocket connection.
+/// Text frames are decoded as JSON and binary frames in the wire format negotiated with
+/// `Hello`, ping and pong frames are skipped, and a close frame ends the stream.
 #[async_trait]
 impl StreamAdapter for AxumWSStream {
     async fn next(&mut self) -> Result<JointMessage, Box<dyn std::error::Error + Send + Sync>> {
-        let message = self.stream.next().await.unwrap()?;
-        let message = match message {
-            Message::Text(text) => text,
-            _ => {
-                return Err(Box::new(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Invalid data",
-                )))
+        loop {
+            let message =
+                self.stream.next().await.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::BrokenPipe, "Connection closed")
+                })??;
+            let message: JointMessage = match message {
+                Message::Text(text) => serde_json::from_slice(text.as_ref())?,
+                Message::Binary(bytes) => self.format.decode(bytes.as_ref())?,
+                Message::Ping(_) | Message::Pong(_) => continue,
+                Message::Close(_) => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "Connection closed",
+                    )))
+                }
+            };
+            if let JointMessageMethod::Hello {
+                format: Some(format),
+                ..
+            } = message.message
+            {
+                self.format = format;
             }
-        };
-        let message = serde_json::from_slice((&message).as_ref())?;
-        Ok(message)
+            return Ok(message);
+        }
     }
 }
 
+/// Reason a WebSocket upgrade request is rejected before the socket opens.
+///
+/// Rendered as the given status with a `{ "error": "<reason>" }` JSON body,
+/// so front-ends can read the reason from the failed handshake response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpgradeRejection {
+    /// HTTP status of the rejection response.
+    pub status: StatusCode,
+    /// Machine-readable rejection reason.
+    pub error: String,
+}
+
+impl UpgradeRejection {
+    /// Creates a new rejection with the given status and reason.
+    pub fn new(status: StatusCode, error: &str) -> Self {
+        UpgradeRejection {
+            status,
+            error: error.to_string(),
+        }
+    }
+
+    /// Rejects an unauthenticated request with `401 Unauthorized`.
+    pub fn unauthorized() -> Self {
+        UpgradeRejection::new(StatusCode::UNAUTHORIZED, "unauthorized")
+    }
+
+    /// Rejects a request that is not allowed to connect with `403 Forbidden`.
+    pub fn forbidden() -> Self {
+        UpgradeRejection::new(StatusCode::FORBIDDEN, "forbidden")
+    }
+}
+
+impl IntoResponse for UpgradeRejection {
+    fn into_response(self) -> axum::response::Response {
+        (
+            self.status,
+            Json(serde_json::json!({ "error": self.error })),
+        )
+            .into_response()
+    }
+}
+
+/// Check run against the headers of every upgrade request before the socket opens.
+pub type UpgradeGuard =
+    Arc<dyn Fn(&HeaderMap) -> Result<(), UpgradeRejection> + Send + Sync + 'static>;
+
 pub struct AxumWSJoint<R: Dispatchable + 'static> {
     joint: Arc<AbstractJoint<R, AxumWSSink>>,
     tcp_listener: Option<TcpListener>,
+    upgrade_guard: Option<UpgradeGuard>,
 }
 
 /// An `injoint` joint specifically designed for integration with the Axum web framework.
@@ -84,32 +192,362 @@ impl<R: Dispatchable + 'static> AxumWSJoint<R> {
     ///
     /// Requires a default instance of the application's `Dispatchable` reducer.
     pub fn new(default_reducer: R) -> Self {
+        Self::from_joint(AbstractJoint::new(default_reducer))
+    }
+
+    /// Creates a new `AxumJoint` constructing the reducer of each new room with `reducer_factory`
+    /// instead of cloning a default reducer.
+    pub fn new_with_factory(reducer_factory: impl Fn() -> R + Send + Sync + 'static) -> Self {
+        Self::from_joint(AbstractJoint::new_with_factory(reducer_factory))
+    }
+
+    /// wraps a joint, not bound to any address yet
+    fn from_joint(joint: AbstractJoint<R, AxumWSSink>) -> Self {
         AxumWSJoint {
-            joint: Arc::new(AbstractJoint::new(default_reducer)),
+            joint: Arc::new(joint),
             tcp_listener: None,
+            upgrade_guard: None,
         }
     }
 
+    /// Returns a mutable reference to the underlying joint for configuration.
+    ///
+    /// # Panics
+    /// * This method panics if the joint is already shared with connection tasks.
+    fn joint_mut(&mut self) -> &mut AbstractJoint<R, AxumWSSink> {
+        Arc::get_mut(&mut self.joint).expect("joint must be configured before handling connections")
+    }
+
+    /// Sets whether joining clients must acknowledge the initial state with an `Ack` message
+    /// before they are considered joined and included in room broadcasts.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_join_requires_ack(&mut self, join_requires_ack: bool) {
+        self.joint_mut().set_join_requires_ack(join_requires_ack);
+    }
+
+    /// Sets the maximum size of a room state in bytes.
+    ///
+    /// Actions growing the state beyond the limit are reverted and rejected with a `ClientError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_state_bytes(&mut self, max_state_bytes: Option<usize>) {
+        self.joint_mut().set_max_state_bytes(max_state_bytes);
+    }
+
+    /// Sets the maximum size in bytes of the `data` returned by the reducer for an action.
+    ///
+    /// Actions returning more data are reverted and their author is sent a `ServerError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_data_bytes(&mut self, max_data_bytes: Option<usize>) {
+        self.joint_mut().set_max_data_bytes(max_data_bytes);
+    }
+
+    /// Sets whether every applied action is recorded with its author in the per-room action log.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_record_actions(&mut self, record_actions: bool) {
+        self.joint_mut().set_record_actions(record_actions);
+    }
+
+    /// Sets the maximum time a single send to a client may take.
+    ///
+    /// Clients whose sink does not accept a message in time are removed from the joint.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_send_timeout(&mut self, send_timeout: Option<Duration>) {
+        self.joint_mut().set_send_timeout(send_timeout);
+    }
+
+    /// Sets the per-client action rate limit used in rooms that don't override it.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.joint_mut().set_rate_limit(rate_limit);
+    }
+
+    /// Sets the most permissive rate limit room creators may request in `CreateOptions`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_rate_limit(&mut self, max_rate_limit: Option<RateLimit>) {
+        self.joint_mut().set_max_rate_limit(max_rate_limit);
+    }
+
+    /// Sets the maximum size of a single outgoing JSON-serialized response in bytes.
+    ///
+    /// Oversized action broadcasts and initial states are replaced with a `ServerError` to the author.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_outgoing_bytes(&mut self, max_outgoing_bytes: Option<usize>) {
+        self.joint_mut().set_max_outgoing_bytes(max_outgoing_bytes);
+    }
+
+    /// Sets whether rooms are deleted as soon as their last client leaves or disconnects.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_delete_empty_rooms(&mut self, delete_empty_rooms: bool) {
+        self.joint_mut().set_delete_empty_rooms(delete_empty_rooms);
+    }
+
+    /// Sets whether clients may be members of several rooms at once, targeting them with `RoomAction`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_multi_room(&mut self, multi_room: bool) {
+        self.joint_mut().set_multi_room(multi_room);
+    }
+
+    /// Enables single-room mode: every connecting client automatically joins one shared room,
+    /// so clients can send actions right away without `Create` or `Join`.
+    ///
+    /// Returns the ID of the shared room.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn single_room_mode(&mut self) -> u64 {
+        self.joint_mut().single_room_mode()
+    }
+
+    /// Sets the interval after which idle clients are sent an application-level `Keepalive`,
+    /// so NATs and load balancers don't drop idle connections.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_keepalive_interval(&mut self, keepalive_interval: Option<Duration>) {
+        self.joint_mut().set_keepalive_interval(keepalive_interval);
+    }
+
+    /// Sets the hook run before each action received from a client is dispatched.
+    /// Returning an error from the hook aborts the action with a `ClientError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn with_pre_hook(&mut self, hook: PreDispatchHook) {
+        self.joint_mut().with_pre_hook(hook);
+    }
+
+    /// Sets the hook run after each action received from a client is applied,
+    /// receiving the resulting `ActionResponse`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn with_post_hook(&mut self, hook: PostDispatchHook<R::State>) {
+        self.joint_mut().with_post_hook(hook);
+    }
+
+    /// Registers a named projection of the room states, sent instead of the full state
+    /// to the clients joining a room with `JoinProjected` and its name.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn add_projection(&mut self, name: impl Into<String>, projection: Projection<R::State>) {
+        self.joint_mut().add_projection(name, projection);
+    }
+
+    /// Sets whether client actions are applied by a dedicated actor task per room,
+    /// so actions to different rooms run concurrently.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_room_actors(&mut self, room_actors: bool) {
+        self.joint_mut().set_room_actors(room_actors);
+    }
+
+    /// Sets whether action broadcasts carry a JSON Patch (RFC 6902) from the previous room state
+    /// in a `patch` field instead of the full `state`, saving bandwidth for large states.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_state_diffs(&mut self, state_diffs: bool) {
+        self.joint_mut().set_state_diffs(state_diffs);
+    }
+
+    /// Sets whether `StateSent` and `Action` responses carry the room's sequence number in a
+    /// `seq` field and the server time in a `timestamp` field, to detect missed broadcasts.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_broadcast_stamps(&mut self, stamps: bool) {
+        self.joint_mut().set_broadcast_stamps(stamps);
+    }
+
+    /// Sets the number of action broadcasts kept per room for clients resuming their session.
+    ///
+    /// Resuming clients missing fewer actions are replayed them instead of being sent the full state.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_history_size(&mut self, history_size: usize) {
+        self.joint_mut().set_history_size(history_size);
+    }
+
+    /// Sets the callback verifying the `client_token` of new connections.
+    /// Connections rejected by the callback are answered with a `ClientError` and closed.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_authenticator(&mut self, authenticator: Option<Authenticator>) {
+        self.joint_mut().set_authenticator(authenticator);
+    }
+
+    /// Sets the policy rejecting low-priority actions with `Overloaded` while the joint is under pressure.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_load_shedding(&mut self, load_shedding: Option<LoadShedding>) {
+        self.joint_mut().set_load_shedding(load_shedding);
+    }
+
+    /// Sets the number of room members above which action broadcasts are skipped,
+    /// members of larger rooms pulling the room state with `Sync` instead.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_fanout(&mut self, max_fanout: Option<usize>) {
+        self.joint_mut().set_max_fanout(max_fanout);
+    }
+
+    /// Sets the callback run with the ID of every client right after its connection is registered.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn on_connect(&mut self, hook: ConnectionHook) {
+        self.joint_mut().on_connect(hook);
+    }
+
+    /// Sets the callback run with the ID of every client right before its connection is removed,
+    /// however the connection ends.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn on_disconnect(&mut self, hook: ConnectionHook) {
+        self.joint_mut().on_disconnect(hook);
+    }
+
+    /// Sets the compression applied to the action broadcasts kept per room for resuming clients.
+    /// Defaults to `Compression::None`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_history_compression(&mut self, compression: Compression) {
+        self.joint_mut().set_history_compression(compression);
+    }
+
+    /// Sets the number of room broadcasts queued for each client disconnected from a room
+    /// with a session token, sent to it once it resumes its session. Disabled with `0`, the default.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_offline_buffer_size(&mut self, buffer_size: usize) {
+        self.joint_mut().set_offline_buffer_size(buffer_size);
+    }
+
+    /// Seeds the generator of client IDs, so a fixed seed yields the same IDs across runs.
+    /// Meant for reproducible benchmarks and fuzzing, as seeded IDs are predictable.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_id_seed(&mut self, seed: u64) {
+        self.joint_mut().set_id_seed(seed);
+    }
+
+    /// Sets how long after disconnecting a client can resume its session with the session token
+    /// it was sent on connect. Sessions are disabled with `None`, the default.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_session_grace(&mut self, grace: Option<Duration>) {
+        self.joint_mut().set_session_grace(grace);
+    }
+
+    /// Sets a check run against the headers of every upgrade request, e.g. to authenticate
+    /// clients or validate their origin.
+    ///
+    /// Rejected requests receive the rejection status with a JSON error body
+    /// instead of being upgraded. Must be set before calling `attach_router`.
+    pub fn set_upgrade_guard<F>(&mut self, guard: F)
+    where
+        F: Fn(&HeaderMap) -> Result<(), UpgradeRejection> + Send + Sync + 'static,
+    {
+        self.upgrade_guard = Some(Arc::new(guard));
+    }
+
+    /// Binds the joint to the given address, to be served with [`serve`](Self::serve).
+    ///
+    /// # Panics
+    /// * This method panics if the address cannot be bound.
     pub async fn bind(&mut self, addr: &str) {
         let tcp_listener = TcpListener::bind(addr).await.unwrap();
         self.tcp_listener = Some(tcp_listener);
     }
 
+    /// Returns the local address the joint is bound to, if it is.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.tcp_listener
+            .as_ref()
+            .and_then(|tcp_listener| tcp_listener.local_addr().ok())
+    }
+
+    /// Serves the joint on the bound address, with the WebSocket handler at `path`.
+    ///
+    /// Builds a router with `attach_router` and runs `axum::serve` on the listener stored
+    /// by `bind`, for standalone servers not needing a router of their own. The returned
+    /// future only completes on I/O errors.
+    ///
+    /// # Panics
+    /// * This method panics if the joint is not bound to an address.
+    pub async fn serve(mut self, path: &str) -> io::Result<()> {
+        let Some(tcp_listener) = self.tcp_listener.take() else {
+            panic!("Axum joint serve error: no listener bound");
+        };
+        let router = self.attach_router(path, Router::new());
+        axum::serve(tcp_listener, router).await
+    }
+
     /// Axum WebSocket handler function.
     ///
     /// This function should be used with `axum::routing::get` to handle WebSocket upgrade requests.
     /// It manages the WebSocket lifecycle, splitting it into a sink and stream, and passes
     /// them to the underlying `AbstractJoint`.
+    ///
+    /// Requests failing the upgrade guard are answered with an [`UpgradeRejection`] instead of
+    /// being upgraded, requests that are not valid upgrades with the Axum upgrade rejection.
     pub async fn ws_handler(
-        ws: WebSocketUpgrade,
+        ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+        headers: HeaderMap,
         joint: Arc<AbstractJoint<R, AxumWSSink>>,
+        upgrade_guard: Option<UpgradeGuard>,
     ) -> impl IntoResponse {
+        if let Some(upgrade_guard) = upgrade_guard {
+            if let Err(rejection) = upgrade_guard(&headers) {
+                return rejection.into_response();
+            }
+        }
+
+        let ws = match ws {
+            Ok(ws) => ws,
+            Err(rejection) => return rejection.into_response(),
+        };
+
         ws.on_upgrade(|socket| async move {
             let (mut websocket_sink, websocket_stream) = socket.split();
 
             let (tx, mut rx) = mpsc::channel::<Result<Message, axum::Error>>(100);
+            let forwarder = ForwarderLiveness::new();
+            let forwarder_guard = forwarder.guard();
 
             tokio::spawn(async move {
+                let _forwarder_guard = forwarder_guard;
                 while let Some(result) = rx.recv().await {
                     match result {
                         Ok(msg) => {
@@ -129,15 +567,22 @@ impl<R: Dispatchable + 'static> AxumWSJoint<R> {
 
             let mut stream_adapter = AxumWSStream {
                 stream: websocket_stream,
+                format: WireFormat::Json,
             };
 
-            let sink_adapter = AxumWSSink { tx };
+            let sink_adapter = AxumWSSink {
+                tx,
+                format: WireFormat::Json,
+                compression: Compression::None,
+                forwarder,
+            };
 
             joint
                 .clone()
                 .handle_stream(&mut stream_adapter, sink_adapter)
                 .await;
         })
+        .into_response()
     }
 
     /// Attaches the WebSocket handler (`ws_handler`) to an Axum router at the specified path.
@@ -145,19 +590,139 @@ impl<R: Dispatchable + 'static> AxumWSJoint<R> {
     /// This is a convenience method for setting up the WebSocket route.
     pub fn attach_router(&self, path: &str, router: Router) -> Router {
         let joint = self.joint.clone();
-        router.route(path, get(move |ws| AxumWSJoint::ws_handler(ws, joint)))
+        let upgrade_guard = self.upgrade_guard.clone();
+        router.route(
+            path,
+            get(move |ws, headers| AxumWSJoint::ws_handler(ws, headers, joint, upgrade_guard)),
+        )
+    }
+
+    /// Serves the state of a room as JSON on `GET` requests to the given path, e.g. `/rooms/{id}/state`.
+    ///
+    /// The path must capture the room ID as `{id}`. Responses carry an `ETag` derived from
+    /// the room state version, so clients polling with `If-None-Match` receive
+    /// `304 Not Modified` until the state changes. Unknown rooms are answered with `404`.
+    pub fn attach_state_router(&self, path: &str, router: Router) -> Router {
+        let joint = self.joint.clone();
+        router.route(
+            path,
+            get(move |room_id, headers| AxumWSJoint::state_handler(room_id, headers, joint)),
+        )
+    }
+
+    /// Axum handler serving the state of a room, used by `attach_state_router`.
+    pub async fn state_handler(
+        Path(room_id): Path<u64>,
+        headers: HeaderMap,
+        joint: Arc<AbstractJoint<R, AxumWSSink>>,
+    ) -> axum::response::Response {
+        let Some((version, state)) = joint.room_state(room_id).await else {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "room not found" })),
+            )
+                .into_response();
+        };
+
+        let etag = format!("\"{}\"", version);
+        let not_modified = headers
+            .get(IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .any(|tag| tag == "*" || tag == etag)
+            });
+        let cache_headers = [(ETAG, etag), (CACHE_CONTROL, "no-cache".to_string())];
+        if not_modified {
+            return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+        }
+        (cache_headers, Json(state)).into_response()
+    }
+
+    /// Creates an empty public room managed by the given reducer instance, returning its ID.
+    pub async fn preload_room(&self, reducer: R) -> u64 {
+        self.joint.preload_room(reducer).await
+    }
+
+    /// Subscribes to the state of a room, received right away and after every applied action.
+    pub async fn subscribe(&self, room_id: u64) -> Result<UnboundedReceiver<R::State>, String> {
+        self.joint.subscribe(room_id).await
+    }
+
+    /// Subscribes to the summaries of all rooms, received right away and whenever a room
+    /// is created, changes members or is deleted.
+    pub async fn subscribe_overview(&self) -> UnboundedReceiver<Response> {
+        self.joint.subscribe_overview().await
+    }
+
+    /// Drains a room before maintenance, rejecting actions and joins for `grace`,
+    /// then deletes it if `delete` is set or reopens it otherwise.
+    pub async fn drain_room(
+        &self,
+        room_id: u64,
+        grace: Duration,
+        reason: &str,
+        delete: bool,
+    ) -> Result<(), String> {
+        self.joint.drain_room(room_id, grace, reason, delete).await
+    }
+
+    /// Kicks a client out of the room owned by `owner_id`, notifying it and the room with `RoomLeft`.
+    pub async fn kick_client(&self, owner_id: u64, target_id: u64) -> Result<(), Response> {
+        self.joint.kick_client(owner_id, target_id).await
+    }
+
+    /// Returns the current state of a room, or `None` if the room doesn't exist.
+    pub async fn get_room_state(&self, room_id: u64) -> Option<R::State> {
+        self.joint.get_room_state(room_id).await
+    }
+
+    /// Returns a snapshot of runtime statistics (connected clients, rooms, actions, failed sends).
+    pub async fn stats(&self) -> JointStats {
+        self.joint.stats().await
+    }
+
+    /// Returns a machine-readable description of the control protocol spoken by the joint.
+    pub fn protocol_description(&self) -> serde_json::Value {
+        self.joint.protocol_description()
+    }
+
+    /// Renders current runtime statistics in the Prometheus text exposition format.
+    #[cfg(feature = "prometheus")]
+    pub async fn prometheus_metrics(&self) -> String {
+        self.joint.prometheus_metrics().await
+    }
+
+    /// Returns the recorded action log of a room in order of application.
+    pub async fn action_log(&self, room_id: u64) -> Option<Vec<RecordedAction>> {
+        self.joint.action_log(room_id).await
+    }
+
+    /// Replays an action log of a room against the reducer the room started from, dispatching
+    /// actions with the context they were recorded with, and returns the resulting state.
+    ///
+    /// Also takes `(author, action)` pairs, dispatched in the default protocol version.
+    pub async fn replay<A>(&self, room_id: u64, actions: &[A]) -> Result<R::State, String>
+    where
+        A: Clone + Into<RecordedAction>,
+    {
+        self.joint.replay(room_id, actions).await
     }
 
     /// Allows dispatching an action to the joint\'s reducer from outside the WebSocket context.
     ///
     /// This can be useful for triggering state changes from other parts of the application
-    /// (e.g., HTTP request handlers, background jobs).
+    /// (e.g., HTTP request handlers, background jobs). The action is broadcast to the
+    /// client's room like an in-band one.
     ///
     /// # Arguments
     /// * `client_id` - The ID of the client on whose behalf the action is dispatched.
     ///                 Note: The client must exist and be in a room for the dispatch to succeed.
     /// * `action` - A string slice representing the action to be dispatched (must be JSON serializable
     ///              according to the `Dispatchable::Action` type).
+    #[allow(clippy::doc_overindented_list_items)]
     pub async fn dispatch(
         &self,
         client_id: u64,
@@ -165,4 +730,53 @@ impl<R: Dispatchable + 'static> AxumWSJoint<R> {
     ) -> Result<ActionResponse<R::State>, String> {
         self.joint.dispatch(client_id, action).await
     }
+
+    /// Dispatches an action and broadcasts it to the client's room, then calls `callback`.
+    ///
+    /// Useful for server-side sagas reacting to a specific dispatch once its broadcast completes.
+    ///
+    /// # Arguments
+    /// * `client_id` - The ID of the client on whose behalf the action is dispatched.
+    /// * `action` - The action string to be dispatched.
+    /// * `callback` - Called with the correlation ID of the dispatch, also returned by this method,
+    ///   and the action response.
+    pub async fn dispatch_with_callback<F>(
+        &self,
+        client_id: u64,
+        action: &str,
+        callback: F,
+    ) -> Result<u64, String>
+    where
+        F: FnOnce(u64, ActionResponse<R::State>) + Send,
+    {
+        self.joint
+            .dispatch_with_callback(client_id, action, callback)
+            .await
+    }
+
+    /// Dispatches an action on behalf of `client_id` directly to a room, without the client
+    /// having to be a member, and broadcasts it to the room.
+    pub async fn dispatch_to_room(
+        &self,
+        room_id: u64,
+        client_id: u64,
+        action: &str,
+    ) -> Result<ActionResponse<R::State>, String> {
+        self.joint
+            .dispatch_to_room(room_id, client_id, action)
+            .await
+    }
+
+    /// Applies an action on behalf of `author` to several rooms as a single transaction,
+    /// rolling every room back if it fails in any of them.
+    pub async fn dispatch_multi_room(
+        &self,
+        room_ids: &[u64],
+        author: u64,
+        action: &str,
+    ) -> Result<Vec<ActionResponse<R::State>>, String> {
+        self.joint
+            .dispatch_multi_room(room_ids, author, action)
+            .await
+    }
 }
@@ -1,14 +1,19 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
     use crate::client::Client;
+    use crate::codec::{Compression, WireFormat};
     use crate::dispatcher::{ActionResponse, Dispatchable};
-    use crate::joint::axum::AxumWSJoint;
+    use crate::joint::axum::{AxumWSJoint, UpgradeRejection};
+    use crate::message::{JointMessage, JointMessageMethod};
+    use crate::response::Response;
     use crate::room::{Room, RoomStatus};
     use crate::utils::types::{Broadcastable, Receivable};
+    #[allow(unused_imports)]
     use axum::{
         body::Body,
-        extract::ws::{Message, WebSocket},
+        extract::ws::WebSocket,
         http::{Request, StatusCode},
         Router,
     };
@@ -20,6 +25,7 @@ mod tests {
     use tokio::net::TcpListener;
     use tokio::sync::Mutex;
     use tower::ServiceExt;
+    use tungstenite::Message;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     enum TestAction {
@@ -44,7 +50,9 @@ mod tests {
     impl Dispatchable for TestReducer {
         type Action = TestAction;
         type State = TestState;
+        type Error = String;
 
+        #[allow(clippy::manual_async_fn)]
         fn dispatch(
             &mut self,
             client_id: u64,
@@ -69,6 +77,7 @@ mod tests {
             }
         }
 
+        #[allow(clippy::manual_async_fn)]
         fn extern_dispatch(
             &mut self,
             client_id: u64,
@@ -123,13 +132,49 @@ mod tests {
         assert_eq!(response.status(), 426);
     }
 
+    #[tokio::test]
+    async fn test_rejected_upgrade_returns_json_error() {
+        let mut joint = AxumWSJoint::new(TestReducer::default());
+        joint.set_upgrade_guard(|headers| match headers.get("authorization") {
+            Some(token) if token == "Bearer secret" => Ok(()),
+            _ => Err(UpgradeRejection::unauthorized()),
+        });
+        let app = joint.attach_router("/ws", Router::new());
+
+        let request = Request::builder()
+            .uri("/ws")
+            .header("connection", "upgrade")
+            .header("upgrade", "websocket")
+            .header("sec-websocket-version", "13")
+            .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), 401);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({ "error": "unauthorized" }));
+
+        let request = Request::builder()
+            .uri("/ws")
+            .header("authorization", "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_ne!(response.status(), 401);
+    }
+
     #[tokio::test]
     async fn test_joint_creation_and_dispatch() {
         let reducer = TestReducer::default();
         let joint = AxumWSJoint::new(reducer);
 
         let rooms = joint.joint.broadcaster.get_rooms().clone();
-        let mut rooms = rooms.lock().await;
+        let mut rooms = rooms.write(1).await;
         rooms.insert(
             1,
             Room::new(
@@ -143,7 +188,7 @@ mod tests {
         drop(rooms);
 
         let clients = joint.joint.broadcaster.get_clients().clone();
-        let mut clients = clients.lock().await;
+        let mut clients = clients.write().await;
         clients.insert(1, Client::new(1, Some(1), String::new(), String::new()));
         drop(clients);
 
@@ -157,4 +202,181 @@ mod tests {
         assert_eq!(response.author, client_id);
         assert_eq!(response.state.counter, 1);
     }
+
+    #[tokio::test]
+    async fn test_state_route_supports_conditional_requests() {
+        let joint = AxumWSJoint::new(TestReducer::default());
+        let broadcaster = &joint.joint.broadcaster;
+        broadcaster
+            .get_clients()
+            .write()
+            .await
+            .insert(1, Client::new(1, Some(1), String::new(), String::new()));
+        broadcaster.get_rooms().write(1).await.insert(
+            1,
+            Room::new(
+                1,
+                1,
+                HashSet::from([1]),
+                RoomStatus::Public,
+                Arc::new(Mutex::new(TestReducer::default())),
+            ),
+        );
+        let app = joint.attach_state_router("/rooms/{id}/state", Router::new());
+        let get_state = |etag: Option<&str>| {
+            let mut request = Request::builder().uri("/rooms/1/state");
+            if let Some(etag) = etag {
+                request = request.header("if-none-match", etag);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(get_state(None)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let etag = response.headers()["etag"].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let state: TestState = serde_json::from_slice(&body).unwrap();
+        assert_eq!(state.counter, 0);
+
+        let response = app.clone().oneshot(get_state(Some(&etag))).await.unwrap();
+        assert_eq!(response.status(), 304);
+        assert_eq!(response.headers()["etag"], etag.as_str());
+
+        joint.dispatch(1, r#"{"Add":3}"#).await.unwrap();
+        let response = app.clone().oneshot(get_state(Some(&etag))).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_ne!(response.headers()["etag"], etag.as_str());
+
+        let request = Request::builder()
+            .uri("/rooms/7/state")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_control_frames_are_skipped() {
+        let (app, addr) = setup_test_router().await;
+        let listener = TcpListener::bind(addr).await.unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        let (mut write, mut read) = socket.split();
+
+        let hello = JointMessage::new(
+            JointMessageMethod::Hello {
+                version: 1,
+                compression: Compression::None,
+                format: None,
+            },
+            String::new(),
+        );
+        let create = JointMessage::new(JointMessageMethod::Create(None), String::new());
+        for frame in [
+            Message::Ping(b"ping".to_vec().into()),
+            Message::Pong(b"pong".to_vec().into()),
+            Message::Binary(serde_json::to_vec(&hello).unwrap().into()),
+            Message::Text(serde_json::to_string(&create).unwrap().into()),
+        ] {
+            write.send(frame).await.unwrap();
+        }
+
+        let mut responses = Vec::new();
+        while responses.len() < 4 {
+            match read.next().await.unwrap().unwrap() {
+                Message::Text(text) => {
+                    responses.push(serde_json::from_str::<Response>(&text).unwrap())
+                }
+                Message::Pong(_) => {}
+                other => panic!("Unexpected frame {:?}", other),
+            }
+        }
+        assert!(matches!(responses[0], Response::Connected(_)));
+        assert!(matches!(responses[1], Response::Hello(1)));
+        assert!(matches!(responses[2], Response::StateSent(..)));
+        assert!(matches!(responses[3], Response::RoomCreated(_)));
+    }
+
+    #[tokio::test]
+    async fn test_wire_format_negotiated_with_hello() {
+        let (app, addr) = setup_test_router().await;
+        let listener = TcpListener::bind(addr).await.unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        assert!(matches!(
+            socket.next().await.unwrap().unwrap(),
+            Message::Text(_)
+        ));
+
+        let hello = JointMessage::new(
+            JointMessageMethod::Hello {
+                version: 1,
+                compression: Compression::None,
+                format: Some(WireFormat::MessagePack),
+            },
+            String::new(),
+        );
+        socket
+            .send(Message::Text(serde_json::to_string(&hello).unwrap().into()))
+            .await
+            .unwrap();
+        // the client now sends its messages in MessagePack as well
+        let create = JointMessage::new(JointMessageMethod::Create(None), String::new());
+        let bytes = WireFormat::MessagePack.encode(&create).unwrap();
+        socket.send(Message::Binary(bytes.into())).await.unwrap();
+
+        let mut responses = Vec::new();
+        while responses.len() < 3 {
+            match socket.next().await.unwrap().unwrap() {
+                Message::Binary(bytes) => {
+                    responses.push(WireFormat::MessagePack.decode::<Response>(&bytes).unwrap())
+                }
+                other => panic!("Expected binary frame, got {:?}", other),
+            }
+        }
+        assert!(matches!(responses[0], Response::Hello(1)));
+        assert!(matches!(responses[1], Response::StateSent(..)));
+        assert!(matches!(responses[2], Response::RoomCreated(_)));
+    }
+
+    #[tokio::test]
+    async fn test_serve_uses_bound_listener() {
+        let mut joint = AxumWSJoint::<TestReducer>::new(TestReducer::default());
+        assert!(joint.local_addr().is_none());
+        joint.bind("127.0.0.1:0").await;
+        let addr = joint.local_addr().unwrap();
+        tokio::spawn(joint.serve("/ws"));
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        let create = JointMessage::new(JointMessageMethod::Create(None), String::new());
+        socket
+            .send(Message::Text(
+                serde_json::to_string(&create).unwrap().into(),
+            ))
+            .await
+            .unwrap();
+
+        let mut responses = Vec::new();
+        while responses.len() < 3 {
+            match socket.next().await.unwrap().unwrap() {
+                Message::Text(text) => {
+                    responses.push(serde_json::from_str::<Response>(&text).unwrap())
+                }
+                other => panic!("Unexpected frame {:?}", other),
+            }
+        }
+        assert!(matches!(responses[0], Response::Connected(_)));
+        assert!(matches!(responses[1], Response::StateSent(..)));
+        assert!(matches!(responses[2], Response::RoomCreated(_)));
+    }
 }
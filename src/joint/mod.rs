@@ -1,8 +1,18 @@
 use crate::broadcaster::Broadcaster;
 use crate::client::Client;
+use crate::codec::Compression;
 use crate::connection::{SinkAdapter, StreamAdapter};
-use crate::dispatcher::{ActionResponse, Dispatchable};
-use rand::Rng;
+use crate::dispatcher::{
+    ActionResponse, Dispatchable, PostDispatchHook, PreDispatchHook, Projection, RecordedAction,
+};
+use crate::load_shedding::LoadShedding;
+use crate::message::JointMessage;
+use crate::metrics::JointStats;
+use crate::rate_limit::RateLimit;
+use crate::response::{ErrorCode, Response};
+use futures::future::BoxFuture;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
 
 #[cfg(not(tarpaulin))]
 pub mod axum;
@@ -11,6 +21,14 @@ mod test;
 #[cfg(not(tarpaulin))]
 pub mod ws;
 
+/// Callback verifying the `client_token` of a new connection before it is registered.
+///
+/// Returning an error rejects the connection, answering it with a `ClientError` carrying the error.
+pub type Authenticator = Box<dyn Fn(&str) -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+/// Async callback run with the ID of a client when its connection is registered or removed.
+pub type ConnectionHook = Box<dyn Fn(u64) -> BoxFuture<'static, ()> + Send + Sync>;
+
 /// Root abstract struct that provides all publish-subscribe functionality
 ///
 /// This struct is generic over the reducer type `R` and the sink type `Sink`.
@@ -24,23 +42,378 @@ where
 {
     /// Broadcaster instance that handles the communication between clients and the reducer.
     pub(crate) broadcaster: Broadcaster<Sink, R>,
+    /// Callback verifying the token of new connections, accepting all of them if `None`.
+    authenticator: Option<Authenticator>,
+    /// Callback run after a client connection is registered.
+    on_connect: Option<ConnectionHook>,
+    /// Callback run before a client connection is removed.
+    on_disconnect: Option<ConnectionHook>,
 }
 
 impl<R, Sink> AbstractJoint<R, Sink>
 where
     Sink: SinkAdapter + Unpin + Clone,
-    R: Dispatchable + Send + Clone,
+    R: Dispatchable + Send + Clone + 'static,
 {
-    /// Creates a new instance of `AbstractJoint`.
+    /// Creates a new instance of `AbstractJoint`, cloning `default_reducer` for each new room.
     pub fn new(default_reducer: R) -> Self {
+        Self::from_broadcaster(Broadcaster::new(default_reducer))
+    }
+
+    /// Creates a new instance of `AbstractJoint`, constructing the reducer of each new room
+    /// with `reducer_factory` instead of cloning a default reducer.
+    ///
+    /// Meant for reducers whose initial state is cheap to construct but expensive to clone.
+    pub fn new_with_factory(reducer_factory: impl Fn() -> R + Send + Sync + 'static) -> Self {
+        Self::from_broadcaster(Broadcaster::new_with_factory(Box::new(reducer_factory)))
+    }
+
+    /// wraps a broadcaster without any callback
+    fn from_broadcaster(broadcaster: Broadcaster<Sink, R>) -> Self {
         AbstractJoint {
-            broadcaster: Broadcaster::new(default_reducer),
+            broadcaster,
+            authenticator: None,
+            on_connect: None,
+            on_disconnect: None,
         }
     }
 
+    /// Seeds the generator of client IDs, so a fixed seed yields the same IDs across runs.
+    ///
+    /// Meant for reproducible benchmarks and fuzzing, as seeded IDs are predictable.
+    pub fn set_id_seed(&mut self, seed: u64) {
+        self.broadcaster.set_id_seed(seed);
+    }
+
+    /// Sets whether joining clients must acknowledge the initial state with an `Ack` message
+    /// before they are considered joined and included in room broadcasts.
+    pub fn set_join_requires_ack(&mut self, join_requires_ack: bool) {
+        self.broadcaster.set_join_requires_ack(join_requires_ack);
+    }
+
+    /// Sets the maximum size of a room state in bytes (as reported by `Dispatchable::state_size_bytes`).
+    ///
+    /// Actions growing the state beyond the limit are reverted and rejected with a `ClientError`.
+    pub fn set_max_state_bytes(&mut self, max_state_bytes: Option<usize>) {
+        self.broadcaster.set_max_state_bytes(max_state_bytes);
+    }
+
+    /// Sets the maximum size in bytes of the `data` returned by the reducer for an action.
+    ///
+    /// Actions returning more data are reverted and not broadcast, protecting the room from
+    /// a single oversized result. Their author is sent a `ServerError` and the action is logged.
+    pub fn set_max_data_bytes(&mut self, max_data_bytes: Option<usize>) {
+        self.broadcaster.set_max_data_bytes(max_data_bytes);
+    }
+
+    /// Sets whether every applied action is recorded with its author in the per-room action log.
+    pub fn set_record_actions(&mut self, record_actions: bool) {
+        self.broadcaster.set_record_actions(record_actions);
+    }
+
+    /// Sets the number of action broadcasts kept per room for clients resuming their session.
+    ///
+    /// Clients disconnected with a session token, see `set_session_grace`, can rejoin their room
+    /// with `Resume`, carrying the sequence number of the last action they saw (the state version it produced). They are
+    /// replayed the broadcasts they missed if still kept, or sent the full state otherwise.
+    /// Disabled with `0`, the default.
+    pub fn set_history_size(&mut self, history_size: usize) {
+        self.broadcaster.set_history_size(history_size);
+    }
+
+    /// Sets the compression applied to the action broadcasts kept per room for resuming clients.
+    ///
+    /// Saves memory on servers hosting many rooms with long histories, at the cost of
+    /// compressing every applied action and decompressing replays. Defaults to `Compression::None`.
+    pub fn set_history_compression(&mut self, compression: Compression) {
+        self.broadcaster.set_history_compression(compression);
+    }
+
+    /// Sets the number of room broadcasts queued for each client disconnected from a room
+    /// with a session token, sent to it once it reconnects with `resume_stream` or `Resume`.
+    ///
+    /// Only the latest broadcasts are kept. Disabled with `0`, the default.
+    pub fn set_offline_buffer_size(&mut self, buffer_size: usize) {
+        self.broadcaster.set_offline_buffer_size(buffer_size);
+    }
+
+    /// Sets how long after disconnecting a client can resume its session.
+    ///
+    /// Clients are sent a random `SessionToken` on connect, which resumes their session once.
+    /// Sessions are only kept for disconnected clients when set, disabled with `None`, the default.
+    pub fn set_session_grace(&mut self, grace: Option<Duration>) {
+        self.broadcaster.set_session_grace(grace);
+    }
+
+    /// Returns the recorded action log of a room in order of application.
+    ///
+    /// The log is only populated when recording is enabled with `set_record_actions`.
+    pub async fn action_log(&self, room_id: u64) -> Option<Vec<RecordedAction>> {
+        self.broadcaster.action_log(room_id).await
+    }
+
+    /// Replays an action log of a room against the reducer the room started from, a new one
+    /// from the reducer factory unless the room was preloaded, and returns the resulting state.
+    ///
+    /// Actions are dispatched with the protocol version and client context they were recorded
+    /// with, like live ones. Useful to verify that room state is a pure function of its action log.
+    ///
+    /// Takes the log returned by `action_log`, or `(author, action)` pairs, which are dispatched
+    /// straight to the reducer in the default protocol version.
+    pub async fn replay<A>(&self, room_id: u64, actions: &[A]) -> Result<R::State, String>
+    where
+        A: Clone + Into<RecordedAction>,
+    {
+        let actions: Vec<RecordedAction> = actions.iter().cloned().map(Into::into).collect();
+        self.broadcaster.replay(room_id, &actions).await
+    }
+
+    /// Sets the maximum time a single send to a client may take.
+    ///
+    /// Clients whose sink does not accept a message in time are removed from the joint,
+    /// so one stuck client can't stall broadcasts to the rest of its room.
+    pub fn set_send_timeout(&mut self, send_timeout: Option<Duration>) {
+        self.broadcaster.set_send_timeout(send_timeout);
+    }
+
+    /// Sets the interval after which idle clients are sent an application-level `Keepalive`,
+    /// so NATs and load balancers don't drop idle connections.
+    ///
+    /// A client is idle when nothing was broadcast to its room during the last interval,
+    /// or when it is not in a room. Disabled by default.
+    pub fn set_keepalive_interval(&mut self, keepalive_interval: Option<Duration>) {
+        self.broadcaster.set_keepalive_interval(keepalive_interval);
+    }
+
+    /// Sets the per-client action rate limit used in rooms that don't override it.
+    ///
+    /// Actions over the limit are rejected with a `ClientError` instead of being dispatched.
+    pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.broadcaster.set_rate_limit(rate_limit);
+    }
+
+    /// Sets the most permissive rate limit room creators may request in `CreateOptions`.
+    ///
+    /// Rooms requesting a more permissive limit are not created. Without a maximum,
+    /// rooms may not override the default rate limit at all.
+    pub fn set_max_rate_limit(&mut self, max_rate_limit: Option<RateLimit>) {
+        self.broadcaster.set_max_rate_limit(max_rate_limit);
+    }
+
+    /// Sets the policy rejecting low-priority actions with `Overloaded` while the joint is under pressure.
+    ///
+    /// Action priorities are given by `Dispatchable::priority`. Disabled by default.
+    pub fn set_load_shedding(&mut self, load_shedding: Option<LoadShedding>) {
+        self.broadcaster.set_load_shedding(load_shedding);
+    }
+
+    /// Sets the number of room members above which action broadcasts are skipped.
+    ///
+    /// Members of rooms over the cap no longer receive `Action` responses and pull the room state
+    /// with `Sync` instead, bounding the cost of a single action. Broadcast fan-out is reported
+    /// by `stats`. Unlimited by default.
+    pub fn set_max_fanout(&mut self, max_fanout: Option<usize>) {
+        self.broadcaster.set_max_fanout(max_fanout);
+    }
+
+    /// Sets the maximum size of a single outgoing JSON-serialized response in bytes.
+    ///
+    /// Actions producing a larger broadcast are reverted and rejected with a `ServerError`,
+    /// and clients can't create or join rooms whose initial state is larger.
+    pub fn set_max_outgoing_bytes(&mut self, max_outgoing_bytes: Option<usize>) {
+        self.broadcaster.set_max_outgoing_bytes(max_outgoing_bytes);
+    }
+
+    /// Sets whether rooms are deleted as soon as their last client leaves or disconnects.
+    ///
+    /// Disabled by default, so empty rooms stay available to join until deleted explicitly.
+    pub fn set_delete_empty_rooms(&mut self, delete_empty_rooms: bool) {
+        self.broadcaster.set_delete_empty_rooms(delete_empty_rooms);
+    }
+
+    /// Sets whether clients may be members of several rooms at once.
+    ///
+    /// Clients already in a room then create and join further rooms, dispatch actions to any
+    /// of them with `RoomAction` and leave them with `LeaveRoom`. Messages not naming a room
+    /// target the first room joined. Disabled by default.
+    pub fn set_multi_room(&mut self, multi_room: bool) {
+        self.broadcaster.set_multi_room(multi_room);
+    }
+
+    /// Sets the hook run before each action received from a client is dispatched.
+    ///
+    /// The hook receives the context of the acting client and the raw action payload.
+    /// Returning an error aborts the action, answering the client with a `ClientError`.
+    pub fn with_pre_hook(&mut self, hook: PreDispatchHook) {
+        self.broadcaster.with_pre_hook(hook);
+    }
+
+    /// Sets the callback verifying the `client_token` of new connections, e.g. a JWT or session ID.
+    ///
+    /// With an authenticator, the first message of a connection is checked before its client
+    /// is registered. Connections rejected by the callback are answered with a `ClientError`
+    /// and closed without ever being added to the joint.
+    pub fn set_authenticator(&mut self, authenticator: Option<Authenticator>) {
+        self.authenticator = authenticator;
+    }
+
+    /// Sets the callback run with the ID of every client right after its connection is registered.
+    pub fn on_connect(&mut self, hook: ConnectionHook) {
+        self.on_connect = Some(hook);
+    }
+
+    /// Sets the callback run with the ID of every client right before its connection is removed.
+    ///
+    /// Runs however the connection ends, including when its stream fails.
+    pub fn on_disconnect(&mut self, hook: ConnectionHook) {
+        self.on_disconnect = Some(hook);
+    }
+
+    /// Sets the hook run after each action received from a client is applied.
+    ///
+    /// The hook receives the context of the acting client and the resulting `ActionResponse`.
+    pub fn with_post_hook(&mut self, hook: PostDispatchHook<R::State>) {
+        self.broadcaster.with_post_hook(hook);
+    }
+
+    /// Registers a named projection of the room states.
+    ///
+    /// Clients joining a room with `JoinProjected` and the projection's name are sent the room
+    /// state rendered through it instead of the full state, in `StateSent` responses and in the
+    /// `state` (or `patch`) of `Action` broadcasts, until they leave the room. Meant to be called
+    /// before rooms are created: existing rooms don't render their actions through it.
+    pub fn add_projection(&mut self, name: impl Into<String>, projection: Projection<R::State>) {
+        self.broadcaster.add_projection(name.into(), projection);
+    }
+
+    /// Sets whether action broadcasts carry a JSON Patch instead of the full state.
+    ///
+    /// When enabled, the `state` field of broadcast `ActionResponse`s is replaced with a `patch`
+    /// field holding the JSON Patch (RFC 6902) from the previous room state to the new one,
+    /// which clients apply to the state they were sent on join. Disabled by default.
+    pub fn set_state_diffs(&mut self, state_diffs: bool) {
+        self.broadcaster.set_state_diffs(state_diffs);
+    }
+
+    /// Sets whether `StateSent` and `Action` responses carry a sequence number and a timestamp.
+    ///
+    /// When enabled, these responses carry `seq` and `timestamp` fields: the state version of
+    /// the room, increasing by one with each applied action, and the UNIX time in milliseconds
+    /// the server applied the action or sent the state at. Disabled by default.
+    pub fn set_broadcast_stamps(&mut self, stamps: bool) {
+        self.broadcaster.set_broadcast_stamps(stamps);
+    }
+
+    /// Sets whether client actions are applied by a dedicated actor task per room.
+    ///
+    /// Each room's actor applies its actions one at a time, without blocking other clients,
+    /// so actions to different rooms run concurrently. Each client's actions are applied in
+    /// order, and clients with backed up actions take turns, so a client flooding a room
+    /// doesn't starve the others. Disabled by default.
+    pub fn set_room_actors(&mut self, room_actors: bool) {
+        self.broadcaster.set_room_actors(room_actors);
+    }
+
+    /// Enables single-room mode: a shared room is created right away and every connecting client
+    /// joins it automatically, receiving its state, so clients can send actions without `Create` or `Join`.
+    ///
+    /// Returns the ID of the shared room.
+    pub fn single_room_mode(&mut self) -> u64 {
+        self.broadcaster.single_room_mode()
+    }
+
+    /// Creates an empty public room managed by the given reducer instance, returning its ID.
+    ///
+    /// Lets rooms start from a preloaded state, or use reducers built with constructor
+    /// arguments, instead of a clone of the default reducer. The room is owned by no client.
+    pub async fn preload_room(&self, reducer: R) -> u64 {
+        self.broadcaster.preload_room(reducer).await
+    }
+
+    /// Subscribes to the state of a room from within the process, without connecting a client.
+    ///
+    /// The current state is received right away, then the new state after every applied action.
+    /// The subscription ends when the room is deleted.
+    pub async fn subscribe(&self, room_id: u64) -> Result<UnboundedReceiver<R::State>, String> {
+        self.broadcaster.subscribe(room_id).await
+    }
+
+    /// Subscribes to the summaries of all rooms, powering a live room browser without polling.
+    ///
+    /// The `RoomSummary` of every existing room is received right away, then another one
+    /// whenever a room is created or its members change, and `RoomDeleted` when it is deleted.
+    pub async fn subscribe_overview(&self) -> UnboundedReceiver<Response> {
+        self.broadcaster.subscribe_overview().await
+    }
+
+    /// Drains a room before maintenance.
+    ///
+    /// The room immediately stops accepting actions and joins (answered with `RoomDraining`)
+    /// and its members are notified with a `RoomDraining` broadcast carrying `reason`.
+    /// Once `grace` elapses the room is deleted, notifying members with `RoomDeleted`,
+    /// or reopened if `delete` is false. The returned future completes after the grace period.
+    pub async fn drain_room(
+        &self,
+        room_id: u64,
+        grace: Duration,
+        reason: &str,
+        delete: bool,
+    ) -> Result<(), String> {
+        self.broadcaster
+            .drain_room(room_id, grace, reason, delete)
+            .await
+    }
+
+    /// Kicks a client out of the room owned by `owner_id`.
+    ///
+    /// The kicked client and the remaining members receive `RoomLeft` with the kicked client's ID.
+    /// Fails with a `ClientError` response if `owner_id` doesn't own its room
+    /// or `target_id` is not a member of it.
+    pub async fn kick_client(&self, owner_id: u64, target_id: u64) -> Result<(), Response> {
+        self.broadcaster
+            .kick_client(owner_id, target_id)
+            .await
+            .map_err(|error| error.response)
+    }
+
+    /// returns the state of a room along with its version, which changes whenever the state does
+    pub(crate) async fn room_state(&self, room_id: u64) -> Option<(u64, R::State)> {
+        self.broadcaster.room_state(room_id).await
+    }
+
+    /// Returns the current state of a room, or `None` if the room doesn't exist.
+    ///
+    /// Reads the state without dispatching anything, e.g. for HTTP endpoints rendering it.
+    pub async fn get_room_state(&self, room_id: u64) -> Option<R::State> {
+        self.room_state(room_id).await.map(|(_, state)| state)
+    }
+
+    /// Returns a snapshot of runtime statistics (connected clients, rooms, actions, failed sends).
+    pub async fn stats(&self) -> JointStats {
+        self.broadcaster.stats().await
+    }
+
+    /// Returns a machine-readable description of the control protocol spoken by the joint.
+    ///
+    /// Lists the message methods clients may send, including the ones enabled by the joint
+    /// configuration, the responses they receive, and the reducer's `action_schema`.
+    /// Useful to generate client SDKs and real-time API docs.
+    pub fn protocol_description(&self) -> serde_json::Value {
+        self.broadcaster.protocol_description()
+    }
+
+    /// Renders current runtime statistics in the Prometheus text exposition format,
+    /// ready to be served at a `/metrics` endpoint.
+    #[cfg(feature = "prometheus")]
+    pub async fn prometheus_metrics(&self) -> String {
+        self.stats().await.to_prometheus()
+    }
+
     /// Dispatches developer-defined action (performed by user) to joint reducer
     ///
     /// This method takes a `client_id` and an `action` string as parameters.
+    /// The resulting `Response::Action` is broadcast to the client's room, like for
+    /// actions received from the client.
     pub async fn dispatch(
         &self,
         client_id: u64,
@@ -49,14 +422,156 @@ where
         self.broadcaster.extern_dispatch(client_id, action).await
     }
 
+    /// Dispatches an action on behalf of `client_id` directly to a room and broadcasts it
+    ///
+    /// Unlike `dispatch`, the room is not looked up through the client, so system actions
+    /// don't need a client in the room: `client_id` is only passed to the reducer as the
+    /// author. The resulting `Response::Action` is broadcast to every member of the room.
+    pub async fn dispatch_to_room(
+        &self,
+        room_id: u64,
+        client_id: u64,
+        action: &str,
+    ) -> Result<ActionResponse<R::State>, String> {
+        self.broadcaster
+            .dispatch_to_room(room_id, client_id, action)
+            .await
+    }
+
+    /// Dispatches an action and broadcasts it to the client's room, then calls `callback`
+    ///
+    /// The callback receives the correlation ID of the dispatch, also returned by this method,
+    /// and the action response once the broadcast completes.
+    pub async fn dispatch_with_callback<F>(
+        &self,
+        client_id: u64,
+        action: &str,
+        callback: F,
+    ) -> Result<u64, String>
+    where
+        F: FnOnce(u64, ActionResponse<R::State>) + Send,
+    {
+        self.broadcaster
+            .dispatch_with_callback(client_id, action, callback)
+            .await
+    }
+
+    /// Applies an action on behalf of `author` to several rooms as a single transaction.
+    ///
+    /// The room reducers are locked in order of room ID, so concurrent multi-room dispatches
+    /// can't deadlock. If the action fails in any room, all rooms are rolled back to their state
+    /// before the dispatch and the error is returned; otherwise the action is broadcast to every
+    /// room and the responses are returned in order of room ID.
+    pub async fn dispatch_multi_room(
+        &self,
+        room_ids: &[u64],
+        author: u64,
+        action: &str,
+    ) -> Result<Vec<ActionResponse<R::State>>, String> {
+        self.broadcaster
+            .dispatch_multi_room(room_ids, author, action)
+            .await
+    }
+
     /// handles new abstract split sink
     ///
     /// This method takes a mutable reference to a `StreamAdapter` and a `Sink` as parameters.
-    pub async fn handle_stream<S>(&self, receiver: &mut S, sender: Sink)
+    /// With an authenticator set, the client is only registered once the token of its first
+    /// message is accepted.
+    pub async fn handle_stream<S>(&self, receiver: &mut S, mut sender: Sink)
+    where
+        S: StreamAdapter + Unpin + Send + Sync,
+    {
+        let first_message = match &self.authenticator {
+            Some(authenticator) => {
+                let Ok(message) = receiver.next().await else {
+                    return;
+                };
+                if let Err(reason) = authenticator(&message.client_token).await {
+                    if let Err(e) = sender
+                        .send(Response::ClientError {
+                            code: ErrorCode::Unauthorized,
+                            message: reason,
+                        })
+                        .await
+                    {
+                        eprintln!("Error rejecting unauthorized connection: {}", e);
+                    }
+                    return;
+                }
+                Some(message)
+            }
+            None => None,
+        };
+
+        let new_client_id = self.connect_client(sender).await;
+
+        if let Some(message) = first_message {
+            self.handle_message(new_client_id, message).await;
+        }
+
+        self.broadcaster.handle_rx(new_client_id, receiver).await;
+
+        self.disconnect_client(new_client_id).await;
+    }
+
+    /// Handles a reconnecting client stream, resuming the session of `session_token`
+    ///
+    /// `session_token` is the `SessionToken` the client was sent by the server. The client keeps
+    /// the ID and room it had when its previous connection dropped, and is sent the current room
+    /// state and a new session token on `sender`. Returns an error without handling the stream
+    /// if the session is not found or its room can't be rejoined.
+    ///
+    /// The session token alone is not accepted as a credential: with an authenticator set, the
+    /// `client_token` the client first connected with is checked again, so clients whose
+    /// credentials were revoked since are answered with a `ClientError` and not resumed.
+    pub async fn resume_stream<S>(
+        &self,
+        session_token: &str,
+        receiver: &mut S,
+        mut sender: Sink,
+    ) -> Result<(), String>
     where
         S: StreamAdapter + Unpin + Send + Sync,
     {
-        let new_client_id = rand::rng().random::<u64>();
+        if let Some(authenticator) = &self.authenticator {
+            let client_token = self
+                .broadcaster
+                .session_client_token(session_token)
+                .await
+                .ok_or_else(|| "Session not found".to_string())?;
+            if let Err(reason) = authenticator(&client_token).await {
+                if let Err(e) = sender
+                    .send(Response::ClientError {
+                        code: ErrorCode::Unauthorized,
+                        message: reason.clone(),
+                    })
+                    .await
+                {
+                    eprintln!("Error rejecting unauthorized connection: {}", e);
+                }
+                return Err(reason);
+            }
+        }
+
+        let client_id = self
+            .broadcaster
+            .resume_client(session_token, sender)
+            .await?;
+
+        if let Some(on_connect) = &self.on_connect {
+            on_connect(client_id).await;
+        }
+
+        self.broadcaster.handle_rx(client_id, receiver).await;
+
+        self.disconnect_client(client_id).await;
+        Ok(())
+    }
+
+    /// registers a new client sending responses to `sender`, returning its generated ID
+    pub(crate) async fn connect_client(&self, sender: Sink) -> u64 {
+        let new_client_id = self.broadcaster.next_client_id();
 
         self.broadcaster
             .add_client_connection(
@@ -64,12 +579,36 @@ where
                 sender,
             )
             .await;
-
-        self.broadcaster.handle_rx(new_client_id, receiver).await;
-
         self.broadcaster
-            .remove_client_connection(new_client_id)
+            .react_to_client(new_client_id, Response::Connected(new_client_id))
             .await;
+        self.broadcaster.open_session(new_client_id).await;
+
+        if let Some(on_connect) = &self.on_connect {
+            on_connect(new_client_id).await;
+        }
+
+        if let Err(e) = self.broadcaster.join_single_room(new_client_id).await {
+            eprintln!(
+                "Error joining client {} to the shared room: {}",
+                new_client_id, e
+            );
+        }
+
+        new_client_id
+    }
+
+    /// processes a single message sent by a connected client
+    pub(crate) async fn handle_message(&self, client_id: u64, message: JointMessage) {
+        self.broadcaster.handle_event(client_id, message).await;
+    }
+
+    /// removes a connected client, leaving its room
+    pub(crate) async fn disconnect_client(&self, client_id: u64) {
+        if let Some(on_disconnect) = &self.on_disconnect {
+            on_disconnect(client_id).await;
+        }
+        self.broadcaster.remove_client_connection(client_id).await;
     }
 
     /// Returns a reference to the `Broadcaster` instance.
@@ -3,15 +3,22 @@
 /// This is useful for in-process communication or testing where network connections are not required.
 mod test;
 
+use crate::codec::Compression;
 use crate::connection::{SinkAdapter, StreamAdapter};
-use crate::dispatcher::{ActionResponse, Dispatchable};
-use crate::joint::AbstractJoint;
+use crate::dispatcher::{
+    ActionResponse, Dispatchable, PostDispatchHook, PreDispatchHook, Projection, RecordedAction,
+};
+use crate::joint::{AbstractJoint, Authenticator, ConnectionHook};
+use crate::load_shedding::LoadShedding;
 use crate::message::JointMessage;
+use crate::metrics::JointStats;
+use crate::rate_limit::RateLimit;
 use crate::response::Response;
 use async_trait::async_trait;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io;
-use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::mpsc::{self, Receiver, Sender, UnboundedReceiver};
 
 /// An implementation of [`SinkAdapter`] that sends responses over a `tokio::sync::mpsc::Sender`.
 #[derive(Clone)]
@@ -48,6 +55,54 @@ impl StreamAdapter for MPSCStream {
     }
 }
 
+/// A client connection processed on the caller's task, created by [`MPSCJoint::connect_inline`].
+///
+/// Each step processes exactly one message sent by the client, so tests can assert
+/// joint state and responses between messages.
+pub struct MPSCDriver<R: Dispatchable + Send + 'static> {
+    joint: Arc<AbstractJoint<R, MPSCSink>>,
+    client_id: u64,
+    stream: MPSCStream,
+}
+
+impl<R: Dispatchable + Send + Clone + 'static> MPSCDriver<R> {
+    /// Returns the ID assigned to the driven client.
+    pub fn client_id(&self) -> u64 {
+        self.client_id
+    }
+
+    /// Waits for the next message sent by the client and processes it.
+    ///
+    /// Returns `false` without processing anything once the client's sender is dropped.
+    pub async fn step(&mut self) -> bool {
+        match self.stream.next().await {
+            Ok(message) => {
+                self.joint.handle_message(self.client_id, message).await;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Processes the next message sent by the client if one is already queued, without waiting.
+    ///
+    /// Returns whether a message was processed.
+    pub async fn try_step(&mut self) -> bool {
+        match self.stream.receiver.try_recv() {
+            Ok(message) => {
+                self.joint.handle_message(self.client_id, message).await;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Disconnects the client from the joint, removing it from its room.
+    pub async fn disconnect(self) {
+        self.joint.disconnect_client(self.client_id).await;
+    }
+}
+
 /// An `injoint` joint that uses Tokio MPSC channels for communication.
 ///
 /// This allows clients to connect and interact with the joint by sending `JointMessage`s
@@ -70,6 +125,274 @@ impl<R: Dispatchable + Send + Clone + 'static> MPSCJoint<R> {
         }
     }
 
+    /// Creates a new `MPSCJoint` constructing the reducer of each new room with `reducer_factory`
+    /// instead of cloning a default reducer.
+    pub fn new_with_factory(reducer_factory: impl Fn() -> R + Send + Sync + 'static) -> Self {
+        MPSCJoint {
+            joint: Arc::new(AbstractJoint::new_with_factory(reducer_factory)),
+        }
+    }
+
+    /// Returns a mutable reference to the underlying joint for configuration.
+    ///
+    /// # Panics
+    /// * This method panics if the joint is already shared with connection tasks.
+    fn joint_mut(&mut self) -> &mut AbstractJoint<R, MPSCSink> {
+        Arc::get_mut(&mut self.joint).expect("joint must be configured before handling connections")
+    }
+
+    /// Sets whether joining clients must acknowledge the initial state with an `Ack` message
+    /// before they are considered joined and included in room broadcasts.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_join_requires_ack(&mut self, join_requires_ack: bool) {
+        self.joint_mut().set_join_requires_ack(join_requires_ack);
+    }
+
+    /// Sets the maximum size of a room state in bytes.
+    ///
+    /// Actions growing the state beyond the limit are reverted and rejected with a `ClientError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_state_bytes(&mut self, max_state_bytes: Option<usize>) {
+        self.joint_mut().set_max_state_bytes(max_state_bytes);
+    }
+
+    /// Sets the maximum size in bytes of the `data` returned by the reducer for an action.
+    ///
+    /// Actions returning more data are reverted and their author is sent a `ServerError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_data_bytes(&mut self, max_data_bytes: Option<usize>) {
+        self.joint_mut().set_max_data_bytes(max_data_bytes);
+    }
+
+    /// Sets whether every applied action is recorded with its author in the per-room action log.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_record_actions(&mut self, record_actions: bool) {
+        self.joint_mut().set_record_actions(record_actions);
+    }
+
+    /// Sets the maximum time a single send to a client may take.
+    ///
+    /// Clients whose sink does not accept a message in time are removed from the joint.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_send_timeout(&mut self, send_timeout: Option<Duration>) {
+        self.joint_mut().set_send_timeout(send_timeout);
+    }
+
+    /// Sets the per-client action rate limit used in rooms that don't override it.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.joint_mut().set_rate_limit(rate_limit);
+    }
+
+    /// Sets the most permissive rate limit room creators may request in `CreateOptions`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_rate_limit(&mut self, max_rate_limit: Option<RateLimit>) {
+        self.joint_mut().set_max_rate_limit(max_rate_limit);
+    }
+
+    /// Sets the maximum size of a single outgoing JSON-serialized response in bytes.
+    ///
+    /// Oversized action broadcasts and initial states are replaced with a `ServerError` to the author.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_outgoing_bytes(&mut self, max_outgoing_bytes: Option<usize>) {
+        self.joint_mut().set_max_outgoing_bytes(max_outgoing_bytes);
+    }
+
+    /// Sets whether rooms are deleted as soon as their last client leaves or disconnects.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_delete_empty_rooms(&mut self, delete_empty_rooms: bool) {
+        self.joint_mut().set_delete_empty_rooms(delete_empty_rooms);
+    }
+
+    /// Sets whether clients may be members of several rooms at once, targeting them with `RoomAction`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_multi_room(&mut self, multi_room: bool) {
+        self.joint_mut().set_multi_room(multi_room);
+    }
+
+    /// Enables single-room mode: every connecting client automatically joins one shared room,
+    /// so clients can send actions right away without `Create` or `Join`.
+    ///
+    /// Returns the ID of the shared room.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn single_room_mode(&mut self) -> u64 {
+        self.joint_mut().single_room_mode()
+    }
+
+    /// Sets the interval after which idle clients are sent an application-level `Keepalive`,
+    /// so NATs and load balancers don't drop idle connections.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_keepalive_interval(&mut self, keepalive_interval: Option<Duration>) {
+        self.joint_mut().set_keepalive_interval(keepalive_interval);
+    }
+
+    /// Sets the hook run before each action received from a client is dispatched.
+    /// Returning an error from the hook aborts the action with a `ClientError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn with_pre_hook(&mut self, hook: PreDispatchHook) {
+        self.joint_mut().with_pre_hook(hook);
+    }
+
+    /// Sets the hook run after each action received from a client is applied,
+    /// receiving the resulting `ActionResponse`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn with_post_hook(&mut self, hook: PostDispatchHook<R::State>) {
+        self.joint_mut().with_post_hook(hook);
+    }
+
+    /// Registers a named projection of the room states, sent instead of the full state
+    /// to the clients joining a room with `JoinProjected` and its name.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn add_projection(&mut self, name: impl Into<String>, projection: Projection<R::State>) {
+        self.joint_mut().add_projection(name, projection);
+    }
+
+    /// Sets whether client actions are applied by a dedicated actor task per room,
+    /// so actions to different rooms run concurrently.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_room_actors(&mut self, room_actors: bool) {
+        self.joint_mut().set_room_actors(room_actors);
+    }
+
+    /// Sets whether action broadcasts carry a JSON Patch (RFC 6902) from the previous room state
+    /// in a `patch` field instead of the full `state`, saving bandwidth for large states.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_state_diffs(&mut self, state_diffs: bool) {
+        self.joint_mut().set_state_diffs(state_diffs);
+    }
+
+    /// Sets whether `StateSent` and `Action` responses carry the room's sequence number in a
+    /// `seq` field and the server time in a `timestamp` field, to detect missed broadcasts.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_broadcast_stamps(&mut self, stamps: bool) {
+        self.joint_mut().set_broadcast_stamps(stamps);
+    }
+
+    /// Sets the number of action broadcasts kept per room for clients resuming their session.
+    ///
+    /// Resuming clients missing fewer actions are replayed them instead of being sent the full state.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_history_size(&mut self, history_size: usize) {
+        self.joint_mut().set_history_size(history_size);
+    }
+
+    /// Sets the callback verifying the `client_token` of new connections.
+    /// Connections rejected by the callback are answered with a `ClientError` and closed.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_authenticator(&mut self, authenticator: Option<Authenticator>) {
+        self.joint_mut().set_authenticator(authenticator);
+    }
+
+    /// Sets the policy rejecting low-priority actions with `Overloaded` while the joint is under pressure.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_load_shedding(&mut self, load_shedding: Option<LoadShedding>) {
+        self.joint_mut().set_load_shedding(load_shedding);
+    }
+
+    /// Sets the number of room members above which action broadcasts are skipped,
+    /// members of larger rooms pulling the room state with `Sync` instead.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_fanout(&mut self, max_fanout: Option<usize>) {
+        self.joint_mut().set_max_fanout(max_fanout);
+    }
+
+    /// Sets the callback run with the ID of every client right after its connection is registered.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn on_connect(&mut self, hook: ConnectionHook) {
+        self.joint_mut().on_connect(hook);
+    }
+
+    /// Sets the callback run with the ID of every client right before its connection is removed,
+    /// however the connection ends.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn on_disconnect(&mut self, hook: ConnectionHook) {
+        self.joint_mut().on_disconnect(hook);
+    }
+
+    /// Sets the compression applied to the action broadcasts kept per room for resuming clients.
+    /// Defaults to `Compression::None`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_history_compression(&mut self, compression: Compression) {
+        self.joint_mut().set_history_compression(compression);
+    }
+
+    /// Sets the number of room broadcasts queued for each client disconnected from a room
+    /// with a session token, sent to it once it resumes its session. Disabled with `0`, the default.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_offline_buffer_size(&mut self, buffer_size: usize) {
+        self.joint_mut().set_offline_buffer_size(buffer_size);
+    }
+
+    /// Seeds the generator of client IDs, so a fixed seed yields the same IDs across runs.
+    /// Meant for reproducible benchmarks and fuzzing, as seeded IDs are predictable.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_id_seed(&mut self, seed: u64) {
+        self.joint_mut().set_id_seed(seed);
+    }
+
+    /// Sets how long after disconnecting a client can resume its session with the session token
+    /// it was sent on connect. Sessions are disabled with `None`, the default.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_session_grace(&mut self, grace: Option<Duration>) {
+        self.joint_mut().set_session_grace(grace);
+    }
+
     /// Connects a new client via MPSC channels.
     ///
     /// Creates a pair of channels: one for the client to send messages (`Sender<JointMessage>`)
@@ -99,15 +422,125 @@ impl<R: Dispatchable + Send + Clone + 'static> MPSCJoint<R> {
         (msg_tx, resp_rx)
     }
 
+    /// Connects a new client via MPSC channels, processed on the caller's task.
+    ///
+    /// Unlike [`connect`](Self::connect), no background task is spawned: messages sent to the
+    /// returned `Sender<JointMessage>` are only processed when the returned [`MPSCDriver`] is stepped,
+    /// one message per step. Responses are already queued in the `Receiver<Response>` when a step
+    /// returns, which makes the order of events deterministic in tests.
+    ///
+    /// Responses are sent on the caller's task too, so `buffer_size` must fit every response
+    /// produced by a step, or the receiver must be drained between steps.
+    ///
+    /// Inline clients are registered right away, bypassing the authenticator.
+    ///
+    /// # Arguments
+    /// * `buffer_size` - The buffer size for the created MPSC channels.
+    ///
+    /// # Returns
+    /// A tuple containing the sender for client messages, the receiver for server responses
+    /// and the driver processing the messages.
+    pub async fn connect_inline(
+        &self,
+        buffer_size: usize,
+    ) -> (Sender<JointMessage>, Receiver<Response>, MPSCDriver<R>) {
+        let (msg_tx, msg_rx) = mpsc::channel(buffer_size);
+        let (resp_tx, resp_rx) = mpsc::channel(buffer_size);
+
+        let client_id = self
+            .joint
+            .connect_client(MPSCSink { sender: resp_tx })
+            .await;
+        let driver = MPSCDriver {
+            joint: self.joint.clone(),
+            client_id,
+            stream: MPSCStream { receiver: msg_rx },
+        };
+
+        (msg_tx, resp_rx, driver)
+    }
+
+    /// Creates an empty public room managed by the given reducer instance, returning its ID.
+    pub async fn preload_room(&self, reducer: R) -> u64 {
+        self.joint.preload_room(reducer).await
+    }
+
+    /// Subscribes to the state of a room, received right away and after every applied action.
+    pub async fn subscribe(&self, room_id: u64) -> Result<UnboundedReceiver<R::State>, String> {
+        self.joint.subscribe(room_id).await
+    }
+
+    /// Subscribes to the summaries of all rooms, received right away and whenever a room
+    /// is created, changes members or is deleted.
+    pub async fn subscribe_overview(&self) -> UnboundedReceiver<Response> {
+        self.joint.subscribe_overview().await
+    }
+
+    /// Drains a room before maintenance, rejecting actions and joins for `grace`,
+    /// then deletes it if `delete` is set or reopens it otherwise.
+    pub async fn drain_room(
+        &self,
+        room_id: u64,
+        grace: Duration,
+        reason: &str,
+        delete: bool,
+    ) -> Result<(), String> {
+        self.joint.drain_room(room_id, grace, reason, delete).await
+    }
+
+    /// Kicks a client out of the room owned by `owner_id`, notifying it and the room with `RoomLeft`.
+    pub async fn kick_client(&self, owner_id: u64, target_id: u64) -> Result<(), Response> {
+        self.joint.kick_client(owner_id, target_id).await
+    }
+
+    /// Returns the current state of a room, or `None` if the room doesn't exist.
+    pub async fn get_room_state(&self, room_id: u64) -> Option<R::State> {
+        self.joint.get_room_state(room_id).await
+    }
+
+    /// Returns a snapshot of runtime statistics (connected clients, rooms, actions, failed sends).
+    pub async fn stats(&self) -> JointStats {
+        self.joint.stats().await
+    }
+
+    /// Returns a machine-readable description of the control protocol spoken by the joint.
+    pub fn protocol_description(&self) -> serde_json::Value {
+        self.joint.protocol_description()
+    }
+
+    /// Renders current runtime statistics in the Prometheus text exposition format.
+    #[cfg(feature = "prometheus")]
+    pub async fn prometheus_metrics(&self) -> String {
+        self.joint.prometheus_metrics().await
+    }
+
+    /// Returns the recorded action log of a room in order of application.
+    pub async fn action_log(&self, room_id: u64) -> Option<Vec<RecordedAction>> {
+        self.joint.action_log(room_id).await
+    }
+
+    /// Replays an action log of a room against the reducer the room started from, dispatching
+    /// actions with the context they were recorded with, and returns the resulting state.
+    ///
+    /// Also takes `(author, action)` pairs, dispatched in the default protocol version.
+    pub async fn replay<A>(&self, room_id: u64, actions: &[A]) -> Result<R::State, String>
+    where
+        A: Clone + Into<RecordedAction>,
+    {
+        self.joint.replay(room_id, actions).await
+    }
+
     /// Allows dispatching an action directly to the joint's reducer.
     ///
     /// Useful for controlling the joint state from outside the MPSC client connections.
+    /// The action is broadcast to the client's room like an in-band one.
     ///
     /// # Arguments
     /// * `client_id` - The ID of the client on whose behalf the action is dispatched.
     ///                 Note: The client must exist and be in a room for the dispatch to succeed.
     /// * `action` - A string slice representing the action to be dispatched (must be JSON serializable
     ///              according to the `Dispatchable::Action` type).
+    #[allow(clippy::doc_overindented_list_items)]
     pub async fn dispatch(
         &self,
         client_id: u64,
@@ -115,4 +548,53 @@ impl<R: Dispatchable + Send + Clone + 'static> MPSCJoint<R> {
     ) -> Result<ActionResponse<R::State>, String> {
         self.joint.dispatch(client_id, action).await
     }
+
+    /// Dispatches an action and broadcasts it to the client's room, then calls `callback`.
+    ///
+    /// Useful for server-side sagas reacting to a specific dispatch once its broadcast completes.
+    ///
+    /// # Arguments
+    /// * `client_id` - The ID of the client on whose behalf the action is dispatched.
+    /// * `action` - The action string to be dispatched.
+    /// * `callback` - Called with the correlation ID of the dispatch, also returned by this method,
+    ///   and the action response.
+    pub async fn dispatch_with_callback<F>(
+        &self,
+        client_id: u64,
+        action: &str,
+        callback: F,
+    ) -> Result<u64, String>
+    where
+        F: FnOnce(u64, ActionResponse<R::State>) + Send,
+    {
+        self.joint
+            .dispatch_with_callback(client_id, action, callback)
+            .await
+    }
+
+    /// Dispatches an action on behalf of `client_id` directly to a room, without the client
+    /// having to be a member, and broadcasts it to the room.
+    pub async fn dispatch_to_room(
+        &self,
+        room_id: u64,
+        client_id: u64,
+        action: &str,
+    ) -> Result<ActionResponse<R::State>, String> {
+        self.joint
+            .dispatch_to_room(room_id, client_id, action)
+            .await
+    }
+
+    /// Applies an action on behalf of `author` to several rooms as a single transaction,
+    /// rolling every room back if it fails in any of them.
+    pub async fn dispatch_multi_room(
+        &self,
+        room_ids: &[u64],
+        author: u64,
+        action: &str,
+    ) -> Result<Vec<ActionResponse<R::State>>, String> {
+        self.joint
+            .dispatch_multi_room(room_ids, author, action)
+            .await
+    }
 }
@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
     use crate::dispatcher::{ActionResponse, Dispatchable};
     use crate::joint::mpsc::MPSCJoint;
@@ -7,8 +8,8 @@ mod tests {
     use crate::response::Response;
     use crate::utils::types::{Broadcastable, Receivable};
     use serde::{Deserialize, Serialize};
-    use std::sync::mpsc::Receiver;
     use std::time::Duration;
+    use tokio::sync::mpsc::Receiver;
     use tokio::time::sleep;
 
     #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -36,6 +37,7 @@ mod tests {
     impl Dispatchable for TestReducer {
         type Action = TestAction;
         type State = TestState;
+        type Error = String;
 
         async fn dispatch(
             &mut self,
@@ -91,6 +93,7 @@ mod tests {
     fn create_message(method: JointMessageMethod) -> JointMessage {
         JointMessage {
             client_token: "test-token".to_string(),
+            v: None,
             message: method,
         }
     }
@@ -100,8 +103,17 @@ mod tests {
         create_message(JointMessageMethod::Action(action_json))
     }
 
+    /// Reads the client ID from the `Connected` response opening every connection.
+    async fn connected_client_id(rx: &mut Receiver<Response>) -> u64 {
+        match rx.recv().await {
+            Some(Response::Connected(client_id)) => client_id,
+            other => panic!("Expected Connected response, got {:?}", other),
+        }
+    }
+
 
     #[tokio::test]
+    #[allow(unused_mut)]
     async fn test_basic_connection() {
         let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
         let (tx, mut rx) = joint.connect(10);
@@ -117,7 +129,7 @@ mod tests {
         let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
         let (tx, mut rx) = joint.connect(10);
 
-        let create_msg = create_message(JointMessageMethod::Create);
+        let create_msg = create_message(JointMessageMethod::Create(None));
         tx.send(create_msg)
             .await
             .expect("Failed to send create message");
@@ -129,7 +141,7 @@ mod tests {
                     room_id = Some(id);
                     break;
                 }
-                Response::StateSent(_) => {
+                Response::Connected(_) | Response::StateSent(..) => {
                 }
                 other => {
                     panic!("Unexpected response: {:?}", other);
@@ -148,7 +160,7 @@ mod tests {
         let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
         let (tx, mut rx) = joint.connect(10);
 
-        let create_msg = create_message(JointMessageMethod::Create);
+        let create_msg = create_message(JointMessageMethod::Create(None));
         tx.send(create_msg)
             .await
             .expect("Failed to send create message");
@@ -162,7 +174,7 @@ mod tests {
                     Response::RoomCreated(id) => {
                         room_id = Some(id);
                     }
-                    Response::StateSent(state_json) => {
+                    Response::StateSent(state_json, _) => {
                         let state: TestState =
                             serde_json::from_str(&state_json).expect("Failed to parse state JSON");
                         initial_state = Some(state);
@@ -187,7 +199,7 @@ mod tests {
         while updated_state.is_none() {
             if let Some(response) = rx.recv().await {
                 match response {
-                    Response::Action(action_json) => {
+                    Response::Action(action_json, _) => {
                         let action_response: ActionResponse<TestState> =
                             serde_json::from_str(&action_json)
                                 .expect("Failed to parse action response");
@@ -212,7 +224,7 @@ mod tests {
         while updated_state2.is_none() {
             if let Some(response) = rx.recv().await {
                 match response {
-                    Response::Action(action_json) => {
+                    Response::Action(action_json, _) => {
                         let action_response: ActionResponse<TestState> =
                             serde_json::from_str(&action_json)
                                 .expect("Failed to parse action response");
@@ -239,12 +251,13 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::collapsible_match)]
     async fn test_join_existing_room() {
         let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
 
         let (tx1, mut rx1) = joint.connect(10);
 
-        let create_msg = create_message(JointMessageMethod::Create);
+        let create_msg = create_message(JointMessageMethod::Create(None));
         tx1.send(create_msg)
             .await
             .expect("Failed to send create message");
@@ -266,7 +279,7 @@ mod tests {
         let mut action_processed = false;
         while !action_processed {
             if let Some(response) = rx1.recv().await {
-                if let Response::Action(_) = response {
+                if let Response::Action(..) = response {
                     action_processed = true;
                 }
             }
@@ -288,7 +301,7 @@ mod tests {
                     Response::RoomJoined(_) => {
                         join_confirmed = true;
                     }
-                    Response::StateSent(state_json) => {
+                    Response::StateSent(state_json, _) => {
                         let state: TestState =
                             serde_json::from_str(&state_json).expect("Failed to parse state JSON");
                         received_state = Some(state);
@@ -310,12 +323,13 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::collapsible_match)]
     async fn test_multiple_clients_interaction() {
         let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
 
         let (tx1, mut rx1) = joint.connect(10);
 
-        let create_msg = create_message(JointMessageMethod::Create);
+        let create_msg = create_message(JointMessageMethod::Create(None));
         tx1.send(create_msg)
             .await
             .expect("Failed to send create message");
@@ -371,7 +385,7 @@ mod tests {
         ) {
             while !*updated {
                 if let Some(response) = rx.recv().await {
-                    if let Response::Action(action_json) = response {
+                    if let Response::Action(action_json, _) = response {
                         let action_response: ActionResponse<TestState> =
                             serde_json::from_str(&action_json)
                                 .expect("Failed to parse action response");
@@ -407,27 +421,16 @@ mod tests {
         let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
         let (tx, mut rx) = joint.connect(10);
 
-        let create_msg = create_message(JointMessageMethod::Create);
+        let create_msg = create_message(JointMessageMethod::Create(None));
         tx.send(create_msg)
             .await
             .expect("Failed to send create message");
 
-        let mut client_id: Option<u64> = None;
-
-        while client_id.is_none() {
-            if let Some(response) = rx.recv().await {
-                match response {
-                    Response::RoomCreated(_) => {
-                        let clients = joint.joint.broadcaster.get_clients().clone();
-                        client_id = Some(*clients.lock().await.iter().next().unwrap().0);
-                    }
-                    _ => {}
-                }
-            }
-        }
+        let client_id = connected_client_id(&mut rx).await;
+        while !matches!(rx.recv().await, Some(Response::RoomCreated(_))) {}
 
         let action_json = r#"{"Add":15}"#;
-        let result = joint.dispatch(client_id.unwrap(), action_json).await;
+        let result = joint.dispatch(client_id, action_json).await;
 
         assert!(result.is_ok(), "Dispatch should succeed");
         let response = result.unwrap();
@@ -438,6 +441,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::collapsible_match)]
     async fn test_error_handling() {
         let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
         let (tx, mut rx) = joint.connect(10);
@@ -450,7 +454,7 @@ mod tests {
         let mut received_error = false;
         while !received_error {
             if let Some(response) = rx.recv().await {
-                if let Response::NotFound(_) = response {
+                if let Response::NotFound { .. } = response {
                     received_error = true;
                 }
             }
@@ -469,7 +473,7 @@ mod tests {
         let mut received_error = false;
         while !received_error {
             if let Some(response) = rx.recv().await {
-                if let Response::NotFound(_) = response {
+                if let Response::NotFound { .. } = response {
                     received_error = true;
                 }
             }
@@ -485,6 +489,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[allow(clippy::collapsible_match)]
     async fn test_channel_closing() {
         let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
 
@@ -494,7 +499,7 @@ mod tests {
 
         let (tx2, mut rx2) = joint.connect(10);
 
-        let create_msg = create_message(JointMessageMethod::Create);
+        let create_msg = create_message(JointMessageMethod::Create(None));
         tx2.send(create_msg)
             .await
             .expect("Failed to send create message");
@@ -516,4 +521,202 @@ mod tests {
         drop(tx2);
         drop(rx2);
     }
+
+    #[tokio::test]
+    async fn test_replay_action_log() {
+        let mut joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
+        joint.set_record_actions(true);
+        let (tx, mut rx) = joint.connect(10);
+
+        tx.send(create_message(JointMessageMethod::Create(None)))
+            .await
+            .expect("Failed to send create message");
+
+        let mut room_id: Option<u64> = None;
+        while room_id.is_none() {
+            if let Some(Response::RoomCreated(id)) = rx.recv().await {
+                room_id = Some(id);
+            }
+        }
+        let room_id = room_id.unwrap();
+
+        let actions = vec![
+            TestAction::Add(3),
+            TestAction::Message("hello".to_string()),
+            TestAction::Increment,
+        ];
+        let action_count = actions.len();
+        for action in actions {
+            tx.send(create_action_message(action))
+                .await
+                .expect("Failed to send action message");
+        }
+
+        let mut live_state: Option<TestState> = None;
+        let mut received = 0;
+        while received < action_count {
+            if let Some(Response::Action(action_json, _)) = rx.recv().await {
+                let action_response: ActionResponse<TestState> =
+                    serde_json::from_str(&action_json).expect("Failed to parse action response");
+                live_state = Some(action_response.state);
+                received += 1;
+            }
+        }
+
+        let log = joint.action_log(room_id).await.expect("Room should exist");
+        assert_eq!(log.len(), action_count);
+
+        let replayed = joint
+            .replay(room_id, &log)
+            .await
+            .expect("Replay should succeed");
+        assert_eq!(Some(replayed), live_state);
+
+        // the log also replays as plain (author, action) pairs
+        let pairs: Vec<(u64, String)> = log
+            .iter()
+            .map(|recorded| (recorded.author, recorded.action.clone()))
+            .collect();
+        let replayed = joint
+            .replay(room_id, &pairs)
+            .await
+            .expect("Replay should succeed");
+        assert_eq!(Some(replayed), live_state);
+
+        drop(tx);
+        drop(rx);
+    }
+
+    #[tokio::test]
+    async fn test_connect_inline_steps() {
+        let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
+        let (tx, mut rx, mut driver) = joint.connect_inline(10).await;
+
+        assert!(!driver.try_step().await);
+
+        tx.send(create_message(JointMessageMethod::Create(None)))
+            .await
+            .unwrap();
+        tx.send(create_action_message(TestAction::Add(5)))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Response::Connected(id) if id == driver.client_id()
+        ));
+        assert!(driver.step().await);
+        let room_id = match rx.try_recv().unwrap() {
+            Response::StateSent(state, _) => {
+                let state: TestState = serde_json::from_str(&state).unwrap();
+                assert_eq!(state.counter, 0);
+                match rx.try_recv().unwrap() {
+                    Response::RoomCreated(id) => id,
+                    other => panic!("Unexpected response: {:?}", other),
+                }
+            }
+            other => panic!("Unexpected response: {:?}", other),
+        };
+        assert!(rx.try_recv().is_err());
+
+        let rooms = joint.joint.get_broadcaster().get_rooms();
+        let state = rooms.read(room_id).await[&room_id]
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(state.counter, 0);
+
+        assert!(driver.step().await);
+        match rx.try_recv().unwrap() {
+            Response::Action(payload, _) => {
+                let action: ActionResponse<TestState> = serde_json::from_str(&payload).unwrap();
+                assert_eq!(action.state.counter, 5);
+            }
+            other => panic!("Unexpected response: {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+
+        let state = rooms.read(room_id).await[&room_id]
+            .reducer
+            .lock()
+            .await
+            .get_state();
+        assert_eq!(state.counter, 5);
+
+        drop(tx);
+        assert!(!driver.step().await);
+
+        let client_id = driver.client_id();
+        driver.disconnect().await;
+        let clients = joint.joint.get_broadcaster().get_clients();
+        assert!(!clients.read().await.contains_key(&client_id));
+    }
+
+    #[tokio::test]
+    async fn test_connected_id_is_usable_in_dispatch() {
+        let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
+        let (tx, mut rx) = joint.connect(10);
+        let client_id = connected_client_id(&mut rx).await;
+
+        tx.send(create_message(JointMessageMethod::Create(None)))
+            .await
+            .unwrap();
+        while !matches!(rx.recv().await, Some(Response::RoomCreated(_))) {}
+
+        let response = joint.dispatch(client_id, r#""Increment""#).await.unwrap();
+        assert_eq!(response.author, client_id);
+        assert_eq!(response.state.counter, 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_every_state_change() {
+        let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
+        let (tx, mut rx) = joint.connect(10);
+
+        tx.send(create_message(JointMessageMethod::Create(None)))
+            .await
+            .unwrap();
+        let room_id = loop {
+            if let Some(Response::RoomCreated(id)) = rx.recv().await {
+                break id;
+            }
+        };
+        assert!(joint.subscribe(room_id + 1).await.is_err());
+        let mut states = joint.subscribe(room_id).await.unwrap();
+        assert_eq!(states.recv().await.unwrap().counter, 0);
+
+        for (value, counter) in [(1, 1), (2, 3), (4, 7)] {
+            tx.send(create_action_message(TestAction::Add(value)))
+                .await
+                .unwrap();
+            assert_eq!(states.recv().await.unwrap().counter, counter);
+        }
+
+        tx.send(create_message(JointMessageMethod::Delete))
+            .await
+            .unwrap();
+        assert!(states.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_room_state_reflects_dispatched_actions() {
+        let joint = MPSCJoint::<TestReducer>::new(TestReducer::default());
+        let (tx, mut rx) = joint.connect(10);
+        let client_id = connected_client_id(&mut rx).await;
+
+        tx.send(create_message(JointMessageMethod::Create(None)))
+            .await
+            .unwrap();
+        let room_id = loop {
+            if let Some(Response::RoomCreated(id)) = rx.recv().await {
+                break id;
+            }
+        };
+        assert_eq!(joint.get_room_state(room_id).await.unwrap().counter, 0);
+        assert!(joint.get_room_state(room_id + 1).await.is_none());
+
+        joint.dispatch(client_id, r#"{"Add":7}"#).await.unwrap();
+        assert_eq!(joint.get_room_state(room_id).await.unwrap().counter, 7);
+    }
 }
@@ -1,18 +1,29 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
     use crate::broadcaster::Broadcaster;
     use crate::client::Client;
     use crate::connection::{SinkAdapter, StreamAdapter};
     use crate::dispatcher::{ActionResponse, Dispatchable};
-    use crate::message::{JointMessage, JointMessageMethod};
-    use crate::response::{ClientResponse, Response, RoomResponse};
+    use crate::joint::AbstractJoint;
+    use crate::message::{CreateOptions, JointMessage, JointMessageMethod};
+    use crate::rate_limit::RateLimit;
+    #[allow(unused_imports)]
+    use crate::response::{
+        ClientResponse, ErrorCode, EventResponse, Response, ResponseStatus, RoomResponse,
+    };
+    #[allow(unused_imports)]
     use crate::room::{Room, RoomStatus};
+    use crate::testing::RecordingSink;
     use crate::utils::types::{Broadcastable, Receivable};
     use async_trait::async_trait;
     use serde::{Deserialize, Serialize};
+    #[allow(unused_imports)]
     use std::collections::{HashMap, HashSet};
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex as StdMutex};
+    #[allow(unused_imports)]
     use tokio::sync::Mutex;
 
     #[derive(Clone)]
@@ -78,6 +89,7 @@ mod tests {
     impl Dispatchable for TestReducer {
         type Action = TestAction;
         type State = TestState;
+        type Error = String;
 
         async fn dispatch(
             &mut self,
@@ -130,6 +142,49 @@ mod tests {
         }
     }
 
+    /// Test reducer counting how many times it was cloned.
+    #[derive(Default)]
+    struct CountedReducer {
+        inner: TestReducer,
+        clones: Arc<AtomicUsize>,
+    }
+
+    impl Clone for CountedReducer {
+        fn clone(&self) -> Self {
+            self.clones.fetch_add(1, Ordering::SeqCst);
+            CountedReducer {
+                inner: self.inner.clone(),
+                clones: self.clones.clone(),
+            }
+        }
+    }
+
+    impl Dispatchable for CountedReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.dispatch(client_id, action).await
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            self.inner.extern_dispatch(client_id, action_str).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.inner.get_state()
+        }
+    }
+
     fn create_client(id: u64) -> Client {
         Client::new(id, None, format!("User{}", id), String::new())
     }
@@ -137,6 +192,7 @@ mod tests {
     fn create_message(client_id: u64, method: JointMessageMethod) -> JointMessage {
         JointMessage {
             client_token: client_id.to_string(),
+            v: None,
             message: method,
         }
     }
@@ -161,9 +217,9 @@ mod tests {
         let reducer = TestReducer::default();
         let broadcaster = Broadcaster::<MockSink, TestReducer>::new(reducer);
 
-        assert_eq!(broadcaster.get_clients().clone().lock().await.len(), 0);
-        assert_eq!(broadcaster.get_rooms().clone().lock().await.len(), 0);
-        assert_eq!(broadcaster.get_connections().clone().lock().await.len(), 0);
+        assert_eq!(broadcaster.get_clients().clone().read().await.len(), 0);
+        assert_eq!(broadcaster.get_rooms().room_count().await, 0);
+        assert_eq!(broadcaster.get_connections().clone().read().await.len(), 0);
     }
 
     #[tokio::test]
@@ -180,12 +236,12 @@ mod tests {
 
         {
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.len(), 1);
             assert!(clients.contains_key(&1));
 
             let connections = broadcaster.get_connections();
-            let connections = connections.lock().await;
+            let connections = connections.read().await;
             assert_eq!(connections.len(), 1);
             assert!(connections.contains_key(&1));
         }
@@ -194,11 +250,11 @@ mod tests {
 
         {
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.len(), 0);
 
             let connections = broadcaster.get_connections();
-            let connections = connections.lock().await;
+            let connections = connections.read().await;
             assert_eq!(connections.len(), 0);
         }
     }
@@ -215,7 +271,7 @@ mod tests {
 
         broadcaster.add_client_connection(client, sink).await;
 
-        let result = broadcaster.handle_create(1).await;
+        let result = broadcaster.handle_create(1, CreateOptions::default()).await;
 
         assert!(result.is_ok());
         let room_response = result.unwrap();
@@ -223,15 +279,15 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
-            assert_eq!(rooms.len(), 1);
+            assert_eq!(rooms.room_count().await, 1);
+            let rooms = rooms.read(0).await;
             let room = rooms.get(&0).unwrap();
             assert_eq!(room.id, 0);
             assert_eq!(room.owner_id, 1);
             assert!(room.client_ids.contains(&1));
 
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             let client = clients.get(&1).unwrap();
             assert_eq!(client.room_id, Some(0));
         }
@@ -257,7 +313,7 @@ mod tests {
         broadcaster.add_client_connection(client1, sink1).await;
         broadcaster.add_client_connection(client2, sink2).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
         let room_id = match create_result.unwrap().response {
             Response::RoomCreated(id) => id,
@@ -266,7 +322,7 @@ mod tests {
 
         responses1.lock().unwrap().clear();
 
-        let join_result = broadcaster.handle_join(2, room_id).await;
+        let join_result = broadcaster.handle_join(2, room_id, None).await;
 
         assert!(join_result.is_ok());
         let room_response = join_result.unwrap();
@@ -274,14 +330,14 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             assert_eq!(room.client_ids.len(), 2);
             assert!(room.client_ids.contains(&1));
             assert!(room.client_ids.contains(&2));
 
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.get(&1).unwrap().room_id, Some(room_id));
             assert_eq!(clients.get(&2).unwrap().room_id, Some(room_id));
         }
@@ -300,7 +356,7 @@ mod tests {
 
         broadcaster.add_client_connection(client, sink).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
         let room_id = match create_result.unwrap().response {
             Response::RoomCreated(id) => id,
@@ -311,18 +367,25 @@ mod tests {
 
         let room_reducer = {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             rooms.get(&room_id).unwrap().reducer.clone()
         };
 
         let action = TestAction::Add(5);
         let action_result = broadcaster
-            .handle_action(1, action, room_reducer.clone())
+            .handle_action(
+                1,
+                None,
+                "",
+                &serde_json::to_string(&action).unwrap(),
+                None,
+                room_reducer.clone(),
+            )
             .await;
 
         assert!(action_result.is_ok());
         let room_response = action_result.unwrap();
-        assert!(matches!(room_response.response, Response::Action(_)));
+        assert!(matches!(room_response.response, Response::Action(..)));
 
         {
             let reducer = room_reducer.lock().await;
@@ -331,7 +394,14 @@ mod tests {
 
         let action = TestAction::Message("Hello".to_string());
         let action_result = broadcaster
-            .handle_action(1, action, room_reducer.clone())
+            .handle_action(
+                1,
+                None,
+                "",
+                &serde_json::to_string(&action).unwrap(),
+                None,
+                room_reducer.clone(),
+            )
             .await;
         assert!(action_result.is_ok());
 
@@ -363,14 +433,14 @@ mod tests {
         broadcaster.add_client_connection(client1, sink1).await;
         broadcaster.add_client_connection(client2, sink2).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
         let room_id = match create_result.unwrap().response {
             Response::RoomCreated(id) => id,
             _ => panic!("Expected RoomCreated response"),
         };
 
-        let join_result = broadcaster.handle_join(2, room_id).await;
+        let join_result = broadcaster.handle_join(2, room_id, None).await;
         assert!(join_result.is_ok());
 
         responses1.lock().unwrap().clear();
@@ -384,14 +454,14 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             assert_eq!(room.client_ids.len(), 1);
             assert!(!room.client_ids.contains(&1));
             assert!(room.client_ids.contains(&2));
 
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.get(&1).unwrap().room_id, None);
             assert_eq!(clients.get(&2).unwrap().room_id, Some(room_id));
         }
@@ -410,12 +480,15 @@ mod tests {
 
         broadcaster.add_client_connection(client, sink).await;
 
-        let create_event = create_message(1, JointMessageMethod::Create);
+        let create_event = create_message(1, JointMessageMethod::Create(None));
         let result = broadcaster.process_event(1, create_event).await;
 
         assert!(result.is_ok());
-        let room_id = match result.unwrap().response {
-            Response::RoomCreated(id) => id,
+        let room_id = match result.unwrap() {
+            EventResponse::Room(RoomResponse {
+                response: Response::RoomCreated(id),
+                ..
+            }) => id,
             _ => panic!("Expected RoomCreated response"),
         };
 
@@ -428,7 +501,7 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             let state = room.reducer.lock().await.get_state();
             assert_eq!(state.counter, 10);
@@ -441,7 +514,7 @@ mod tests {
 
         {
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.get(&1).unwrap().room_id, None);
         }
     }
@@ -459,7 +532,7 @@ mod tests {
 
         broadcaster.add_client_connection(client, sink).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
 
         let action_json = r#"{"Increment":null}"#;
@@ -497,7 +570,7 @@ mod tests {
         broadcaster.add_client_connection(client1, sink1).await;
         broadcaster.add_client_connection(client2, sink2).await;
 
-        let create_result = broadcaster.handle_create(1).await;
+        let create_result = broadcaster.handle_create(1, CreateOptions::default()).await;
         assert!(create_result.is_ok());
         let room_id = match create_result.unwrap().response {
             Response::RoomCreated(id) => id,
@@ -506,7 +579,7 @@ mod tests {
 
         {
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             let mut reducer = room.reducer.lock().await;
             reducer.state.counter = 42;
@@ -521,17 +594,17 @@ mod tests {
 
         {
             let clients = broadcaster.get_clients();
-            let clients = clients.lock().await;
+            let clients = clients.read().await;
             assert_eq!(clients.get(&2).unwrap().room_id, Some(room_id));
 
             let rooms = broadcaster.get_rooms();
-            let rooms = rooms.lock().await;
+            let rooms = rooms.read(room_id).await;
             let room = rooms.get(&room_id).unwrap();
             assert!(room.client_ids.contains(&2));
         }
 
         assert!(get_response_count(&responses2) > 0);
-        if let Some(Response::StateSent(state_json)) = get_last_response(&responses2) {
+        if let Some(Response::StateSent(state_json, _)) = get_last_response(&responses2) {
             let state: TestState = serde_json::from_str(&state_json).unwrap();
             assert_eq!(state.counter, 42);
             assert_eq!(state.messages, vec!["Initial"]);
@@ -554,7 +627,7 @@ mod tests {
         broadcaster.add_client_connection(client, sink).await;
 
         let messages = vec![
-            create_message(1, JointMessageMethod::Create),
+            create_message(1, JointMessageMethod::Create(None)),
             create_action_message(1, TestAction::Add(7)),
             create_action_message(1, TestAction::Message("Test".to_string())),
             create_message(1, JointMessageMethod::Leave),
@@ -591,7 +664,7 @@ mod tests {
 
         assert!(result.is_err());
         match result.err().unwrap().response {
-            Response::NotFound(_) => {}
+            Response::NotFound { .. } => {}
             _ => panic!("Expected NotFound response"),
         }
 
@@ -606,8 +679,329 @@ mod tests {
 
         assert!(result.is_err());
         match result.err().unwrap().response {
-            Response::NotFound(_) => {}
+            Response::NotFound { .. } => {}
             _ => panic!("Expected NotFound response"),
         }
     }
+
+    #[tokio::test]
+    async fn test_authenticator_rejects_connection_before_registration() {
+        let mut joint = AbstractJoint::<TestReducer, MockSink>::new(TestReducer::default());
+        joint.set_authenticator(Some(Box::new(|token: &str| {
+            let token = token.to_string();
+            Box::pin(async move {
+                if token == "revoked" {
+                    Err("Invalid token".to_string())
+                } else {
+                    Ok(())
+                }
+            })
+        })));
+
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        let mut stream = MockStream {
+            messages: vec![
+                JointMessage {
+                    client_token: "revoked".to_string(),
+                    v: None,
+                    message: JointMessageMethod::Create(None),
+                },
+                create_action_message(1, TestAction::Add(1)),
+            ],
+            index: 0,
+        };
+
+        joint.handle_stream(&mut stream, sink).await;
+
+        assert_eq!(joint.stats().await.clients, 0);
+        assert_eq!(joint.stats().await.rooms, 0);
+        let rejected = responses.lock().unwrap().clone();
+        assert_eq!(rejected.len(), 1);
+        assert!(
+            matches!(&rejected[0], Response::ClientError { code: ErrorCode::Unauthorized, message: reason } if reason == "Invalid token")
+        );
+        assert_eq!(stream.index, 1);
+
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        let mut stream = MockStream {
+            messages: vec![create_message(1, JointMessageMethod::Create(None))],
+            index: 0,
+        };
+        joint.handle_stream(&mut stream, sink).await;
+
+        assert_eq!(joint.stats().await.rooms, 1);
+        assert!(responses
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|response| matches!(response, Response::RoomCreated(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_rejects_action_burst() {
+        let mut joint = AbstractJoint::<TestReducer, MockSink>::new(TestReducer::default());
+        let room_id = joint.single_room_mode();
+        joint.set_rate_limit(Some(RateLimit::new(1, 3)));
+
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        let mut stream = MockStream {
+            messages: (0..10)
+                .map(|_| create_action_message(1, TestAction::Add(1)))
+                .collect(),
+            index: 0,
+        };
+        joint.handle_stream(&mut stream, sink).await;
+
+        let rejected = responses
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|response| {
+                matches!(response, Response::ClientError { code: ErrorCode::RateLimited, message: reason } if reason == "rate limited")
+            })
+            .count();
+        assert_eq!(rejected, 7);
+
+        let (_, state) = joint.room_state(room_id).await.unwrap();
+        assert_eq!(state.counter, 3);
+    }
+
+    #[tokio::test]
+    async fn test_connection_hooks_fire_once_per_connection() {
+        let mut joint = AbstractJoint::<TestReducer, MockSink>::new(TestReducer::default());
+        let connected = Arc::new(StdMutex::new(Vec::new()));
+        let disconnected = Arc::new(StdMutex::new(Vec::new()));
+        joint.on_connect(Box::new({
+            let connected = connected.clone();
+            move |client_id| {
+                connected.lock().unwrap().push(client_id);
+                Box::pin(async {})
+            }
+        }));
+        joint.on_disconnect(Box::new({
+            let disconnected = disconnected.clone();
+            move |client_id| {
+                disconnected.lock().unwrap().push(client_id);
+                Box::pin(async {})
+            }
+        }));
+
+        let sink = MockSink {
+            responses: Arc::new(StdMutex::new(Vec::new())),
+        };
+        let mut stream = MockStream {
+            messages: vec![create_message(1, JointMessageMethod::Create(None))],
+            index: 0,
+        };
+        // the stream fails once its messages are exhausted
+        joint.handle_stream(&mut stream, sink).await;
+
+        let connected = connected.lock().unwrap().clone();
+        let disconnected = disconnected.lock().unwrap().clone();
+        assert_eq!(connected.len(), 1);
+        assert_eq!(connected, disconnected);
+        assert_eq!(joint.stats().await.clients, 0);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_to_room_broadcasts_to_every_member() {
+        let mut joint = AbstractJoint::<TestReducer, RecordingSink>::new(TestReducer::default());
+        let room_id = joint.single_room_mode();
+
+        let sinks = [RecordingSink::new(), RecordingSink::new()];
+        for sink in &sinks {
+            joint.connect_client(sink.clone()).await;
+        }
+
+        // the system author is not a connected client
+        let system_id = 0;
+        let result = joint
+            .dispatch_to_room(room_id, system_id, r#"{"Add":4}"#)
+            .await
+            .unwrap();
+        assert_eq!(result.author, system_id);
+        assert_eq!(result.state.counter, 4);
+
+        for sink in &sinks {
+            let actions = sink.received_of_kind(ResponseStatus::Action);
+            assert_eq!(actions.len(), 1);
+            assert!(
+                matches!(&actions[0], Response::Action(payload, _) if payload.contains("\"counter\":4"))
+            );
+        }
+        assert_eq!(joint.get_room_state(room_id).await.unwrap().counter, 4);
+
+        assert!(joint
+            .dispatch_to_room(room_id + 1, system_id, r#"{"Add":4}"#)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resume_stream_requires_known_session() {
+        let joint = AbstractJoint::<TestReducer, RecordingSink>::new(TestReducer::default());
+        let sink = RecordingSink::new();
+        let mut stream = MockStream {
+            messages: vec![create_message(1, JointMessageMethod::Create(None))],
+            index: 0,
+        };
+
+        assert!(joint
+            .resume_stream("unknown", &mut stream, sink.clone())
+            .await
+            .is_err());
+        // the stream is left untouched and no client was registered
+        assert_eq!(stream.index, 0);
+        assert!(sink.is_empty());
+        assert_eq!(joint.stats().await.clients, 0);
+    }
+
+    #[tokio::test]
+    async fn test_resume_stream_runs_authenticator() {
+        let mut joint = AbstractJoint::<TestReducer, MockSink>::new(TestReducer::default());
+        joint.set_session_grace(Some(std::time::Duration::from_secs(60)));
+        let revoked = Arc::new(StdMutex::new(HashSet::new()));
+        let revoked_tokens = revoked.clone();
+        joint.set_authenticator(Some(Box::new(move |token: &str| {
+            let rejected = revoked_tokens.lock().unwrap().contains(token);
+            Box::pin(async move {
+                if rejected {
+                    Err("Invalid token".to_string())
+                } else {
+                    Ok(())
+                }
+            })
+        })));
+
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        let mut stream = MockStream {
+            messages: vec![create_message(1, JointMessageMethod::Create(None))],
+            index: 0,
+        };
+        joint.handle_stream(&mut stream, sink).await;
+        let session_token = responses
+            .lock()
+            .unwrap()
+            .iter()
+            .find_map(|response| match response {
+                Response::SessionToken(token) => Some(token.clone()),
+                _ => None,
+            })
+            .unwrap();
+
+        // the credentials the client connected with were revoked since
+        revoked.lock().unwrap().insert("1".to_string());
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        let mut stream = MockStream {
+            messages: Vec::new(),
+            index: 0,
+        };
+        assert_eq!(
+            joint.resume_stream(&session_token, &mut stream, sink).await,
+            Err("Invalid token".to_string())
+        );
+        assert!(matches!(
+            responses.lock().unwrap().as_slice(),
+            [Response::ClientError {
+                code: ErrorCode::Unauthorized,
+                ..
+            }]
+        ));
+        assert_eq!(joint.stats().await.clients, 0);
+
+        revoked.lock().unwrap().clear();
+        let responses = Arc::new(StdMutex::new(Vec::new()));
+        let sink = MockSink {
+            responses: responses.clone(),
+        };
+        assert!(joint
+            .resume_stream(&session_token, &mut stream, sink)
+            .await
+            .is_ok());
+        assert!(responses
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|response| matches!(response, Response::StateSent(..))));
+    }
+
+    #[tokio::test]
+    async fn test_seeded_client_ids_are_reproducible() {
+        async fn connect_clients(seed: u64) -> Vec<u64> {
+            let mut joint =
+                AbstractJoint::<TestReducer, RecordingSink>::new(TestReducer::default());
+            joint.set_id_seed(seed);
+            let mut ids = Vec::new();
+            for _ in 0..5 {
+                ids.push(joint.connect_client(RecordingSink::new()).await);
+            }
+            ids
+        }
+
+        let ids = connect_clients(7).await;
+        assert_eq!(ids, connect_clients(7).await);
+        assert_ne!(ids, connect_clients(8).await);
+        assert_eq!(
+            ids.iter().collect::<std::collections::HashSet<_>>().len(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reducer_factory_replaces_cloning() {
+        async fn create_rooms(joint: &AbstractJoint<CountedReducer, RecordingSink>) {
+            for _ in 0..3 {
+                let client_id = joint.connect_client(RecordingSink::new()).await;
+                let created = joint
+                    .broadcaster
+                    .handle_create(client_id, CreateOptions::default())
+                    .await
+                    .unwrap();
+                assert!(matches!(created.response, Response::RoomCreated(_)));
+            }
+        }
+
+        // the default reducer is cloned for each new room
+        let clones = Arc::new(AtomicUsize::new(0));
+        let joint = AbstractJoint::<CountedReducer, RecordingSink>::new(CountedReducer {
+            inner: TestReducer::default(),
+            clones: clones.clone(),
+        });
+        create_rooms(&joint).await;
+        assert_eq!(clones.load(Ordering::SeqCst), 3);
+
+        // the factory is called instead, and nothing is cloned
+        let clones = Arc::new(AtomicUsize::new(0));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let joint = AbstractJoint::<CountedReducer, RecordingSink>::new_with_factory({
+            let clones = clones.clone();
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                CountedReducer {
+                    inner: TestReducer::default(),
+                    clones: clones.clone(),
+                }
+            }
+        });
+        create_rooms(&joint).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(clones.load(Ordering::SeqCst), 0);
+        assert_eq!(joint.stats().await.rooms, 3);
+    }
 }
@@ -3,26 +3,50 @@
 /// It allows for real-time room-split communication between clients and the server using WebSocket connections.
 mod test;
 
-use crate::connection::{SinkAdapter, StreamAdapter};
-use crate::dispatcher::{ActionResponse, Dispatchable};
-use crate::joint::AbstractJoint;
-use crate::message::JointMessage;
+use crate::codec::{Compression, WireFormat};
+use crate::connection::{ForwarderLiveness, SinkAdapter, SinkClosed, StreamAdapter};
+use crate::dispatcher::{
+    ActionResponse, Dispatchable, PostDispatchHook, PreDispatchHook, Projection, RecordedAction,
+};
+use crate::joint::{AbstractJoint, Authenticator, ConnectionHook};
+use crate::load_shedding::LoadShedding;
+use crate::message::{JointMessage, JointMessageMethod};
+use crate::metrics::JointStats;
+use crate::rate_limit::RateLimit;
 use crate::response::Response;
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures_util::stream::SplitStream;
 use futures_util::{SinkExt, StreamExt};
+use std::future::Future;
 use std::net::SocketAddr;
+#[cfg(feature = "tls")]
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::io;
+use std::time::Duration;
+use tokio::io::{self, AsyncRead, AsyncWrite};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::sync::watch;
+use tokio::time::Instant;
+#[cfg(feature = "tls")]
+use tokio_rustls::rustls::{
+    self,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    ServerConfig,
+};
+#[cfg(feature = "tls")]
+use tokio_rustls::TlsAcceptor;
 use tokio_tungstenite::{accept_async, WebSocketStream};
-use tungstenite::Message;
+use tungstenite::{Message, Utf8Bytes};
 
 /// `WSSink` is a struct that implements the `SinkAdapter` trait for websocket joint implementation.
 #[derive(Clone)]
 struct WSSink {
     tx: mpsc::Sender<Result<Message, tungstenite::Error>>,
+    format: WireFormat,
+    compression: Compression,
+    forwarder: ForwarderLiveness,
 }
 
 /// `StreamAdapter` is a struct that implements the `StreamAdapter` trait for websocket joint implementation.
@@ -32,36 +56,195 @@ impl SinkAdapter for WSSink {
         &mut self,
         response: Response,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let message_text = serde_json::to_string(&response)?;
+        let payload = self.format.encode(&response)?;
+        self.send_raw(payload.into()).await
+    }
+
+    fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    fn set_wire_format(&mut self, format: WireFormat) {
+        self.format = format;
+    }
+
+    fn wire_format(&self) -> Option<WireFormat> {
+        Some(self.format)
+    }
+
+    async fn send_raw(
+        &mut self,
+        bytes: Bytes,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !self.forwarder.is_alive() {
+            return Err(Box::new(SinkClosed));
+        }
+        let message = if self.compression.is_enabled() {
+            Message::Binary(self.compression.compress(bytes.to_vec())?.into())
+        } else if self.format.is_binary() {
+            Message::Binary(bytes)
+        } else {
+            Message::Text(Utf8Bytes::try_from(bytes)?)
+        };
         self.tx
-            .send(Ok(Message::Text(message_text.into())))
+            .send(Ok(message))
             .await
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+            .map_err(|_| Box::new(SinkClosed) as Box<dyn std::error::Error + Send + Sync>)?;
         Ok(())
     }
 }
 
+/// Pings sent to a websocket client to detect dead connections.
+struct Heartbeat {
+    /// Interval between pings.
+    interval: Duration,
+    /// Maximum time to wait for the pong answering a ping.
+    timeout: Duration,
+    /// When the unanswered ping was sent, if any.
+    ping_sent: Option<Instant>,
+    /// When the next ping is due.
+    next_ping: Instant,
+}
+
+impl Heartbeat {
+    /// Returns when the heartbeat needs attention: the pong timeout or the next ping.
+    fn deadline(&self) -> Instant {
+        match self.ping_sent {
+            Some(ping_sent) => ping_sent + self.timeout,
+            None => self.next_ping,
+        }
+    }
+}
+
 /// `WSStream` is a struct that implements the `StreamAdapter` trait for websocket joint implementation.
-struct WSStream {
-    stream: SplitStream<WebSocketStream<TcpStream>>,
+struct WSStream<T> {
+    stream: SplitStream<WebSocketStream<T>>,
+    format: WireFormat,
+    /// Channel to the task forwarding messages to the websocket sink.
+    tx: mpsc::Sender<Result<Message, tungstenite::Error>>,
+    heartbeat: Option<Heartbeat>,
+    /// Set when the joint shuts down and closes existing connections.
+    shutdown: watch::Receiver<bool>,
 }
 
 /// `StreamAdapter` is a trait that defines the interface for receiving messages.
+///
+/// Ping and pong frames are skipped, a close frame ends the stream. With a heartbeat,
+/// the stream also sends pings and ends if one is not answered in time. When the joint
+/// shuts down closing existing connections, the stream sends a close frame and ends.
 #[async_trait]
-impl StreamAdapter for WSStream {
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> StreamAdapter for WSStream<T> {
     async fn next(&mut self) -> Result<JointMessage, Box<dyn std::error::Error + Send + Sync>> {
-        let message = self.stream.next().await.unwrap()?;
-        let message = match message {
-            Message::Text(text) => text,
-            _ => {
-                return Err(Box::new(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Invalid data",
-                )))
+        loop {
+            let deadline = self.heartbeat.as_ref().map(Heartbeat::deadline);
+            let shutdown = &mut self.shutdown;
+            let message = tokio::select! {
+                message = self.stream.next() => message.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::BrokenPipe, "Connection closed")
+                })??,
+                _ = async {
+                    match deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    let Some(heartbeat) = self.heartbeat.as_mut() else {
+                        continue;
+                    };
+                    if heartbeat.ping_sent.is_some() {
+                        return Err(Box::new(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "Heartbeat timed out",
+                        )));
+                    }
+                    let now = Instant::now();
+                    heartbeat.ping_sent = Some(now);
+                    heartbeat.next_ping = now + heartbeat.interval;
+                    let _ = self.tx.send(Ok(Message::Ping(Vec::new().into()))).await;
+                    continue;
+                }
+                _ = async {
+                    // the sender is dropped without closing connections once listening stops
+                    if shutdown.wait_for(|closing| *closing).await.is_err() {
+                        std::future::pending::<()>().await;
+                    }
+                } => {
+                    let _ = self.tx.send(Ok(Message::Close(None))).await;
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "Joint shut down",
+                    )));
+                }
+            };
+            let message: JointMessage = match message {
+                Message::Text(text) => serde_json::from_slice(text.as_ref())?,
+                Message::Binary(bytes) => self.format.decode(bytes.as_ref())?,
+                Message::Pong(_) => {
+                    if let Some(heartbeat) = self.heartbeat.as_mut() {
+                        heartbeat.ping_sent = None;
+                    }
+                    continue;
+                }
+                Message::Ping(_) | Message::Frame(_) => continue,
+                Message::Close(_) => {
+                    return Err(Box::new(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "Connection closed",
+                    )))
+                }
+            };
+            if let JointMessageMethod::Hello {
+                format: Some(format),
+                ..
+            } = message.message
+            {
+                self.format = format;
             }
-        };
-        let message = serde_json::from_slice((&message).as_ref())?;
-        Ok(message)
+            return Ok(message);
+        }
+    }
+}
+
+/// Certificate chain and private key used to serve websocket connections over TLS (WSS).
+///
+/// Available with the `tls` feature.
+#[cfg(feature = "tls")]
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// Path to the PEM file with the certificate chain, leaf certificate first.
+    pub cert_chain_path: PathBuf,
+    /// Path to the PEM file with the private key of the leaf certificate.
+    pub private_key_path: PathBuf,
+}
+
+#[cfg(feature = "tls")]
+impl TlsConfig {
+    /// Creates a TLS configuration from the paths of the PEM certificate chain and private key.
+    pub fn new(cert_chain_path: impl Into<PathBuf>, private_key_path: impl Into<PathBuf>) -> Self {
+        TlsConfig {
+            cert_chain_path: cert_chain_path.into(),
+            private_key_path: private_key_path.into(),
+        }
+    }
+
+    /// loads the certificate chain and private key into a TLS acceptor
+    fn acceptor(&self) -> io::Result<TlsAcceptor> {
+        let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+        let cert_chain = CertificateDer::pem_file_iter(&self.cert_chain_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| invalid(e.to_string()))?;
+        let private_key = PrivateKeyDer::from_pem_file(&self.private_key_path)
+            .map_err(|e| invalid(e.to_string()))?;
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| {
+                builder
+                    .with_no_client_auth()
+                    .with_single_cert(cert_chain, private_key)
+            })
+            .map_err(|e| invalid(e.to_string()))?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
     }
 }
 
@@ -75,19 +258,325 @@ impl StreamAdapter for WSStream {
 pub struct WebsocketJoint<R: Dispatchable + Send + 'static> {
     joint: Arc<AbstractJoint<R, WSSink>>,
     tcp_listener: Option<TcpListener>,
+    #[cfg(feature = "tls")]
+    tls_acceptor: Option<TlsAcceptor>,
     local_addr: Option<SocketAddr>,
+    format: WireFormat,
+    heartbeat: Option<(Duration, Duration)>,
 }
 
 impl<R: Dispatchable + Send + 'static> WebsocketJoint<R> {
     /// Creates a new `WebsocketJoint` instance with the given default reducer.
     pub fn new(default_reducer: R) -> Self {
+        Self::from_joint(AbstractJoint::new(default_reducer))
+    }
+
+    /// Creates a new `WebsocketJoint` instance constructing the reducer of each new room with
+    /// `reducer_factory` instead of cloning a default reducer.
+    pub fn new_with_factory(reducer_factory: impl Fn() -> R + Send + Sync + 'static) -> Self {
+        Self::from_joint(AbstractJoint::new_with_factory(reducer_factory))
+    }
+
+    /// wraps a joint, not bound to any address yet
+    fn from_joint(joint: AbstractJoint<R, WSSink>) -> Self {
         WebsocketJoint {
-            joint: Arc::new(AbstractJoint::new(default_reducer)),
+            joint: Arc::new(joint),
             tcp_listener: None,
+            #[cfg(feature = "tls")]
+            tls_acceptor: None,
             local_addr: None,
+            format: WireFormat::default(),
+            heartbeat: None,
+        }
+    }
+
+    /// Creates a new `WebsocketJoint` instance speaking the given wire format.
+    ///
+    /// Responses are encoded with `format`, and binary frames received from clients are
+    /// decoded with it. Text frames are always decoded as JSON.
+    pub fn new_with_format(default_reducer: R, format: WireFormat) -> Self {
+        WebsocketJoint {
+            format,
+            ..Self::new(default_reducer)
         }
     }
 
+    /// Returns a mutable reference to the underlying joint for configuration.
+    ///
+    /// # Panics
+    /// * This method panics if the joint is already shared with connection tasks.
+    fn joint_mut(&mut self) -> &mut AbstractJoint<R, WSSink> {
+        Arc::get_mut(&mut self.joint).expect("joint must be configured before handling connections")
+    }
+
+    /// Sets whether joining clients must acknowledge the initial state with an `Ack` message
+    /// before they are considered joined and included in room broadcasts.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_join_requires_ack(&mut self, join_requires_ack: bool) {
+        self.joint_mut().set_join_requires_ack(join_requires_ack);
+    }
+
+    /// Sets the maximum size of a room state in bytes.
+    ///
+    /// Actions growing the state beyond the limit are reverted and rejected with a `ClientError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_state_bytes(&mut self, max_state_bytes: Option<usize>) {
+        self.joint_mut().set_max_state_bytes(max_state_bytes);
+    }
+
+    /// Sets the maximum size in bytes of the `data` returned by the reducer for an action.
+    ///
+    /// Actions returning more data are reverted and their author is sent a `ServerError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_data_bytes(&mut self, max_data_bytes: Option<usize>) {
+        self.joint_mut().set_max_data_bytes(max_data_bytes);
+    }
+
+    /// Sets whether every applied action is recorded with its author in the per-room action log.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_record_actions(&mut self, record_actions: bool) {
+        self.joint_mut().set_record_actions(record_actions);
+    }
+
+    /// Sets the maximum time a single send to a client may take.
+    ///
+    /// Clients whose sink does not accept a message in time are removed from the joint.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_send_timeout(&mut self, send_timeout: Option<Duration>) {
+        self.joint_mut().set_send_timeout(send_timeout);
+    }
+
+    /// Sets the per-client action rate limit used in rooms that don't override it.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.joint_mut().set_rate_limit(rate_limit);
+    }
+
+    /// Sets the most permissive rate limit room creators may request in `CreateOptions`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_rate_limit(&mut self, max_rate_limit: Option<RateLimit>) {
+        self.joint_mut().set_max_rate_limit(max_rate_limit);
+    }
+
+    /// Sets the maximum size of a single outgoing JSON-serialized response in bytes.
+    ///
+    /// Oversized action broadcasts and initial states are replaced with a `ServerError` to the author.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_outgoing_bytes(&mut self, max_outgoing_bytes: Option<usize>) {
+        self.joint_mut().set_max_outgoing_bytes(max_outgoing_bytes);
+    }
+
+    /// Sets whether rooms are deleted as soon as their last client leaves or disconnects.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_delete_empty_rooms(&mut self, delete_empty_rooms: bool) {
+        self.joint_mut().set_delete_empty_rooms(delete_empty_rooms);
+    }
+
+    /// Sets whether clients may be members of several rooms at once, targeting them with `RoomAction`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_multi_room(&mut self, multi_room: bool) {
+        self.joint_mut().set_multi_room(multi_room);
+    }
+
+    /// Enables single-room mode: every connecting client automatically joins one shared room,
+    /// so clients can send actions right away without `Create` or `Join`.
+    ///
+    /// Returns the ID of the shared room.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn single_room_mode(&mut self) -> u64 {
+        self.joint_mut().single_room_mode()
+    }
+
+    /// Sets the interval after which idle clients are sent an application-level `Keepalive`,
+    /// so NATs and load balancers don't drop idle connections.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_keepalive_interval(&mut self, keepalive_interval: Option<Duration>) {
+        self.joint_mut().set_keepalive_interval(keepalive_interval);
+    }
+
+    /// Sets the hook run before each action received from a client is dispatched.
+    /// Returning an error from the hook aborts the action with a `ClientError`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn with_pre_hook(&mut self, hook: PreDispatchHook) {
+        self.joint_mut().with_pre_hook(hook);
+    }
+
+    /// Sets the hook run after each action received from a client is applied,
+    /// receiving the resulting `ActionResponse`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn with_post_hook(&mut self, hook: PostDispatchHook<R::State>) {
+        self.joint_mut().with_post_hook(hook);
+    }
+
+    /// Registers a named projection of the room states, sent instead of the full state
+    /// to the clients joining a room with `JoinProjected` and its name.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn add_projection(&mut self, name: impl Into<String>, projection: Projection<R::State>) {
+        self.joint_mut().add_projection(name, projection);
+    }
+
+    /// Sets whether client actions are applied by a dedicated actor task per room,
+    /// so actions to different rooms run concurrently.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_room_actors(&mut self, room_actors: bool) {
+        self.joint_mut().set_room_actors(room_actors);
+    }
+
+    /// Sets whether action broadcasts carry a JSON Patch (RFC 6902) from the previous room state
+    /// in a `patch` field instead of the full `state`, saving bandwidth for large states.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_state_diffs(&mut self, state_diffs: bool) {
+        self.joint_mut().set_state_diffs(state_diffs);
+    }
+
+    /// Sets whether `StateSent` and `Action` responses carry the room's sequence number in a
+    /// `seq` field and the server time in a `timestamp` field, to detect missed broadcasts.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_broadcast_stamps(&mut self, stamps: bool) {
+        self.joint_mut().set_broadcast_stamps(stamps);
+    }
+
+    /// Sets the number of action broadcasts kept per room for clients resuming their session.
+    ///
+    /// Resuming clients missing fewer actions are replayed them instead of being sent the full state.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_history_size(&mut self, history_size: usize) {
+        self.joint_mut().set_history_size(history_size);
+    }
+
+    /// Sets the callback verifying the `client_token` of new connections.
+    /// Connections rejected by the callback are answered with a `ClientError` and closed.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_authenticator(&mut self, authenticator: Option<Authenticator>) {
+        self.joint_mut().set_authenticator(authenticator);
+    }
+
+    /// Sets the policy rejecting low-priority actions with `Overloaded` while the joint is under pressure.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_load_shedding(&mut self, load_shedding: Option<LoadShedding>) {
+        self.joint_mut().set_load_shedding(load_shedding);
+    }
+
+    /// Sets the number of room members above which action broadcasts are skipped,
+    /// members of larger rooms pulling the room state with `Sync` instead.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_max_fanout(&mut self, max_fanout: Option<usize>) {
+        self.joint_mut().set_max_fanout(max_fanout);
+    }
+
+    /// Sets the callback run with the ID of every client right after its connection is registered.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn on_connect(&mut self, hook: ConnectionHook) {
+        self.joint_mut().on_connect(hook);
+    }
+
+    /// Sets the callback run with the ID of every client right before its connection is removed,
+    /// however the connection ends.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn on_disconnect(&mut self, hook: ConnectionHook) {
+        self.joint_mut().on_disconnect(hook);
+    }
+
+    /// Sets the compression applied to the action broadcasts kept per room for resuming clients.
+    /// Defaults to `Compression::None`.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_history_compression(&mut self, compression: Compression) {
+        self.joint_mut().set_history_compression(compression);
+    }
+
+    /// Sets the number of room broadcasts queued for each client disconnected from a room
+    /// with a session token, sent to it once it resumes its session. Disabled with `0`, the default.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_offline_buffer_size(&mut self, buffer_size: usize) {
+        self.joint_mut().set_offline_buffer_size(buffer_size);
+    }
+
+    /// Seeds the generator of client IDs, so a fixed seed yields the same IDs across runs.
+    /// Meant for reproducible benchmarks and fuzzing, as seeded IDs are predictable.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_id_seed(&mut self, seed: u64) {
+        self.joint_mut().set_id_seed(seed);
+    }
+
+    /// Sets how long after disconnecting a client can resume its session with the session token
+    /// it was sent on connect. Sessions are disabled with `None`, the default.
+    ///
+    /// # Panics
+    /// * This method panics if called after connections started being handled.
+    pub fn set_session_grace(&mut self, grace: Option<Duration>) {
+        self.joint_mut().set_session_grace(grace);
+    }
+
+    /// Sets the wire format used to encode responses sent to clients and decode their binary frames.
+    ///
+    /// `WireFormat::MessagePack` responses are sent as binary frames, so states reach native
+    /// clients without being embedded in a JSON text frame. Defaults to `WireFormat::Json`.
+    pub fn set_wire_format(&mut self, format: WireFormat) {
+        self.format = format;
+    }
+
+    /// Sets a heartbeat pinging every client each `interval` to detect dead connections.
+    ///
+    /// Clients not answering a ping with a pong within `timeout` are disconnected and removed
+    /// from the joint, instead of staying registered until the OS notices the dead connection.
+    pub fn set_heartbeat(&mut self, interval: Duration, timeout: Duration) {
+        self.heartbeat = Some((interval, timeout));
+    }
+
     /// Binds the joint to the given address.
     ///
     /// This method creates a TCP listener and sets the local address of the joint.
@@ -98,6 +587,29 @@ impl<R: Dispatchable + Send + 'static> WebsocketJoint<R> {
         let tcp_listener = TcpListener::bind(addr).await?;
         self.local_addr = Some(tcp_listener.local_addr()?);
         self.tcp_listener = Some(tcp_listener);
+        #[cfg(feature = "tls")]
+        {
+            self.tls_acceptor = None;
+        }
+        Ok(())
+    }
+
+    /// Binds the joint to the given address, serving connections over TLS (WSS).
+    ///
+    /// Accepted connections complete a TLS handshake before the websocket handshake;
+    /// connections failing it are dropped. Available with the `tls` feature.
+    ///
+    /// # Arguments
+    /// * `addr` - The address to bind the joint to.
+    /// * `tls` - The certificate chain and private key presented to clients.
+    ///
+    /// # Errors
+    /// * Fails if the certificate chain or private key can't be loaded, or the address can't be bound.
+    #[cfg(feature = "tls")]
+    pub async fn bind_addr_tls(&mut self, addr: &str, tls: TlsConfig) -> io::Result<()> {
+        let tls_acceptor = tls.acceptor()?;
+        self.bind_addr(addr).await?;
+        self.tls_acceptor = Some(tls_acceptor);
         Ok(())
     }
 
@@ -111,41 +623,110 @@ impl<R: Dispatchable + Send + 'static> WebsocketJoint<R> {
     /// Listens for incoming connections on the bound address.
     ///
     /// This method accepts incoming TCP connections and spawns a new task for each connection.
+    /// It never returns; use [`listen_with_shutdown`](Self::listen_with_shutdown) to stop it.
     ///
     /// # Panics
     /// * This method panics if the joint is not bound to an address.
     pub async fn listen(&mut self) {
-        loop {
-            if let Some(tcp_listener) = &self.tcp_listener {
-                let (stream, _) = tcp_listener.accept().await.unwrap();
+        self.listen_with_shutdown(std::future::pending(), false)
+            .await
+    }
 
-                tokio::spawn(Self::stream_worker(stream, self.joint.clone()));
-            } else {
-                panic!("Websocket joint poll error: no listener bound");
+    /// Listens for incoming connections on the bound address until `shutdown` completes.
+    ///
+    /// Once `shutdown` completes, the listener is closed so no new connections are accepted,
+    /// and the method returns. The joint must be bound again before listening again.
+    /// If `close_connections` is set, existing connections are sent a close frame and
+    /// their clients are disconnected; otherwise they are served until they disconnect.
+    ///
+    /// # Arguments
+    /// * `shutdown` - Future completing when the joint should shut down,
+    ///   e.g. `tokio::signal::ctrl_c()` or a `tokio::sync::watch` change.
+    /// * `close_connections` - Whether existing connections are closed on shutdown.
+    ///
+    /// # Panics
+    /// * This method panics if the joint is not bound to an address.
+    pub async fn listen_with_shutdown<F>(&mut self, shutdown: F, close_connections: bool)
+    where
+        F: Future<Output = ()>,
+    {
+        let Some(tcp_listener) = self.tcp_listener.take() else {
+            panic!("Websocket joint poll error: no listener bound");
+        };
+        let (closing, _) = watch::channel(false);
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                accepted = tcp_listener.accept() => {
+                    let (stream, _) = accepted.unwrap();
+                    self.spawn_connection(stream, closing.subscribe());
+                }
+                _ = &mut shutdown => break,
             }
         }
+
+        self.local_addr = None;
+        if close_connections {
+            closing.send_replace(true);
+        }
+    }
+
+    /// spawns a task handling an accepted TCP connection, after a TLS handshake if TLS is enabled
+    fn spawn_connection(&self, stream: TcpStream, shutdown: watch::Receiver<bool>) {
+        let joint = self.joint.clone();
+        let (format, heartbeat) = (self.format, self.heartbeat);
+
+        #[cfg(feature = "tls")]
+        if let Some(tls_acceptor) = self.tls_acceptor.clone() {
+            tokio::spawn(async move {
+                match tls_acceptor.accept(stream).await {
+                    Ok(stream) => {
+                        Self::stream_worker(stream, joint, format, heartbeat, shutdown).await
+                    }
+                    Err(e) => eprintln!("Warning: TLS handshake failed: {}", e),
+                }
+            });
+            return;
+        }
+
+        tokio::spawn(Self::stream_worker(
+            stream, joint, format, heartbeat, shutdown,
+        ));
     }
 
     /// Handles a new incoming connection.
     ///
-    /// This method accepts a TCP stream and spawns a new task to handle the connection.
+    /// This method accepts a TCP or TLS stream and spawns a new task to handle the connection.
     ///
     /// # Arguments
-    /// * `stream` - The TCP stream representing the incoming connection.
+    /// * `stream` - The stream representing the incoming connection.
     /// * `joint` - The joint instance to handle the connection.
+    /// * `format` - The wire format used to encode responses.
+    /// * `heartbeat` - The ping interval and pong timeout, if clients are pinged.
+    /// * `shutdown` - Set when the joint shuts down closing existing connections.
     ///
     /// # Panics
     /// * This method panics if the joint is not bound to an address.
-    async fn stream_worker(stream: TcpStream, joint: Arc<AbstractJoint<R, WSSink>>)
-    where
+    async fn stream_worker<T>(
+        stream: T,
+        joint: Arc<AbstractJoint<R, WSSink>>,
+        format: WireFormat,
+        heartbeat: Option<(Duration, Duration)>,
+        shutdown: watch::Receiver<bool>,
+    ) where
         R: Dispatchable + Send + 'static,
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
     {
         let websocket = accept_async(stream).await.unwrap();
         let (mut websocket_sink, websocket_stream) = websocket.split();
 
         let (tx, mut rx) = mpsc::channel::<Result<Message, tungstenite::Error>>(100);
+        let forwarder = ForwarderLiveness::new();
+        let forwarder_guard = forwarder.guard();
 
         tokio::spawn(async move {
+            let _forwarder_guard = forwarder_guard;
             while let Some(result) = rx.recv().await {
                 match result {
                     Ok(msg) => {
@@ -167,16 +748,101 @@ impl<R: Dispatchable + Send + 'static> WebsocketJoint<R> {
 
         let mut stream_adapter = WSStream {
             stream: websocket_stream,
+            format,
+            tx: tx.clone(),
+            heartbeat: heartbeat.map(|(interval, timeout)| Heartbeat {
+                interval,
+                timeout,
+                ping_sent: None,
+                next_ping: Instant::now() + interval,
+            }),
+            shutdown,
         };
 
-        let sink_adapter = WSSink { tx };
+        let sink_adapter = WSSink {
+            tx,
+            format,
+            compression: Compression::None,
+            forwarder,
+        };
 
         joint.handle_stream(&mut stream_adapter, sink_adapter).await;
     }
 
+    /// Creates an empty public room managed by the given reducer instance, returning its ID.
+    pub async fn preload_room(&self, reducer: R) -> u64 {
+        self.joint.preload_room(reducer).await
+    }
+
+    /// Subscribes to the state of a room, received right away and after every applied action.
+    pub async fn subscribe(&self, room_id: u64) -> Result<UnboundedReceiver<R::State>, String> {
+        self.joint.subscribe(room_id).await
+    }
+
+    /// Subscribes to the summaries of all rooms, received right away and whenever a room
+    /// is created, changes members or is deleted.
+    pub async fn subscribe_overview(&self) -> UnboundedReceiver<Response> {
+        self.joint.subscribe_overview().await
+    }
+
+    /// Drains a room before maintenance, rejecting actions and joins for `grace`,
+    /// then deletes it if `delete` is set or reopens it otherwise.
+    pub async fn drain_room(
+        &self,
+        room_id: u64,
+        grace: Duration,
+        reason: &str,
+        delete: bool,
+    ) -> Result<(), String> {
+        self.joint.drain_room(room_id, grace, reason, delete).await
+    }
+
+    /// Kicks a client out of the room owned by `owner_id`, notifying it and the room with `RoomLeft`.
+    pub async fn kick_client(&self, owner_id: u64, target_id: u64) -> Result<(), Response> {
+        self.joint.kick_client(owner_id, target_id).await
+    }
+
+    /// Returns the current state of a room, or `None` if the room doesn't exist.
+    pub async fn get_room_state(&self, room_id: u64) -> Option<R::State> {
+        self.joint.get_room_state(room_id).await
+    }
+
+    /// Returns a snapshot of runtime statistics (connected clients, rooms, actions, failed sends).
+    pub async fn stats(&self) -> JointStats {
+        self.joint.stats().await
+    }
+
+    /// Returns a machine-readable description of the control protocol spoken by the joint.
+    pub fn protocol_description(&self) -> serde_json::Value {
+        self.joint.protocol_description()
+    }
+
+    /// Renders current runtime statistics in the Prometheus text exposition format.
+    #[cfg(feature = "prometheus")]
+    pub async fn prometheus_metrics(&self) -> String {
+        self.joint.prometheus_metrics().await
+    }
+
+    /// Returns the recorded action log of a room in order of application.
+    pub async fn action_log(&self, room_id: u64) -> Option<Vec<RecordedAction>> {
+        self.joint.action_log(room_id).await
+    }
+
+    /// Replays an action log of a room against the reducer the room started from, dispatching
+    /// actions with the context they were recorded with, and returns the resulting state.
+    ///
+    /// Also takes `(author, action)` pairs, dispatched in the default protocol version.
+    pub async fn replay<A>(&self, room_id: u64, actions: &[A]) -> Result<R::State, String>
+    where
+        A: Clone + Into<RecordedAction>,
+    {
+        self.joint.replay(room_id, actions).await
+    }
+
     /// Dispatches an action to the joint.
     ///
     /// This method takes a `client_id` and an `action` string as parameters.
+    /// The action is broadcast to the client's room like an in-band one.
     ///
     /// # Arguments
     /// * `client_id` - The ID of the client dispatching the action.
@@ -188,4 +854,53 @@ impl<R: Dispatchable + Send + 'static> WebsocketJoint<R> {
     ) -> Result<ActionResponse<R::State>, String> {
         self.joint.dispatch(client_id, action).await
     }
+
+    /// Dispatches an action and broadcasts it to the client's room, then calls `callback`.
+    ///
+    /// Useful for server-side sagas reacting to a specific dispatch once its broadcast completes.
+    ///
+    /// # Arguments
+    /// * `client_id` - The ID of the client on whose behalf the action is dispatched.
+    /// * `action` - The action string to be dispatched.
+    /// * `callback` - Called with the correlation ID of the dispatch, also returned by this method,
+    ///   and the action response.
+    pub async fn dispatch_with_callback<F>(
+        &self,
+        client_id: u64,
+        action: &str,
+        callback: F,
+    ) -> Result<u64, String>
+    where
+        F: FnOnce(u64, ActionResponse<R::State>) + Send,
+    {
+        self.joint
+            .dispatch_with_callback(client_id, action, callback)
+            .await
+    }
+
+    /// Dispatches an action on behalf of `client_id` directly to a room, without the client
+    /// having to be a member, and broadcasts it to the room.
+    pub async fn dispatch_to_room(
+        &self,
+        room_id: u64,
+        client_id: u64,
+        action: &str,
+    ) -> Result<ActionResponse<R::State>, String> {
+        self.joint
+            .dispatch_to_room(room_id, client_id, action)
+            .await
+    }
+
+    /// Applies an action on behalf of `author` to several rooms as a single transaction,
+    /// rolling every room back if it fails in any of them.
+    pub async fn dispatch_multi_room(
+        &self,
+        room_ids: &[u64],
+        author: u64,
+        action: &str,
+    ) -> Result<Vec<ActionResponse<R::State>>, String> {
+        self.joint
+            .dispatch_multi_room(room_ids, author, action)
+            .await
+    }
 }
@@ -1,16 +1,24 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
     use crate::client::Client;
+    use crate::codec::{Compression, WireFormat};
+    use crate::connection::{ForwarderLiveness, SinkAdapter, SinkClosed};
     use crate::dispatcher::{ActionResponse, Dispatchable};
-    use crate::joint::ws::WebsocketJoint;
+    use crate::joint::ws::{WSSink, WebsocketJoint};
+    use crate::message::{CreateOptions, JointMessage, JointMessageMethod};
+    use crate::response::Response;
     use crate::room::{Room, RoomStatus};
     use crate::utils::types::{Broadcastable, Receivable};
+    use futures_util::{SinkExt, StreamExt};
     use serde::{Deserialize, Serialize};
     use std::collections::HashSet;
     use std::future::Future;
     use std::sync::Arc;
+    use std::time::Duration;
     use tokio::sync::Mutex;
+    use tungstenite::Message;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     enum TestAction {
@@ -35,7 +43,9 @@ mod tests {
     impl Dispatchable for TestReducer {
         type Action = TestAction;
         type State = TestState;
+        type Error = String;
 
+        #[allow(clippy::manual_async_fn)]
         fn dispatch(
             &mut self,
             client_id: u64,
@@ -60,6 +70,7 @@ mod tests {
             }
         }
 
+        #[allow(clippy::manual_async_fn)]
         fn extern_dispatch(
             &mut self,
             client_id: u64,
@@ -114,7 +125,7 @@ mod tests {
         let joint = WebsocketJoint::new(reducer);
 
         let rooms = joint.joint.broadcaster.get_rooms().clone();
-        let mut rooms = rooms.lock().await;
+        let mut rooms = rooms.write(1).await;
         rooms.insert(
             1,
             Room::new(
@@ -128,7 +139,7 @@ mod tests {
         drop(rooms);
 
         let clients = joint.joint.broadcaster.get_clients().clone();
-        let mut clients = clients.lock().await;
+        let mut clients = clients.write().await;
         clients.insert(1, Client::new(1, Some(1), String::new(), String::new()));
         drop(clients);
 
@@ -149,4 +160,594 @@ mod tests {
         let response = result.unwrap();
         assert_eq!(response.state.counter, 6);
     }
+
+    #[tokio::test]
+    async fn test_ws_sink_raw_send_matches_send() {
+        let response = Response::StateSent(r#"{"counter":42}"#.to_string(), None);
+        for format in [WireFormat::Json, WireFormat::MessagePack] {
+            for compression in [Compression::None, Compression::Deflate] {
+                let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+                let mut sink = WSSink {
+                    tx,
+                    format,
+                    compression,
+                    forwarder: ForwarderLiveness::new(),
+                };
+                assert_eq!(sink.wire_format(), Some(format));
+
+                sink.send(response.clone()).await.unwrap();
+                let encoded = format.encode(&response).unwrap();
+                sink.send_raw(encoded.into()).await.unwrap();
+
+                let sent = rx.recv().await.unwrap().unwrap();
+                let sent_raw = rx.recv().await.unwrap().unwrap();
+                assert_eq!(sent, sent_raw, "{:?} with {:?}", format, compression);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ws_sink_encodes_with_wire_format() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let mut json_sink = WSSink {
+            tx: tx.clone(),
+            format: WireFormat::Json,
+            compression: Compression::None,
+            forwarder: ForwarderLiveness::new(),
+        };
+        let mut msgpack_sink = WSSink {
+            tx,
+            format: WireFormat::MessagePack,
+            compression: Compression::None,
+            forwarder: ForwarderLiveness::new(),
+        };
+        let state = r#"{"counter":42}"#.to_string();
+
+        json_sink
+            .send(Response::StateSent(state.clone(), None))
+            .await
+            .unwrap();
+        match rx.recv().await.unwrap().unwrap() {
+            Message::Text(text) => {
+                let response: Response = serde_json::from_str(&text).unwrap();
+                assert!(matches!(response, Response::StateSent(payload, _) if payload == state));
+            }
+            other => panic!("Expected text frame, got {:?}", other),
+        }
+
+        msgpack_sink
+            .send(Response::StateSent(state.clone(), None))
+            .await
+            .unwrap();
+        match rx.recv().await.unwrap().unwrap() {
+            Message::Binary(bytes) => {
+                let response: Response = WireFormat::MessagePack.decode(&bytes).unwrap();
+                assert!(matches!(response, Response::StateSent(payload, _) if payload == state));
+            }
+            other => panic!("Expected binary frame, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dead_forwarder_removes_client() {
+        let joint = WebsocketJoint::new(TestReducer::default());
+        let broadcaster = &joint.joint.broadcaster;
+
+        let (tx, _rx) = tokio::sync::mpsc::channel(4);
+        let forwarder = ForwarderLiveness::new();
+        let forwarder_guard = forwarder.guard();
+        tokio::spawn(async move {
+            let _forwarder_guard = forwarder_guard;
+            panic!("forwarder died");
+        })
+        .await
+        .unwrap_err();
+
+        let mut sink = WSSink {
+            tx,
+            format: WireFormat::Json,
+            compression: Compression::None,
+            forwarder,
+        };
+        let error = sink.send(Response::RoomCreated(0)).await.unwrap_err();
+        assert!(error.is::<SinkClosed>());
+
+        broadcaster
+            .add_client_connection(Client::new(1, None, String::new(), String::new()), sink)
+            .await;
+        let room_id = broadcaster
+            .handle_create(1, CreateOptions::default())
+            .await
+            .unwrap()
+            .room;
+        broadcaster
+            .react_on_message(room_id, Response::RoomCreated(room_id))
+            .await;
+
+        assert!(!broadcaster.get_clients().read().await.contains_key(&1));
+        assert_eq!(joint.stats().await.failed_sends_total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_single_room_mode_accepts_actions_without_join() {
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        joint.single_room_mode();
+        joint.bind_addr("127.0.0.1:0").await.unwrap();
+        let addr = joint.local_addr().unwrap();
+        tokio::spawn(async move { joint.listen().await });
+
+        let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        let (mut write, mut read) = socket.split();
+
+        let mut next_response = async || match read.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str::<Response>(&text).unwrap(),
+            other => panic!("Expected text frame, got {:?}", other),
+        };
+        assert!(matches!(next_response().await, Response::Connected(_)));
+        let initial_state = next_response().await;
+        assert!(
+            matches!(initial_state, Response::StateSent(state, _) if state.contains("\"counter\":0"))
+        );
+
+        let message = JointMessage {
+            client_token: String::new(),
+            v: None,
+            message: JointMessageMethod::Action(
+                serde_json::to_string(&TestAction::Increment).unwrap(),
+            ),
+        };
+        write
+            .send(Message::Text(
+                serde_json::to_string(&message).unwrap().into(),
+            ))
+            .await
+            .unwrap();
+
+        match next_response().await {
+            Response::Action(payload, _) => {
+                let action: ActionResponse<TestState> = serde_json::from_str(&payload).unwrap();
+                assert_eq!(action.state.counter, 1);
+            }
+            other => panic!("Expected Action response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compression_negotiated_per_client() {
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        joint.bind_addr("127.0.0.1:0").await.unwrap();
+        let addr = joint.local_addr().unwrap();
+        tokio::spawn(async move { joint.listen().await });
+
+        for compression in [Compression::Deflate, Compression::None] {
+            let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+                .await
+                .unwrap();
+
+            let hello = JointMessage {
+                client_token: String::new(),
+                v: None,
+                message: JointMessageMethod::Hello {
+                    version: 1,
+                    compression,
+                    format: None,
+                },
+            };
+            socket
+                .send(Message::Text(serde_json::to_string(&hello).unwrap().into()))
+                .await
+                .unwrap();
+
+            // the client ID is sent before compression is negotiated
+            match socket.next().await.unwrap().unwrap() {
+                Message::Text(text) => assert!(matches!(
+                    serde_json::from_str::<Response>(&text).unwrap(),
+                    Response::Connected(_)
+                )),
+                other => panic!("Unexpected frame {:?}", other),
+            }
+
+            let response = match socket.next().await.unwrap().unwrap() {
+                Message::Binary(payload) if compression.is_enabled() => {
+                    let payload = compression.decompress(&payload).unwrap();
+                    serde_json::from_slice::<Response>(&payload).unwrap()
+                }
+                Message::Text(text) if !compression.is_enabled() => {
+                    serde_json::from_str::<Response>(&text).unwrap()
+                }
+                other => panic!("Unexpected frame for {:?}: {:?}", compression, other),
+            };
+            assert!(matches!(response, Response::Hello(1)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_message_pack_action_round_trip() {
+        let mut joint =
+            WebsocketJoint::new_with_format(TestReducer::default(), WireFormat::MessagePack);
+        joint.single_room_mode();
+        joint.bind_addr("127.0.0.1:0").await.unwrap();
+        let addr = joint.local_addr().unwrap();
+        tokio::spawn(async move { joint.listen().await });
+
+        let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        let (mut write, mut read) = socket.split();
+
+        let mut next_response = async || match read.next().await.unwrap().unwrap() {
+            Message::Binary(bytes) => WireFormat::MessagePack.decode::<Response>(&bytes).unwrap(),
+            other => panic!("Expected binary frame, got {:?}", other),
+        };
+        assert!(matches!(next_response().await, Response::Connected(_)));
+        let initial_state = next_response().await;
+        assert!(
+            matches!(initial_state, Response::StateSent(state, _) if state.contains("\"counter\":0"))
+        );
+
+        for action in [TestAction::Increment, TestAction::Add(5)] {
+            let message = JointMessage {
+                client_token: String::new(),
+                v: None,
+                message: JointMessageMethod::Action(serde_json::to_string(&action).unwrap()),
+            };
+            let bytes = WireFormat::MessagePack.encode(&message).unwrap();
+            write.send(Message::Binary(bytes.into())).await.unwrap();
+        }
+
+        for expected in [1, 6] {
+            match next_response().await {
+                Response::Action(payload, _) => {
+                    let action: ActionResponse<TestState> = serde_json::from_str(&payload).unwrap();
+                    assert_eq!(action.state.counter, expected);
+                }
+                other => panic!("Expected Action response, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wire_format_negotiated_per_client() {
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        joint.single_room_mode();
+        joint.bind_addr("127.0.0.1:0").await.unwrap();
+        let addr = joint.local_addr().unwrap();
+        tokio::spawn(async move { joint.listen().await });
+
+        let mut sockets = Vec::new();
+        for _ in 0..2 {
+            let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+                .await
+                .unwrap();
+            // the client ID and the initial state are sent in the joint's format
+            for _ in 0..2 {
+                assert!(matches!(
+                    socket.next().await.unwrap().unwrap(),
+                    Message::Text(_)
+                ));
+            }
+            sockets.push(socket);
+        }
+        let mut json_socket = sockets.remove(0);
+        let mut pack_socket = sockets.remove(0);
+
+        let hello = JointMessage {
+            client_token: String::new(),
+            v: None,
+            message: JointMessageMethod::Hello {
+                version: 1,
+                compression: Compression::None,
+                format: Some(WireFormat::MessagePack),
+            },
+        };
+        pack_socket
+            .send(Message::Text(serde_json::to_string(&hello).unwrap().into()))
+            .await
+            .unwrap();
+        match pack_socket.next().await.unwrap().unwrap() {
+            Message::Binary(bytes) => assert!(matches!(
+                WireFormat::MessagePack.decode::<Response>(&bytes).unwrap(),
+                Response::Hello(1)
+            )),
+            other => panic!("Expected binary frame, got {:?}", other),
+        }
+
+        // the negotiated client now sends its messages in MessagePack as well
+        let message = JointMessage {
+            client_token: String::new(),
+            v: None,
+            message: JointMessageMethod::Action(
+                serde_json::to_string(&TestAction::Increment).unwrap(),
+            ),
+        };
+        let bytes = WireFormat::MessagePack.encode(&message).unwrap();
+        pack_socket
+            .send(Message::Binary(bytes.into()))
+            .await
+            .unwrap();
+
+        let from_pack = match pack_socket.next().await.unwrap().unwrap() {
+            Message::Binary(bytes) => WireFormat::MessagePack.decode::<Response>(&bytes).unwrap(),
+            other => panic!("Expected binary frame, got {:?}", other),
+        };
+        let from_json = match json_socket.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str::<Response>(&text).unwrap(),
+            other => panic!("Expected text frame, got {:?}", other),
+        };
+        for response in [from_pack, from_json] {
+            match response {
+                Response::Action(payload, _) => {
+                    let action: ActionResponse<TestState> = serde_json::from_str(&payload).unwrap();
+                    assert_eq!(action.state.counter, 1);
+                }
+                other => panic!("Expected Action response, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_control_frames_are_skipped() {
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        joint.bind_addr("127.0.0.1:0").await.unwrap();
+        let addr = joint.local_addr().unwrap();
+        tokio::spawn(async move { joint.listen().await });
+
+        let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        let (mut write, mut read) = socket.split();
+
+        let hello = JointMessage {
+            client_token: String::new(),
+            v: None,
+            message: JointMessageMethod::Hello {
+                version: 1,
+                compression: Compression::None,
+                format: None,
+            },
+        };
+        let create = JointMessage {
+            client_token: String::new(),
+            v: None,
+            message: JointMessageMethod::Create(None),
+        };
+        for frame in [
+            Message::Ping(b"ping".to_vec().into()),
+            Message::Pong(b"pong".to_vec().into()),
+            Message::Binary(serde_json::to_vec(&hello).unwrap().into()),
+            Message::Text(serde_json::to_string(&create).unwrap().into()),
+        ] {
+            write.send(frame).await.unwrap();
+        }
+
+        let mut responses = Vec::new();
+        while responses.len() < 4 {
+            match read.next().await.unwrap().unwrap() {
+                Message::Text(text) => {
+                    responses.push(serde_json::from_str::<Response>(&text).unwrap())
+                }
+                Message::Pong(_) => {}
+                other => panic!("Unexpected frame {:?}", other),
+            }
+        }
+        assert!(matches!(responses[0], Response::Connected(_)));
+        assert!(matches!(responses[1], Response::Hello(1)));
+        assert!(matches!(responses[2], Response::StateSent(..)));
+        assert!(matches!(responses[3], Response::RoomCreated(_)));
+    }
+
+    #[tokio::test]
+    async fn test_disconnected_clients_are_removed() {
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        joint.bind_addr("127.0.0.1:0").await.unwrap();
+        let addr = joint.local_addr().unwrap();
+        let inner = joint.joint.clone();
+        tokio::spawn(async move { joint.listen().await });
+
+        let create = JointMessage::new(JointMessageMethod::Create(None), String::new());
+        for graceful in [true, false] {
+            let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+                .await
+                .unwrap();
+            socket
+                .send(Message::Text(
+                    serde_json::to_string(&create).unwrap().into(),
+                ))
+                .await
+                .unwrap();
+            socket.next().await.unwrap().unwrap();
+            assert_eq!(inner.stats().await.clients, 1);
+
+            if graceful {
+                socket.close(None).await.unwrap();
+            }
+            drop(socket);
+
+            tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                while inner.stats().await.clients > 0 {
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .expect("disconnected client was not removed");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_removes_unresponsive_clients() {
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        joint.set_heartbeat(Duration::from_millis(50), Duration::from_millis(100));
+        joint.bind_addr("127.0.0.1:0").await.unwrap();
+        let addr = joint.local_addr().unwrap();
+        let inner = joint.joint.clone();
+        tokio::spawn(async move { joint.listen().await });
+
+        let (responsive, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        let (_, mut responsive_read) = responsive.split();
+        tokio::spawn(async move { while let Some(Ok(_)) = responsive_read.next().await {} });
+
+        let (_silent, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while inner.stats().await.clients < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while inner.stats().await.clients > 1 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("unresponsive client was not removed");
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(inner.stats().await.clients, 1);
+    }
+
+    #[tokio::test]
+    async fn test_listen_with_shutdown_returns_and_closes_connections() {
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        joint.bind_addr("127.0.0.1:0").await.unwrap();
+        let addr = joint.local_addr().unwrap();
+        let inner = joint.joint.clone();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let listener = tokio::spawn(async move {
+            joint
+                .listen_with_shutdown(
+                    async {
+                        let _ = shutdown_rx.await;
+                    },
+                    true,
+                )
+                .await;
+            joint
+        });
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while inner.stats().await.clients < 1 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        shutdown_tx.send(()).unwrap();
+        let joint = tokio::time::timeout(Duration::from_secs(5), listener)
+            .await
+            .expect("listen did not return after shutdown")
+            .unwrap();
+        assert!(joint.local_addr().is_none());
+
+        let closed = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match socket.next().await {
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    Some(Ok(_)) => continue,
+                }
+            }
+        })
+        .await;
+        assert!(closed.is_ok(), "connection was not closed on shutdown");
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while inner.stats().await.clients > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("client was not disconnected on shutdown");
+
+        assert!(tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_create_room_over_tls() {
+        use crate::joint::ws::TlsConfig;
+        use tokio_rustls::rustls::{self, pki_types::ServerName, ClientConfig, RootCertStore};
+        use tokio_rustls::TlsConnector;
+
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let dir = std::env::temp_dir().join(format!("injoint-tls-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert_path, key_path) = (dir.join("cert.pem"), dir.join("key.pem"));
+        std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified.key_pair.serialize_pem()).unwrap();
+
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        joint
+            .bind_addr_tls("127.0.0.1:0", TlsConfig::new(&cert_path, &key_path))
+            .await
+            .unwrap();
+        let addr = joint.local_addr().unwrap();
+        tokio::spawn(async move { joint.listen().await });
+
+        let mut roots = RootCertStore::empty();
+        roots.add(certified.cert.der().clone()).unwrap();
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let tls = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), tcp)
+            .await
+            .unwrap();
+        let (mut socket, _) = tokio_tungstenite::client_async("wss://localhost/", tls)
+            .await
+            .unwrap();
+
+        let create = JointMessage {
+            client_token: String::new(),
+            v: None,
+            message: JointMessageMethod::Create(None),
+        };
+        socket
+            .send(Message::Text(
+                serde_json::to_string(&create).unwrap().into(),
+            ))
+            .await
+            .unwrap();
+
+        let mut responses = Vec::new();
+        while responses.len() < 3 {
+            match socket.next().await.unwrap().unwrap() {
+                Message::Text(text) => {
+                    responses.push(serde_json::from_str::<Response>(&text).unwrap())
+                }
+                other => panic!("Unexpected frame {:?}", other),
+            }
+        }
+        assert!(matches!(responses[0], Response::Connected(_)));
+        assert!(matches!(responses[1], Response::StateSent(..)));
+        assert!(matches!(responses[2], Response::RoomCreated(_)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_bind_addr_tls_rejects_missing_certificate() {
+        use crate::joint::ws::TlsConfig;
+
+        let mut joint = WebsocketJoint::new(TestReducer::default());
+        let tls = TlsConfig::new("/nonexistent/cert.pem", "/nonexistent/key.pem");
+        assert!(joint.bind_addr_tls("127.0.0.1:0", tls).await.is_err());
+        assert!(joint.local_addr().is_none());
+    }
 }
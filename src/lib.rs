@@ -17,7 +17,7 @@
 //! # Usage
 //! Example of minimalistic websocket chat server taken from [GitHub repository](https://github.com/PixelQuasar/injoint):
 //!
-//! ```rust
+//! ```rust,no_run
 //! use injoint::codegen::{reducer_actions, Broadcastable};
 //! use injoint::joint::ws::WebsocketJoint;
 //! use serde::Serialize;
@@ -204,12 +204,17 @@
 //! "message": 0 // client id
 //! }
 //!
+// The crate documentation above predates the clippy checks.
+#![allow(clippy::tabs_in_doc_comments, clippy::doc_lazy_continuation)]
 /// Broadcaster is core structure responsible handling room-split communication and multiple reducers.
 mod broadcaster;
 
 /// Client is a structure that represents a client connected to the server.
 mod client;
 
+/// Codec contains wire formats used to encode responses sent to clients.
+pub mod codec;
+
 /// Connection is a structure that represents a connection to a client.
 pub mod connection;
 
@@ -219,15 +224,32 @@ pub mod dispatcher;
 /// Joint is a structure that represents a joint implementation for real-time communication.
 pub mod joint;
 
+/// Load shedding contains the policy rejecting low-priority actions under pressure.
+pub mod load_shedding;
+
 /// Message is a structure that represents a message sent over the WebSocket connection.
 pub mod message;
 
+/// Metrics contains runtime statistics collected by the joint.
+pub mod metrics;
+
+/// Protocol contains a machine-readable description of the control protocol.
+mod protocol;
+
+/// Rate limit contains the configuration of per-client action rate limits.
+pub mod rate_limit;
+
 /// Response is a structure that represents a response sent back to the client.
 pub mod response;
 
 /// Room is a structure that represents a room in which clients can join and communicate.
 mod room;
 
+/// Testing contains helpers for testing reducers and joints: a recording sink, and
+/// property-based reducer checks with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 /// State is a structure that represents the state of the application.
 pub mod utils;
 
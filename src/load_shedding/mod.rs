@@ -0,0 +1,73 @@
+/// This module contains the load shedding configuration and the pending action gauge it is based on.
+mod test;
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Load shedding policy rejecting low-priority actions while the joint is under pressure.
+///
+/// The joint is under pressure when more than `max_pending_actions` client actions are being
+/// processed or waiting to be, including the one being checked. Under pressure, actions whose
+/// `Dispatchable::priority` is below `min_priority` are rejected with `Overloaded`.
+///
+/// # example
+/// ```
+/// use injoint::load_shedding::LoadShedding;
+///
+/// let policy = LoadShedding::new(64, 1);
+/// assert!(!policy.sheds(64, 0));
+/// assert!(policy.sheds(65, 0));
+/// assert!(!policy.sheds(65, 1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadShedding {
+    /// Number of pending actions above which the joint is under pressure.
+    pub max_pending_actions: usize,
+    /// Lowest priority of the actions still processed under pressure.
+    pub min_priority: u8,
+}
+
+impl LoadShedding {
+    /// Creates a new load shedding policy.
+    pub fn new(max_pending_actions: usize, min_priority: u8) -> Self {
+        LoadShedding {
+            max_pending_actions,
+            min_priority,
+        }
+    }
+
+    /// Returns `true` if an action of given priority is shed with `pending` actions in the joint.
+    pub fn sheds(&self, pending: usize, priority: u8) -> bool {
+        pending > self.max_pending_actions && priority < self.min_priority
+    }
+}
+
+/// Gauge of the client actions being processed or waiting to be.
+#[derive(Debug, Default)]
+pub(crate) struct PendingActions {
+    count: AtomicUsize,
+}
+
+impl PendingActions {
+    /// Counts a new pending action until the returned guard is dropped.
+    pub fn enter(&self) -> PendingGuard<'_> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        PendingGuard { pending: self }
+    }
+
+    /// Returns the number of pending actions.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+}
+
+/// Keeps an action counted as pending while alive.
+pub(crate) struct PendingGuard<'a> {
+    pending: &'a PendingActions,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.pending.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
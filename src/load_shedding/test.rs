@@ -0,0 +1,27 @@
+#[cfg(test)]
+mod tests {
+    use crate::load_shedding::{LoadShedding, PendingActions};
+
+    #[test]
+    fn test_load_shedding_sheds_only_low_priority_under_pressure() {
+        let policy = LoadShedding::new(2, 5);
+        assert!(!policy.sheds(2, 0));
+        assert!(policy.sheds(3, 0));
+        assert!(policy.sheds(3, 4));
+        assert!(!policy.sheds(3, 5));
+        assert!(!policy.sheds(100, u8::MAX));
+    }
+
+    #[test]
+    fn test_pending_actions_gauge() {
+        let pending = PendingActions::default();
+        let first = pending.enter();
+        let second = pending.enter();
+        assert_eq!(pending.count(), 2);
+
+        drop(first);
+        assert_eq!(pending.count(), 1);
+        drop(second);
+        assert_eq!(pending.count(), 0);
+    }
+}
@@ -1,9 +1,60 @@
 /// This module contains the `JointMessage` struct and the `JointMessageMethod` enum.
 mod test;
 
+use crate::codec::{Compression, WireFormat};
+use crate::rate_limit::RateLimit;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
+/// Protocol version assumed for clients that never negotiated one with `Hello`.
+pub const DEFAULT_PROTOCOL_VERSION: u32 = 1;
+
+/// Version of the wire schema of messages and responses, sent as `v` in every response.
+///
+/// Messages carrying a different `v` are rejected, so mismatched clients are detected early
+/// instead of being misunderstood. Unlike the protocol version, it covers the control envelope
+/// rather than reducer actions.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Options of a room being created, sent as optional data of the `Create` message.
+///
+/// Every field may be omitted, falling back to the joint defaults.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct CreateOptions {
+    /// Action rate limit overriding the joint default in this room.
+    /// Must not exceed the maximum rate limit configured on the joint.
+    pub rate_limit: Option<RateLimit>,
+    /// Password making the room private, required to join it with `JoinWithPassword`.
+    pub password: Option<String>,
+    /// Maximum number of clients in the room, owner included. Unlimited if omitted.
+    pub capacity: Option<usize>,
+    /// Whether clients joining the room at capacity are put on a waitlist instead of refused.
+    pub waitlist: bool,
+    /// Group of mutually exclusive rooms: in multi-room mode, a client may be a member of at most
+    /// one room per group.
+    pub room_group: Option<String>,
+}
+
+/// New configuration of a room, sent as data of the `UpdateRoomConfig` message.
+///
+/// The whole configuration is replaced: omitted fields fall back to their defaults, that is
+/// no name, unlimited capacity, the joint default rate limit and a public room.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct RoomConfig {
+    /// Display name of the room.
+    pub name: Option<String>,
+    /// Maximum number of clients in the room, owner included. Unlimited if omitted.
+    /// Must not be below the number of clients currently in the room.
+    pub capacity: Option<usize>,
+    /// Action rate limit overriding the joint default in this room.
+    /// Must not exceed the maximum rate limit configured on the joint.
+    pub rate_limit: Option<RateLimit>,
+    /// Password making the room private, required to join it with `JoinWithPassword`.
+    pub password: Option<String>,
+}
+
 /// Message method enum that represents messages receiving by `Joint`.
 ///
 /// This enum is used to represent the different types of messages that can be sent
@@ -12,14 +63,68 @@ use std::fmt::Debug;
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(tag = "type", content = "data")]
 pub enum JointMessageMethod {
-    /// Create a new room (triggering RoomCreated response)
-    Create,
+    /// Create a new room with optional options (triggering RoomCreated response)
+    Create(Option<CreateOptions>),
     /// Join an existing room by id (triggering RoomJoined and StateSent responses)
     Join(u64),
+    /// Join the room created for a key, creating it first if there is none (triggering RoomCreated
+    /// or RoomJoined and StateSent responses)
+    CreateOrJoin(String),
+    /// Join an existing private room by id and password (triggering RoomJoined and StateSent responses)
+    JoinWithPassword { room_id: u64, password: String },
+    /// Join an existing room by id, receiving its state through the named projection registered
+    /// on the joint until leaving (triggering RoomJoined and StateSent responses)
+    JoinProjected { room_id: u64, projection: String },
     /// Leave the current room (triggering RoomLeft response)
     Leave,
+    /// Leave one of the rooms joined in multi-room mode by id (triggering RoomLeft response)
+    LeaveRoom(u64),
+    /// Delete the current room, owner only (triggering RoomDeleted response to every member)
+    Delete,
     /// Send a message to the room (triggering Action response)
     Action(String), // maybe this should be a generic type that deserializable?
+    /// Send a message to the given room the client is a member of, or to its joined room if `None` (triggering Action response).
+    /// If `base_version` is set and differs from the state version of the room, the action is
+    /// rejected (triggering Conflict response to the sender)
+    RoomAction {
+        room_id: Option<u64>,
+        payload: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base_version: Option<u64>,
+    },
+    /// Acknowledge the initial state received on join (triggering RoomJoined response to the sender)
+    Ack,
+    /// Share a cursor position with the other room members, bypassing the reducer (triggering Cursor response)
+    Cursor(serde_json::Value),
+    /// Declare the protocol version spoken by the client, the compression it can inflate
+    /// and optionally the wire format it speaks (triggering Hello response to the sender)
+    Hello {
+        version: u32,
+        #[serde(default)]
+        compression: Compression,
+        #[serde(default)]
+        format: Option<WireFormat>,
+    },
+    /// List the existing rooms (triggering RoomList response to the sender)
+    ListRooms,
+    /// List the clients in the current room (triggering Presence response to the sender)
+    Presence,
+    /// Describe the session of the sender (triggering SessionInfo response to the sender)
+    WhoAmI,
+    /// Send a payload to another client in the same room only (triggering DirectMessage response to the target)
+    DirectMessage { target: u64, payload: String },
+    /// Mute a member of the current room by id, owner only (triggering Muted response)
+    Mute(u64),
+    /// Unmute a member of the current room by id, owner only (triggering Unmuted response)
+    Unmute(u64),
+    /// Replace the configuration of the current room, owner only (triggering RoomConfigChanged response)
+    UpdateRoomConfig(RoomConfig),
+    /// Rejoin the room of a disconnected session by its token, replaying the actions applied after
+    /// `last_seq` (triggering Action or StateSent responses to the sender and RoomJoined response)
+    Resume { token: String, last_seq: u64 },
+    /// Request the current state of the current room, pulling the updates of rooms exceeding
+    /// the fan-out cap (triggering StateSent response to the sender)
+    Sync,
 }
 
 /// JointMessage struct that represents a message received by the `Joint`.
@@ -34,7 +139,7 @@ pub enum JointMessageMethod {
 /// use injoint::message::JointMessageMethod;
 /// use injoint::message::JointMessage;
 ///
-/// let message = JointMessage::new(JointMessageMethod::Create, String::new());
+/// let message = JointMessage::new(JointMessageMethod::Create(None), String::new());
 ///
 /// let json = serde_json::to_string(&message).unwrap();
 ///
@@ -50,6 +155,9 @@ pub struct JointMessage {
     /// The client token that is used to identify the client.
     /// (usage is developer-defined, not used for anything yet)
     pub client_token: String,
+    /// The schema version the message was written for, checked against `SCHEMA_VERSION` if present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub v: Option<u32>,
 }
 
 impl JointMessage {
@@ -58,6 +166,7 @@ impl JointMessage {
         JointMessage {
             message,
             client_token,
+            v: Some(SCHEMA_VERSION),
         }
     }
 }
@@ -1,14 +1,18 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
+    use crate::codec::Compression;
     use crate::message::{JointMessage, JointMessageMethod};
+    use crate::rate_limit::RateLimit;
+    #[allow(unused_imports)]
     use serde_json::{json, Value};
 
     #[test]
     fn test_joint_message_creation() {
-        let message = JointMessage::new(JointMessageMethod::Create, "client123".to_string());
+        let message = JointMessage::new(JointMessageMethod::Create(None), "client123".to_string());
         assert_eq!(message.client_token, "client123");
-        assert!(matches!(message.message, JointMessageMethod::Create));
+        assert!(matches!(message.message, JointMessageMethod::Create(None)));
 
         let room_id = 42;
         let message = JointMessage::new(JointMessageMethod::Join(room_id), "client456".to_string());
@@ -48,7 +52,24 @@ mod tests {
         "#;
         let message: JointMessage = serde_json::from_str(json_str).unwrap();
         assert_eq!(message.client_token, "client123");
-        assert!(matches!(message.message, JointMessageMethod::Create));
+        assert!(matches!(message.message, JointMessageMethod::Create(None)));
+
+        let json_str = r#"
+        {
+            "message": {
+                "type": "Create",
+                "data": { "rate_limit": { "actions_per_sec": 5, "burst": 10 } }
+            },
+            "client_token": "client123"
+        }
+        "#;
+        let message: JointMessage = serde_json::from_str(json_str).unwrap();
+        match message.message {
+            JointMessageMethod::Create(Some(options)) => {
+                assert_eq!(options.rate_limit, Some(RateLimit::new(5, 10)))
+            }
+            _ => panic!("Expected Create message with options"),
+        }
 
         let json_str = r#"
         {
@@ -95,6 +116,29 @@ mod tests {
         } else {
             panic!("Expected Action message");
         }
+
+        let json_str =
+            r#"{"message": {"type": "Hello", "data": {"version": 2}}, "client_token": ""}"#;
+        let message: JointMessage = serde_json::from_str(json_str).unwrap();
+        assert!(matches!(
+            message.message,
+            JointMessageMethod::Hello {
+                version: 2,
+                compression: Compression::None,
+                format: None
+            }
+        ));
+
+        let json_str = r#"{"message": {"type": "Hello", "data": {"version": 2, "compression": "Deflate"}}, "client_token": ""}"#;
+        let message: JointMessage = serde_json::from_str(json_str).unwrap();
+        assert!(matches!(
+            message.message,
+            JointMessageMethod::Hello {
+                version: 2,
+                compression: Compression::Deflate,
+                format: None
+            }
+        ));
     }
 
     #[test]
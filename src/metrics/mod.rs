@@ -0,0 +1,165 @@
+/// This module contains runtime statistics collected by the joint.
+mod test;
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Snapshot of joint runtime statistics.
+///
+/// Gauges (`clients`, `rooms`) reflect the moment the snapshot was taken,
+/// counters are monotonically increasing since the joint was created.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct JointStats {
+    /// Number of currently connected clients.
+    pub clients: usize,
+    /// Number of currently existing rooms.
+    pub rooms: usize,
+    /// Total number of successfully applied actions.
+    pub actions_total: u64,
+    /// Total number of sends that failed or timed out.
+    pub failed_sends_total: u64,
+    /// Total number of bytes of applied action payloads.
+    pub action_bytes_total: u64,
+    /// Total number of action broadcasts sent to their room.
+    pub broadcasts_total: u64,
+    /// Total number of recipients of action broadcasts, the fan-out summed over broadcasts.
+    pub fanout_total: u64,
+    /// Largest number of recipients of a single action broadcast.
+    pub max_fanout: u64,
+    /// Total number of action broadcasts skipped because their room exceeded `max_fanout`.
+    pub capped_broadcasts_total: u64,
+}
+
+impl JointStats {
+    /// Renders statistics in the Prometheus text exposition format.
+    ///
+    /// # example
+    /// ```
+    /// use injoint::metrics::JointStats;
+    ///
+    /// let metrics = JointStats::default().to_prometheus();
+    /// assert!(metrics.contains("# TYPE injoint_clients gauge"));
+    /// ```
+    #[cfg(feature = "prometheus")]
+    pub fn to_prometheus(&self) -> String {
+        let metrics: [(&str, &str, &str, u64); 9] = [
+            (
+                "injoint_clients",
+                "gauge",
+                "Number of currently connected clients.",
+                self.clients as u64,
+            ),
+            (
+                "injoint_rooms",
+                "gauge",
+                "Number of currently existing rooms.",
+                self.rooms as u64,
+            ),
+            (
+                "injoint_actions_total",
+                "counter",
+                "Total number of successfully applied actions.",
+                self.actions_total,
+            ),
+            (
+                "injoint_failed_sends_total",
+                "counter",
+                "Total number of sends that failed or timed out.",
+                self.failed_sends_total,
+            ),
+            (
+                "injoint_action_bytes_total",
+                "counter",
+                "Total number of bytes of applied action payloads.",
+                self.action_bytes_total,
+            ),
+            (
+                "injoint_broadcasts_total",
+                "counter",
+                "Total number of action broadcasts sent to their room.",
+                self.broadcasts_total,
+            ),
+            (
+                "injoint_fanout_total",
+                "counter",
+                "Total number of recipients of action broadcasts.",
+                self.fanout_total,
+            ),
+            (
+                "injoint_max_fanout",
+                "gauge",
+                "Largest number of recipients of a single action broadcast.",
+                self.max_fanout,
+            ),
+            (
+                "injoint_capped_broadcasts_total",
+                "counter",
+                "Total number of action broadcasts skipped in rooms exceeding the fan-out cap.",
+                self.capped_broadcasts_total,
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, kind, help, value) in metrics {
+            output.push_str(&format!("# HELP {} {}\n", name, help));
+            output.push_str(&format!("# TYPE {} {}\n", name, kind));
+            output.push_str(&format!("{} {}\n", name, value));
+        }
+        output
+    }
+}
+
+/// Monotonic counters updated by the broadcaster.
+#[derive(Default)]
+pub(crate) struct Counters {
+    actions_total: AtomicU64,
+    failed_sends_total: AtomicU64,
+    action_bytes_total: AtomicU64,
+    broadcasts_total: AtomicU64,
+    fanout_total: AtomicU64,
+    max_fanout: AtomicU64,
+    capped_broadcasts_total: AtomicU64,
+}
+
+impl Counters {
+    /// records a successfully applied action with given payload size
+    pub fn record_action(&self, payload_bytes: usize) {
+        self.actions_total.fetch_add(1, Ordering::Relaxed);
+        self.action_bytes_total
+            .fetch_add(payload_bytes as u64, Ordering::Relaxed);
+    }
+
+    /// records a failed or timed out send
+    pub fn record_failed_send(&self) {
+        self.failed_sends_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// records an action broadcast sent to given number of recipients
+    pub fn record_fanout(&self, recipients: usize) {
+        self.broadcasts_total.fetch_add(1, Ordering::Relaxed);
+        self.fanout_total
+            .fetch_add(recipients as u64, Ordering::Relaxed);
+        self.max_fanout
+            .fetch_max(recipients as u64, Ordering::Relaxed);
+    }
+
+    /// records an action broadcast skipped in a room exceeding the fan-out cap
+    pub fn record_capped_broadcast(&self) {
+        self.capped_broadcasts_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// builds a statistics snapshot using given gauge values
+    pub fn snapshot(&self, clients: usize, rooms: usize) -> JointStats {
+        JointStats {
+            clients,
+            rooms,
+            actions_total: self.actions_total.load(Ordering::Relaxed),
+            failed_sends_total: self.failed_sends_total.load(Ordering::Relaxed),
+            action_bytes_total: self.action_bytes_total.load(Ordering::Relaxed),
+            broadcasts_total: self.broadcasts_total.load(Ordering::Relaxed),
+            fanout_total: self.fanout_total.load(Ordering::Relaxed),
+            max_fanout: self.max_fanout.load(Ordering::Relaxed),
+            capped_broadcasts_total: self.capped_broadcasts_total.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use crate::metrics::{Counters, JointStats};
+
+    #[test]
+    fn test_counters_snapshot() {
+        let counters = Counters::default();
+        counters.record_action(10);
+        counters.record_action(5);
+        counters.record_failed_send();
+        counters.record_fanout(3);
+        counters.record_fanout(7);
+        counters.record_capped_broadcast();
+
+        let stats = counters.snapshot(3, 2);
+        assert_eq!(
+            stats,
+            JointStats {
+                clients: 3,
+                rooms: 2,
+                actions_total: 2,
+                failed_sends_total: 1,
+                action_bytes_total: 15,
+                broadcasts_total: 2,
+                fanout_total: 10,
+                max_fanout: 7,
+                capped_broadcasts_total: 1,
+            }
+        );
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_prometheus_exposition_format() {
+        let counters = Counters::default();
+        counters.record_action(42);
+        let output = counters.snapshot(1, 1).to_prometheus();
+
+        let expected = [
+            ("injoint_clients", "gauge"),
+            ("injoint_rooms", "gauge"),
+            ("injoint_actions_total", "counter"),
+            ("injoint_failed_sends_total", "counter"),
+            ("injoint_action_bytes_total", "counter"),
+            ("injoint_broadcasts_total", "counter"),
+            ("injoint_fanout_total", "counter"),
+            ("injoint_max_fanout", "gauge"),
+            ("injoint_capped_broadcasts_total", "counter"),
+        ];
+        for (name, kind) in expected {
+            assert!(output.contains(&format!("# HELP {} ", name)));
+            assert!(output.contains(&format!("# TYPE {} {}\n", name, kind)));
+        }
+
+        for line in output.lines() {
+            if line.starts_with('#') {
+                let mut parts = line.splitn(4, ' ');
+                assert_eq!(parts.next(), Some("#"));
+                assert!(matches!(parts.next(), Some("HELP") | Some("TYPE")));
+                assert!(parts.next().is_some());
+                assert!(parts.next().is_some());
+            } else {
+                let (name, value) = line.split_once(' ').expect("sample must have a value");
+                assert!(name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':'));
+                value.parse::<f64>().expect("sample value must be numeric");
+            }
+        }
+        assert!(output.contains("injoint_actions_total 1\n"));
+        assert!(output.contains("injoint_action_bytes_total 42\n"));
+    }
+}
@@ -0,0 +1,270 @@
+/// This module describes the control protocol spoken by joints in a machine-readable form.
+///
+/// The description lists the `JointMessage` methods clients may send and the `Response`s
+/// they receive, along with the action schema of the reducer, so client SDKs and API docs
+/// can be generated from a running joint.
+mod test;
+
+use serde_json::{json, Value};
+
+/// Protocol features enabled on a joint, changing the methods and payloads clients see.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ProtocolExtensions {
+    /// Joining clients must acknowledge the initial state with `Ack`.
+    pub join_requires_ack: bool,
+    /// `Action` responses carry a JSON Patch of the state instead of the full state.
+    pub state_diffs: bool,
+    /// `StateSent` and `Action` responses carry the room's sequence number and a timestamp.
+    pub stamps: bool,
+    /// Every client is joined to a single room on connection.
+    pub single_room: bool,
+    /// Clients may join several rooms at once, targeting them with `RoomAction`.
+    pub multi_room: bool,
+}
+
+/// Methods always understood by joints: name, shape of `data`, and description.
+const METHODS: &[(&str, &str, &str)] = &[
+    (
+        "Create",
+        r#"null | {"rate_limit"?: {"actions_per_sec": integer, "burst": integer}, "password"?: string, "capacity"?: integer, "waitlist"?: boolean, "room_group"?: string}"#,
+        "Create a new room",
+    ),
+    ("Join", "integer", "Join an existing room by id"),
+    (
+        "CreateOrJoin",
+        "string",
+        "Join the room created for a key, creating it first if there is none",
+    ),
+    (
+        "JoinWithPassword",
+        r#"{"room_id": integer, "password": string}"#,
+        "Join an existing private room by id and password",
+    ),
+    (
+        "JoinProjected",
+        r#"{"room_id": integer, "projection": string}"#,
+        "Join an existing room by id, receiving its state through a named projection",
+    ),
+    ("Leave", "null", "Leave the current room"),
+    (
+        "LeaveRoom",
+        "integer",
+        "Leave one of the rooms joined in multi-room mode by id",
+    ),
+    ("Delete", "null", "Delete the current room, owner only"),
+    (
+        "Action",
+        "string",
+        "Dispatch a JSON-encoded reducer action to the current room",
+    ),
+    (
+        "RoomAction",
+        r#"{"room_id": integer | null, "payload": string, "base_version"?: integer}"#,
+        "Dispatch a JSON-encoded reducer action to a given room the client is a member of, optionally based on a state version",
+    ),
+    (
+        "Cursor",
+        "any",
+        "Share a cursor position with the other room members",
+    ),
+    (
+        "Hello",
+        r#"{"version": integer, "compression"?: "None" | "Deflate", "format"?: "Json" | "MessagePack"}"#,
+        "Declare the protocol version, compression and wire format spoken by the client",
+    ),
+    ("ListRooms", "null", "List the existing rooms"),
+    ("Presence", "null", "List the clients in the current room"),
+    ("WhoAmI", "null", "Describe the session of the sender"),
+    (
+        "DirectMessage",
+        r#"{"target": integer, "payload": string}"#,
+        "Send a payload to another client in the same room",
+    ),
+    (
+        "Mute",
+        "integer",
+        "Mute a member of the current room, owner only",
+    ),
+    (
+        "Unmute",
+        "integer",
+        "Unmute a member of the current room, owner only",
+    ),
+    (
+        "UpdateRoomConfig",
+        r#"{"name"?: string, "capacity"?: integer, "rate_limit"?: {"actions_per_sec": integer, "burst": integer}, "password"?: string}"#,
+        "Replace the configuration of the current room, owner only",
+    ),
+    (
+        "Resume",
+        r#"{"token": string, "last_seq": integer}"#,
+        "Rejoin the room of a disconnected session, replaying the actions applied after last_seq",
+    ),
+    (
+        "Sync",
+        "null",
+        "Request the current state of the current room",
+    ),
+];
+
+/// Method understood when joins must be acknowledged.
+const ACK_METHOD: (&str, &str, &str) = (
+    "Ack",
+    "null",
+    "Acknowledge the initial state received on join",
+);
+
+/// Responses sent by joints: status, shape of `message`, and description.
+const RESPONSES: &[(&str, &str, &str)] = &[
+    ("RoomCreated", "integer", "A room has been created"),
+    ("RoomJoined", "integer", "A client has joined the room"),
+    (
+        "StateSent",
+        "string",
+        "The JSON-encoded room state, sent to joining clients",
+    ),
+    (
+        "Action",
+        "string",
+        "A JSON-encoded action response applied to the room",
+    ),
+    ("RoomLeft", "integer", "A client has left the room"),
+    ("RoomDeleted", "integer", "The room has been deleted"),
+    (
+        "RoomDraining",
+        "string",
+        "The room is being drained and rejects actions",
+    ),
+    (
+        "ServerError",
+        "string",
+        "A server error occurred, with a machine-readable `code`",
+    ),
+    (
+        "ClientError",
+        "string",
+        "A client error occurred, with a machine-readable `code`",
+    ),
+    (
+        "NotFound",
+        "string",
+        "A room or client was not found, with a machine-readable `code`",
+    ),
+    ("Hello", "integer", "The negotiated protocol version"),
+    (
+        "Cursor",
+        r#"{"client_id": integer, "data": any}"#,
+        "A cursor position shared by another client",
+    ),
+    ("RoomFull", "integer", "The room is at capacity"),
+    (
+        "WrongPassword",
+        "integer",
+        "A wrong password was given for a private room",
+    ),
+    ("Banned", "integer", "The client is banned from the room"),
+    (
+        "InviteRequired",
+        "integer",
+        "The room can only be joined by invited clients",
+    ),
+    (
+        "RoomList",
+        r#"[{"id": integer, "owner_id": integer, "client_count": integer, "private": boolean}]"#,
+        "The existing rooms",
+    ),
+    (
+        "Presence",
+        r#"[{"id": integer, "label": string}]"#,
+        "The clients in the room",
+    ),
+    ("Keepalive", "null", "No-op keeping idle connections alive"),
+    ("Muted", "integer", "A client of the room was muted"),
+    ("Unmuted", "integer", "A client of the room was unmuted"),
+    (
+        "DirectMessage",
+        r#"{"from": integer, "payload": string}"#,
+        "A payload sent directly by another client",
+    ),
+    (
+        "Waitlisted",
+        r#"{"position": integer}"#,
+        "The client is waiting for a slot in a full room",
+    ),
+    (
+        "Overloaded",
+        "null",
+        "The action was shed because the server is overloaded",
+    ),
+    (
+        "Conflict",
+        r#"{"current_version": integer}"#,
+        "The action was based on a stale state version of the room",
+    ),
+    (
+        "Connected",
+        "integer",
+        "The ID assigned to the client on connect",
+    ),
+    (
+        "SessionToken",
+        "string",
+        "The token to resume the session of the client with after reconnecting",
+    ),
+    (
+        "SessionInfo",
+        r#"{"client_id": integer, "room_id": integer | null, "label": string, "roles": [string]}"#,
+        "The session of the client: its ID, current room, label and roles in the room",
+    ),
+    (
+        "RoomConfigChanged",
+        r#"{"name": string | null, "capacity": integer | null, "rate_limit": {"actions_per_sec": integer, "burst": integer} | null, "private": boolean}"#,
+        "The configuration of the room was changed by its owner",
+    ),
+    (
+        "RoomSummary",
+        r#"{"id": integer, "owner_id": integer, "name": string | null, "client_count": integer, "last_activity": integer}"#,
+        "A room was created or its members changed, sent to overview subscribers",
+    ),
+];
+
+/// Describes table entries, naming the shape of their payload `data_key`.
+fn describe(entries: &[(&str, &str, &str)], data_key: &str) -> Vec<Value> {
+    entries
+        .iter()
+        .map(|(name, data, description)| {
+            json!({
+                "name": name,
+                data_key: data,
+                "description": description,
+            })
+        })
+        .collect()
+}
+
+/// Builds the description of the control protocol for the given extensions and action schema.
+pub(crate) fn describe_protocol(extensions: ProtocolExtensions, action_schema: Value) -> Value {
+    let mut methods = describe(METHODS, "data");
+    if extensions.join_requires_ack {
+        methods.extend(describe(&[ACK_METHOD], "data"));
+    }
+
+    json!({
+        "message": {
+            "envelope": {"message": {"type": "<method name>", "data": "<method data>"}, "client_token": "string", "v": "integer, optional"},
+            "methods": methods,
+        },
+        "response": {
+            "envelope": {"status": "<response status>", "message": "<response message>", "code": "string, error responses only", "seq": "integer, stamped broadcasts only", "timestamp": "integer, stamped broadcasts only", "v": "integer"},
+            "responses": describe(RESPONSES, "message"),
+        },
+        "extensions": {
+            "join_requires_ack": extensions.join_requires_ack,
+            "state_diffs": extensions.state_diffs,
+            "stamps": extensions.stamps,
+            "single_room": extensions.single_room,
+            "multi_room": extensions.multi_room,
+        },
+        "actions": action_schema,
+    })
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use crate::protocol::{describe_protocol, ProtocolExtensions};
+    use serde_json::{json, Value};
+
+    fn method_names(description: &Value) -> Vec<&str> {
+        description["message"]["methods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|method| method["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_describe_protocol_lists_methods_and_responses() {
+        let description = describe_protocol(ProtocolExtensions::default(), Value::Null);
+
+        let methods = method_names(&description);
+        for method in ["Create", "Join", "Leave", "Action", "Hello"] {
+            assert!(methods.contains(&method), "missing method {}", method);
+        }
+        assert!(!methods.contains(&"Ack"));
+
+        let responses = description["response"]["responses"].as_array().unwrap();
+        assert!(responses
+            .iter()
+            .any(|response| response["name"] == "StateSent" && response["message"] == "string"));
+        assert_eq!(description["extensions"]["state_diffs"], false);
+    }
+
+    #[test]
+    fn test_describe_protocol_includes_enabled_extensions() {
+        let extensions = ProtocolExtensions {
+            join_requires_ack: true,
+            state_diffs: true,
+            stamps: true,
+            single_room: false,
+            multi_room: false,
+        };
+        let schema = json!({"actions": []});
+        let description = describe_protocol(extensions, schema.clone());
+
+        assert!(method_names(&description).contains(&"Ack"));
+        assert_eq!(description["extensions"]["join_requires_ack"], true);
+        assert_eq!(description["extensions"]["state_diffs"], true);
+        assert_eq!(description["extensions"]["stamps"], true);
+        assert_eq!(description["actions"], schema);
+    }
+}
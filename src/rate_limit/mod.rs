@@ -0,0 +1,74 @@
+/// This module contains the action rate limit configuration and its token bucket.
+mod test;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+/// Action rate limit applied to each client individually.
+///
+/// Clients may send up to `burst` actions at once, refilled at `actions_per_sec`.
+///
+/// # example
+/// ```
+/// use injoint::rate_limit::RateLimit;
+///
+/// let limit = RateLimit::new(10, 20);
+/// assert!(limit.within(&RateLimit::new(50, 100)));
+/// assert!(!limit.within(&RateLimit::new(5, 100)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Number of actions a client regains per second.
+    pub actions_per_sec: u32,
+    /// Maximum number of actions a client may send at once.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Creates a new rate limit.
+    pub fn new(actions_per_sec: u32, burst: u32) -> Self {
+        RateLimit {
+            actions_per_sec,
+            burst,
+        }
+    }
+
+    /// Returns `true` if this limit is not more permissive than `max`.
+    pub fn within(&self, max: &RateLimit) -> bool {
+        self.actions_per_sec <= max.actions_per_sec && self.burst <= max.burst
+    }
+}
+
+/// Token bucket tracking the actions available to a single client.
+///
+/// The bucket starts full on first use and is refilled lazily, so the limit
+/// it is checked against may change between calls.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TokenBucket {
+    tokens: f64,
+    last_refill: Option<Instant>,
+}
+
+impl TokenBucket {
+    /// Takes a token from the bucket, returning `false` if the limit is exhausted.
+    pub fn try_acquire(&mut self, limit: &RateLimit) -> bool {
+        let now = Instant::now();
+        let burst = limit.burst as f64;
+        self.tokens = match self.last_refill {
+            None => burst,
+            Some(last_refill) => {
+                let refill =
+                    now.duration_since(last_refill).as_secs_f64() * limit.actions_per_sec as f64;
+                (self.tokens + refill).min(burst)
+            }
+        };
+        self.last_refill = Some(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,28 @@
+#[cfg(test)]
+mod tests {
+    use crate::rate_limit::{RateLimit, TokenBucket};
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limit_within() {
+        let max = RateLimit::new(10, 20);
+        assert!(RateLimit::new(10, 20).within(&max));
+        assert!(RateLimit::new(1, 1).within(&max));
+        assert!(!RateLimit::new(11, 20).within(&max));
+        assert!(!RateLimit::new(10, 21).within(&max));
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket_burst_and_refill() {
+        let limit = RateLimit::new(100, 3);
+        let mut bucket = TokenBucket::default();
+
+        assert!(bucket.try_acquire(&limit));
+        assert!(bucket.try_acquire(&limit));
+        assert!(bucket.try_acquire(&limit));
+        assert!(!bucket.try_acquire(&limit));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(bucket.try_acquire(&limit));
+    }
+}
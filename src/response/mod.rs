@@ -1,12 +1,16 @@
 /// This module contains the response types used in the injoint library.
 mod test;
 
+use crate::message::SCHEMA_VERSION;
+use crate::rate_limit::RateLimit;
 use serde::de::{self, MapAccess, Visitor};
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::Debug;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Enum representing the status of a response, available in joint.
 ///
@@ -19,7 +23,7 @@ use std::fmt::Debug;
 /// let status = ResponseStatus::RoomCreated;
 /// assert_eq!(format!("{:?}", status), "RoomCreated");
 /// ```
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ResponseStatus {
     /// Indicates that a room has been created successfully. Per-room response.
     RoomCreated,
@@ -31,12 +35,115 @@ pub enum ResponseStatus {
     Action,
     /// Indicates that a client has left the room. Per-room response.
     RoomLeft,
+    /// Indicates that a room has been deleted. Sent to every former member.
+    RoomDeleted,
+    /// Indicates that a room is being drained before maintenance and rejects actions.
+    /// Broadcast to the room when draining starts, and per-client on rejected actions.
+    RoomDraining,
     /// Indicates a server error occurred. Per-client response.
     ServerError,
     /// Indicates a client error occurred. Per-client response.
     ClientError,
     /// Indicates that a room was not found. Per-client response.
     NotFound,
+    /// Confirms the protocol version negotiated with `Hello`. Per-client response.
+    Hello,
+    /// Carries a client's cursor position to the other members of its room. Per-room response.
+    Cursor,
+    /// Indicates that a room is at capacity and cannot be joined. Per-client response.
+    RoomFull,
+    /// Indicates that a wrong password was given for a private room. Per-client response.
+    WrongPassword,
+    /// Indicates that the client is banned from a room. Per-client response.
+    Banned,
+    /// Indicates that a room can only be joined by invited clients. Per-client response.
+    InviteRequired,
+    /// Lists the existing rooms. Per-client response.
+    RoomList,
+    /// Lists the clients in the sender's room. Per-client response.
+    Presence,
+    /// Application-level no-op keeping idle connections alive. Per-client response.
+    Keepalive,
+    /// Carries a payload sent directly by another client of the room. Per-client response.
+    DirectMessage,
+    /// Indicates that a client was muted by the room owner. Per-room response,
+    /// and per-client on actions rejected because the sender is muted.
+    Muted,
+    /// Indicates that a client was unmuted by the room owner. Per-room response.
+    Unmuted,
+    /// Indicates that the client was put on the waitlist of a full room. Per-client response.
+    Waitlisted,
+    /// Indicates that an action was shed because the server is overloaded. Per-client response.
+    Overloaded,
+    /// Carries the ID assigned to the client on connect. Per-client response.
+    Connected,
+    /// Carries the new configuration of a room changed by its owner. Per-room response.
+    RoomConfigChanged,
+    /// Describes the session of the client, answering `WhoAmI`. Per-client response.
+    SessionInfo,
+    /// Summarizes a room for overview subscribers. Overview response.
+    RoomSummary,
+    /// Indicates that an action was based on a stale state version. Per-client response.
+    Conflict,
+    /// Carries the token to resume the client's session with. Per-client response.
+    SessionToken,
+}
+
+/// Machine-readable reason of an error response, sent as its `code` field
+/// alongside the human-readable message.
+///
+/// # example
+/// ```
+/// use injoint::response::{ErrorCode, Response};
+///
+/// let response = Response::NotFound {
+///     code: ErrorCode::RoomNotFound,
+///     message: "Room not found".to_string(),
+/// };
+/// let json = serde_json::to_string(&response).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"{"status":"NotFound","message":"Room not found","code":"RoomNotFound","v":1}"#
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ErrorCode {
+    /// The action could not be parsed, or was rejected by the reducer or a pre-dispatch hook.
+    InvalidAction,
+    /// The client is not allowed to perform the request, e.g. a room owner only operation,
+    /// or its connection token was rejected.
+    Unauthorized,
+    /// The client, or the client it targets, is not a member of the room.
+    NotInRoom,
+    /// The client must leave its current room first.
+    AlreadyInRoom,
+    /// The room is at capacity.
+    RoomFull,
+    /// The client is unknown to the joint.
+    ClientNotFound,
+    /// The room does not exist.
+    RoomNotFound,
+    /// The session to resume does not exist or has expired.
+    SessionNotFound,
+    /// The client sent actions faster than the rate limit of its room.
+    RateLimited,
+    /// The requested room configuration is invalid.
+    InvalidConfig,
+    /// The client must acknowledge the initial state of the room first.
+    StateNotAcknowledged,
+    /// The request is not valid in the current state of the client or room.
+    InvalidRequest,
+    /// The message was sent in an unsupported schema version.
+    UnsupportedVersion,
+    /// The action made the state or a response exceed a configured size limit.
+    LimitExceeded,
+    /// The server failed to process the request.
+    Internal,
+    /// No projection of room states is registered under the requested name.
+    ProjectionNotFound,
+    /// A code unknown to this version of the library, or missing from the response.
+    #[serde(other)]
+    Unknown,
 }
 
 /// Enum representing the response sent back to the client, available in joint.
@@ -51,27 +158,200 @@ pub enum ResponseStatus {
 ///
 /// let response = Response::RoomCreated(123);
 /// let json = serde_json::to_string(&response).unwrap();
-/// assert_eq!(json, r#"{"status":"RoomCreated","message":123}"#);
+/// assert_eq!(json, r#"{"status":"RoomCreated","message":123,"v":1}"#);
 /// ```
 ///
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Response {
     /// Indicates that a room has been created successfully. Per-room response.
     RoomCreated(u64),
     /// Indicates that a client has joined a room successfully. Per-room response.
     RoomJoined(u64),
-    /// Each new user in room receives a state object individually when they join. Per-client response.
-    StateSent(String),
-    /// Indicates that an action has been sent to the room. Per-room response.
-    Action(String), // maybe this should be a generic type that serializable?
+    /// Each new user in room receives a state object individually when they join, stamped with
+    /// the room's sequence if enabled. Per-client response.
+    StateSent(String, Option<Stamp>),
+    /// Indicates that an action has been sent to the room, stamped with the room's sequence
+    /// if enabled. Per-room response.
+    Action(String, Option<Stamp>), // maybe this should be a generic type that serializable?
     /// Indicates that a client has left the room. Per-room response.
     RoomLeft(u64),
+    /// Indicates that a room has been deleted. Sent to every former member.
+    RoomDeleted(u64),
+    /// Indicates that a room is being drained before maintenance and rejects actions.
+    /// Broadcast to the room when draining starts, and per-client on rejected actions.
+    RoomDraining(String),
     /// Indicates a server error occurred. Per-client response.
-    ServerError(String),
+    ServerError { code: ErrorCode, message: String },
     /// Indicates a client error occurred. Per-client response.
-    ClientError(String),
+    ClientError { code: ErrorCode, message: String },
     /// Indicates that a room was not found. Per-client response.
-    NotFound(String),
+    NotFound { code: ErrorCode, message: String },
+    /// Confirms the protocol version negotiated with `Hello`. Per-client response.
+    Hello(u32),
+    /// Carries a client's cursor position to the other members of its room. Per-room response.
+    Cursor { client_id: u64, data: Value },
+    /// Indicates that a room is at capacity and cannot be joined. Per-client response.
+    RoomFull(u64),
+    /// Indicates that a wrong password was given for a private room. Per-client response.
+    WrongPassword(u64),
+    /// Indicates that the client is banned from a room. Per-client response.
+    Banned(u64),
+    /// Indicates that a room can only be joined by invited clients. Per-client response.
+    InviteRequired(u64),
+    /// Lists the existing rooms. Per-client response.
+    RoomList(Vec<RoomInfo>),
+    /// Lists the clients in the sender's room. Per-client response.
+    Presence(Vec<ClientInfo>),
+    /// Application-level no-op keeping idle connections alive, to be ignored by clients. Per-client response.
+    Keepalive,
+    /// Carries a payload sent directly by another client of the room. Per-client response.
+    DirectMessage { from: u64, payload: String },
+    /// Indicates that a client was muted by the room owner. Per-room response,
+    /// and per-client on actions rejected because the sender is muted.
+    Muted(u64),
+    /// Indicates that a client was unmuted by the room owner. Per-room response.
+    Unmuted(u64),
+    /// Indicates that the client was put on the waitlist of a full room, at the given 1-based
+    /// position. The client joins the room once a slot frees up. Per-client response.
+    Waitlisted { position: usize },
+    /// Indicates that an action was shed because the server is overloaded, and may be retried
+    /// later. Per-client response.
+    Overloaded,
+    /// Carries the ID assigned to the client, sent once on connect before any other response.
+    /// Per-client response.
+    Connected(u64),
+    /// Carries the new configuration of a room changed by its owner. Per-room response.
+    RoomConfigChanged(RoomConfigInfo),
+    /// Describes the session of the client, answering `WhoAmI`: its ID, current room, label
+    /// and roles in the room (`member`, `owner`, `muted`). Per-client response.
+    SessionInfo {
+        client_id: u64,
+        room_id: Option<u64>,
+        label: String,
+        roles: Vec<String>,
+    },
+    /// Summarizes a room that was created or whose members changed, sent to overview
+    /// subscribers rather than room members. Overview response.
+    RoomSummary(RoomSummary),
+    /// Indicates that an action was rejected because it was based on another state version
+    /// than the current one of the room, carried to rebase the action on. Per-client response.
+    Conflict { current_version: u64 },
+    /// Carries the token issued to the client to resume its session with after reconnecting,
+    /// sent on connect when sessions are enabled. Per-client response.
+    SessionToken(String),
+}
+
+impl Response {
+    /// Returns the status of the response, sent as its `status` field.
+    pub fn status(&self) -> ResponseStatus {
+        match self {
+            Response::RoomCreated(_) => ResponseStatus::RoomCreated,
+            Response::RoomJoined(_) => ResponseStatus::RoomJoined,
+            Response::StateSent(..) => ResponseStatus::StateSent,
+            Response::Action(..) => ResponseStatus::Action,
+            Response::RoomLeft(_) => ResponseStatus::RoomLeft,
+            Response::RoomDeleted(_) => ResponseStatus::RoomDeleted,
+            Response::RoomDraining(_) => ResponseStatus::RoomDraining,
+            Response::ServerError { .. } => ResponseStatus::ServerError,
+            Response::ClientError { .. } => ResponseStatus::ClientError,
+            Response::NotFound { .. } => ResponseStatus::NotFound,
+            Response::Hello(_) => ResponseStatus::Hello,
+            Response::Cursor { .. } => ResponseStatus::Cursor,
+            Response::RoomFull(_) => ResponseStatus::RoomFull,
+            Response::WrongPassword(_) => ResponseStatus::WrongPassword,
+            Response::Banned(_) => ResponseStatus::Banned,
+            Response::InviteRequired(_) => ResponseStatus::InviteRequired,
+            Response::RoomList(_) => ResponseStatus::RoomList,
+            Response::Presence(_) => ResponseStatus::Presence,
+            Response::Keepalive => ResponseStatus::Keepalive,
+            Response::DirectMessage { .. } => ResponseStatus::DirectMessage,
+            Response::Muted(_) => ResponseStatus::Muted,
+            Response::Unmuted(_) => ResponseStatus::Unmuted,
+            Response::Waitlisted { .. } => ResponseStatus::Waitlisted,
+            Response::Overloaded => ResponseStatus::Overloaded,
+            Response::Connected(_) => ResponseStatus::Connected,
+            Response::RoomConfigChanged(_) => ResponseStatus::RoomConfigChanged,
+            Response::SessionInfo { .. } => ResponseStatus::SessionInfo,
+            Response::RoomSummary(_) => ResponseStatus::RoomSummary,
+            Response::Conflict { .. } => ResponseStatus::Conflict,
+            Response::SessionToken(_) => ResponseStatus::SessionToken,
+        }
+    }
+}
+
+/// Position of a state or action broadcast in the sequence of its room, sent as its `seq`
+/// and `timestamp` fields.
+///
+/// The sequence number is the state version of the room, increasing by one with each applied
+/// action, so clients can detect dropped or reordered broadcasts and measure their latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    /// The state version of the room, that is the number of actions applied to it.
+    pub seq: u64,
+    /// The UNIX time in milliseconds the server applied the action or sent the state at.
+    pub timestamp: u64,
+}
+
+impl Stamp {
+    /// Stamps the given state version with the current time.
+    pub fn now(seq: u64) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_millis() as u64);
+        Stamp { seq, timestamp }
+    }
+}
+
+/// Public description of a client, listed in `Presence` responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientInfo {
+    /// The ID of the client.
+    pub id: u64,
+    /// The label (username) of the client.
+    pub label: String,
+}
+
+/// Public description of a room, listed in `RoomList` responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomInfo {
+    /// The ID of the room.
+    pub id: u64,
+    /// The ID of the owner of the room.
+    pub owner_id: u64,
+    /// The number of clients currently in the room.
+    pub client_count: usize,
+    /// Whether joining the room requires a password.
+    pub private: bool,
+}
+
+/// Compact description of a room, sent to overview subscribers in `RoomSummary` responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomSummary {
+    /// The ID of the room.
+    pub id: u64,
+    /// The ID of the owner of the room.
+    pub owner_id: u64,
+    /// The display name of the room, if any.
+    pub name: Option<String>,
+    /// The number of clients currently in the room.
+    pub client_count: usize,
+    /// The UNIX time in milliseconds of the last broadcast to the room.
+    pub last_activity: u64,
+}
+
+/// Public description of a room configuration, broadcast in `RoomConfigChanged` responses.
+///
+/// The password of private rooms is not disclosed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomConfigInfo {
+    /// The display name of the room, if any.
+    pub name: Option<String>,
+    /// The maximum number of clients in the room, unlimited if `None`.
+    pub capacity: Option<usize>,
+    /// The action rate limit overriding the joint default in this room, if any.
+    pub rate_limit: Option<RateLimit>,
+    /// Whether joining the room requires a password.
+    pub private: bool,
 }
 
 const ROOM_STR: &str = "room";
@@ -81,12 +361,34 @@ const STATUS_STR: &str = "status";
 const MESSAGE_STR: &str = "message";
 const CLIENT_STR: &str = "client";
 const ERROR_STR: &str = "error";
+const CLIENT_ID_STR: &str = "client_id";
+const DATA_STR: &str = "data";
+const FROM_STR: &str = "from";
+const POSITION_STR: &str = "position";
+const VERSION_STR: &str = "v";
+const ROOM_ID_STR: &str = "room_id";
+const LABEL_STR: &str = "label";
+const ROLES_STR: &str = "roles";
+const CURRENT_VERSION_STR: &str = "current_version";
+const CODE_STR: &str = "code";
+const SEQ_STR: &str = "seq";
+const TIMESTAMP_STR: &str = "timestamp";
+
 impl serde::ser::Serialize for Response {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
-        let mut s = serializer.serialize_struct(RESPONSE_STR, 2)?;
+        // binary formats write the number of fields upfront, error responses carry a code
+        // and stamped broadcasts their stamp
+        let fields = 3 + match self {
+            Response::ServerError { .. }
+            | Response::ClientError { .. }
+            | Response::NotFound { .. } => 1,
+            Response::StateSent(_, Some(_)) | Response::Action(_, Some(_)) => 2,
+            _ => 0,
+        };
+        let mut s = serializer.serialize_struct(RESPONSE_STR, fields)?;
         match self {
             Response::RoomCreated(room_id) => {
                 s.serialize_field(STATUS_STR, &ResponseStatus::RoomCreated)?;
@@ -96,41 +398,142 @@ impl serde::ser::Serialize for Response {
                 s.serialize_field(STATUS_STR, &ResponseStatus::RoomJoined)?;
                 s.serialize_field(MESSAGE_STR, client_id)?;
             }
-            Response::StateSent(payload) => {
-                s.serialize_field(STATUS_STR, &ResponseStatus::StateSent)?;
+            Response::StateSent(payload, stamp) | Response::Action(payload, stamp) => {
+                s.serialize_field(STATUS_STR, &self.status())?;
                 match serde_json::from_str::<Value>(payload) {
                     Ok(json_value) => s.serialize_field("message", &json_value)?,
                     Err(_) => {
                         s.serialize_field("message", payload)?;
                     }
                 }
-            }
-            Response::Action(payload) => {
-                s.serialize_field(STATUS_STR, &ResponseStatus::Action)?;
-                match serde_json::from_str::<Value>(payload) {
-                    Ok(json_value) => s.serialize_field("message", &json_value)?,
-                    Err(_) => {
-                        s.serialize_field("message", payload)?;
-                    }
+                if let Some(stamp) = stamp {
+                    s.serialize_field(SEQ_STR, &stamp.seq)?;
+                    s.serialize_field(TIMESTAMP_STR, &stamp.timestamp)?;
                 }
             }
             Response::RoomLeft(client_id) => {
                 s.serialize_field(STATUS_STR, &ResponseStatus::RoomLeft)?;
                 s.serialize_field(MESSAGE_STR, client_id)?;
             }
-            Response::ServerError(message) => {
-                s.serialize_field(STATUS_STR, &ResponseStatus::ServerError)?;
-                s.serialize_field(MESSAGE_STR, message)?;
+            Response::RoomDeleted(room_id) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::RoomDeleted)?;
+                s.serialize_field(MESSAGE_STR, room_id)?;
             }
-            Response::ClientError(message) => {
-                s.serialize_field(STATUS_STR, &ResponseStatus::ClientError)?;
-                s.serialize_field(MESSAGE_STR, message)?;
+            Response::RoomDraining(reason) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::RoomDraining)?;
+                s.serialize_field(MESSAGE_STR, reason)?;
+            }
+            Response::SessionToken(token) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::SessionToken)?;
+                s.serialize_field(MESSAGE_STR, token)?;
             }
-            Response::NotFound(message) => {
-                s.serialize_field(STATUS_STR, &ResponseStatus::NotFound)?;
+            Response::ServerError { code, message }
+            | Response::ClientError { code, message }
+            | Response::NotFound { code, message } => {
+                s.serialize_field(STATUS_STR, &self.status())?;
                 s.serialize_field(MESSAGE_STR, message)?;
+                s.serialize_field(CODE_STR, code)?;
+            }
+            Response::Hello(version) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Hello)?;
+                s.serialize_field(MESSAGE_STR, version)?;
+            }
+            Response::Cursor { client_id, data } => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Cursor)?;
+                s.serialize_field(
+                    MESSAGE_STR,
+                    &serde_json::json!({ CLIENT_ID_STR: client_id, DATA_STR: data }),
+                )?;
+            }
+            Response::RoomFull(room_id) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::RoomFull)?;
+                s.serialize_field(MESSAGE_STR, room_id)?;
+            }
+            Response::WrongPassword(room_id) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::WrongPassword)?;
+                s.serialize_field(MESSAGE_STR, room_id)?;
+            }
+            Response::Banned(room_id) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Banned)?;
+                s.serialize_field(MESSAGE_STR, room_id)?;
+            }
+            Response::InviteRequired(room_id) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::InviteRequired)?;
+                s.serialize_field(MESSAGE_STR, room_id)?;
+            }
+            Response::Muted(client_id) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Muted)?;
+                s.serialize_field(MESSAGE_STR, client_id)?;
+            }
+            Response::Unmuted(client_id) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Unmuted)?;
+                s.serialize_field(MESSAGE_STR, client_id)?;
+            }
+            Response::Waitlisted { position } => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Waitlisted)?;
+                s.serialize_field(MESSAGE_STR, &serde_json::json!({ POSITION_STR: position }))?;
+            }
+            Response::Conflict { current_version } => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Conflict)?;
+                s.serialize_field(
+                    MESSAGE_STR,
+                    &serde_json::json!({ CURRENT_VERSION_STR: current_version }),
+                )?;
+            }
+            Response::RoomList(rooms) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::RoomList)?;
+                s.serialize_field(MESSAGE_STR, rooms)?;
+            }
+            Response::Presence(clients) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Presence)?;
+                s.serialize_field(MESSAGE_STR, clients)?;
+            }
+            Response::Keepalive => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Keepalive)?;
+                s.serialize_field(MESSAGE_STR, &Value::Null)?;
+            }
+            Response::Overloaded => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Overloaded)?;
+                s.serialize_field(MESSAGE_STR, &Value::Null)?;
+            }
+            Response::Connected(client_id) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::Connected)?;
+                s.serialize_field(MESSAGE_STR, client_id)?;
+            }
+            Response::RoomConfigChanged(config) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::RoomConfigChanged)?;
+                s.serialize_field(MESSAGE_STR, config)?;
+            }
+            Response::RoomSummary(summary) => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::RoomSummary)?;
+                s.serialize_field(MESSAGE_STR, summary)?;
+            }
+            Response::SessionInfo {
+                client_id,
+                room_id,
+                label,
+                roles,
+            } => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::SessionInfo)?;
+                s.serialize_field(
+                    MESSAGE_STR,
+                    &serde_json::json!({
+                        CLIENT_ID_STR: client_id,
+                        ROOM_ID_STR: room_id,
+                        LABEL_STR: label,
+                        ROLES_STR: roles,
+                    }),
+                )?;
+            }
+            Response::DirectMessage { from, payload } => {
+                s.serialize_field(STATUS_STR, &ResponseStatus::DirectMessage)?;
+                s.serialize_field(
+                    MESSAGE_STR,
+                    &serde_json::json!({ FROM_STR: from, PAYLOAD_STR: payload }),
+                )?;
             }
         }
+        s.serialize_field(VERSION_STR, &SCHEMA_VERSION)?;
         s.end()
     }
 }
@@ -143,6 +546,10 @@ impl<'de> Deserialize<'de> for Response {
         enum Field {
             Status,
             Message,
+            Code,
+            Seq,
+            Timestamp,
+            Version,
         }
 
         impl<'de> Deserialize<'de> for Field {
@@ -166,6 +573,10 @@ impl<'de> Deserialize<'de> for Response {
                         match value {
                             STATUS_STR => Ok(Field::Status),
                             MESSAGE_STR => Ok(Field::Message),
+                            CODE_STR => Ok(Field::Code),
+                            SEQ_STR => Ok(Field::Seq),
+                            TIMESTAMP_STR => Ok(Field::Timestamp),
+                            VERSION_STR => Ok(Field::Version),
                             _ => Err(de::Error::unknown_field(value, FIELDS)),
                         }
                     }
@@ -191,6 +602,9 @@ impl<'de> Deserialize<'de> for Response {
                 let mut status: Option<ResponseStatus> = None;
                 // Use Value initially for message to handle different types
                 let mut message_value: Option<Value> = None;
+                let mut code: Option<ErrorCode> = None;
+                let mut seq: Option<u64> = None;
+                let mut timestamp: Option<u64> = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -208,10 +622,38 @@ impl<'de> Deserialize<'de> for Response {
                             // Deserialize message as a generic Value first
                             message_value = Some(map.next_value()?);
                         }
+                        Field::Code => {
+                            if code.is_some() {
+                                return Err(de::Error::duplicate_field(CODE_STR));
+                            }
+                            code = Some(map.next_value()?);
+                        }
+                        Field::Seq => {
+                            if seq.is_some() {
+                                return Err(de::Error::duplicate_field(SEQ_STR));
+                            }
+                            seq = Some(map.next_value()?);
+                        }
+                        Field::Timestamp => {
+                            if timestamp.is_some() {
+                                return Err(de::Error::duplicate_field(TIMESTAMP_STR));
+                            }
+                            timestamp = Some(map.next_value()?);
+                        }
+                        Field::Version => {
+                            // responses are understood regardless of the schema version they carry
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
                     }
                 }
 
                 let status = status.ok_or_else(|| de::Error::missing_field(STATUS_STR))?;
+                let stamp = match (seq, timestamp) {
+                    (Some(seq), Some(timestamp)) => Some(Stamp { seq, timestamp }),
+                    (None, None) => None,
+                    (None, Some(_)) => return Err(de::Error::missing_field(SEQ_STR)),
+                    (Some(_), None) => return Err(de::Error::missing_field(TIMESTAMP_STR)),
+                };
                 let message_value =
                     message_value.ok_or_else(|| de::Error::missing_field(MESSAGE_STR))?;
 
@@ -219,7 +661,15 @@ impl<'de> Deserialize<'de> for Response {
                 match status {
                     ResponseStatus::RoomCreated
                     | ResponseStatus::RoomJoined
-                    | ResponseStatus::RoomLeft => {
+                    | ResponseStatus::RoomLeft
+                    | ResponseStatus::RoomDeleted
+                    | ResponseStatus::RoomFull
+                    | ResponseStatus::WrongPassword
+                    | ResponseStatus::Banned
+                    | ResponseStatus::InviteRequired
+                    | ResponseStatus::Muted
+                    | ResponseStatus::Unmuted
+                    | ResponseStatus::Connected => {
                         let id = message_value.as_u64().ok_or_else(|| {
                             de::Error::invalid_type(
                                 de::Unexpected::Other("non-u64 value"),
@@ -230,6 +680,14 @@ impl<'de> Deserialize<'de> for Response {
                             ResponseStatus::RoomCreated => Ok(Response::RoomCreated(id)),
                             ResponseStatus::RoomJoined => Ok(Response::RoomJoined(id)),
                             ResponseStatus::RoomLeft => Ok(Response::RoomLeft(id)),
+                            ResponseStatus::RoomDeleted => Ok(Response::RoomDeleted(id)),
+                            ResponseStatus::RoomFull => Ok(Response::RoomFull(id)),
+                            ResponseStatus::WrongPassword => Ok(Response::WrongPassword(id)),
+                            ResponseStatus::Banned => Ok(Response::Banned(id)),
+                            ResponseStatus::InviteRequired => Ok(Response::InviteRequired(id)),
+                            ResponseStatus::Muted => Ok(Response::Muted(id)),
+                            ResponseStatus::Unmuted => Ok(Response::Unmuted(id)),
+                            ResponseStatus::Connected => Ok(Response::Connected(id)),
                             _ => unreachable!(), // Should not happen due to outer match
                         }
                     }
@@ -245,14 +703,20 @@ impl<'de> Deserialize<'de> for Response {
                         };
 
                         match status {
-                            ResponseStatus::StateSent => Ok(Response::StateSent(payload_str)),
-                            ResponseStatus::Action => Ok(Response::Action(payload_str)),
+                            ResponseStatus::StateSent => {
+                                Ok(Response::StateSent(payload_str, stamp))
+                            }
+                            ResponseStatus::Action => Ok(Response::Action(payload_str, stamp)),
                             _ => unreachable!(),
                         }
                     }
                     ResponseStatus::ServerError
                     | ResponseStatus::ClientError
-                    | ResponseStatus::NotFound => {
+                    | ResponseStatus::NotFound
+                    | ResponseStatus::RoomDraining
+                    | ResponseStatus::SessionToken => {
+                        // errors of peers predating codes are still understood
+                        let code = code.unwrap_or(ErrorCode::Unknown);
                         let msg = message_value
                             .as_str()
                             .ok_or_else(|| {
@@ -263,17 +727,129 @@ impl<'de> Deserialize<'de> for Response {
                             })?
                             .to_string();
                         match status {
-                            ResponseStatus::ServerError => Ok(Response::ServerError(msg)),
-                            ResponseStatus::ClientError => Ok(Response::ClientError(msg)),
-                            ResponseStatus::NotFound => Ok(Response::NotFound(msg)),
+                            ResponseStatus::ServerError => {
+                                Ok(Response::ServerError { code, message: msg })
+                            }
+                            ResponseStatus::ClientError => {
+                                Ok(Response::ClientError { code, message: msg })
+                            }
+                            ResponseStatus::NotFound => {
+                                Ok(Response::NotFound { code, message: msg })
+                            }
+                            ResponseStatus::RoomDraining => Ok(Response::RoomDraining(msg)),
+                            ResponseStatus::SessionToken => Ok(Response::SessionToken(msg)),
                             _ => unreachable!(),
                         }
                     }
+                    ResponseStatus::Hello => {
+                        let version = message_value
+                            .as_u64()
+                            .and_then(|version| u32::try_from(version).ok())
+                            .ok_or_else(|| {
+                                de::Error::invalid_type(
+                                    de::Unexpected::Other("non-u32 value"),
+                                    &"an unsigned 32-bit integer",
+                                )
+                            })?;
+                        Ok(Response::Hello(version))
+                    }
+                    ResponseStatus::Cursor => {
+                        let client_id = message_value
+                            .get(CLIENT_ID_STR)
+                            .and_then(Value::as_u64)
+                            .ok_or_else(|| de::Error::missing_field(CLIENT_ID_STR))?;
+                        let data = message_value
+                            .get(DATA_STR)
+                            .cloned()
+                            .ok_or_else(|| de::Error::missing_field(DATA_STR))?;
+                        Ok(Response::Cursor { client_id, data })
+                    }
+                    ResponseStatus::RoomList => {
+                        let rooms = Vec::<RoomInfo>::deserialize(message_value)
+                            .map_err(de::Error::custom)?;
+                        Ok(Response::RoomList(rooms))
+                    }
+                    ResponseStatus::Presence => {
+                        let clients = Vec::<ClientInfo>::deserialize(message_value)
+                            .map_err(de::Error::custom)?;
+                        Ok(Response::Presence(clients))
+                    }
+                    ResponseStatus::RoomConfigChanged => {
+                        let config = RoomConfigInfo::deserialize(message_value)
+                            .map_err(de::Error::custom)?;
+                        Ok(Response::RoomConfigChanged(config))
+                    }
+                    ResponseStatus::RoomSummary => {
+                        let summary =
+                            RoomSummary::deserialize(message_value).map_err(de::Error::custom)?;
+                        Ok(Response::RoomSummary(summary))
+                    }
+                    ResponseStatus::Keepalive => Ok(Response::Keepalive),
+                    ResponseStatus::Overloaded => Ok(Response::Overloaded),
+                    ResponseStatus::Waitlisted => {
+                        let position = message_value
+                            .get(POSITION_STR)
+                            .and_then(Value::as_u64)
+                            .ok_or_else(|| de::Error::missing_field(POSITION_STR))?;
+                        Ok(Response::Waitlisted {
+                            position: position as usize,
+                        })
+                    }
+                    ResponseStatus::Conflict => {
+                        let current_version = message_value
+                            .get(CURRENT_VERSION_STR)
+                            .and_then(Value::as_u64)
+                            .ok_or_else(|| de::Error::missing_field(CURRENT_VERSION_STR))?;
+                        Ok(Response::Conflict { current_version })
+                    }
+                    ResponseStatus::DirectMessage => {
+                        let from = message_value
+                            .get(FROM_STR)
+                            .and_then(Value::as_u64)
+                            .ok_or_else(|| de::Error::missing_field(FROM_STR))?;
+                        let payload = message_value
+                            .get(PAYLOAD_STR)
+                            .and_then(Value::as_str)
+                            .ok_or_else(|| de::Error::missing_field(PAYLOAD_STR))?
+                            .to_string();
+                        Ok(Response::DirectMessage { from, payload })
+                    }
+                    ResponseStatus::SessionInfo => {
+                        let client_id = message_value
+                            .get(CLIENT_ID_STR)
+                            .and_then(Value::as_u64)
+                            .ok_or_else(|| de::Error::missing_field(CLIENT_ID_STR))?;
+                        let room_id = message_value.get(ROOM_ID_STR).and_then(Value::as_u64);
+                        let label = message_value
+                            .get(LABEL_STR)
+                            .and_then(Value::as_str)
+                            .ok_or_else(|| de::Error::missing_field(LABEL_STR))?
+                            .to_string();
+                        let roles = message_value
+                            .get(ROLES_STR)
+                            .cloned()
+                            .ok_or_else(|| de::Error::missing_field(ROLES_STR))?;
+                        let roles = Vec::<String>::deserialize(roles).map_err(de::Error::custom)?;
+                        Ok(Response::SessionInfo {
+                            client_id,
+                            room_id,
+                            label,
+                            roles,
+                        })
+                    }
                 }
             }
         }
 
-        const FIELDS: &'static [&'static str] = &[STATUS_STR, MESSAGE_STR];
+        #[allow(clippy::redundant_static_lifetimes)]
+        const FIELDS: &'static [&'static str] = &[
+            STATUS_STR,
+            MESSAGE_STR,
+            CODE_STR,
+            SEQ_STR,
+            TIMESTAMP_STR,
+            VERSION_STR,
+        ];
         deserializer.deserialize_struct(RESPONSE_STR, FIELDS, ResponseVisitor)
     }
 }
@@ -285,13 +861,30 @@ impl<'de> Deserialize<'de> for Response {
 pub(crate) struct RoomResponse {
     pub room: u64,
     pub response: Response,
+    /// Room members excluded from the broadcast, e.g. the sender of the message
+    /// or the members a reducer hides an action from.
+    pub skip_clients: HashSet<u64>,
+    /// The response rendered through each projection, sent instead of `response`
+    /// to the members that joined with the projection.
+    pub projected: HashMap<String, Response>,
 }
 
 impl RoomResponse {
+    pub fn new(room: u64, response: Response) -> Self {
+        RoomResponse {
+            room,
+            response,
+            skip_clients: HashSet::new(),
+            projected: HashMap::new(),
+        }
+    }
+
     pub fn create_room(room: u64) -> Self {
         RoomResponse {
             room,
             response: Response::RoomCreated(room),
+            skip_clients: HashSet::new(),
+            projected: HashMap::new(),
         }
     }
 
@@ -299,13 +892,17 @@ impl RoomResponse {
         RoomResponse {
             room,
             response: Response::RoomJoined(client),
+            skip_clients: HashSet::new(),
+            projected: HashMap::new(),
         }
     }
 
     pub fn action(room: u64, payload: String) -> Self {
         RoomResponse {
             room,
-            response: Response::Action(payload),
+            response: Response::Action(payload, None),
+            skip_clients: HashSet::new(),
+            projected: HashMap::new(),
         }
     }
 
@@ -313,6 +910,42 @@ impl RoomResponse {
         RoomResponse {
             room,
             response: Response::RoomLeft(client),
+            skip_clients: HashSet::new(),
+            projected: HashMap::new(),
+        }
+    }
+
+    pub fn mute(room: u64, client: u64, muted: bool) -> Self {
+        RoomResponse {
+            room,
+            response: if muted {
+                Response::Muted(client)
+            } else {
+                Response::Unmuted(client)
+            },
+            skip_clients: HashSet::new(),
+            projected: HashMap::new(),
+        }
+    }
+
+    pub fn config_changed(room: u64, config: RoomConfigInfo) -> Self {
+        RoomResponse {
+            room,
+            response: Response::RoomConfigChanged(config),
+            skip_clients: HashSet::new(),
+            projected: HashMap::new(),
+        }
+    }
+
+    pub fn cursor(room: u64, client: u64, data: Value) -> Self {
+        RoomResponse {
+            room,
+            response: Response::Cursor {
+                client_id: client,
+                data,
+            },
+            skip_clients: HashSet::from([client]),
+            projected: HashMap::new(),
         }
     }
 }
@@ -339,24 +972,28 @@ pub(crate) struct ClientResponse {
 }
 
 impl ClientResponse {
-    pub fn server_error(client: u64, message: String) -> Self {
+    pub fn new(client: u64, response: Response) -> Self {
+        ClientResponse { client, response }
+    }
+
+    pub fn server_error(client: u64, code: ErrorCode, message: String) -> Self {
         ClientResponse {
             client,
-            response: Response::ServerError(message),
+            response: Response::ServerError { code, message },
         }
     }
 
-    pub fn client_error(client: u64, message: String) -> Self {
+    pub fn client_error(client: u64, code: ErrorCode, message: String) -> Self {
         ClientResponse {
             client,
-            response: Response::ClientError(message),
+            response: Response::ClientError { code, message },
         }
     }
 
-    pub fn not_found(client: u64, message: String) -> Self {
+    pub fn not_found(client: u64, code: ErrorCode, message: String) -> Self {
         ClientResponse {
             client,
-            response: Response::NotFound(message),
+            response: Response::NotFound { code, message },
         }
     }
 }
@@ -372,3 +1009,50 @@ impl serde::ser::Serialize for ClientResponse {
         s.end()
     }
 }
+
+/// Struct representing responses sent to an explicit set of clients.
+///
+/// Used when the recipients can't be resolved from a room anymore, e.g. after its deletion.
+#[derive(Debug)]
+pub(crate) struct ClientsResponse {
+    pub clients: Vec<u64>,
+    pub response: Response,
+}
+
+impl ClientsResponse {
+    pub fn delete_room(room: u64, clients: Vec<u64>) -> Self {
+        ClientsResponse {
+            clients,
+            response: Response::RoomDeleted(room),
+        }
+    }
+}
+
+/// Enum representing the successful outcome of a processed event.
+///
+/// Per-room responses are broadcast to every member of the room,
+/// per-client responses are sent only to the addressed client(s).
+#[derive(Debug)]
+pub(crate) enum EventResponse {
+    Room(RoomResponse),
+    Client(ClientResponse),
+    Clients(ClientsResponse),
+}
+
+impl From<RoomResponse> for EventResponse {
+    fn from(response: RoomResponse) -> Self {
+        EventResponse::Room(response)
+    }
+}
+
+impl From<ClientResponse> for EventResponse {
+    fn from(response: ClientResponse) -> Self {
+        EventResponse::Client(response)
+    }
+}
+
+impl From<ClientsResponse> for EventResponse {
+    fn from(response: ClientsResponse) -> Self {
+        EventResponse::Clients(response)
+    }
+}
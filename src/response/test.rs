@@ -1,7 +1,13 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
-    use crate::response::{ClientResponse, Response, RoomResponse};
+    use crate::message::SCHEMA_VERSION;
+    use crate::response::{
+        ClientInfo, ClientResponse, ErrorCode, Response, RoomConfigInfo, RoomInfo, RoomResponse,
+        RoomSummary, Stamp,
+    };
+    #[allow(unused_imports)]
     use serde_json::{json, Value};
 
     #[test]
@@ -12,6 +18,7 @@ mod tests {
             serialized,
             json!({
                 "status": "RoomCreated",
+                "v": 1,
                 "message": 123
             })
         );
@@ -22,39 +29,277 @@ mod tests {
             serialized,
             json!({
                 "status": "RoomJoined",
+                "v": 1,
                 "message": 456
             })
         );
 
         let state_json = r#"{"value": 42, "name": "test"}"#;
-        let response = Response::StateSent(state_json.to_string());
+        let response = Response::StateSent(state_json.to_string(), None);
         let serialized = serde_json::to_value(&response).unwrap();
         assert_eq!(
             serialized,
             json!({
                 "status": "StateSent",
+                "v": 1,
                 "message": {"value": 42, "name": "test"}
             })
         );
 
         let action_json = r#"{"type": "increment", "value": 5}"#;
-        let response = Response::Action(action_json.to_string());
+        let response = Response::Action(action_json.to_string(), None);
         let serialized = serde_json::to_value(&response).unwrap();
         assert_eq!(
             serialized,
             json!({
                 "status": "Action",
+                "v": 1,
                 "message": {"type": "increment", "value": 5}
             })
         );
 
-        let response = Response::ServerError("Server error".to_string());
+        let stamp = Stamp {
+            seq: 7,
+            timestamp: 1_700_000_000_000,
+        };
+        let response = Response::Action(action_json.to_string(), Some(stamp));
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "Action",
+                "v": 1,
+                "message": {"type": "increment", "value": 5},
+                "seq": 7,
+                "timestamp": 1_700_000_000_000u64
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<Response>(serialized).unwrap(),
+            Response::Action(r#"{"type":"increment","value":5}"#.to_string(), Some(stamp))
+        );
+
+        let response = Response::RoomList(vec![RoomInfo {
+            id: 1,
+            owner_id: 4,
+            client_count: 2,
+            private: true,
+        }]);
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "RoomList",
+                "v": 1,
+                "message": [{"id": 1, "owner_id": 4, "client_count": 2, "private": true}]
+            })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert!(
+            matches!(deserialized, Response::RoomList(rooms) if rooms.len() == 1 && rooms[0].private)
+        );
+
+        let response = Response::Presence(vec![ClientInfo {
+            id: 3,
+            label: "alice".to_string(),
+        }]);
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "Presence",
+                "v": 1,
+                "message": [{"id": 3, "label": "alice"}]
+            })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert!(matches!(deserialized, Response::Presence(clients) if clients[0].label == "alice"));
+
+        let response = Response::DirectMessage {
+            from: 2,
+            payload: "psst".to_string(),
+        };
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "DirectMessage",
+                "v": 1,
+                "message": {"from": 2, "payload": "psst"}
+            })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert!(matches!(
+            deserialized,
+            Response::DirectMessage { from: 2, payload } if payload == "psst"
+        ));
+
+        let serialized = serde_json::to_value(Response::Keepalive).unwrap();
+        assert_eq!(
+            serialized,
+            json!({"v": 1, "status": "Keepalive", "message": null})
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert!(matches!(deserialized, Response::Keepalive));
+
+        let serialized = serde_json::to_value(Response::Overloaded).unwrap();
+        assert_eq!(
+            serialized,
+            json!({"v": 1, "status": "Overloaded", "message": null})
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert!(matches!(deserialized, Response::Overloaded));
+
+        let response = Response::Hello(2);
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "Hello",
+                "v": 1,
+                "message": 2
+            })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert!(matches!(deserialized, Response::Hello(2)));
+
+        for (response, status) in [
+            (Response::RoomFull(7), "RoomFull"),
+            (Response::WrongPassword(7), "WrongPassword"),
+            (Response::Banned(7), "Banned"),
+            (Response::InviteRequired(7), "InviteRequired"),
+            (Response::Muted(7), "Muted"),
+            (Response::Unmuted(7), "Unmuted"),
+            (Response::Connected(7), "Connected"),
+        ] {
+            let serialized = serde_json::to_value(&response).unwrap();
+            assert_eq!(
+                serialized,
+                json!({ "v": 1, "status": status, "message": 7 })
+            );
+            let deserialized: Response = serde_json::from_value(serialized).unwrap();
+            assert_eq!(format!("{:?}", deserialized), format!("{:?}", response));
+        }
+
+        let response = Response::SessionToken("a1b2".to_string());
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({ "v": 1, "status": "SessionToken", "message": "a1b2" })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, response);
+
+        let response = Response::Waitlisted { position: 2 };
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({ "v": 1, "status": "Waitlisted", "message": { "position": 2 } })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert!(matches!(deserialized, Response::Waitlisted { position: 2 }));
+
+        let response = Response::Conflict { current_version: 3 };
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({ "v": 1, "status": "Conflict", "message": { "current_version": 3 } })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, response);
+
+        let response = Response::RoomConfigChanged(RoomConfigInfo {
+            name: Some("Lobby".to_string()),
+            capacity: Some(4),
+            rate_limit: None,
+            private: true,
+        });
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "RoomConfigChanged",
+                "v": 1,
+                "message": { "name": "Lobby", "capacity": 4, "rate_limit": null, "private": true }
+            })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, response);
+
+        let response = Response::RoomSummary(RoomSummary {
+            id: 3,
+            owner_id: 7,
+            name: None,
+            client_count: 2,
+            last_activity: 1_700_000_000_000,
+        });
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "RoomSummary",
+                "v": 1,
+                "message": {
+                    "id": 3,
+                    "owner_id": 7,
+                    "name": null,
+                    "client_count": 2,
+                    "last_activity": 1_700_000_000_000u64
+                }
+            })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, response);
+
+        let response = Response::SessionInfo {
+            client_id: 5,
+            room_id: None,
+            label: "ada".to_string(),
+            roles: Vec::new(),
+        };
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "SessionInfo",
+                "v": 1,
+                "message": { "client_id": 5, "room_id": null, "label": "ada", "roles": [] }
+            })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, response);
+
+        let response = Response::Cursor {
+            client_id: 3,
+            data: json!({ "line": 4 }),
+        };
+        let serialized = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            serialized,
+            json!({
+                "status": "Cursor",
+                "v": 1,
+                "message": { "client_id": 3, "data": { "line": 4 } }
+            })
+        );
+        let deserialized: Response = serde_json::from_value(serialized).unwrap();
+        assert!(matches!(
+            deserialized,
+            Response::Cursor { client_id: 3, data } if data == json!({ "line": 4 })
+        ));
+
+        let response = Response::ServerError {
+            code: ErrorCode::Internal,
+            message: "Server error".to_string(),
+        };
         let serialized = serde_json::to_value(&response).unwrap();
         assert_eq!(
             serialized,
             json!({
                 "status": "ServerError",
-                "message": "Server error"
+                "v": 1,
+                "message": "Server error",
+                "code": "Internal"
             })
         );
     }
@@ -77,6 +322,7 @@ mod tests {
                 "room": room_id,
                 "payload": {
                     "status": "RoomCreated",
+                    "v": 1,
                     "message": room_id
                 }
             })
@@ -92,7 +338,7 @@ mod tests {
 
         let payload = r#"{"value": 42}"#.to_string();
         let response = RoomResponse::action(room_id, payload.clone());
-        if let Response::Action(p) = &response.response {
+        if let Response::Action(p, _) = &response.response {
             assert_eq!(p, &payload);
         } else {
             panic!("Expected Action response");
@@ -110,9 +356,11 @@ mod tests {
     fn test_client_response() {
         let client_id = 123;
         let message = "Server error message".to_string();
-        let response = ClientResponse::server_error(client_id, message.clone());
+        let response =
+            ClientResponse::server_error(client_id, ErrorCode::Internal, message.clone());
         assert_eq!(response.client, client_id);
-        if let Response::ServerError(msg) = &response.response {
+        if let Response::ServerError { code, message: msg } = &response.response {
+            assert_eq!(*code, ErrorCode::Internal);
             assert_eq!(msg, &message);
         } else {
             panic!("Expected ServerError response");
@@ -125,25 +373,120 @@ mod tests {
                 "client": client_id,
                 "error": {
                     "status": "ServerError",
-                    "message": message
+                    "v": 1,
+                    "message": message,
+                    "code": "Internal"
                 }
             })
         );
 
         let message = "Client error message".to_string();
-        let response = ClientResponse::client_error(client_id, message.clone());
-        if let Response::ClientError(msg) = &response.response {
+        let response =
+            ClientResponse::client_error(client_id, ErrorCode::InvalidAction, message.clone());
+        if let Response::ClientError { code, message: msg } = &response.response {
+            assert_eq!(*code, ErrorCode::InvalidAction);
             assert_eq!(msg, &message);
         } else {
             panic!("Expected ClientError response");
         }
 
         let message = "Not found message".to_string();
-        let response = ClientResponse::not_found(client_id, message.clone());
-        if let Response::NotFound(msg) = &response.response {
+        let response =
+            ClientResponse::not_found(client_id, ErrorCode::RoomNotFound, message.clone());
+        if let Response::NotFound { code, message: msg } = &response.response {
+            assert_eq!(*code, ErrorCode::RoomNotFound);
             assert_eq!(msg, &message);
         } else {
             panic!("Expected NotFound response");
         }
     }
+
+    #[test]
+    fn test_response_carries_schema_version() {
+        for response in [
+            Response::RoomCreated(1),
+            Response::Keepalive,
+            Response::ClientError {
+                code: ErrorCode::InvalidRequest,
+                message: "bad".to_string(),
+            },
+        ] {
+            let serialized = serde_json::to_value(&response).unwrap();
+            assert_eq!(serialized["v"], json!(SCHEMA_VERSION));
+        }
+
+        // responses of servers predating the tag still deserialize
+        let deserialized: Response =
+            serde_json::from_value(json!({"status": "RoomCreated", "message": 5})).unwrap();
+        assert!(matches!(deserialized, Response::RoomCreated(5)));
+    }
+
+    #[test]
+    fn test_error_codes_round_trip() {
+        let errors = [
+            Response::ClientError {
+                code: ErrorCode::NotInRoom,
+                message: "Client not in room".to_string(),
+            },
+            Response::ClientError {
+                code: ErrorCode::RoomFull,
+                message: "Room is full".to_string(),
+            },
+            Response::ClientError {
+                code: ErrorCode::InvalidAction,
+                message: "Invalid action".to_string(),
+            },
+            Response::ClientError {
+                code: ErrorCode::Unauthorized,
+                message: "Invalid token".to_string(),
+            },
+            Response::ServerError {
+                code: ErrorCode::LimitExceeded,
+                message: "Action data size limit exceeded".to_string(),
+            },
+            Response::NotFound {
+                code: ErrorCode::RoomNotFound,
+                message: "Room not found".to_string(),
+            },
+        ];
+
+        for response in errors {
+            let serialized = serde_json::to_value(&response).unwrap();
+            let (Response::ServerError { code, message }
+            | Response::ClientError { code, message }
+            | Response::NotFound { code, message }) = &response
+            else {
+                unreachable!()
+            };
+            assert_eq!(serialized["code"], json!(code));
+            assert_eq!(serialized["message"], json!(message));
+
+            let deserialized: Response = serde_json::from_value(serialized).unwrap();
+            assert_eq!(deserialized, response);
+        }
+
+        // errors without a code, or with a code unknown to this version, still deserialize
+        let deserialized: Response = serde_json::from_value(
+            json!({"status": "NotFound", "message": "Room not found", "v": 1}),
+        )
+        .unwrap();
+        assert_eq!(
+            deserialized,
+            Response::NotFound {
+                code: ErrorCode::Unknown,
+                message: "Room not found".to_string(),
+            }
+        );
+        let deserialized: Response = serde_json::from_value(
+            json!({"status": "ClientError", "message": "bad", "code": "FromTheFuture", "v": 1}),
+        )
+        .unwrap();
+        assert!(matches!(
+            deserialized,
+            Response::ClientError {
+                code: ErrorCode::Unknown,
+                ..
+            }
+        ));
+    }
 }
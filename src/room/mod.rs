@@ -1,8 +1,23 @@
 #![allow(unused)]
 /// This module defines the `Room` struct and its associated types.
-use std::collections::HashSet;
+mod test;
+
+use crate::broadcaster::RoomActor;
+use crate::codec::Compression;
+use crate::dispatcher::{Dispatchable, RecordedAction};
+use crate::rate_limit::RateLimit;
+use crate::response::{Response, RoomInfo, RoomSummary, Stamp};
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+
+mod shards;
+pub(crate) use shards::{RoomShards, ROOM_SHARDS};
 
 /// A room status can be either public or private.
 pub enum RoomStatus {
@@ -12,12 +27,72 @@ pub enum RoomStatus {
     Private(String),
 }
 
+const VISIBILITY_STR: &str = "visibility";
+const PASSWORD_STR: &str = "password";
+
+/// Visibility of a room as it appears on the wire.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Visibility {
+    Public,
+    Private,
+}
+
+/// Serializes the status as `{ "visibility": "public" }` or `{ "visibility": "private" }`,
+/// never including the password of a private room.
+impl Serialize for RoomStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let visibility = match self {
+            RoomStatus::Public => Visibility::Public,
+            RoomStatus::Private(_) => Visibility::Private,
+        };
+        let mut s = serializer.serialize_struct("RoomStatus", 1)?;
+        s.serialize_field(VISIBILITY_STR, &visibility)?;
+        s.end()
+    }
+}
+
+/// Deserializes the status sent by clients creating a room, where a private room
+/// carries its password: `{ "visibility": "private", "password": "..." }`.
+impl<'de> Deserialize<'de> for RoomStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawStatus {
+            visibility: Visibility,
+            #[serde(default)]
+            password: Option<String>,
+        }
+
+        let raw = RawStatus::deserialize(deserializer)?;
+        match (raw.visibility, raw.password) {
+            (Visibility::Public, _) => Ok(RoomStatus::Public),
+            (Visibility::Private, Some(password)) => Ok(RoomStatus::Private(password)),
+            (Visibility::Private, None) => Err(D::Error::missing_field(PASSWORD_STR)),
+        }
+    }
+}
+
+/// Configuration of the per-room history buffer kept for resuming clients.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct HistoryConfig {
+    /// The number of action broadcasts kept per room, disabled with `0`.
+    pub size: usize,
+    /// The compression applied to buffered broadcasts at rest.
+    pub compression: Compression,
+}
+
 /// A room is a container for clients and their shared state.
 ///
 /// R is the type of the reducer that manages the state of the room.
 /// The reducer is responsible for handling actions and updating the state.
 ///
-pub struct Room<R> {
+pub struct Room<R: Dispatchable> {
     /// The ID of the room.
     pub id: u64,
     /// The ID of the owner of the room.
@@ -28,9 +103,57 @@ pub struct Room<R> {
     pub client_ids: HashSet<u64>,
     /// The reducer that manages the state of the room.
     pub reducer: Arc<Mutex<R>>,
+    /// Held from applying an action outside of the room actor until the action is recorded,
+    /// so actions are versioned in the order they are applied. Taken before the room's bucket.
+    pub(crate) sequencer: Arc<Mutex<()>>,
+    /// Applied actions with their authors, in order of application.
+    pub action_log: Vec<RecordedAction>,
+    /// The reducer the room was preloaded with, kept for replays of the action log when
+    /// actions are recorded. Replays of other rooms start from a new reducer.
+    pub(crate) origin: Option<R>,
+    /// The display name of the room, if any.
+    pub name: Option<String>,
+    /// The maximum number of clients in the room, unlimited if `None`.
+    pub capacity: Option<usize>,
+    /// Whether only invited clients may join the room.
+    pub invite_only: bool,
+    /// The set of client IDs invited to the room.
+    pub invited: HashSet<u64>,
+    /// The set of client IDs banned from the room.
+    pub banned: HashSet<u64>,
+    /// The set of client IDs whose actions are rejected while they keep receiving broadcasts.
+    pub muted: HashSet<u64>,
+    /// Whether clients joining the room at capacity are put on the waitlist instead of refused.
+    pub waitlist_enabled: bool,
+    /// The IDs of the clients waiting for a free slot in the room, in order of arrival.
+    pub waitlist: VecDeque<u64>,
+    /// The group of mutually exclusive rooms the room belongs to, if any.
+    pub room_group: Option<String>,
+    /// The action rate limit overriding the joint default in this room.
+    pub rate_limit: Option<RateLimit>,
+    /// The reason the room is being drained, if it is. Draining rooms reject actions and joins.
+    pub draining: Option<String>,
+    /// The time of the last broadcast to the room, used to detect idle rooms.
+    ///
+    /// Behind its own lock, so broadcasts only need shared access to the rooms.
+    pub last_broadcast: std::sync::Mutex<Instant>,
+    /// The number of actions applied to the room state, changing whenever the state does.
+    pub state_version: u64,
+    /// Broadcast payloads of the last applied actions with their sequence numbers and the stamps
+    /// they were broadcast with, oldest first.
+    ///
+    /// The sequence number of an action is the state version it produced. Payloads are stored
+    /// compressed with the compression of the joint's history config.
+    pub history: VecDeque<(u64, Option<Stamp>, Vec<u8>)>,
+    /// In-process subscribers receiving the state every time it changes.
+    pub(crate) state_subscribers: Vec<mpsc::UnboundedSender<R::State>>,
+    /// The serialized state along with the state version it was serialized at.
+    pub(crate) serialized_state: Option<(u64, String)>,
+    /// The actor applying client actions to the room, once spawned in room actor mode.
+    pub(crate) actor: Option<RoomActor<R>>,
 }
 
-impl<R> Room<R> {
+impl<R: Dispatchable> Room<R> {
     /// Creates a new room with the given ID, owner ID, client IDs, status, and reducer.
     pub fn new(
         id: u64,
@@ -45,6 +168,173 @@ impl<R> Room<R> {
             client_ids,
             status,
             reducer,
+            sequencer: Arc::new(Mutex::new(())),
+            action_log: Vec::new(),
+            origin: None,
+            name: None,
+            capacity: None,
+            invite_only: false,
+            invited: HashSet::new(),
+            banned: HashSet::new(),
+            muted: HashSet::new(),
+            waitlist_enabled: false,
+            waitlist: VecDeque::new(),
+            room_group: None,
+            rate_limit: None,
+            draining: None,
+            last_broadcast: std::sync::Mutex::new(Instant::now()),
+            state_version: 0,
+            history: VecDeque::new(),
+            state_subscribers: Vec::new(),
+            serialized_state: None,
+            actor: None,
+        }
+    }
+
+    /// Returns the public description of the room, without its state.
+    pub fn info(&self) -> RoomInfo {
+        RoomInfo {
+            id: self.id,
+            owner_id: self.owner_id,
+            client_count: self.client_ids.len(),
+            private: matches!(self.status, RoomStatus::Private(_)),
+        }
+    }
+
+    /// Returns the compact description of the room sent to overview subscribers.
+    pub fn summary(&self) -> RoomSummary {
+        let idle = self.last_broadcast.lock().unwrap().elapsed();
+        let last_activity = SystemTime::now()
+            .checked_sub(idle)
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since_epoch| since_epoch.as_millis() as u64);
+        RoomSummary {
+            id: self.id,
+            owner_id: self.owner_id,
+            name: self.name.clone(),
+            client_count: self.client_ids.len(),
+            last_activity,
+        }
+    }
+
+    /// Records the broadcast payload and stamp of the action that produced the current state
+    /// version, keeping at most `config.size` entries.
+    pub(crate) fn push_history(
+        &mut self,
+        payload: &str,
+        stamp: Option<Stamp>,
+        config: HistoryConfig,
+    ) {
+        if config.size == 0 {
+            return;
+        }
+        match config.compression.compress(payload.as_bytes().to_vec()) {
+            Ok(payload) => self.history.push_back((self.state_version, stamp, payload)),
+            Err(e) => {
+                // a gap in the history would replay partial actions, resume from the state instead
+                eprintln!("Error compressing history of room {}: {}", self.id, e);
+                self.history.clear();
+                return;
+            }
+        }
+        while self.history.len() > config.size {
+            self.history.pop_front();
+        }
+    }
+
+    /// Returns the broadcasts of the actions applied after `last_seq`, oldest first, with the
+    /// stamps they were sent with, decompressing their payloads with `compression`.
+    ///
+    /// Returns `None` if some of them already left the history buffer, or if `last_seq`
+    /// is ahead of the room, in which case the client needs the full state instead.
+    pub fn actions_since(&self, last_seq: u64, compression: Compression) -> Option<Vec<Response>> {
+        if last_seq == self.state_version {
+            return Some(Vec::new());
+        }
+        match self.history.front() {
+            Some((first_seq, ..))
+                if *first_seq <= last_seq + 1 && last_seq < self.state_version =>
+            {
+                self.history
+                    .iter()
+                    .filter(|(seq, ..)| *seq > last_seq)
+                    .map(|(_, stamp, payload)| {
+                        let payload = compression.decompress(payload).ok()?;
+                        Some(Response::Action(String::from_utf8(payload).ok()?, *stamp))
+                    })
+                    .collect()
+            }
+            _ => None,
+        }
+    }
+
+    /// Sends the current state to every state subscriber, forgetting the ones that hung up.
+    pub(crate) async fn publish_state(&mut self) {
+        if self.state_subscribers.is_empty() {
+            return;
+        }
+        let reducer = self.reducer.lock().await;
+        self.state_subscribers
+            .retain(|subscriber| subscriber.send(reducer.get_state()).is_ok());
+    }
+
+    /// Returns the number of bytes taken by the payloads kept in the history buffer.
+    pub fn history_bytes(&self) -> usize {
+        self.history.iter().map(|(.., payload)| payload.len()).sum()
+    }
+
+    /// Returns the JSON-serialized state of the room.
+    ///
+    /// The state is serialized once per state version and reused for every client
+    /// receiving it until the next action is applied.
+    pub async fn serialized_state(&mut self) -> serde_json::Result<String> {
+        if let Some((version, state)) = &self.serialized_state {
+            if *version == self.state_version {
+                return Ok(state.clone());
+            }
+        }
+        let state = serde_json::to_string(&*self.reducer.lock().await.state_arc())?;
+        self.serialized_state = Some((self.state_version, state.clone()));
+        Ok(state)
+    }
+
+    /// Puts the given client on the waitlist, returning its 1-based position.
+    ///
+    /// Clients already on the waitlist keep their position.
+    pub fn enqueue_waitlist(&mut self, client_id: u64) -> usize {
+        match self.waitlist.iter().position(|id| *id == client_id) {
+            Some(index) => index + 1,
+            None => {
+                self.waitlist.push_back(client_id);
+                self.waitlist.len()
+            }
+        }
+    }
+
+    /// Checks whether the given client may join the room with the given password.
+    ///
+    /// Returns the response explaining the refusal if the room is not joinable.
+    pub fn check_joinable(&self, client_id: u64, password: Option<&str>) -> Result<(), Response> {
+        if let Some(reason) = &self.draining {
+            return Err(Response::RoomDraining(reason.clone()));
+        }
+        if self.banned.contains(&client_id) {
+            return Err(Response::Banned(self.id));
+        }
+        if self.invite_only && !self.invited.contains(&client_id) {
+            return Err(Response::InviteRequired(self.id));
+        }
+        if let RoomStatus::Private(room_password) = &self.status {
+            if password != Some(room_password.as_str()) {
+                return Err(Response::WrongPassword(self.id));
+            }
+        }
+        if self
+            .capacity
+            .is_some_and(|capacity| self.client_ids.len() >= capacity)
+        {
+            return Err(Response::RoomFull(self.id));
         }
+        Ok(())
     }
 }
@@ -0,0 +1,118 @@
+/// This module contains the rooms map of a broadcaster, sharded to reduce lock contention.
+mod test;
+
+use crate::dispatcher::Dispatchable;
+use crate::room::Room;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Number of buckets the rooms of a broadcaster are spread over.
+pub(crate) const ROOM_SHARDS: usize = 16;
+
+/// Bucket of rooms by their ID, locked as a whole.
+pub(crate) type RoomShard<R> = HashMap<u64, Room<R>>;
+
+/// Rooms spread over buckets by `room_id % N`, each behind its own read-write lock.
+///
+/// Rooms of different buckets are accessed in parallel, so activity in one room only
+/// contends with the rooms sharing its bucket. Operations spanning several buckets lock
+/// them in bucket order, so they can't deadlock with each other.
+pub(crate) struct RoomShards<R: Dispatchable> {
+    shards: Box<[RwLock<RoomShard<R>>]>,
+}
+
+impl<R: Dispatchable> RoomShards<R> {
+    /// Creates `count` empty buckets.
+    ///
+    /// # Panics
+    /// * This function panics if `count` is 0.
+    pub fn new(count: usize) -> Self {
+        assert!(count > 0, "rooms must be spread over at least one bucket");
+        Self {
+            shards: (0..count).map(|_| RwLock::new(HashMap::new())).collect(),
+        }
+    }
+
+    /// returns the index of the bucket of a room
+    fn index(&self, room_id: u64) -> usize {
+        (room_id % self.shards.len() as u64) as usize
+    }
+
+    /// Locks the bucket of a room for reading.
+    pub async fn read(&self, room_id: u64) -> RwLockReadGuard<'_, RoomShard<R>> {
+        self.shards[self.index(room_id)].read().await
+    }
+
+    /// Locks the bucket of a room for writing.
+    pub async fn write(&self, room_id: u64) -> RwLockWriteGuard<'_, RoomShard<R>> {
+        self.shards[self.index(room_id)].write().await
+    }
+
+    /// Locks the bucket of a room for writing if it is not locked elsewhere.
+    pub fn try_write(&self, room_id: u64) -> Option<RwLockWriteGuard<'_, RoomShard<R>>> {
+        self.shards[self.index(room_id)].try_write().ok()
+    }
+
+    /// Locks every bucket for reading, in bucket order.
+    pub async fn read_all(&self) -> Vec<RwLockReadGuard<'_, RoomShard<R>>> {
+        let mut guards = Vec::with_capacity(self.shards.len());
+        for shard in self.shards.iter() {
+            guards.push(shard.read().await);
+        }
+        guards
+    }
+
+    /// Locks the buckets of the given rooms for writing, in bucket order.
+    pub async fn write_many(&self, room_ids: &[u64]) -> RoomsWriteGuard<'_, R> {
+        let mut indices = room_ids
+            .iter()
+            .map(|room_id| self.index(*room_id))
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+
+        let mut guards = BTreeMap::new();
+        for index in indices {
+            guards.insert(index, self.shards[index].write().await);
+        }
+        RoomsWriteGuard {
+            shards: self,
+            guards,
+        }
+    }
+
+    /// Returns the number of rooms, counted bucket by bucket.
+    pub async fn room_count(&self) -> usize {
+        let mut count = 0;
+        for shard in self.shards.iter() {
+            count += shard.read().await.len();
+        }
+        count
+    }
+}
+
+/// Write locks over the buckets of several rooms, taken by `RoomShards::write_many`.
+pub(crate) struct RoomsWriteGuard<'a, R: Dispatchable> {
+    shards: &'a RoomShards<R>,
+    guards: BTreeMap<usize, RwLockWriteGuard<'a, RoomShard<R>>>,
+}
+
+impl<R: Dispatchable> RoomsWriteGuard<'_, R> {
+    /// Returns a room of the locked buckets, if it exists.
+    pub fn get(&self, room_id: u64) -> Option<&Room<R>> {
+        self.guards
+            .get(&self.shards.index(room_id))
+            .and_then(|shard| shard.get(&room_id))
+    }
+
+    /// Returns the bucket of a room.
+    ///
+    /// # Panics
+    /// * This function panics if the bucket of the room was not locked.
+    pub fn shard_mut(&mut self, room_id: u64) -> &mut RoomShard<R> {
+        let index = self.shards.index(room_id);
+        self.guards
+            .get_mut(&index)
+            .expect("bucket of the room must be locked")
+    }
+}
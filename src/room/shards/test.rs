@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use crate::dispatcher::{ActionResponse, Dispatchable};
+    use crate::room::{Room, RoomShards, RoomStatus};
+    use crate::utils::types::{Broadcastable, Receivable};
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct TestAction;
+
+    impl Receivable for TestAction {}
+
+    #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+    struct TestState;
+
+    impl Broadcastable for TestState {}
+
+    #[derive(Clone, Default)]
+    struct TestReducer;
+
+    impl Dispatchable for TestReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            _action: Self::Action,
+        ) -> Result<ActionResponse<Self::State>, String> {
+            Ok(ActionResponse {
+                status: "success".to_string(),
+                state: TestState,
+                author: client_id,
+                data: "".to_string(),
+            })
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            _action: &str,
+        ) -> Result<ActionResponse<Self::State>, String> {
+            self.dispatch(client_id, TestAction).await
+        }
+
+        fn get_state(&self) -> Self::State {
+            TestState
+        }
+    }
+
+    fn create_room(room_id: u64) -> Room<TestReducer> {
+        Room::new(
+            room_id,
+            0,
+            HashSet::new(),
+            RoomStatus::Public,
+            Arc::new(Mutex::new(TestReducer)),
+        )
+    }
+
+    async fn shards_with_rooms(count: usize, room_ids: &[u64]) -> RoomShards<TestReducer> {
+        let shards = RoomShards::new(count);
+        for room_id in room_ids {
+            shards
+                .write(*room_id)
+                .await
+                .insert(*room_id, create_room(*room_id));
+        }
+        shards
+    }
+
+    #[tokio::test]
+    async fn test_rooms_are_locked_per_bucket() {
+        let shards = shards_with_rooms(4, &[0, 1, 2, 4]).await;
+        assert_eq!(shards.room_count().await, 4);
+
+        let bucket = shards.write(0).await;
+        // rooms 0 and 4 share a bucket, the others stay available
+        assert!(bucket.contains_key(&0) && bucket.contains_key(&4));
+        assert!(shards.try_write(4).is_none());
+        assert!(shards.try_write(1).unwrap().contains_key(&1));
+        assert!(shards.read(2).await.contains_key(&2));
+        drop(bucket);
+
+        let rooms = shards.read_all().await;
+        let mut room_ids = rooms
+            .iter()
+            .flat_map(|shard| shard.keys().copied())
+            .collect::<Vec<_>>();
+        room_ids.sort_unstable();
+        assert_eq!(room_ids, vec![0, 1, 2, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_write_many_locks_the_buckets_of_the_rooms() {
+        let shards = shards_with_rooms(4, &[0, 1, 2, 5]).await;
+
+        let mut rooms = shards.write_many(&[5, 0, 1]).await;
+        assert!(rooms.get(0).is_some() && rooms.get(5).is_some());
+        assert!(rooms.get(4).is_none());
+        // room 2 sits in a bucket that wasn't asked for
+        assert!(rooms.get(2).is_none());
+        assert!(shards.try_write(2).is_some());
+        assert!(shards.try_write(1).is_none());
+
+        rooms.shard_mut(4).insert(4, create_room(4));
+        drop(rooms);
+        assert!(shards.read(4).await.contains_key(&4));
+    }
+
+    #[test]
+    #[should_panic(expected = "rooms must be spread over at least one bucket")]
+    fn test_rooms_need_a_bucket() {
+        RoomShards::<TestReducer>::new(0);
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use crate::room::RoomStatus;
+    use serde_json::json;
+
+    #[test]
+    fn test_room_status_serialization_omits_password() {
+        let public = serde_json::to_value(RoomStatus::Public).unwrap();
+        assert_eq!(public, json!({ "visibility": "public" }));
+
+        let private = RoomStatus::Private("hunter2".to_string());
+        let serialized = serde_json::to_string(&private).unwrap();
+        assert_eq!(serialized, r#"{"visibility":"private"}"#);
+        assert!(!serialized.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_room_status_deserialization() {
+        let status: RoomStatus =
+            serde_json::from_value(json!({ "visibility": "private", "password": "hunter2" }))
+                .unwrap();
+        assert!(matches!(&status, RoomStatus::Private(password) if password == "hunter2"));
+
+        // the listing representation reads back as the same visibility
+        let public: RoomStatus =
+            serde_json::from_value(serde_json::to_value(RoomStatus::Public).unwrap()).unwrap();
+        assert!(matches!(public, RoomStatus::Public));
+
+        // a private room can't be created without its password
+        assert!(serde_json::from_value::<RoomStatus>(json!({ "visibility": "private" })).is_err());
+        assert!(serde_json::from_value::<RoomStatus>(json!({ "visibility": "hidden" })).is_err());
+    }
+}
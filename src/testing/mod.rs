@@ -0,0 +1,15 @@
+/// This module contains testing helpers for reducers and joints.
+///
+/// [`RecordingSink`] records the responses sent to a client and asserts on them. With the
+/// `testing` feature, reducers can also be checked against random action sequences, see
+/// `check_reducer`.
+#[cfg(feature = "testing")]
+mod property;
+mod recording;
+
+#[cfg(feature = "testing")]
+pub use proptest;
+
+#[cfg(feature = "testing")]
+pub use property::{check_reducer, check_reducer_with, ArbitraryAction};
+pub use recording::RecordingSink;
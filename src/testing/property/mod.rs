@@ -0,0 +1,144 @@
+/// This module contains property-based testing helpers for reducers.
+///
+/// Available with the `testing` feature. Action enums generated by `#[reducer_actions]`
+/// implement [`ArbitraryAction`] automatically, so a reducer can be checked against random
+/// action sequences with a single call to [`check_reducer`].
+///
+/// # example
+///
+/// Invariant test for the chat reducer: every message must be authored by an identified user.
+///
+/// ```rust
+/// use injoint::codegen::{reducer_actions, Broadcastable};
+/// use injoint::testing::check_reducer;
+/// use serde::Serialize;
+/// use std::collections::HashMap;
+///
+/// #[derive(Serialize, Debug, Clone, Broadcastable)]
+/// struct Message {
+///     pub author: u64,
+///     pub content: String,
+/// }
+///
+/// #[derive(Serialize, Debug, Default, Clone, Broadcastable)]
+/// struct State {
+///     users: HashMap<u64, String>,
+///     messages: Vec<Message>,
+/// }
+///
+/// #[derive(Default, Serialize, Clone, Broadcastable)]
+/// struct Reducer {
+///     state: State,
+/// }
+///
+/// #[reducer_actions(State)]
+/// impl Reducer {
+///     async fn identify_user(&mut self, client_id: u64, name: String) -> Result<String, String> {
+///         if self.state.users.contains_key(&client_id) {
+///             return Err("User already identified".to_string());
+///         }
+///         self.state.users.insert(client_id, name.clone());
+///         Ok(name)
+///     }
+///
+///     async fn send_message(&mut self, client_id: u64, text: String) -> Result<String, String> {
+///         if !self.state.users.contains_key(&client_id) {
+///             return Err("User not identified".to_string());
+///         }
+///         self.state.messages.push(Message {
+///             author: client_id,
+///             content: text.clone(),
+///         });
+///         Ok(text)
+///     }
+/// }
+///
+/// check_reducer(Reducer::default(), |state: &State| {
+///     match state.messages.iter().find(|m| !state.users.contains_key(&m.author)) {
+///         Some(message) => Err(format!("message from unidentified user {}", message.author)),
+///         None => Ok(()),
+///     }
+/// })
+/// .unwrap();
+/// ```
+mod test;
+
+use crate::dispatcher::Dispatchable;
+use proptest::collection::vec;
+use proptest::strategy::{BoxedStrategy, Strategy};
+use proptest::test_runner::{Config, TestCaseError, TestRunner};
+use std::fmt::Debug;
+
+/// Number of random action sequences run by `check_reducer`.
+const DEFAULT_CASES: u32 = 256;
+/// Maximum length of a random action sequence.
+const MAX_SEQUENCE_LEN: usize = 32;
+/// Number of distinct client ids actions are randomly attributed to.
+const MAX_CLIENTS: u64 = 4;
+
+/// Action enum able to generate random valid actions.
+///
+/// Implemented automatically by `#[reducer_actions]` when the `testing` feature is enabled,
+/// for actions whose arguments implement `proptest::arbitrary::Arbitrary`.
+pub trait ArbitraryAction: Sized + Debug {
+    /// Returns a strategy generating any action of the enum.
+    fn strategy() -> BoxedStrategy<Self>;
+}
+
+/// Checks reducer invariants against random action sequences generated from the action enum.
+///
+/// See [`check_reducer_with`] for details.
+pub fn check_reducer<R, F>(reducer: R, invariant: F) -> Result<(), String>
+where
+    R: Dispatchable,
+    R::Action: ArbitraryAction,
+    F: Fn(&R::State) -> Result<(), String>,
+{
+    check_reducer_with(reducer, R::Action::strategy(), invariant)
+}
+
+/// Checks reducer invariants against random sequences of actions generated by `actions`.
+///
+/// Every sequence is dispatched to a fresh clone of `reducer` by a few random clients.
+/// After each action, rejected or not, the state must serialize and satisfy `invariant`.
+/// On failure, the error describes the minimal failing sequence found.
+///
+/// # Panics
+/// * This function panics if called from within an asynchronous runtime.
+pub fn check_reducer_with<R, S, F>(reducer: R, actions: S, invariant: F) -> Result<(), String>
+where
+    R: Dispatchable,
+    R::Action: Debug,
+    S: Strategy<Value = R::Action>,
+    F: Fn(&R::State) -> Result<(), String>,
+{
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut runner = TestRunner::new(Config {
+        cases: DEFAULT_CASES,
+        failure_persistence: None,
+        ..Config::default()
+    });
+
+    let sequences = vec((0..MAX_CLIENTS, actions), 0..=MAX_SEQUENCE_LEN);
+    runner
+        .run(&sequences, |sequence| {
+            let mut reducer = reducer.clone();
+            runtime.block_on(async {
+                for (client_id, action) in sequence {
+                    let _ = reducer.dispatch(client_id, action).await;
+
+                    let state = reducer.get_state();
+                    serde_json::to_vec(&state).map_err(|e| {
+                        TestCaseError::fail(format!("state is not serializable: {}", e))
+                    })?;
+                    invariant(&state).map_err(TestCaseError::fail)?;
+                }
+                Ok(())
+            })
+        })
+        .map_err(|e| e.to_string())
+}
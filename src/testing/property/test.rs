@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use crate::dispatcher::{ActionResponse, Dispatchable};
+    use crate::testing::{check_reducer, check_reducer_with, ArbitraryAction};
+    use crate::utils::types::{Broadcastable, Receivable};
+    use proptest::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Deserialize, Serialize)]
+    enum TestAction {
+        Add(i32),
+        Withdraw(u8),
+    }
+
+    impl Receivable for TestAction {}
+
+    impl ArbitraryAction for TestAction {
+        fn strategy() -> BoxedStrategy<Self> {
+            prop_oneof![
+                (0..100i32).prop_map(TestAction::Add),
+                any::<u8>().prop_map(TestAction::Withdraw),
+            ]
+            .boxed()
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+    struct TestState {
+        balance: i32,
+    }
+
+    impl Broadcastable for TestState {}
+
+    #[derive(Clone, Default)]
+    struct TestReducer {
+        state: TestState,
+        allow_overdraft: bool,
+    }
+
+    impl Dispatchable for TestReducer {
+        type Action = TestAction;
+        type State = TestState;
+        type Error = String;
+
+        async fn dispatch(
+            &mut self,
+            client_id: u64,
+            action: TestAction,
+        ) -> Result<ActionResponse<TestState>, String> {
+            match action {
+                TestAction::Add(value) => self.state.balance += value,
+                TestAction::Withdraw(value) => {
+                    if !self.allow_overdraft && self.state.balance < value as i32 {
+                        return Err("Insufficient balance".to_string());
+                    }
+                    self.state.balance -= value as i32;
+                }
+            }
+            Ok(ActionResponse {
+                status: "success".into(),
+                state: self.state.clone(),
+                author: client_id,
+                data: String::new(),
+            })
+        }
+
+        async fn extern_dispatch(
+            &mut self,
+            client_id: u64,
+            action_str: &str,
+        ) -> Result<ActionResponse<TestState>, String> {
+            let action: TestAction = serde_json::from_str(action_str)
+                .map_err(|e| format!("Failed to parse action: {}", e))?;
+            self.dispatch(client_id, action).await
+        }
+
+        fn get_state(&self) -> TestState {
+            self.state.clone()
+        }
+    }
+
+    fn non_negative(state: &TestState) -> Result<(), String> {
+        if state.balance < 0 {
+            return Err(format!("negative balance {}", state.balance));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_reducer_holds() {
+        assert!(check_reducer(TestReducer::default(), non_negative).is_ok());
+    }
+
+    #[test]
+    fn test_check_reducer_finds_violation() {
+        let reducer = TestReducer {
+            allow_overdraft: true,
+            ..TestReducer::default()
+        };
+
+        let error = check_reducer(reducer, non_negative).unwrap_err();
+        assert!(error.contains("negative balance"));
+    }
+
+    #[test]
+    fn test_check_reducer_with_custom_strategy() {
+        let reducer = TestReducer {
+            allow_overdraft: true,
+            ..TestReducer::default()
+        };
+
+        let deposits = (0..100i32).prop_map(TestAction::Add);
+        assert!(check_reducer_with(reducer, deposits, non_negative).is_ok());
+    }
+}
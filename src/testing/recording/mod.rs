@@ -0,0 +1,144 @@
+/// This module contains a sink recording the responses sent to a client, for unit tests.
+///
+/// # example
+///
+/// ```rust
+/// use injoint::connection::SinkAdapter;
+/// use injoint::response::{Response, ResponseStatus};
+/// use injoint::testing::RecordingSink;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let sink = RecordingSink::new();
+/// let mut connection = sink.clone();
+/// tokio::spawn(async move {
+///     connection.send(Response::RoomCreated(1)).await.unwrap();
+///     connection.send(Response::RoomJoined(7)).await.unwrap();
+/// });
+///
+/// let joined = sink
+///     .wait_for(|response| matches!(response, Response::RoomJoined(_)), Duration::from_secs(1))
+///     .await;
+/// assert_eq!(joined, Some(Response::RoomJoined(7)));
+/// sink.assert_received(Response::RoomCreated(1));
+/// assert_eq!(sink.received_of_kind(ResponseStatus::RoomJoined).len(), 1);
+/// # }
+/// ```
+mod test;
+
+use crate::connection::SinkAdapter;
+use crate::response::{Response, ResponseStatus};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Sink recording every response sent to it, with assertion helpers.
+///
+/// Clones share the same recording, so a clone can be handed to a joint or broadcaster
+/// while the test keeps another one to assert on.
+#[derive(Clone, Default)]
+pub struct RecordingSink {
+    responses: Arc<Mutex<Vec<Response>>>,
+    /// Woken up on every recorded response.
+    received: Arc<Notify>,
+}
+
+impl RecordingSink {
+    /// Creates a sink with an empty recording.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns every response received so far, in order.
+    pub fn received(&self) -> Vec<Response> {
+        self.responses.lock().unwrap().clone()
+    }
+
+    /// Returns the responses of the given kind received so far, in order.
+    pub fn received_of_kind(&self, status: ResponseStatus) -> Vec<Response> {
+        self.responses
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|response| response.status() == status)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the last response received, if any.
+    pub fn last(&self) -> Option<Response> {
+        self.responses.lock().unwrap().last().cloned()
+    }
+
+    /// Returns the number of responses received so far.
+    pub fn len(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+
+    /// Returns true if no response was received yet.
+    pub fn is_empty(&self) -> bool {
+        self.responses.lock().unwrap().is_empty()
+    }
+
+    /// Forgets the responses received so far.
+    pub fn clear(&self) {
+        self.responses.lock().unwrap().clear();
+    }
+
+    /// Asserts that the response was received.
+    ///
+    /// # Panics
+    /// * This function panics, listing the received responses, if the response was not received.
+    #[track_caller]
+    pub fn assert_received(&self, expected: Response) {
+        let responses = self.responses.lock().unwrap();
+        assert!(
+            responses.contains(&expected),
+            "expected {:?} to be received, got {:?}",
+            expected,
+            *responses
+        );
+    }
+
+    /// Waits until a response matching the predicate is received, and returns it.
+    ///
+    /// Responses received before the call are matched as well. Returns `None` if no
+    /// matching response is received within `timeout`.
+    pub async fn wait_for<F>(&self, predicate: F, timeout: Duration) -> Option<Response>
+    where
+        F: Fn(&Response) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            // registered before checking, so a response recorded in between still wakes us up
+            let notified = self.received.notified();
+            if let Some(response) = self
+                .responses
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|response| predicate(response))
+            {
+                return Some(response.clone());
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return None;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SinkAdapter for RecordingSink {
+    async fn send(
+        &mut self,
+        response: Response,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.responses.lock().unwrap().push(response);
+        self.received.notify_waiters();
+        Ok(())
+    }
+}
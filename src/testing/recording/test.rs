@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use crate::connection::SinkAdapter;
+    use crate::response::{Response, ResponseStatus};
+    use crate::testing::RecordingSink;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_recording_sink_records_in_order() {
+        let sink = RecordingSink::new();
+        let mut connection = sink.clone();
+        assert!(sink.is_empty());
+
+        for response in [
+            Response::RoomCreated(1),
+            Response::Hello(1),
+            Response::RoomCreated(2),
+        ] {
+            connection.send(response).await.unwrap();
+        }
+
+        assert_eq!(sink.len(), 3);
+        assert_eq!(sink.last(), Some(Response::RoomCreated(2)));
+        assert_eq!(
+            sink.received_of_kind(ResponseStatus::RoomCreated),
+            vec![Response::RoomCreated(1), Response::RoomCreated(2)]
+        );
+        assert!(sink.received_of_kind(ResponseStatus::Action).is_empty());
+        sink.assert_received(Response::Hello(1));
+
+        sink.clear();
+        assert!(sink.received().is_empty());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "expected RoomJoined(3) to be received")]
+    async fn test_assert_received_panics_on_missing_response() {
+        let sink = RecordingSink::new();
+        sink.clone().send(Response::RoomJoined(2)).await.unwrap();
+        sink.assert_received(Response::RoomJoined(3));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_matching_response() {
+        let sink = RecordingSink::new();
+        let mut connection = sink.clone();
+        connection.send(Response::RoomCreated(1)).await.unwrap();
+
+        // responses received before waiting are matched
+        let created = sink
+            .wait_for(
+                |response| matches!(response, Response::RoomCreated(_)),
+                Duration::from_millis(10),
+            )
+            .await;
+        assert_eq!(created, Some(Response::RoomCreated(1)));
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            connection.send(Response::Keepalive).await.unwrap();
+            connection.send(Response::RoomLeft(4)).await.unwrap();
+        });
+        let left = sink
+            .wait_for(
+                |response| matches!(response, Response::RoomLeft(_)),
+                Duration::from_secs(5),
+            )
+            .await;
+        assert_eq!(left, Some(Response::RoomLeft(4)));
+
+        let missing = sink
+            .wait_for(
+                |response| matches!(response, Response::RoomDeleted(_)),
+                Duration::from_millis(20),
+            )
+            .await;
+        assert_eq!(missing, None);
+    }
+}
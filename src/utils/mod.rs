@@ -2,7 +2,6 @@
 mod test;
 pub mod types;
 
-use serde::{de::DeserializeOwned, Serialize};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// `get_id` generates a unique ID for each call.
@@ -11,3 +10,46 @@ pub fn get_id() -> usize {
     static COUNTER: AtomicUsize = AtomicUsize::new(1);
     COUNTER.fetch_add(1, Ordering::Relaxed)
 }
+
+/// Implements `ArbitraryAction` for an action enum generated by `#[reducer_actions]`.
+///
+/// Expands to nothing unless the `testing` feature is enabled.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "testing")]
+macro_rules! __reducer_action_strategy {
+    ($action:ident {
+        $($variant:ident
+            $(($($name:ident: $arg:ty),*))?
+            $({$($field:ident: $field_arg:ty),*})?
+        ),* $(,)?
+    }) => {
+        impl $crate::testing::ArbitraryAction for $action {
+            fn strategy() -> $crate::testing::proptest::strategy::BoxedStrategy<Self> {
+                use $crate::testing::proptest::strategy::Strategy as _;
+                $crate::testing::proptest::strategy::Union::new(vec![
+                    $(
+                        $crate::testing::proptest::arbitrary::any::<(
+                            $($($arg,)*)? $($($field_arg,)*)?
+                        )>()
+                            .prop_map(|($($($name,)*)? $($($field,)*)?)| {
+                                $action::$variant $(($($name),*))? $({$($field),*})?
+                            })
+                            .boxed()
+                    ),*
+                ])
+                .boxed()
+            }
+        }
+    };
+}
+
+/// Implements `ArbitraryAction` for an action enum generated by `#[reducer_actions]`.
+///
+/// Expands to nothing unless the `testing` feature is enabled.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "testing"))]
+macro_rules! __reducer_action_strategy {
+    ($($tokens:tt)*) => {};
+}
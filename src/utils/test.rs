@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
     use crate::utils::get_id;
     use serde::{Deserialize, Serialize};
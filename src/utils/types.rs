@@ -1,8 +1,37 @@
+use crate::dispatcher::Dispatchable;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::{json, Value};
 
 /// `Broadcastable` trait is used to mark a struct as broadcastable.
-pub trait Broadcastable: Serialize {}
+pub trait Broadcastable: Serialize {
+    /// Returns the JSON Schema of the broadcast value.
+    ///
+    /// Generated by `#[derive(Broadcastable)]` for structs with named fields; defaults to `null`.
+    fn schema() -> Value {
+        Value::Null
+    }
+}
 
 /// `Receivable` trait is used to mark a struct as receivable.
-pub trait Receivable: DeserializeOwned {}
+pub trait Receivable: DeserializeOwned {
+    /// Returns the JSON Schema of the received value.
+    ///
+    /// Generated by `#[reducer_actions]` for the action enum, listing every action
+    /// variant with its data; defaults to `null`.
+    fn schema() -> Value {
+        Value::Null
+    }
+}
+
+/// Returns the JSON Schema of the actions accepted and the state broadcast by reducer `R`.
+///
+/// Meant to be written to a file by a build script or a small binary, so frontends can
+/// generate their action and state types instead of mirroring them by hand.
+pub fn reducer_schema<R: Dispatchable>() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "action": R::Action::schema(),
+        "state": R::State::schema(),
+    })
+}
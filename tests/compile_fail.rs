@@ -0,0 +1,5 @@
+#[test]
+fn derive_misuse_is_reported_at_the_derive_site() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,87 @@
+use injoint::codegen::{reducer_actions, Broadcastable};
+use injoint::dispatcher::{DispatchContext, Dispatchable};
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Default, Clone, Broadcastable)]
+struct State {
+    users: Vec<String>,
+    counter: i64,
+}
+
+#[derive(Default, Serialize, Clone, Broadcastable)]
+struct Reducer {
+    state: State,
+}
+
+#[reducer_actions(State)]
+impl Reducer {
+    async fn identify(&mut self, ctx: &DispatchContext, token: String) -> Result<String, String> {
+        if ctx.token != token {
+            return Err("Invalid token".to_string());
+        }
+        self.state.users.push(ctx.label.clone());
+        Ok(ctx.label.clone())
+    }
+
+    async fn add(&mut self, client_id: u64, value: i64) -> Result<String, String> {
+        let _ = client_id;
+        self.state.counter += value;
+        Ok(value.to_string())
+    }
+}
+
+fn context() -> DispatchContext {
+    DispatchContext {
+        client_id: 7,
+        label: "alice".to_string(),
+        token: "secret".to_string(),
+        ..DispatchContext::default()
+    }
+}
+
+fn action(raw: &str) -> ActionReducer {
+    serde_json::from_str(raw).unwrap()
+}
+
+#[tokio::test]
+async fn context_reaches_actions() {
+    let mut reducer = Reducer::default();
+
+    let result = reducer
+        .dispatch_with_context(
+            &context(),
+            action(r#"{"type":"ActionIdentify","data":"secret"}"#),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.author, 7);
+    assert_eq!(result.data, "alice");
+    assert_eq!(reducer.state.users, vec!["alice"]);
+
+    let result = reducer
+        .dispatch_with_context(
+            &context(),
+            action(r#"{"type":"ActionIdentify","data":"guess"}"#),
+        )
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn client_id_actions_still_dispatch() {
+    let mut reducer = Reducer::default();
+
+    let result = reducer
+        .dispatch(3, action(r#"{"type":"ActionAdd","data":2}"#))
+        .await
+        .unwrap();
+    assert_eq!(result.author, 3);
+    assert_eq!(reducer.state.counter, 2);
+
+    let result = reducer
+        .dispatch_with_context(&context(), action(r#"{"type":"ActionAdd","data":3}"#))
+        .await
+        .unwrap();
+    assert_eq!(result.author, 7);
+    assert_eq!(reducer.state.counter, 5);
+}
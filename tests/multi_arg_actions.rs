@@ -0,0 +1,80 @@
+use injoint::codegen::{reducer_actions, Broadcastable};
+use injoint::joint::mpsc::MPSCJoint;
+use injoint::message::{JointMessage, JointMessageMethod};
+use injoint::response::Response;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Default, Clone, Broadcastable)]
+struct State {
+    board: Vec<u8>,
+    moves: Vec<(u8, u8)>,
+}
+
+#[derive(Serialize, Clone, Broadcastable)]
+struct Reducer {
+    state: State,
+}
+
+impl Default for Reducer {
+    fn default() -> Self {
+        Reducer {
+            state: State {
+                board: (0..8).collect(),
+                moves: Vec::new(),
+            },
+        }
+    }
+}
+
+#[reducer_actions(State)]
+impl Reducer {
+    async fn move_piece(&mut self, client_id: u64, from: u8, to: u8) -> Result<String, String> {
+        let _ = client_id;
+        if from as usize >= self.state.board.len() || to as usize >= self.state.board.len() {
+            return Err("Square out of board".to_string());
+        }
+        self.state.board.swap(from as usize, to as usize);
+        self.state.moves.push((from, to));
+        Ok(format!("{}-{}", from, to))
+    }
+}
+
+fn message(method: JointMessageMethod) -> JointMessage {
+    JointMessage {
+        client_token: "player".to_string(),
+        v: None,
+        message: method,
+    }
+}
+
+#[tokio::test]
+async fn two_argument_action_is_sent_as_named_fields() {
+    let joint = MPSCJoint::new(Reducer::default());
+    let (tx, mut rx) = joint.connect(10);
+
+    tx.send(message(JointMessageMethod::Create(None)))
+        .await
+        .unwrap();
+    loop {
+        if let Response::RoomCreated(_) = rx.recv().await.unwrap() {
+            break;
+        }
+    }
+
+    tx.send(message(JointMessageMethod::Action(
+        r#"{"type":"ActionMovePiece","data":{"from":1,"to":2}}"#.to_string(),
+    )))
+    .await
+    .unwrap();
+
+    let action = loop {
+        if let Response::Action(action, _) = rx.recv().await.unwrap() {
+            break serde_json::from_str::<serde_json::Value>(&action).unwrap();
+        }
+    };
+    assert_eq!(action["status"], "ActionMovePiece");
+    assert_eq!(action["data"], "1-2");
+    assert_eq!(action["state"]["board"][1], 2);
+    assert_eq!(action["state"]["board"][2], 1);
+    assert_eq!(action["state"]["moves"], serde_json::json!([[1, 2]]));
+}
@@ -0,0 +1,62 @@
+use injoint::codegen::{reducer_actions, Broadcastable};
+use injoint::joint::mpsc::MPSCJoint;
+use serde::Serialize;
+use serde_json::json;
+
+#[derive(Serialize, Debug, Default, Clone, Broadcastable)]
+struct State {
+    messages: Vec<String>,
+    counter: i64,
+}
+
+#[derive(Default, Serialize, Clone, Broadcastable)]
+struct Reducer {
+    state: State,
+}
+
+#[reducer_actions(State)]
+impl Reducer {
+    async fn send_message(&mut self, client_id: u64, text: String) -> Result<String, String> {
+        let _ = client_id;
+        self.state.messages.push(text.clone());
+        Ok(text)
+    }
+
+    async fn add(&mut self, client_id: u64, value: i64, times: u32) -> Result<String, String> {
+        let _ = client_id;
+        self.state.counter += value * times as i64;
+        Ok(self.state.counter.to_string())
+    }
+}
+
+#[test]
+fn description_lists_methods_and_action_schema() {
+    let mut joint = MPSCJoint::new(Reducer::default());
+    joint.set_join_requires_ack(true);
+    let description = joint.protocol_description();
+
+    let methods = description["message"]["methods"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|method| method["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    for method in ["Create", "Join", "Leave", "Action", "Ack"] {
+        assert!(methods.contains(&method), "missing method {}", method);
+    }
+
+    assert_eq!(
+        description["actions"],
+        json!({
+            "tag": "type",
+            "content": "data",
+            "actions": [
+                {"type": "ActionSendMessage", "args": [{"name": "text", "type": "String"}]},
+                {"type": "ActionAdd", "args": [
+                    {"name": "value", "type": "i64"},
+                    {"name": "times", "type": "u32"},
+                ]},
+            ],
+        })
+    );
+}
@@ -0,0 +1,119 @@
+use injoint::codegen::{reducer_actions, Broadcastable};
+use injoint::dispatcher::Dispatchable;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Default, Clone, Broadcastable)]
+struct State {
+    counter: i64,
+    tags: Vec<String>,
+    limit: Option<i32>,
+}
+
+#[derive(Default, Serialize, Clone, Broadcastable)]
+struct Reducer {
+    state: State,
+}
+
+// documented single-argument form, expanding to a complete `Dispatchable` impl
+#[reducer_actions(State)]
+impl Reducer {
+    async fn add(&mut self, client_id: u64, value: i64) -> Result<String, String> {
+        let _ = client_id;
+        self.state.counter += value;
+        Ok(value.to_string())
+    }
+
+    async fn reset(&mut self, client_id: u64) -> Result<String, String> {
+        let _ = client_id;
+        self.state.counter = 0;
+        Ok(String::new())
+    }
+
+    async fn tag(&mut self, client_id: u64, tags: Vec<String>) -> Result<String, String> {
+        let _ = client_id;
+        self.state.tags.extend(tags);
+        Ok(self.state.tags.len().to_string())
+    }
+
+    async fn set_limit(&mut self, client_id: u64, limit: Option<i32>) -> Result<String, String> {
+        let _ = client_id;
+        self.state.limit = limit;
+        Ok(String::new())
+    }
+}
+
+#[tokio::test]
+async fn expanded_reducer_dispatches_actions() {
+    let mut reducer = Reducer::default();
+    assert_eq!(reducer.get_state().counter, 0);
+
+    let response = reducer
+        .extern_dispatch(1, r#"{"type":"ActionAdd","data":5}"#)
+        .await
+        .unwrap();
+    assert_eq!(response.status, "ActionAdd");
+    assert_eq!(response.author, 1);
+    assert_eq!(response.data, "5");
+    assert_eq!(response.state.counter, 5);
+    assert_eq!(reducer.get_state().counter, 5);
+}
+
+#[tokio::test]
+async fn actions_without_arguments_dispatch() {
+    let mut reducer = Reducer::default();
+    reducer
+        .dispatch(1, ActionReducer::ActionAdd(3))
+        .await
+        .unwrap();
+
+    let response = reducer
+        .extern_dispatch(1, r#"{"type":"ActionReset"}"#)
+        .await
+        .unwrap();
+    assert_eq!(response.status, "ActionReset");
+    assert_eq!(reducer.get_state().counter, 0);
+
+    reducer
+        .dispatch(1, ActionReducer::ActionReset)
+        .await
+        .unwrap();
+    assert_eq!(reducer.get_state().counter, 0);
+}
+
+#[tokio::test]
+async fn actions_with_generic_arguments_deserialize() {
+    let mut reducer = Reducer::default();
+
+    reducer
+        .extern_dispatch(1, r#"{"type":"ActionTag","data":["red","blue"]}"#)
+        .await
+        .unwrap();
+    assert_eq!(reducer.get_state().tags, vec!["red", "blue"]);
+
+    reducer
+        .extern_dispatch(1, r#"{"type":"ActionSetLimit","data":7}"#)
+        .await
+        .unwrap();
+    assert_eq!(reducer.get_state().limit, Some(7));
+
+    reducer
+        .extern_dispatch(1, r#"{"type":"ActionSetLimit","data":null}"#)
+        .await
+        .unwrap();
+    assert_eq!(reducer.get_state().limit, None);
+}
+
+#[tokio::test]
+async fn malformed_actions_are_rejected() {
+    let mut reducer = Reducer::default();
+
+    for action in [
+        "not json",
+        r#"{"type":"ActionUnknown"}"#,
+        r#"{"type":"ActionAdd","data":"five"}"#,
+    ] {
+        let error = reducer.extern_dispatch(1, action).await.unwrap_err();
+        assert!(error.starts_with("Failed to parse action"), "{}", error);
+    }
+    assert_eq!(reducer.get_state().counter, 0);
+}
@@ -0,0 +1,64 @@
+use injoint::codegen::{reducer_actions, Broadcastable};
+use injoint::dispatcher::Dispatchable;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Default, Clone, Broadcastable)]
+struct State {
+    counter: i64,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+enum CounterError {
+    Negative(i64),
+    Invalid(String),
+}
+
+impl From<String> for CounterError {
+    fn from(message: String) -> Self {
+        CounterError::Invalid(message)
+    }
+}
+
+#[derive(Default, Serialize, Clone, Broadcastable)]
+struct CounterReducer {
+    state: State,
+}
+
+#[reducer_actions(State, error = CounterError)]
+impl CounterReducer {
+    async fn add(&mut self, client_id: u64, value: i64) -> Result<String, CounterError> {
+        let _ = client_id;
+        if value < 0 {
+            return Err(CounterError::Negative(value));
+        }
+        self.state.counter += value;
+        Ok(value.to_string())
+    }
+}
+
+#[tokio::test]
+async fn actions_return_the_declared_error_type() {
+    let mut reducer = CounterReducer::default();
+
+    let response = reducer
+        .extern_dispatch(1, r#"{"type":"ActionAdd","data":3}"#)
+        .await
+        .unwrap();
+    assert_eq!(response.state.counter, 3);
+
+    let error = reducer
+        .extern_dispatch(1, r#"{"type":"ActionAdd","data":-1}"#)
+        .await
+        .unwrap_err();
+    assert_eq!(error, CounterError::Negative(-1));
+}
+
+#[tokio::test]
+async fn parse_errors_convert_into_the_declared_error_type() {
+    let mut reducer = CounterReducer::default();
+
+    let error = reducer.extern_dispatch(1, "not json").await.unwrap_err();
+    assert!(
+        matches!(error, CounterError::Invalid(message) if message.starts_with("Failed to parse action"))
+    );
+}
@@ -0,0 +1,100 @@
+use injoint::codegen::{reducer_actions, Broadcastable};
+use injoint::utils::types::{reducer_schema, Broadcastable as _, Receivable as _};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+
+#[derive(Serialize, Debug, Default, Clone, Broadcastable)]
+struct State {
+    players: HashMap<u64, String>,
+    board: Vec<u8>,
+    winner: Option<u64>,
+}
+
+#[derive(Default, Serialize, Clone, Broadcastable)]
+struct Reducer {
+    state: State,
+}
+
+#[reducer_actions(State)]
+impl Reducer {
+    async fn join(&mut self, client_id: u64, name: String) -> Result<String, String> {
+        self.state.players.insert(client_id, name.clone());
+        Ok(name)
+    }
+
+    async fn move_piece(&mut self, client_id: u64, from: u8, to: u8) -> Result<String, String> {
+        let _ = client_id;
+        self.state.board.swap(from as usize, to as usize);
+        Ok(String::new())
+    }
+
+    async fn resign(&mut self, client_id: u64) -> Result<String, String> {
+        self.state.winner = Some(client_id);
+        Ok(String::new())
+    }
+}
+
+#[test]
+fn action_schema_lists_variants_with_fields() {
+    assert_eq!(
+        ActionReducer::schema(),
+        json!({
+            "title": "ActionReducer",
+            "oneOf": [
+                {
+                    "type": "object",
+                    "properties": {
+                        "type": {"const": "ActionJoin"},
+                        "data": {"type": "string"},
+                    },
+                    "required": ["type", "data"],
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "type": {"const": "ActionMovePiece"},
+                        "data": {
+                            "type": "object",
+                            "properties": {
+                                "from": {"type": "integer", "minimum": 0},
+                                "to": {"type": "integer", "minimum": 0},
+                            },
+                            "required": ["from", "to"],
+                        },
+                    },
+                    "required": ["type", "data"],
+                },
+                {
+                    "type": "object",
+                    "properties": {"type": {"const": "ActionResign"}},
+                    "required": ["type"],
+                },
+            ],
+        })
+    );
+}
+
+#[test]
+fn state_schema_describes_fields() {
+    assert_eq!(
+        State::schema(),
+        json!({
+            "title": "State",
+            "type": "object",
+            "properties": {
+                "players": {
+                    "type": "object",
+                    "additionalProperties": {"type": "string"},
+                },
+                "board": {"type": "array", "items": {"type": "integer", "minimum": 0}},
+                "winner": {"anyOf": [{"type": "integer", "minimum": 0}, {"type": "null"}]},
+            },
+            "required": ["players", "board"],
+        })
+    );
+
+    let schema = reducer_schema::<Reducer>();
+    assert_eq!(schema["action"], ActionReducer::schema());
+    assert_eq!(schema["state"], State::schema());
+}
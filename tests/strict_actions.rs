@@ -0,0 +1,68 @@
+use injoint::codegen::{reducer_actions, Broadcastable};
+use injoint::dispatcher::Dispatchable;
+use injoint::message::DEFAULT_PROTOCOL_VERSION;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Default, Clone, Broadcastable)]
+struct State {
+    counter: i64,
+}
+
+#[derive(Default, Serialize, Clone, Broadcastable)]
+struct LaxReducer {
+    state: State,
+}
+
+#[reducer_actions(State)]
+impl LaxReducer {
+    async fn add(&mut self, client_id: u64, value: i64) -> Result<String, String> {
+        let _ = client_id;
+        self.state.counter += value;
+        Ok(value.to_string())
+    }
+}
+
+#[derive(Default, Serialize, Clone, Broadcastable)]
+struct StrictReducer {
+    state: State,
+}
+
+#[reducer_actions(State, strict_actions)]
+impl StrictReducer {
+    async fn add(&mut self, client_id: u64, value: i64) -> Result<String, String> {
+        let _ = client_id;
+        self.state.counter += value;
+        Ok(value.to_string())
+    }
+}
+
+const VALID_ACTION: &str = r#"{"type":"ActionAdd","data":3}"#;
+const ACTION_WITH_EXTRA_FIELD: &str = r#"{"type":"ActionAdd","data":3,"extra":true}"#;
+
+#[test]
+fn lax_actions_ignore_unknown_fields() {
+    assert!(LaxReducer::deserialize_action(VALID_ACTION, DEFAULT_PROTOCOL_VERSION).is_ok());
+    assert!(
+        LaxReducer::deserialize_action(ACTION_WITH_EXTRA_FIELD, DEFAULT_PROTOCOL_VERSION).is_ok()
+    );
+}
+
+#[test]
+fn strict_actions_reject_unknown_fields() {
+    assert!(StrictReducer::deserialize_action(VALID_ACTION, DEFAULT_PROTOCOL_VERSION).is_ok());
+
+    let error =
+        StrictReducer::deserialize_action(ACTION_WITH_EXTRA_FIELD, DEFAULT_PROTOCOL_VERSION)
+            .unwrap_err();
+    assert!(error.contains("extra"));
+}
+
+#[tokio::test]
+async fn strict_actions_dispatch_valid_actions() {
+    let mut reducer = StrictReducer::default();
+    let action = StrictReducer::deserialize_action(VALID_ACTION, DEFAULT_PROTOCOL_VERSION).unwrap();
+    let response = reducer.dispatch(1, action).await.unwrap();
+
+    assert_eq!(response.status, "ActionAdd");
+    assert_eq!(response.state.counter, 3);
+}
@@ -0,0 +1,8 @@
+use injoint::codegen::Broadcastable;
+
+#[derive(Broadcastable)]
+struct State {
+    counter: i64,
+}
+
+fn main() {}